@@ -1,16 +1,21 @@
-use std::sync::Arc;
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use winit::{
     application::ApplicationHandler,
     dpi::PhysicalSize,
     event::{ElementState, KeyEvent, WindowEvent},
-    event_loop::ActiveEventLoop,
+    event_loop::{ActiveEventLoop, ControlFlow},
     keyboard::{Key, NamedKey},
     window::{Window, WindowAttributes},
 };
 
 use crate::graphics::GraphicsContext;
 
+const PREPARE_INTERVAL: Duration = Duration::from_millis(100);
+
 #[derive(Default)]
 pub struct App {
     scale_factor: f64,
@@ -63,6 +68,12 @@ impl ApplicationHandler for App {
             return;
         }
 
+        // the ui gets the first look at every event, whatever it uses doesn't
+        // reach the sandbox
+        if gfx_context.handle_ui_event(&event) {
+            return;
+        }
+
         match event {
             WindowEvent::Resized(physical_size) => {
                 gfx_context.resize(physical_size);
@@ -85,6 +96,22 @@ impl ApplicationHandler for App {
                 gfx_context.handle_input(event);
                 window.request_redraw();
             }
+            WindowEvent::CursorMoved { position, .. } => {
+                gfx_context.handle_cursor_moved(position);
+                window.request_redraw();
+            }
+            WindowEvent::MouseInput { state, button, .. } => {
+                gfx_context.handle_mouse_input(button, state);
+                window.request_redraw();
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                gfx_context.handle_mouse_wheel(delta);
+                window.request_redraw();
+            }
+            WindowEvent::DroppedFile(path) => {
+                gfx_context.handle_dropped_file(&path);
+                window.request_redraw();
+            }
             WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
                 self.scale_factor = scale_factor
             }
@@ -92,6 +119,20 @@ impl ApplicationHandler for App {
         }
     }
 
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        let (Some(window), Some(gfx_context)) = (&self.window, &mut self.graphics_context) else {
+            return;
+        };
+
+        if gfx_context.prepare() {
+            window.request_redraw();
+        }
+
+        // wake up now and then even without input, so assets that finish
+        // loading or change on disk show up
+        event_loop.set_control_flow(ControlFlow::WaitUntil(Instant::now() + PREPARE_INTERVAL));
+    }
+
     fn suspended(&mut self, _event_loop: &ActiveEventLoop) {
         let gfx_context = self.graphics_context.as_mut().unwrap();
         gfx_context.suspend();
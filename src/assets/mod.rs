@@ -0,0 +1,493 @@
+use std::{
+    collections::HashMap,
+    marker::PhantomData,
+    path::PathBuf,
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+use crate::texture::{
+    container::{self, ContainerImage},
+    decode::DecodedImage,
+    sampler::SamplerPreset,
+    Mipmaps, Texture, TextureOptions,
+};
+use watch::FileWatcher;
+
+pub mod watch;
+
+// pairs an asset path with its bytes baked into the binary
+#[macro_export]
+macro_rules! embed {
+    ($path:literal) => {
+        (
+            $path,
+            include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/", $path)).as_slice(),
+        )
+    };
+}
+
+// resolves asset paths. files under the root win over embedded copies, so
+// edits show up without a rebuild, while the embedded bytes keep the binary
+// working from any directory and on android
+#[derive(Clone)]
+pub struct AssetSource {
+    root: Option<PathBuf>,
+    embedded: Arc<HashMap<&'static str, &'static [u8]>>,
+}
+
+impl Default for AssetSource {
+    // `ASSET_ROOT` if set, otherwise the crate directory the binary was built
+    // from
+    fn default() -> Self {
+        let root = std::env::var_os("ASSET_ROOT")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(env!("CARGO_MANIFEST_DIR")));
+
+        Self::new(Some(root))
+    }
+}
+
+impl AssetSource {
+    pub fn new(root: Option<PathBuf>) -> Self {
+        Self {
+            root: root.filter(|root| root.is_dir()),
+            embedded: Arc::default(),
+        }
+    }
+
+    pub fn with_embedded(mut self, files: &[(&'static str, &'static [u8])]) -> Self {
+        Arc::make_mut(&mut self.embedded).extend(files.iter().copied());
+        self
+    }
+
+    // the file on disk backing `path`, if there is one
+    pub fn resolve(&self, path: &str) -> Option<PathBuf> {
+        self.root
+            .as_ref()
+            .map(|root| root.join(path))
+            .filter(|path| path.is_file())
+    }
+
+    pub fn read(&self, path: &str) -> Result<Vec<u8>, String> {
+        if let Some(file) = self.resolve(path) {
+            return std::fs::read(&file).map_err(|e| format!("failed to read {path}: {e}"));
+        }
+
+        self.embedded
+            .get(path)
+            .map(|bytes| bytes.to_vec())
+            .ok_or_else(|| format!("asset {path} not found"))
+    }
+}
+
+pub struct Handle<T> {
+    index: usize,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Handle<T> {}
+
+impl<T> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index
+    }
+}
+
+impl<T> Eq for Handle<T> {}
+
+impl<T> std::fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Handle({})", self.index)
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Kind {
+    Texture(TextureOptions),
+    // decoded but kept on the cpu
+    Image,
+    // the file as it is, e.g. json metadata
+    Bytes,
+}
+
+enum Decoded {
+    Image(DecodedImage),
+    Container(ContainerImage),
+    Bytes(Vec<u8>),
+}
+
+enum Slot {
+    Loading,
+    Texture(Texture, wgpu::BindGroup),
+    Image(DecodedImage),
+    Bytes(Vec<u8>),
+    Failed(String),
+}
+
+struct Entry {
+    path: String,
+    kind: Kind,
+    slot: Slot,
+    // bumped by every request, results of older requests are dropped so a
+    // slow decode of an old version can't replace a newer one
+    generation: u32,
+    // bumped every time the slot gets a new version of the asset
+    revision: u32,
+}
+
+struct Job {
+    index: usize,
+    generation: u32,
+    path: String,
+    decode: bool,
+}
+
+// reads and decodes assets on worker threads. decoded textures are uploaded on
+// the thread calling `poll`, until then their handle resolves to a placeholder.
+// files loaded from disk are watched and reloaded in place when they change.
+pub struct AssetLoader {
+    source: AssetSource,
+    jobs: Sender<Job>,
+    results: Receiver<(usize, u32, Result<Decoded, String>)>,
+    entries: Vec<Entry>,
+    placeholder: Texture,
+    placeholder_bind_group: wgpu::BindGroup,
+    watcher: Option<FileWatcher>,
+    watched: HashMap<PathBuf, Vec<usize>>,
+}
+
+impl AssetLoader {
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue, source: AssetSource) -> Self {
+        let (jobs, job_receiver) = mpsc::channel::<Job>();
+        let (result_sender, results) = mpsc::channel();
+        let job_receiver = Arc::new(Mutex::new(job_receiver));
+
+        let workers = thread::available_parallelism().map_or(2, |n| n.get().min(4));
+        for i in 0..workers {
+            let source = source.clone();
+            let job_receiver = job_receiver.clone();
+            let result_sender = result_sender.clone();
+
+            thread::Builder::new()
+                .name(format!("asset worker {i}"))
+                .spawn(move || loop {
+                    // the lock is released before decoding so other workers can
+                    // pick up jobs meanwhile
+                    let job = job_receiver.lock().unwrap().recv();
+                    let Ok(Job {
+                        index,
+                        generation,
+                        path,
+                        decode: should_decode,
+                    }) = job
+                    else {
+                        break;
+                    };
+
+                    let decoded = source.read(&path).and_then(|bytes| match should_decode {
+                        true => decode(&bytes),
+                        false => Ok(Decoded::Bytes(bytes)),
+                    });
+                    let decoded = decoded.map_err(|e| format!("{path}: {e}"));
+
+                    if result_sender.send((index, generation, decoded)).is_err() {
+                        break;
+                    }
+                })
+                .expect("failed to spawn asset worker");
+        }
+
+        // magenta and black checker, hard to mistake for a real texture
+        let placeholder = Texture::from_raw_data(
+            device,
+            queue,
+            &[
+                255, 0, 255, 255, 0, 0, 0, 255, //
+                0, 0, 0, 255, 255, 0, 255, 255,
+            ],
+            (2, 2),
+            TextureOptions {
+                mipmaps: Mipmaps::None,
+                sampler: SamplerPreset::Nearest,
+                ..Default::default()
+            },
+        );
+
+        let placeholder_bind_group = bind_group(device, &placeholder);
+
+        let watcher = FileWatcher::new()
+            .inspect_err(|e| log::warn!("hot reloading disabled: {e}"))
+            .ok();
+
+        Self {
+            source,
+            jobs,
+            results,
+            entries: Vec::new(),
+            placeholder,
+            placeholder_bind_group,
+            watcher,
+            watched: HashMap::new(),
+        }
+    }
+
+    pub fn source(&self) -> &AssetSource {
+        &self.source
+    }
+
+    pub fn load_texture(&mut self, path: &str, options: TextureOptions) -> Handle<Texture> {
+        self.load(path, Kind::Texture(options))
+    }
+
+    // decodes an image without uploading it, for images that are processed
+    // further on the cpu
+    pub fn load_image(&mut self, path: &str) -> Handle<DecodedImage> {
+        self.load(path, Kind::Image)
+    }
+
+    // reads a file without decoding it, so metadata next to an image is
+    // reloaded along with it
+    pub fn load_bytes(&mut self, path: &str) -> Handle<Vec<u8>> {
+        self.load(path, Kind::Bytes)
+    }
+
+    fn load<T>(&mut self, path: &str, kind: Kind) -> Handle<T> {
+        let index = self.entries.len();
+        self.entries.push(Entry {
+            path: path.to_owned(),
+            kind,
+            slot: Slot::Loading,
+            generation: 0,
+            revision: 0,
+        });
+
+        self.watch(path, index);
+        self.request(index);
+
+        Handle {
+            index,
+            _marker: PhantomData,
+        }
+    }
+
+    fn request(&mut self, index: usize) {
+        let entry = &mut self.entries[index];
+        entry.generation += 1;
+
+        self.jobs
+            .send(Job {
+                index,
+                generation: entry.generation,
+                path: entry.path.clone(),
+                decode: !matches!(entry.kind, Kind::Bytes),
+            })
+            .expect("asset workers stopped");
+    }
+
+    fn watch(&mut self, path: &str, index: usize) {
+        let (Some(watcher), Some(file)) = (&mut self.watcher, self.source.resolve(path)) else {
+            return;
+        };
+
+        let Ok(file) = file.canonicalize() else {
+            return;
+        };
+
+        if let Err(e) = watcher.watch(&file) {
+            log::warn!("{e}");
+            return;
+        }
+
+        self.watched.entry(file).or_default().push(index);
+    }
+
+    // queues every asset whose file changed on disk for decoding again. the
+    // current version stays in use until the new one is ready
+    fn reload_changed(&mut self) {
+        let Some(watcher) = &self.watcher else {
+            return;
+        };
+
+        for file in watcher.changed() {
+            let indices = self.watched.get(&file).cloned().unwrap_or_default();
+            for index in indices {
+                log::info!("reloading {}", self.entries[index].path);
+                self.request(index);
+            }
+        }
+    }
+
+    // uploads everything that finished decoding since the last call. returns
+    // true when any asset changed state
+    pub fn poll(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) -> bool {
+        self.reload_changed();
+
+        let mut changed = false;
+
+        while let Ok((index, generation, decoded)) = self.results.try_recv() {
+            let entry = &mut self.entries[index];
+            if generation != entry.generation {
+                continue;
+            }
+            let reloading = !matches!(entry.slot, Slot::Loading);
+
+            let slot = match (decoded, entry.kind) {
+                (Err(e), _) => Slot::Failed(e),
+                (Ok(Decoded::Bytes(bytes)), _) => Slot::Bytes(bytes),
+                (Ok(Decoded::Image(image)), Kind::Texture(options)) => {
+                    let texture = Texture::from_image(device, queue, &image, options);
+                    let bind_group = bind_group(device, &texture);
+                    Slot::Texture(texture, bind_group)
+                }
+                (Ok(Decoded::Image(image)), _) => Slot::Image(image),
+                (Ok(Decoded::Container(image)), Kind::Texture(options)) => {
+                    match Texture::from_container(device, queue, &image, options) {
+                        Ok(texture) => {
+                            let bind_group = bind_group(device, &texture);
+                            Slot::Texture(texture, bind_group)
+                        }
+                        Err(e) => Slot::Failed(format!("{}: {e}", entry.path)),
+                    }
+                }
+                (Ok(Decoded::Container(_)), _) => Slot::Failed(format!(
+                    "{}: containers can only be loaded as textures",
+                    entry.path
+                )),
+            };
+
+            match slot {
+                // a half written file shouldn't throw away the working version
+                Slot::Failed(e) if reloading => log::error!("failed to reload {e}"),
+                Slot::Failed(e) => {
+                    log::error!("failed to load {e}");
+                    entry.slot = Slot::Failed(e);
+                    changed = true;
+                }
+                slot => {
+                    entry.slot = slot;
+                    entry.revision += 1;
+                    changed = true;
+                }
+            }
+        }
+
+        changed
+    }
+
+    pub fn is_loading(&self) -> bool {
+        self.entries
+            .iter()
+            .any(|entry| matches!(entry.slot, Slot::Loading))
+    }
+
+    pub fn is_ready<T>(&self, handle: Handle<T>) -> bool {
+        matches!(
+            self.entries[handle.index].slot,
+            Slot::Texture(..) | Slot::Image(_) | Slot::Bytes(_)
+        )
+    }
+
+    pub fn error<T>(&self, handle: Handle<T>) -> Option<&str> {
+        match &self.entries[handle.index].slot {
+            Slot::Failed(e) => Some(e),
+            _ => None,
+        }
+    }
+
+    // changes whenever the asset is loaded or reloaded, so work derived from
+    // it on the cpu can be redone only for the assets that changed
+    pub fn revision<T>(&self, handle: Handle<T>) -> u32 {
+        self.entries[handle.index].revision
+    }
+
+    pub fn placeholder(&self) -> &Texture {
+        &self.placeholder
+    }
+
+    // the loaded texture, or the placeholder while it is loading or failed
+    pub fn texture(&self, handle: Handle<Texture>) -> &Texture {
+        match &self.entries[handle.index].slot {
+            Slot::Texture(texture, _) => texture,
+            _ => &self.placeholder,
+        }
+    }
+
+    // a bind group for `Texture::bind_group_layout_for` the texture's view
+    // dimension. fetching it every frame picks up reloads automatically
+    pub fn bind_group(&self, handle: Handle<Texture>) -> &wgpu::BindGroup {
+        match &self.entries[handle.index].slot {
+            Slot::Texture(_, bind_group) => bind_group,
+            _ => &self.placeholder_bind_group,
+        }
+    }
+
+    pub fn image(&self, handle: Handle<DecodedImage>) -> Option<&DecodedImage> {
+        match &self.entries[handle.index].slot {
+            Slot::Image(image) => Some(image),
+            _ => None,
+        }
+    }
+
+    pub fn bytes(&self, handle: Handle<Vec<u8>>) -> Option<&[u8]> {
+        match &self.entries[handle.index].slot {
+            Slot::Bytes(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+}
+
+fn bind_group(device: &wgpu::Device, texture: &Texture) -> wgpu::BindGroup {
+    let layout = Texture::bind_group_layout_for(device, texture.view_dimension);
+    texture.bind_group(device, &layout)
+}
+
+fn decode(bytes: &[u8]) -> Result<Decoded, String> {
+    if container::is_container(bytes) {
+        ContainerImage::decode(bytes).map(Decoded::Container)
+    } else {
+        DecodedImage::decode(bytes).map(Decoded::Image)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn only_the_latest_request_lands() {
+        let (device, queue) = crate::testing::headless_device();
+        let root = std::env::temp_dir().join(format!("asset-test-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("sheet.json"), "old").unwrap();
+
+        let mut loader = AssetLoader::new(&device, &queue, AssetSource::new(Some(root.clone())));
+        let sheet = loader.load_bytes("sheet.json");
+        std::fs::write(root.join("sheet.json"), "new").unwrap();
+        loader.request(0);
+
+        // the watcher may ask for it again too, whichever order the results
+        // come back in only the latest request lands
+        let start = Instant::now();
+        while start.elapsed() < Duration::from_millis(500) {
+            loader.poll(&device, &queue);
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        assert_eq!(loader.bytes(sheet), Some(b"new".as_slice()));
+        assert_eq!(loader.revision(sheet), 1);
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
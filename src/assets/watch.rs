@@ -0,0 +1,62 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver},
+};
+
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+// watches the directories of loaded files rather than the files themselves,
+// editors often save by replacing the file which would end a file watch
+pub struct FileWatcher {
+    watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<notify::Event>>,
+    directories: HashSet<PathBuf>,
+}
+
+impl FileWatcher {
+    pub fn new() -> Result<Self, String> {
+        let (sender, events) = mpsc::channel();
+        let watcher = notify::recommended_watcher(sender)
+            .map_err(|e| format!("failed to create file watcher: {e}"))?;
+
+        Ok(Self {
+            watcher,
+            events,
+            directories: HashSet::new(),
+        })
+    }
+
+    // `file` has to be canonical so it matches the paths of later events
+    pub fn watch(&mut self, file: &Path) -> Result<(), String> {
+        let Some(directory) = file.parent() else {
+            return Ok(());
+        };
+
+        if self.directories.contains(directory) {
+            return Ok(());
+        }
+
+        self.watcher
+            .watch(directory, RecursiveMode::NonRecursive)
+            .map_err(|e| format!("failed to watch {}: {e}", directory.display()))?;
+        self.directories.insert(directory.to_owned());
+
+        Ok(())
+    }
+
+    // files written or created since the last call. a single save usually
+    // fires several events, they are merged into one entry
+    pub fn changed(&self) -> HashSet<PathBuf> {
+        self.events
+            .try_iter()
+            .filter_map(|event| {
+                event
+                    .inspect_err(|e| log::warn!("file watcher error: {e}"))
+                    .ok()
+            })
+            .filter(|event| matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)))
+            .flat_map(|event| event.paths)
+            .collect()
+    }
+}
@@ -0,0 +1,357 @@
+use std::f32::consts::TAU;
+
+use glam::Vec2;
+use wgpu::include_wgsl;
+use winit::dpi::PhysicalSize;
+
+use crate::{
+    sandbox::camera2d::camera::Camera2D,
+    text::{Font, TextRenderer, TextSection},
+    vertices::{Vertex, VertexPosCol},
+};
+
+// in pixels, whatever the zoom
+const ARROW_HEAD: f32 = 10.0;
+const TEXT_SIZE: f32 = 14.0;
+// grids denser than this would only fill the screen
+const MIN_GRID_SPACING: f32 = 4.0;
+
+struct Label {
+    text: String,
+    position: Vec2,
+    color: [f32; 3],
+}
+
+// lines and labels for the current frame, drawn over everything else and
+// thrown away once drawn. sandboxes get it in `Renderable::update` and draw in
+// world space after `set_camera`, in pixels from the top left otherwise
+pub struct DebugDraw {
+    // calls do nothing while it's off
+    pub enabled: bool,
+    // of the window, in pixels
+    size: Vec2,
+    camera: Camera2D,
+    // a line list
+    vertices: Vec<VertexPosCol>,
+    labels: Vec<Label>,
+}
+
+impl DebugDraw {
+    pub fn new(size: PhysicalSize<u32>) -> Self {
+        let mut draw = Self {
+            enabled: true,
+            size: Vec2::ONE,
+            camera: Camera2D::new(1, 1, 1.0),
+            vertices: Vec::new(),
+            labels: Vec::new(),
+        };
+        draw.resize(size);
+
+        draw
+    }
+
+    pub fn resize(&mut self, size: PhysicalSize<u32>) {
+        self.size = Vec2::new(size.width.max(1) as f32, size.height.max(1) as f32);
+        self.camera = Camera2D::new(size.width.max(1), size.height.max(1), 1.0);
+    }
+
+    // set it before drawing. lines are transformed by the last camera of the
+    // frame, but circles, arrows, grids and labels are sized and placed with
+    // the one current when they're added
+    pub fn set_camera(&mut self, camera: &Camera2D) {
+        self.camera = *camera;
+    }
+
+    fn pixels_per_unit(&self) -> f32 {
+        self.camera.scale * self.size.x / self.camera.width
+    }
+
+    pub fn line(&mut self, a: Vec2, b: Vec2, color: [f32; 3]) {
+        if !self.enabled {
+            return;
+        }
+
+        self.vertices.extend([a, b].map(|p| VertexPosCol {
+            position: p.into(),
+            color,
+        }));
+    }
+
+    // `position` is the top left corner
+    pub fn rect(&mut self, position: Vec2, size: Vec2, color: [f32; 3]) {
+        let corners = [
+            position,
+            position + Vec2::new(size.x, 0.0),
+            position + size,
+            position + Vec2::new(0.0, size.y),
+        ];
+
+        for i in 0..4 {
+            self.line(corners[i], corners[(i + 1) % 4], color);
+        }
+    }
+
+    pub fn circle(&mut self, center: Vec2, radius: f32, color: [f32; 3]) {
+        // enough segments to look round at the size it shows up on screen
+        let segments = (radius * self.pixels_per_unit()).sqrt() * 4.0;
+        let segments = segments.clamp(12.0, 128.0) as usize;
+
+        let point = |i: usize| center + Vec2::from_angle(i as f32 / segments as f32 * TAU) * radius;
+        for i in 0..segments {
+            self.line(point(i), point(i + 1), color);
+        }
+    }
+
+    pub fn arrow(&mut self, from: Vec2, to: Vec2, color: [f32; 3]) {
+        self.line(from, to, color);
+
+        let delta = to - from;
+        let length = delta.length();
+        if length <= f32::EPSILON {
+            return;
+        }
+
+        let head = (ARROW_HEAD / self.pixels_per_unit()).min(length * 0.4);
+        let back = -delta / length * head;
+        for angle in [0.45, -0.45] {
+            self.line(to, to + Vec2::from_angle(angle).rotate(back), color);
+        }
+    }
+
+    // lines every `spacing` world units across the visible part of the world
+    pub fn grid(&mut self, spacing: f32, color: [f32; 3]) {
+        if spacing * self.pixels_per_unit() < MIN_GRID_SPACING {
+            return;
+        }
+
+        let min = Vec2::new(self.camera.x, self.camera.y);
+        let max = min + Vec2::new(self.camera.width, self.camera.height) / self.camera.scale;
+        let first = (min / spacing).ceil();
+        let last = (max / spacing).floor();
+
+        for i in first.x as i64..=last.x as i64 {
+            let x = i as f32 * spacing;
+            self.line(Vec2::new(x, min.y), Vec2::new(x, max.y), color);
+        }
+        for i in first.y as i64..=last.y as i64 {
+            let y = i as f32 * spacing;
+            self.line(Vec2::new(min.x, y), Vec2::new(max.x, y), color);
+        }
+    }
+
+    // at a fixed size on screen, with its top left corner at `position`
+    pub fn text(&mut self, text: &str, position: Vec2, color: [f32; 3]) {
+        if !self.enabled {
+            return;
+        }
+
+        self.labels.push(Label {
+            text: text.to_owned(),
+            position,
+            color,
+        });
+    }
+
+    fn to_screen(&self, position: Vec2) -> Vec2 {
+        let camera = Vec2::new(self.camera.x, self.camera.y);
+        let view = Vec2::new(self.camera.width, self.camera.height);
+
+        (position - camera) * self.camera.scale / view * self.size
+    }
+
+    // the camera goes back to pixels too, sandboxes set it every frame
+    fn clear(&mut self) {
+        self.vertices.clear();
+        self.labels.clear();
+        self.camera = Camera2D::new(self.size.x as u32, self.size.y as u32, 1.0);
+    }
+}
+
+// draws a `DebugDraw` straight onto the window, after post processing, so
+// its colors come out as given
+pub struct DebugRenderer {
+    pipeline: wgpu::RenderPipeline,
+    camera_buffer: wgpu::Buffer,
+    camera_bind_group: wgpu::BindGroup,
+    vertex_buffer: wgpu::Buffer,
+    // in vertices
+    capacity: usize,
+    text: TextRenderer,
+}
+
+impl DebugRenderer {
+    pub fn new(device: &wgpu::Device, view_format: wgpu::TextureFormat) -> Self {
+        let shader = device.create_shader_module(include_wgsl!("shader.wgsl"));
+
+        let camera_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("debug draw camera buffer"),
+            size: size_of::<[[f32; 4]; 4]>() as _,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let camera_layout = Camera2D::bind_group_layout(device);
+        let camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("debug draw camera bind group"),
+            layout: &camera_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: camera_buffer.as_entire_binding(),
+            }],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("debug draw pipeline layout"),
+            bind_group_layouts: &[&camera_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("debug draw pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[VertexPosCol::desc()],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: view_format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::LineList,
+                ..Default::default()
+            },
+            multisample: Default::default(),
+            depth_stencil: None,
+            multiview: None,
+            cache: None,
+        });
+
+        let capacity = 1024;
+
+        Self {
+            pipeline,
+            camera_buffer,
+            camera_bind_group,
+            vertex_buffer: vertex_buffer(device, capacity),
+            capacity,
+            text: TextRenderer::new(device, view_format, Font::default()),
+        }
+    }
+
+    pub fn resize(&self, size: PhysicalSize<u32>, queue: &wgpu::Queue) {
+        self.text.resize(size, queue);
+    }
+
+    // draws and clears everything accumulated in `draw`
+    pub fn render(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        draw: &mut DebugDraw,
+    ) {
+        if draw.vertices.is_empty() && draw.labels.is_empty() {
+            return;
+        }
+
+        if draw.vertices.len() > self.capacity {
+            self.capacity = draw.vertices.len().next_power_of_two();
+            self.vertex_buffer = vertex_buffer(device, self.capacity);
+        }
+        queue.write_buffer(&self.vertex_buffer, 0, bytemuck::cast_slice(&draw.vertices));
+        queue.write_buffer(
+            &self.camera_buffer,
+            0,
+            bytemuck::cast_slice(&draw.camera.camera_matrix().to_cols_array_2d()),
+        );
+
+        for label in &draw.labels {
+            let [r, g, b] = label.color;
+            self.text.push(&TextSection {
+                text: &label.text,
+                position: draw.to_screen(label.position).round(),
+                size: TEXT_SIZE,
+                color: [r, g, b, 1.0],
+                ..Default::default()
+            });
+        }
+        self.text.prepare(device, queue);
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("debug draw render pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        if !draw.vertices.is_empty() {
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
+            render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+            render_pass.draw(0..draw.vertices.len() as u32, 0..1);
+        }
+        self.text.render(&mut render_pass);
+
+        draw.clear();
+    }
+}
+
+fn vertex_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("debug draw vertex buffer"),
+        size: (capacity * size_of::<VertexPosCol>()) as _,
+        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grid_covers_the_view() {
+        let mut draw = DebugDraw::new(PhysicalSize::new(100, 50));
+        draw.set_camera(&Camera2D {
+            x: -5.0,
+            y: 0.0,
+            ..Camera2D::new(100, 50, 1.0)
+        });
+        draw.grid(10.0, [1.0; 3]);
+
+        // x from -5 to 95 and y from 0 to 50, with both ends included
+        let lines = draw.vertices.len() / 2;
+        assert_eq!(lines, 10 + 6);
+    }
+
+    #[test]
+    fn nothing_accumulates_while_disabled() {
+        let mut draw = DebugDraw::new(PhysicalSize::new(100, 100));
+        draw.enabled = false;
+
+        draw.line(Vec2::ZERO, Vec2::ONE, [1.0; 3]);
+        draw.circle(Vec2::ZERO, 10.0, [1.0; 3]);
+        draw.text("hidden", Vec2::ZERO, [1.0; 3]);
+
+        assert!(draw.vertices.is_empty());
+        assert!(draw.labels.is_empty());
+    }
+}
@@ -1,28 +1,206 @@
-use std::sync::Arc;
+use std::{path::Path, sync::Arc, time::Instant};
 
-use winit::{dpi::PhysicalSize, event::KeyEvent, window::Window};
+use glam::Vec2;
+use winit::{
+    dpi::{PhysicalPosition, PhysicalSize},
+    event::{ElementState, KeyEvent, MouseButton, MouseScrollDelta, WindowEvent},
+    keyboard::{KeyCode, PhysicalKey},
+    window::Window,
+};
+
+use crate::{
+    debug_draw::{DebugDraw, DebugRenderer},
+    hdr::{HdrPipeline, Tonemapper, HDR_FORMAT},
+    postprocess::{Effect, PostProcess},
+    render_target::{DepthBuffer, RenderTarget, SrgbBlit, DEPTH_FORMAT, FRAME_FORMAT},
+    texture::mipmap::MipmapGenerator,
+    ui::UiOverlay,
+};
+
+#[derive(Debug, Clone, Copy)]
+pub enum MouseEvent {
+    // cursor position in normalized device coordinates, y pointing up
+    Moved(Vec2),
+    Button(MouseButton, ElementState),
+    // in lines, positive when scrolling up / away from the user
+    Wheel(f32),
+}
 
 pub trait Renderable {
+    // checked before the sandbox is created. the scene is then drawn in
+    // `HDR_FORMAT` and tonemapped, so sandboxes can write values above 1
+    fn needs_hdr() -> bool
+    where
+        Self: Sized,
+    {
+        false
+    }
+    // checked before the sandbox is created. pipelines then test against a
+    // `DEPTH_FORMAT` depth buffer
+    fn needs_depth() -> bool
+    where
+        Self: Sized,
+    {
+        false
+    }
     fn handle_input(&mut self, key_event: KeyEvent, queue: &wgpu::Queue) {
         let _ = key_event;
         let _ = queue;
     }
-    fn update(&mut self) {}
+    fn handle_mouse(&mut self, mouse_event: MouseEvent, queue: &wgpu::Queue) {
+        let _ = mouse_event;
+        let _ = queue;
+    }
+    // a file dragged from elsewhere and dropped on the window
+    fn handle_dropped_file(&mut self, path: &Path, queue: &wgpu::Queue) {
+        let _ = path;
+        let _ = queue;
+    }
+    // runs whenever the event loop wakes up, with access to the device, e.g.
+    // to upload assets that finished loading. returns true when the next frame
+    // would look different, so a redraw is requested without waiting for input
+    fn prepare(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) -> bool {
+        let _ = device;
+        let _ = queue;
+        false
+    }
+    // called with the initial window size and whenever it changes
+    fn resize(&mut self, size: PhysicalSize<u32>, queue: &wgpu::Queue) {
+        let _ = size;
+        let _ = queue;
+    }
+    // runs once per frame, before the scene is drawn, with the seconds since
+    // the last one. whatever goes into `debug_draw` shows up over this frame
+    // only
+    fn update(&mut self, delta_time: f32, debug_draw: &mut DebugDraw) {
+        let _ = delta_time;
+        let _ = debug_draw;
+    }
+    // builds this frame's controls in the ui overlay, e.g. an `egui::Window`
+    // with sliders for the sandbox's parameters. runs before `compute` and
+    // `render`, so changes show up in the same frame
+    fn ui(&mut self, ctx: &egui::Context, queue: &wgpu::Queue) {
+        let _ = ctx;
+        let _ = queue;
+    }
+    // recorded into a compute pass every frame, before the scene is drawn, so
+    // buffers written here can feed vertex and indirect draws in `render`
+    fn compute(&mut self, queue: &wgpu::Queue, compute_pass: &mut wgpu::ComputePass) {
+        let _ = queue;
+        let _ = compute_pass;
+    }
     fn render(&self, render_pass: &mut wgpu::RenderPass);
 }
 
+// every pipeline writes linear values and relies on an srgb view to encode
+// them. returns the surface format and the view format pipelines target.
+pub fn choose_surface_format(
+    formats: &[wgpu::TextureFormat],
+) -> (wgpu::TextureFormat, wgpu::TextureFormat) {
+    // formats without an srgb variant, like Rgb10a2Unorm, are viewed as they
+    // are and encoded by SrgbBlit
+    match formats.iter().find(|format| format.is_srgb()) {
+        Some(&format) => (format, format),
+        None => (formats[0], formats[0].add_srgb_suffix()),
+    }
+}
+
+// controls for everything the context draws around the sandbox
+fn rendering_ui(
+    ctx: &egui::Context,
+    post_process: &mut PostProcess,
+    hdr: Option<&mut HdrPipeline>,
+    debug_draw: &mut DebugDraw,
+) {
+    egui::Window::new("Rendering")
+        .default_pos([20.0, 20.0])
+        .default_open(false)
+        .show(ctx, |ui| {
+            for effect in Effect::ALL {
+                // without the hdr pipeline nothing goes above 1 to tonemap
+                if effect == Effect::Tonemap && hdr.is_none() {
+                    continue;
+                }
+
+                let mut enabled = post_process.is_enabled(effect);
+                if ui.checkbox(&mut enabled, format!("{effect:?}")).changed() {
+                    post_process.set_enabled(effect, enabled);
+                }
+            }
+
+            if let Some(hdr) = hdr {
+                ui.separator();
+                egui::ComboBox::from_label("tonemapper")
+                    .selected_text(format!("{:?}", hdr.tonemapper))
+                    .show_ui(ui, |ui| {
+                        for tonemapper in Tonemapper::ALL {
+                            ui.selectable_value(
+                                &mut hdr.tonemapper,
+                                tonemapper,
+                                format!("{tonemapper:?}"),
+                            );
+                        }
+                    });
+                ui.add(egui::Slider::new(&mut hdr.exposure, -4.0..=4.0).text("exposure (ev)"));
+                ui.checkbox(&mut hdr.bloom_enabled, "bloom");
+            }
+
+            ui.separator();
+            ui.checkbox(&mut debug_draw.enabled, "debug drawing");
+            ui.label("f1 hides the ui");
+        });
+}
+
+fn surface_config(
+    format: wgpu::TextureFormat,
+    view_format: wgpu::TextureFormat,
+    size: PhysicalSize<u32>,
+) -> wgpu::SurfaceConfiguration {
+    // a surface may always be viewed through the srgb variant of its format
+    let view_formats = if format != view_format {
+        vec![view_format]
+    } else {
+        vec![]
+    };
+
+    wgpu::SurfaceConfiguration {
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        format,
+        width: size.width.max(1),
+        height: size.height.max(1),
+        present_mode: wgpu::PresentMode::Fifo,
+        desired_maximum_frame_latency: 2,
+        alpha_mode: wgpu::CompositeAlphaMode::Auto,
+        view_formats,
+    }
+}
+
 pub struct GraphicsContext {
     #[allow(unused)]
     instance: wgpu::Instance,
     #[allow(unused)]
     adapter: wgpu::Adapter,
-    device: wgpu::Device,
+    device: Arc<wgpu::Device>,
     queue: wgpu::Queue,
     surface: Option<wgpu::Surface<'static>>,
     renderable: Box<dyn Renderable>,
-    #[allow(unused)]
     view_format: wgpu::TextureFormat,
     surface_config: wgpu::SurfaceConfiguration,
+    // the scene is drawn here instead of the swapchain while effects are on
+    scene_target: RenderTarget,
+    post_process: PostProcess,
+    // set for examples drawing in hdr, the scene is then drawn into its target
+    // and tonemapped into the swapchain or the post processing input
+    hdr: Option<HdrPipeline>,
+    // set for examples drawing with a depth test, cleared every frame
+    depth: Option<DepthBuffer>,
+    // set when the surface has no srgb view, the frame is then drawn here and
+    // encoded onto the surface before the ui
+    srgb_blit: Option<SrgbBlit>,
+    ui: UiOverlay,
+    debug_draw: DebugDraw,
+    debug_renderer: DebugRenderer,
+    last_frame: Instant,
 }
 
 impl GraphicsContext {
@@ -44,35 +222,68 @@ impl GraphicsContext {
         let adapter = pollster::block_on(req_adapter).expect("create adapter failed");
 
         log::debug!("creating wgpu device");
+        // enable whichever block compression families the adapter offers so
+        // compressed textures can be uploaded without cpu decompression
+        let compression_features = wgpu::Features::TEXTURE_COMPRESSION_BC
+            | wgpu::Features::TEXTURE_COMPRESSION_ETC2
+            | wgpu::Features::TEXTURE_COMPRESSION_ASTC;
+        // wireframes, sandboxes check for them before use
+        let polygon_mode_features =
+            wgpu::Features::POLYGON_MODE_LINE | wgpu::Features::POLYGON_MODE_POINT;
         let req_device = adapter.request_device(
             &wgpu::DeviceDescriptor {
                 label: None,
-                required_features: wgpu::Features::empty(),
+                required_features: adapter.features()
+                    & (compression_features | polygon_mode_features),
                 required_limits: wgpu::Limits::downlevel_defaults(),
                 memory_hints: Default::default(),
             },
             None,
         );
         let (device, queue) = pollster::block_on(req_device).expect("create device failed");
+        let device = Arc::new(device);
+        // textures created from here on share the device's mipmap pipelines
+        MipmapGenerator::register(&device);
 
         log::debug!("configuring wgpu surface");
-        let size = window.inner_size();
-        let view_format = surface.get_capabilities(&adapter).formats[0];
-        let config = wgpu::SurfaceConfiguration {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            format: view_format,
-            width: size.width.max(1),
-            height: size.height.max(1),
-            present_mode: wgpu::PresentMode::Fifo,
-            desired_maximum_frame_latency: 2,
-            alpha_mode: wgpu::CompositeAlphaMode::Auto,
-            view_formats: vec![],
-        };
+        let (surface_format, view_format) =
+            choose_surface_format(&surface.get_capabilities(&adapter).formats);
+        let config = surface_config(surface_format, view_format, window.inner_size());
 
         surface.configure(&device, &config);
 
+        // egui encodes by itself, so only the ui draws straight into a surface
+        // without an srgb view
+        let srgb_blit = (!view_format.is_srgb())
+            .then(|| SrgbBlit::new(&device, window.inner_size(), view_format));
+        let frame_format = if srgb_blit.is_some() {
+            FRAME_FORMAT
+        } else {
+            view_format
+        };
+
+        let scene_target = RenderTarget::new(&device, window.inner_size(), frame_format, None);
+        let post_process = PostProcess::new(&device, window.inner_size(), frame_format);
+
         // change this to switch between examples
-        let example = crate::sandbox::camera2d::Sandbox::new(&device, view_format);
+        type Example = crate::sandbox::camera2d::Sandbox;
+        let hdr = Example::needs_hdr()
+            .then(|| HdrPipeline::new(&device, window.inner_size(), frame_format));
+        let scene_format = if hdr.is_some() {
+            HDR_FORMAT
+        } else {
+            frame_format
+        };
+        let depth = Example::needs_depth()
+            .then(|| DepthBuffer::new(&device, window.inner_size(), DEPTH_FORMAT));
+        let mut example = Example::new(&device, scene_format);
+        example.resize(window.inner_size(), &queue);
+
+        let debug_draw = DebugDraw::new(window.inner_size());
+        let debug_renderer = DebugRenderer::new(&device, frame_format);
+        debug_renderer.resize(window.inner_size(), &queue);
+
+        let ui = UiOverlay::new(&device, view_format, window);
 
         Self {
             instance,
@@ -83,6 +294,15 @@ impl GraphicsContext {
             surface: Some(surface),
             view_format,
             surface_config: config,
+            scene_target,
+            post_process,
+            hdr,
+            depth,
+            srgb_blit,
+            ui,
+            debug_draw,
+            debug_renderer,
+            last_frame: Instant::now(),
         }
     }
 
@@ -91,24 +311,78 @@ impl GraphicsContext {
         let render_texture = surface
             .get_current_texture()
             .expect("failed to acquire next swapchain texture");
-        let render_texture_view = render_texture.texture.create_view(&Default::default());
+        let render_texture_view =
+            render_texture
+                .texture
+                .create_view(&wgpu::TextureViewDescriptor {
+                    format: Some(self.view_format),
+                    ..Default::default()
+                });
         let mut encoder = self
             .device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor {
                 label: Some("command encoder"),
             });
 
+        let renderable = &mut self.renderable;
+        let queue = &self.queue;
+        let post_process = &mut self.post_process;
+        let hdr = &mut self.hdr;
+        let debug_draw = &mut self.debug_draw;
+        self.ui.run(|ctx| {
+            renderable.ui(ctx, queue);
+            rendering_ui(ctx, post_process, hdr.as_mut(), debug_draw);
+        });
+        // long stalls (dragging the window, a breakpoint) shouldn't make
+        // everything jump ahead
+        let now = Instant::now();
+        let delta_time = (now - self.last_frame).as_secs_f32().min(0.1);
+        self.last_frame = now;
+        self.renderable.update(delta_time, &mut self.debug_draw);
+
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("compute pass"),
+                timestamp_writes: None,
+            });
+            self.renderable.compute(&self.queue, &mut compute_pass);
+        }
+
+        let frame_view = match &self.srgb_blit {
+            Some(srgb_blit) => srgb_blit.target().color_view(),
+            None => &render_texture_view,
+        };
+        let post_process = self.post_process.is_active();
+        let ldr_view = if post_process {
+            self.scene_target.color_view()
+        } else {
+            frame_view
+        };
+        let scene_view = match &self.hdr {
+            Some(hdr) => hdr.target().color_view(),
+            None => ldr_view,
+        };
+
         let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label: Some("render pass"),
             color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view: &render_texture_view,
+                view: scene_view,
                 resolve_target: None,
                 ops: wgpu::Operations {
                     load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
                     store: wgpu::StoreOp::Store,
                 },
             })],
-            depth_stencil_attachment: None,
+            depth_stencil_attachment: self.depth.as_ref().map(|depth| {
+                wgpu::RenderPassDepthStencilAttachment {
+                    view: depth.view(),
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: wgpu::StoreOp::Discard,
+                    }),
+                    stencil_ops: None,
+                }
+            }),
             timestamp_writes: None,
             occlusion_query_set: None,
         });
@@ -116,19 +390,117 @@ impl GraphicsContext {
         self.renderable.render(&mut render_pass);
         drop(render_pass);
 
+        if let Some(hdr) = &self.hdr {
+            hdr.run(&self.device, &self.queue, &mut encoder, ldr_view);
+        }
+
+        if post_process {
+            self.post_process.run(
+                &self.device,
+                &self.queue,
+                &mut encoder,
+                self.scene_target.color_view(),
+                frame_view,
+            );
+        }
+
+        self.debug_renderer.render(
+            &self.device,
+            &self.queue,
+            &mut encoder,
+            frame_view,
+            &mut self.debug_draw,
+        );
+
+        if let Some(srgb_blit) = &self.srgb_blit {
+            srgb_blit.run(&mut encoder, &render_texture_view);
+        }
+
+        self.ui.render(
+            &self.device,
+            &self.queue,
+            &mut encoder,
+            &render_texture_view,
+            [self.surface_config.width, self.surface_config.height],
+        );
+
         self.queue.submit(Some(encoder.finish()));
         render_texture.present();
     }
 
+    pub fn prepare(&mut self) -> bool {
+        self.renderable.prepare(&self.device, &self.queue)
+    }
+
+    // returns true when the ui overlay used the event
+    pub fn handle_ui_event(&mut self, event: &WindowEvent) -> bool {
+        self.ui.handle_event(event)
+    }
+
     pub fn handle_input(&mut self, key_event: KeyEvent) {
+        // f1 has to bring back a hidden ui, so it can't be a control inside it.
+        // the sandbox sees the key as well
+        if key_event.physical_key == PhysicalKey::Code(KeyCode::F1)
+            && key_event.state == ElementState::Pressed
+        {
+            self.ui.visible = !self.ui.visible;
+        }
+
         self.renderable.handle_input(key_event, &self.queue);
     }
 
+    pub fn handle_cursor_moved(&mut self, position: PhysicalPosition<f64>) {
+        let size = Vec2::new(
+            self.surface_config.width as f32,
+            self.surface_config.height as f32,
+        );
+        let uv = Vec2::new(position.x as f32, position.y as f32) / size;
+        let ndc = Vec2::new(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0);
+
+        self.renderable
+            .handle_mouse(MouseEvent::Moved(ndc), &self.queue);
+    }
+
+    pub fn handle_mouse_input(&mut self, button: MouseButton, state: ElementState) {
+        self.renderable
+            .handle_mouse(MouseEvent::Button(button, state), &self.queue);
+    }
+
+    pub fn handle_mouse_wheel(&mut self, delta: MouseScrollDelta) {
+        // touchpads report pixels, roughly 40 of them make up a line
+        let lines = match delta {
+            MouseScrollDelta::LineDelta(_, y) => y,
+            MouseScrollDelta::PixelDelta(position) => position.y as f32 / 40.0,
+        };
+
+        self.renderable
+            .handle_mouse(MouseEvent::Wheel(lines), &self.queue);
+    }
+
+    pub fn handle_dropped_file(&mut self, path: &Path) {
+        self.renderable.handle_dropped_file(path, &self.queue);
+    }
+
     pub fn resize(&mut self, size: PhysicalSize<u32>) {
         let surface = self.surface.as_ref().unwrap();
         self.surface_config.width = size.width.max(1);
         self.surface_config.height = size.height.max(1);
         surface.configure(&self.device, &self.surface_config);
+
+        self.scene_target.resize(&self.device, size);
+        self.post_process.resize(&self.device, size);
+        if let Some(hdr) = &mut self.hdr {
+            hdr.resize(&self.device, size);
+        }
+        if let Some(depth) = &mut self.depth {
+            depth.resize(&self.device, size);
+        }
+        if let Some(srgb_blit) = &mut self.srgb_blit {
+            srgb_blit.resize(&self.device, size);
+        }
+        self.debug_draw.resize(size);
+        self.debug_renderer.resize(size, &self.queue);
+        self.renderable.resize(size, &self.queue);
     }
 
     #[cfg(not(target_os = "android"))]
@@ -137,17 +509,11 @@ impl GraphicsContext {
     #[cfg(target_os = "android")]
     pub fn resume(&mut self, window: Arc<Window>) {
         let surface = self.instance.create_surface(window.clone()).unwrap();
-        let size = window.inner_size();
-        let config = wgpu::SurfaceConfiguration {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            format: self.view_format,
-            width: size.width.max(1),
-            height: size.height.max(1),
-            present_mode: wgpu::PresentMode::Fifo,
-            desired_maximum_frame_latency: 2,
-            alpha_mode: wgpu::CompositeAlphaMode::Auto,
-            view_formats: vec![],
-        };
+        let config = surface_config(
+            self.surface_config.format,
+            self.view_format,
+            window.inner_size(),
+        );
 
         surface.configure(&self.device, &config);
         self.surface = Some(surface);
@@ -0,0 +1,304 @@
+use bytemuck::{Pod, Zeroable};
+use winit::dpi::PhysicalSize;
+
+use super::HDR_FORMAT;
+use crate::postprocess::fullscreen_shader;
+
+const MAX_LEVELS: u32 = 6;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+struct Params {
+    texel_size: [f32; 2],
+    filter_radius: f32,
+    _padding: f32,
+}
+
+// a half resolution mip chain. the scene is filtered down level by level,
+// then every level is blurred back up and added onto the one above, which
+// leaves a wide, energy preserving glow in level 0
+pub struct Bloom {
+    texture: wgpu::Texture,
+    views: Vec<wgpu::TextureView>,
+    // one per downsample and one per upsample pass
+    params: Vec<wgpu::Buffer>,
+    downsample_first: wgpu::RenderPipeline,
+    downsample: wgpu::RenderPipeline,
+    upsample: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    pub filter_radius: f32,
+}
+
+impl Bloom {
+    pub fn new(device: &wgpu::Device, size: PhysicalSize<u32>) -> Self {
+        let shader = fullscreen_shader(device, "bloom shader", include_str!("bloom.wgsl"));
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("bloom bind group layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("bloom pipeline layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = |entry_point, blend| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("bloom pipeline"),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: Some("vs_main"),
+                    buffers: &[],
+                    compilation_options: Default::default(),
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: Some(entry_point),
+                    compilation_options: Default::default(),
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: HDR_FORMAT,
+                        blend,
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                primitive: wgpu::PrimitiveState::default(),
+                multisample: Default::default(),
+                depth_stencil: None,
+                multiview: None,
+                cache: None,
+            })
+        };
+
+        let additive = wgpu::BlendComponent {
+            src_factor: wgpu::BlendFactor::One,
+            dst_factor: wgpu::BlendFactor::One,
+            operation: wgpu::BlendOperation::Add,
+        };
+
+        let downsample_first = pipeline("fs_downsample_first", None);
+        let downsample = pipeline("fs_downsample", None);
+        let upsample = pipeline(
+            "fs_upsample",
+            Some(wgpu::BlendState {
+                color: additive,
+                alpha: additive,
+            }),
+        );
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("bloom sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let params = (0..MAX_LEVELS * 2)
+            .map(|_| {
+                device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("bloom params"),
+                    size: size_of::<Params>() as _,
+                    usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                })
+            })
+            .collect();
+
+        let (texture, views) = Self::create_views(device, size);
+
+        Self {
+            texture,
+            views,
+            params,
+            downsample_first,
+            downsample,
+            upsample,
+            bind_group_layout,
+            sampler,
+            filter_radius: 0.005,
+        }
+    }
+
+    fn create_views(
+        device: &wgpu::Device,
+        size: PhysicalSize<u32>,
+    ) -> (wgpu::Texture, Vec<wgpu::TextureView>) {
+        let (width, height) = ((size.width / 2).max(1), (size.height / 2).max(1));
+        let levels = crate::texture::mipmap::mip_level_count(width, height).min(MAX_LEVELS);
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("bloom texture"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: levels,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: HDR_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+
+        let views = (0..levels)
+            .map(|level| {
+                texture.create_view(&wgpu::TextureViewDescriptor {
+                    label: Some("bloom view"),
+                    base_mip_level: level,
+                    mip_level_count: Some(1),
+                    ..Default::default()
+                })
+            })
+            .collect();
+
+        (texture, views)
+    }
+
+    pub fn resize(&mut self, device: &wgpu::Device, size: PhysicalSize<u32>) {
+        (self.texture, self.views) = Self::create_views(device, size);
+    }
+
+    // the finished glow, at half the scene resolution
+    pub fn view(&self) -> &wgpu::TextureView {
+        &self.views[0]
+    }
+
+    pub fn run(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        scene: &wgpu::TextureView,
+        scene_size: PhysicalSize<u32>,
+    ) {
+        let level_size = |level: u32| {
+            let width = (self.texture.width() >> level).max(1);
+            let height = (self.texture.height() >> level).max(1);
+            [1.0 / width as f32, 1.0 / height as f32]
+        };
+
+        let mut pass = 0;
+        let mut draw = |pipeline: &wgpu::RenderPipeline,
+                        source: &wgpu::TextureView,
+                        texel_size: [f32; 2],
+                        target: &wgpu::TextureView,
+                        load: wgpu::LoadOp<wgpu::Color>| {
+            let params = &self.params[pass];
+            pass += 1;
+
+            queue.write_buffer(
+                params,
+                0,
+                bytemuck::bytes_of(&Params {
+                    texel_size,
+                    filter_radius: self.filter_radius,
+                    _padding: 0.0,
+                }),
+            );
+
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("bloom bind group"),
+                layout: &self.bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(source),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&self.sampler),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: params.as_entire_binding(),
+                    },
+                ],
+            });
+
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("bloom pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: target,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load,
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            render_pass.set_pipeline(pipeline);
+            render_pass.set_bind_group(0, &bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        };
+
+        let clear = wgpu::LoadOp::Clear(wgpu::Color::BLACK);
+        let scene_texel = [
+            1.0 / scene_size.width.max(1) as f32,
+            1.0 / scene_size.height.max(1) as f32,
+        ];
+
+        draw(
+            &self.downsample_first,
+            scene,
+            scene_texel,
+            &self.views[0],
+            clear,
+        );
+        for level in 1..self.views.len() {
+            let source = &self.views[level - 1];
+            draw(
+                &self.downsample,
+                source,
+                level_size(level as u32 - 1),
+                &self.views[level],
+                clear,
+            );
+        }
+
+        for level in (1..self.views.len()).rev() {
+            let source = &self.views[level];
+            draw(
+                &self.upsample,
+                source,
+                level_size(level as u32),
+                &self.views[level - 1],
+                wgpu::LoadOp::Load,
+            );
+        }
+    }
+}
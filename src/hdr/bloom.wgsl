@@ -0,0 +1,103 @@
+// downsample / upsample chain from "next generation post processing in call
+// of duty: advanced warfare"
+
+struct Params {
+    // one texel of the source in uv units
+    texel_size: vec2<f32>,
+    // upsample tent radius in uv units
+    filter_radius: f32,
+}
+
+@group(0) @binding(0)
+var source: texture_2d<f32>;
+
+@group(0) @binding(1)
+var source_sampler: sampler;
+
+@group(0) @binding(2)
+var<uniform> params: Params;
+
+fn sample_at(uv: vec2<f32>, offset: vec2<f32>) -> vec3<f32> {
+    return textureSample(source, source_sampler, uv + offset * params.texel_size).rgb;
+}
+
+fn luma(color: vec3<f32>) -> f32 {
+    return dot(color, vec3f(0.2126, 0.7152, 0.0722));
+}
+
+// 13 taps around the center, read as five overlapping 2x2 boxes
+struct Taps {
+    boxes: array<vec3<f32>, 5>,
+}
+
+fn taps(uv: vec2<f32>) -> Taps {
+    let a = sample_at(uv, vec2f(-2.0, -2.0));
+    let b = sample_at(uv, vec2f(0.0, -2.0));
+    let c = sample_at(uv, vec2f(2.0, -2.0));
+    let d = sample_at(uv, vec2f(-2.0, 0.0));
+    let e = sample_at(uv, vec2f(0.0, 0.0));
+    let f = sample_at(uv, vec2f(2.0, 0.0));
+    let g = sample_at(uv, vec2f(-2.0, 2.0));
+    let h = sample_at(uv, vec2f(0.0, 2.0));
+    let i = sample_at(uv, vec2f(2.0, 2.0));
+    let j = sample_at(uv, vec2f(-1.0, -1.0));
+    let k = sample_at(uv, vec2f(1.0, -1.0));
+    let l = sample_at(uv, vec2f(-1.0, 1.0));
+    let m = sample_at(uv, vec2f(1.0, 1.0));
+
+    var out: Taps;
+    out.boxes = array(
+        (a + b + d + e) * 0.25,
+        (b + c + e + f) * 0.25,
+        (d + e + g + h) * 0.25,
+        (e + f + h + i) * 0.25,
+        (j + k + l + m) * 0.25,
+    );
+
+    return out;
+}
+
+@fragment
+fn fs_downsample(in: VertexOutput) -> @location(0) vec4<f32> {
+    let t = taps(in.uv);
+    let color = (t.boxes[0] + t.boxes[1] + t.boxes[2] + t.boxes[3]) * 0.125 + t.boxes[4] * 0.5;
+
+    return vec4f(color, 1.0);
+}
+
+// the first downsample weighs every box by its inverse brightness (karis
+// average), so single very bright pixels don't turn into flickering blobs
+@fragment
+fn fs_downsample_first(in: VertexOutput) -> @location(0) vec4<f32> {
+    var boxes = taps(in.uv).boxes;
+    var weights = array(0.125, 0.125, 0.125, 0.125, 0.5);
+
+    var color = vec3f(0.0);
+    var total = 0.0;
+    for (var i = 0; i < 5; i++) {
+        let w = weights[i] / (1.0 + luma(boxes[i]));
+        color += boxes[i] * w;
+        total += w;
+    }
+
+    // nan and inf from the scene would spread over the whole screen
+    return vec4f(max(color / total, vec3f(0.0)), 1.0);
+}
+
+// 3x3 tent, added onto the next larger level by the blend state
+@fragment
+fn fs_upsample(in: VertexOutput) -> @location(0) vec4<f32> {
+    let r = params.filter_radius;
+
+    var color = textureSample(source, source_sampler, in.uv).rgb * 4.0;
+    color += textureSample(source, source_sampler, in.uv + vec2f(-r, 0.0)).rgb * 2.0;
+    color += textureSample(source, source_sampler, in.uv + vec2f(r, 0.0)).rgb * 2.0;
+    color += textureSample(source, source_sampler, in.uv + vec2f(0.0, -r)).rgb * 2.0;
+    color += textureSample(source, source_sampler, in.uv + vec2f(0.0, r)).rgb * 2.0;
+    color += textureSample(source, source_sampler, in.uv + vec2f(-r, -r)).rgb;
+    color += textureSample(source, source_sampler, in.uv + vec2f(r, -r)).rgb;
+    color += textureSample(source, source_sampler, in.uv + vec2f(-r, r)).rgb;
+    color += textureSample(source, source_sampler, in.uv + vec2f(r, r)).rgb;
+
+    return vec4f(color / 16.0, 1.0);
+}
@@ -0,0 +1,100 @@
+struct Params {
+    // linear multiplier
+    exposure: f32,
+    bloom_strength: f32,
+    // 0 reinhard, 1 aces, 2 agx
+    tonemapper: u32,
+    // set when the output view doesn't encode srgb itself
+    encode_srgb: u32,
+}
+
+@group(0) @binding(0)
+var scene: texture_2d<f32>;
+
+@group(0) @binding(1)
+var bloom: texture_2d<f32>;
+
+@group(0) @binding(2)
+var linear_sampler: sampler;
+
+@group(0) @binding(3)
+var<uniform> params: Params;
+
+fn reinhard(color: vec3<f32>) -> vec3<f32> {
+    return color / (1.0 + color);
+}
+
+// krzysztof narkowicz's fit of the aces reference rendering transform
+fn aces(color: vec3<f32>) -> vec3<f32> {
+    let numerator = color * (2.51 * color + 0.03);
+    let denominator = color * (2.43 * color + 0.59) + 0.14;
+
+    return clamp(numerator / denominator, vec3f(0.0), vec3f(1.0));
+}
+
+// minimal agx by benjamin wrensch, polynomial fit of the default contrast
+fn agx_contrast(x: vec3<f32>) -> vec3<f32> {
+    let x2 = x * x;
+    let x4 = x2 * x2;
+
+    return 15.5 * x4 * x2 - 40.14 * x4 * x + 31.96 * x4 - 6.868 * x2 * x + 0.4298 * x2 + 0.1191 * x - 0.00232;
+}
+
+fn agx(color: vec3<f32>) -> vec3<f32> {
+    let inset = mat3x3f(
+        0.842479062253094, 0.0423282422610123, 0.0423756549057051,
+        0.0784335999999992, 0.878468636469772, 0.0784336,
+        0.0792237451477643, 0.0791661274605434, 0.879142973793104,
+    );
+    let outset = mat3x3f(
+        1.19687900512017, -0.0528968517574562, -0.0529716355144438,
+        -0.0980208811401368, 1.15190312990417, -0.0980434501171241,
+        -0.0990297440797205, -0.0989611768448433, 1.15107367264116,
+    );
+    let min_ev = -12.47393;
+    let max_ev = 4.026069;
+
+    var x = inset * color;
+    x = clamp(log2(max(x, vec3f(1e-10))), vec3f(min_ev), vec3f(max_ev));
+    x = (x - min_ev) / (max_ev - min_ev);
+    x = agx_contrast(x);
+    x = outset * x;
+
+    // the curve produces display encoded values, go back to linear so the
+    // output view can encode them
+    return pow(max(x, vec3f(0.0)), vec3f(2.2));
+}
+
+fn srgb_encode(color: vec3<f32>) -> vec3<f32> {
+    let low = color * 12.92;
+    let high = 1.055 * pow(color, vec3f(1.0 / 2.4)) - 0.055;
+
+    return select(high, low, color <= vec3f(0.0031308));
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let hdr = textureSample(scene, linear_sampler, in.uv).rgb;
+    let glow = textureSample(bloom, linear_sampler, in.uv).rgb;
+
+    let exposed = mix(hdr, glow, params.bloom_strength) * params.exposure;
+
+    var color: vec3<f32>;
+    switch params.tonemapper {
+        case 1u: {
+            color = aces(exposed);
+        }
+        case 2u: {
+            color = agx(exposed);
+        }
+        default: {
+            color = reinhard(exposed);
+        }
+    }
+
+    if params.encode_srgb == 1u {
+        color = srgb_encode(color);
+    }
+
+    return vec4f(color, 1.0);
+}
@@ -2,8 +2,23 @@ use winit::event_loop::EventLoop;
 // use winit::platform::android::activity::AndroidApp;
 
 mod app;
+pub mod assets;
+pub mod debug_draw;
 pub mod graphics;
+pub mod hdr;
+pub mod model;
+pub mod polyline;
+pub mod postprocess;
+pub mod render_target;
 pub mod sandbox;
+pub mod sprite;
+pub mod tessellation;
+#[cfg(test)]
+mod testing;
+pub mod text;
+pub mod texture;
+pub mod tilemap;
+pub mod ui;
 pub mod vertices;
 
 fn main() {
@@ -0,0 +1,432 @@
+use base64::Engine;
+use glam::Mat4;
+use gltf::{
+    buffer, image,
+    mesh::Mode,
+    texture::{MagFilter, MinFilter, Sampler, WrappingMode},
+    Document, Gltf,
+};
+
+use super::{relative_to, Material, Mesh, MeshVertex, Model, Node, Primitive, ReadFile};
+use crate::texture::decode::DecodedImage;
+
+// `path` is where `bytes` were read from, a .gltf or a .glb. buffers and
+// images in files of their own are read through `read` relative to it
+pub fn parse(bytes: &[u8], path: &str, read: ReadFile) -> Result<Model, String> {
+    let Gltf { document, mut blob } =
+        Gltf::from_slice(bytes).map_err(|e| format!("invalid gltf {path}: {e}"))?;
+
+    let buffers = document
+        .buffers()
+        .map(|buffer| {
+            let data = match buffer.source() {
+                buffer::Source::Bin => blob
+                    .take()
+                    .ok_or_else(|| format!("{path} has no binary chunk"))?,
+                buffer::Source::Uri(uri) => load_uri(uri, path, read)?,
+            };
+
+            if data.len() < buffer.length() {
+                return Err(format!(
+                    "buffer {} of {path} is shorter than it says",
+                    buffer.index()
+                ));
+            }
+            Ok(data)
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let images = document
+        .images()
+        .map(|image| {
+            let bytes = match image.source() {
+                image::Source::View { view, .. } => buffers[view.buffer().index()]
+                    .get(view.offset()..view.offset() + view.length())
+                    .ok_or_else(|| format!("image {} is out of its buffer", image.index()))?
+                    .to_vec(),
+                image::Source::Uri { uri, .. } => load_uri(uri, path, read)?,
+            };
+
+            DecodedImage::decode(&bytes).map_err(|e| format!("image {}: {e}", image.index()))
+        })
+        .collect::<Result<_, String>>()?;
+
+    let materials = document
+        .materials()
+        .map(|material| {
+            let pbr = material.pbr_metallic_roughness();
+            let texture = pbr.base_color_texture().map(|info| info.texture());
+
+            Material {
+                name: material.name().unwrap_or_default().to_owned(),
+                base_color: pbr.base_color_factor(),
+                base_color_texture: texture.as_ref().map(|texture| texture.source().index()),
+                sampler: texture.map_or_else(
+                    || Material::default().sampler,
+                    |texture| sampler(&texture.sampler()),
+                ),
+            }
+        })
+        .collect();
+
+    let meshes = document
+        .meshes()
+        .map(|mesh| {
+            let name = mesh.name().unwrap_or_default().to_owned();
+            let primitives = mesh
+                .primitives()
+                .filter_map(|primitive| read_primitive(&primitive, &buffers).transpose())
+                .collect::<Result<_, String>>()
+                .map_err(|e| format!("mesh {name:?}: {e}"))?;
+
+            Ok(Mesh { name, primitives })
+        })
+        .collect::<Result<_, String>>()?;
+
+    let nodes = document
+        .nodes()
+        .map(|node| Node {
+            name: node.name().unwrap_or_default().to_owned(),
+            transform: Mat4::from_cols_array_2d(&node.transform().matrix()),
+            mesh: node.mesh().map(|mesh| mesh.index()),
+            children: node.children().map(|child| child.index()).collect(),
+        })
+        .collect();
+
+    Model {
+        meshes,
+        materials,
+        images,
+        nodes,
+        roots: roots(&document),
+    }
+    .validate()
+}
+
+// the nodes of the default scene, or of the first one when there's no
+// default. files without scenes are libraries, all of their top level nodes
+// are shown
+fn roots(document: &Document) -> Vec<usize> {
+    if let Some(scene) = document
+        .default_scene()
+        .or_else(|| document.scenes().next())
+    {
+        return scene.nodes().map(|node| node.index()).collect();
+    }
+
+    let mut has_parent = vec![false; document.nodes().len()];
+    for child in document.nodes().flat_map(|node| node.children()) {
+        has_parent[child.index()] = true;
+    }
+
+    (0..has_parent.len())
+        .filter(|&node| !has_parent[node])
+        .collect()
+}
+
+// `Ok(None)` for the points and lines this doesn't draw
+fn read_primitive(
+    primitive: &gltf::Primitive,
+    buffers: &[Vec<u8>],
+) -> Result<Option<Primitive>, String> {
+    let mode = primitive.mode();
+    if !matches!(
+        mode,
+        Mode::Triangles | Mode::TriangleStrip | Mode::TriangleFan
+    ) {
+        log::warn!("skipping a primitive drawn as {mode:?}");
+        return Ok(None);
+    }
+
+    let reader = primitive.reader(|buffer| buffers.get(buffer.index()).map(Vec::as_slice));
+
+    let mut vertices: Vec<_> = reader
+        .read_positions()
+        .ok_or("a primitive has no positions")?
+        .map(|position| MeshVertex {
+            position,
+            ..Default::default()
+        })
+        .collect();
+
+    let normals = reader.read_normals();
+    let has_normals = normals.is_some();
+    for (vertex, normal) in vertices.iter_mut().zip(normals.into_iter().flatten()) {
+        vertex.normal = normal;
+    }
+    let tangents = reader.read_tangents();
+    let has_tangents = tangents.is_some();
+    for (vertex, tangent) in vertices.iter_mut().zip(tangents.into_iter().flatten()) {
+        vertex.tangent = tangent;
+    }
+    let uvs = reader.read_tex_coords(0).map(|uvs| uvs.into_f32());
+    for (vertex, uv) in vertices.iter_mut().zip(uvs.into_iter().flatten()) {
+        vertex.uv = uv;
+    }
+
+    let indices = match reader.read_indices() {
+        Some(indices) => indices.into_u32().collect(),
+        None => (0..vertices.len() as u32).collect(),
+    };
+
+    let mut primitive = Primitive {
+        vertices,
+        indices: triangle_list(mode, indices),
+        material: primitive.material().index(),
+    };
+
+    // the spec asks for flat shading when normals are left out
+    if !has_normals {
+        primitive.flat_normals();
+    }
+    if !has_normals || !has_tangents {
+        primitive.generate_tangents();
+    }
+
+    Ok(Some(primitive))
+}
+
+// strips and fans as a plain list, every triangle wound the same way
+fn triangle_list(mode: Mode, indices: Vec<u32>) -> Vec<u32> {
+    let count = indices.len().saturating_sub(2);
+
+    match mode {
+        Mode::TriangleStrip => (0..count)
+            .flat_map(|i| {
+                let [a, b, c] = [i, i + 1, i + 2].map(|i| indices[i]);
+                if i % 2 == 0 {
+                    [a, b, c]
+                } else {
+                    [a, c, b]
+                }
+            })
+            .collect(),
+        Mode::TriangleFan => (0..count)
+            .flat_map(|i| [indices[i + 1], indices[i + 2], indices[0]])
+            .collect(),
+        _ => indices,
+    }
+}
+
+fn sampler(sampler: &Sampler) -> wgpu::SamplerDescriptor<'static> {
+    use wgpu::FilterMode::{Linear, Nearest};
+
+    let address_mode = |mode| match mode {
+        WrappingMode::ClampToEdge => wgpu::AddressMode::ClampToEdge,
+        WrappingMode::MirroredRepeat => wgpu::AddressMode::MirrorRepeat,
+        WrappingMode::Repeat => wgpu::AddressMode::Repeat,
+    };
+
+    // the filters without a mipmap part only ever read the largest level
+    let (min_filter, mipmap_filter, lod_max_clamp) = match sampler.min_filter() {
+        Some(MinFilter::Nearest) => (Nearest, Nearest, 0.0),
+        Some(MinFilter::Linear) => (Linear, Nearest, 0.0),
+        Some(MinFilter::NearestMipmapNearest) => (Nearest, Nearest, 32.0),
+        Some(MinFilter::LinearMipmapNearest) => (Linear, Nearest, 32.0),
+        Some(MinFilter::NearestMipmapLinear) => (Nearest, Linear, 32.0),
+        Some(MinFilter::LinearMipmapLinear) | None => (Linear, Linear, 32.0),
+    };
+
+    wgpu::SamplerDescriptor {
+        address_mode_u: address_mode(sampler.wrap_s()),
+        address_mode_v: address_mode(sampler.wrap_t()),
+        mag_filter: match sampler.mag_filter() {
+            Some(MagFilter::Nearest) => Nearest,
+            _ => Linear,
+        },
+        min_filter,
+        mipmap_filter,
+        lod_max_clamp,
+        ..Default::default()
+    }
+}
+
+// data uris carry the bytes themselves, anything else names a file
+fn load_uri(uri: &str, path: &str, read: ReadFile) -> Result<Vec<u8>, String> {
+    if let Some(data) = uri.strip_prefix("data:") {
+        let (_, data) = data
+            .split_once(";base64,")
+            .ok_or_else(|| format!("{path} has a data uri that isn't base64"))?;
+
+        return base64::engine::general_purpose::STANDARD
+            .decode(data)
+            .map_err(|e| format!("invalid base64 in {path}: {e}"));
+    }
+
+    read(&relative_to(path, &percent_decode(uri)))
+}
+
+// spaces in file names come as %20
+fn percent_decode(uri: &str) -> String {
+    let bytes = uri.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 1..i + 3)
+            .filter(|_| bytes[i] == b'%')
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a quad as positions, uvs and a triangle strip of indices
+    fn quad_buffer() -> Vec<u8> {
+        let positions: [[f32; 3]; 4] = [
+            [0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [1.0, 1.0, 0.0],
+        ];
+        let uvs: [[f32; 2]; 4] = [[0.0, 1.0], [1.0, 1.0], [0.0, 0.0], [1.0, 0.0]];
+        let indices: [u16; 4] = [0, 1, 2, 3];
+
+        [
+            bytemuck::cast_slice(&positions),
+            bytemuck::cast_slice(&uvs),
+            bytemuck::cast_slice(&indices),
+        ]
+        .concat()
+    }
+
+    fn document(buffer: &str) -> String {
+        format!(
+            r#"{{
+            "asset": {{ "version": "2.0" }},
+            "scene": 0,
+            "scenes": [{{ "nodes": [0] }}],
+            "nodes": [
+                {{ "name": "root", "translation": [0, 2, 0], "children": [1] }},
+                {{ "name": "quad", "mesh": 0, "scale": [2, 2, 2] }}
+            ],
+            "meshes": [{{ "name": "quad", "primitives": [{{
+                "attributes": {{ "POSITION": 0, "TEXCOORD_0": 1 }},
+                "indices": 2,
+                "mode": 5,
+                "material": 0
+            }}] }}],
+            "materials": [{{ "name": "red", "pbrMetallicRoughness": {{
+                "baseColorFactor": [1, 0, 0, 1]
+            }} }}],
+            "buffers": [{buffer}],
+            "bufferViews": [
+                {{ "buffer": 0, "byteOffset": 0, "byteLength": 48 }},
+                {{ "buffer": 0, "byteOffset": 48, "byteLength": 32 }},
+                {{ "buffer": 0, "byteOffset": 80, "byteLength": 8 }}
+            ],
+            "accessors": [
+                {{ "bufferView": 0, "componentType": 5126, "count": 4, "type": "VEC3",
+                   "min": [0, 0, 0], "max": [1, 1, 0] }},
+                {{ "bufferView": 1, "componentType": 5126, "count": 4, "type": "VEC2" }},
+                {{ "bufferView": 2, "componentType": 5123, "count": 4, "type": "SCALAR" }}
+            ]
+        }}"#
+        )
+    }
+
+    fn no_files(path: &str) -> Result<Vec<u8>, String> {
+        Err(format!("{path} not found"))
+    }
+
+    fn check_quad(model: &Model) {
+        let primitive = &model.meshes[0].primitives[0];
+        // the strip became two triangles, flat shaded as there are no normals
+        assert_eq!(primitive.indices.len(), 6);
+        assert_eq!(primitive.vertices.len(), 6);
+        assert!(primitive
+            .vertices
+            .iter()
+            .all(|v| v.normal == [0.0, 0.0, 1.0] && v.tangent == [1.0, 0.0, 0.0, -1.0]));
+
+        assert_eq!(model.materials[0].base_color, [1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(primitive.material, Some(0));
+
+        let bounds = model.bounds().unwrap();
+        assert_eq!(
+            bounds,
+            (
+                glam::Vec3::new(0.0, 2.0, 0.0),
+                glam::Vec3::new(2.0, 4.0, 0.0)
+            )
+        );
+    }
+
+    #[test]
+    fn reads_separate_and_embedded_buffers() {
+        let separate = document(r#"{ "uri": "mesh%20data.bin", "byteLength": 88 }"#);
+        let read = |path: &str| match path {
+            "models/mesh data.bin" => Ok(quad_buffer()),
+            _ => no_files(path),
+        };
+        check_quad(&parse(separate.as_bytes(), "models/quad.gltf", &read).unwrap());
+
+        let data = base64::engine::general_purpose::STANDARD.encode(quad_buffer());
+        let embedded = document(&format!(
+            r#"{{ "uri": "data:application/octet-stream;base64,{data}", "byteLength": 88 }}"#
+        ));
+        check_quad(&parse(embedded.as_bytes(), "quad.gltf", &no_files).unwrap());
+    }
+
+    #[test]
+    fn reads_binary_gltf() {
+        let mut json = document(r#"{ "byteLength": 88 }"#).into_bytes();
+        json.resize(json.len().next_multiple_of(4), b' ');
+        let bin = quad_buffer();
+
+        let mut glb = Vec::new();
+        glb.extend(b"glTF");
+        glb.extend(2u32.to_le_bytes());
+        glb.extend((12 + 8 + json.len() as u32 + 8 + bin.len() as u32).to_le_bytes());
+        glb.extend((json.len() as u32).to_le_bytes());
+        glb.extend(b"JSON");
+        glb.extend(&json);
+        glb.extend((bin.len() as u32).to_le_bytes());
+        glb.extend(b"BIN\0");
+        glb.extend(&bin);
+
+        check_quad(&parse(&glb, "quad.glb", &no_files).unwrap());
+    }
+
+    #[test]
+    fn reports_what_is_missing() {
+        let separate = document(r#"{ "uri": "quad.bin", "byteLength": 88 }"#);
+        assert!(parse(separate.as_bytes(), "quad.gltf", &no_files).is_err());
+
+        let short = |_: &str| -> Result<Vec<u8>, String> { Ok(vec![0; 40]) };
+        assert!(parse(separate.as_bytes(), "quad.gltf", &short).is_err());
+
+        assert!(parse(b"{ not json", "quad.gltf", &no_files).is_err());
+        assert!(parse(b"glTF\x02\0\0\0", "quad.glb", &no_files).is_err());
+    }
+
+    #[test]
+    fn strips_and_fans_become_lists() {
+        assert_eq!(
+            triangle_list(Mode::TriangleStrip, vec![0, 1, 2, 3, 4]),
+            [0, 1, 2, 1, 3, 2, 2, 3, 4]
+        );
+        assert_eq!(
+            triangle_list(Mode::TriangleFan, vec![0, 1, 2, 3]),
+            [1, 2, 0, 2, 3, 0]
+        );
+        assert!(triangle_list(Mode::TriangleStrip, vec![0, 1]).is_empty());
+        assert_eq!(percent_decode("a%20b%2x%"), "a b%2x%");
+    }
+}
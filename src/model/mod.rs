@@ -0,0 +1,442 @@
+use std::path::Path;
+
+use glam::{Mat4, Vec2, Vec3};
+
+use crate::{assets::AssetSource, texture::decode::DecodedImage, vertices::Vertex};
+
+pub mod gltf;
+pub mod obj;
+pub mod renderer;
+
+pub use renderer::ModelRenderer;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct MeshVertex {
+    pub position: [f32; 3],
+    pub normal: [f32; 3],
+    pub uv: [f32; 2],
+    // xyz along increasing u, w is the sign of the bitangent
+    pub tangent: [f32; 4],
+}
+
+impl MeshVertex {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 4] = wgpu::vertex_attr_array![
+        0 => Float32x3,
+        1 => Float32x3,
+        2 => Float32x2,
+        3 => Float32x4,
+    ];
+}
+
+impl Vertex for MeshVertex {
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: size_of::<MeshVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+// an indexed triangle list drawn with one material
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Primitive {
+    pub vertices: Vec<MeshVertex>,
+    pub indices: Vec<u32>,
+    pub material: Option<usize>,
+}
+
+impl Primitive {
+    // gives every triangle its own vertices, all facing the way it does
+    pub fn flat_normals(&mut self) {
+        let mut vertices = Vec::with_capacity(self.indices.len());
+
+        for triangle in self.indices.chunks_exact(3) {
+            let corners = [0, 1, 2].map(|i| self.vertices[triangle[i] as usize]);
+            let [a, b, c] = corners.map(|v| Vec3::from(v.position));
+            let normal = (b - a).cross(c - a).normalize_or_zero();
+
+            vertices.extend(corners.map(|v| MeshVertex {
+                normal: normal.into(),
+                ..v
+            }));
+        }
+
+        self.indices = (0..vertices.len() as u32).collect();
+        self.vertices = vertices;
+    }
+
+    // per vertex tangents from the uv layout of the triangles around it,
+    // averaged and made perpendicular to the normal
+    pub fn generate_tangents(&mut self) {
+        let mut tangents = vec![Vec3::ZERO; self.vertices.len()];
+        let mut bitangents = vec![Vec3::ZERO; self.vertices.len()];
+
+        for triangle in self.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| self.vertices[triangle[i] as usize]);
+            let edges = [b, c].map(|v| Vec3::from(v.position) - Vec3::from(a.position));
+            let deltas = [b, c].map(|v| Vec2::from(v.uv) - Vec2::from(a.uv));
+
+            let det = deltas[0].perp_dot(deltas[1]);
+            if det.abs() <= f32::EPSILON {
+                continue;
+            }
+            let tangent = (edges[0] * deltas[1].y - edges[1] * deltas[0].y) / det;
+            let bitangent = (edges[1] * deltas[0].x - edges[0] * deltas[1].x) / det;
+
+            for &i in triangle {
+                tangents[i as usize] += tangent;
+                bitangents[i as usize] += bitangent;
+            }
+        }
+
+        for (vertex, (tangent, bitangent)) in self
+            .vertices
+            .iter_mut()
+            .zip(tangents.iter().zip(bitangents))
+        {
+            let normal = Vec3::from(vertex.normal);
+            let mut t = (*tangent - normal * normal.dot(*tangent)).normalize_or_zero();
+            // without uvs any direction along the surface will do
+            if t == Vec3::ZERO {
+                t = normal.any_orthonormal_vector();
+            }
+            let w = if normal.cross(t).dot(bitangent) < 0.0 {
+                -1.0
+            } else {
+                1.0
+            };
+
+            vertex.tangent = t.extend(w).into();
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Mesh {
+    pub name: String,
+    pub primitives: Vec<Primitive>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Material {
+    pub name: String,
+    // linear, multiplied with the texture
+    pub base_color: [f32; 4],
+    // an index into the model's images
+    pub base_color_texture: Option<usize>,
+    pub sampler: wgpu::SamplerDescriptor<'static>,
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            base_color: [1.0; 4],
+            base_color_texture: None,
+            sampler: wgpu::SamplerDescriptor {
+                address_mode_u: wgpu::AddressMode::Repeat,
+                address_mode_v: wgpu::AddressMode::Repeat,
+                mag_filter: wgpu::FilterMode::Linear,
+                min_filter: wgpu::FilterMode::Linear,
+                mipmap_filter: wgpu::FilterMode::Linear,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    pub name: String,
+    // relative to the parent
+    pub transform: Mat4,
+    pub mesh: Option<usize>,
+    pub children: Vec<usize>,
+}
+
+// meshes placed by a tree of nodes, with everything needed to draw them
+#[derive(Default)]
+pub struct Model {
+    pub meshes: Vec<Mesh>,
+    pub materials: Vec<Material>,
+    pub images: Vec<DecodedImage>,
+    pub nodes: Vec<Node>,
+    // the top of the hierarchy, the nodes hanging off them are drawn too
+    pub roots: Vec<usize>,
+}
+
+impl Model {
+    pub fn load(source: &AssetSource, path: &str) -> Result<Self, String> {
+        Self::load_with(path, &|file| source.read(file))
+    }
+
+    // a file anywhere on disk, e.g. one dropped on the window
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let path = path.to_string_lossy();
+
+        Self::load_with(&path, &|file| {
+            std::fs::read(file).map_err(|e| format!("failed to read {file}: {e}"))
+        })
+    }
+
+    fn load_with(path: &str, read: ReadFile) -> Result<Self, String> {
+        let bytes = read(path)?;
+        let extension = path
+            .rsplit_once('.')
+            .map(|(_, extension)| extension.to_ascii_lowercase());
+
+        match extension.as_deref() {
+            Some("gltf" | "glb") => gltf::parse(&bytes, path, read),
+            Some("obj") => obj::parse(&String::from_utf8_lossy(&bytes), path, read),
+            _ => Err(format!("{path} is not a .gltf, .glb or .obj model")),
+        }
+    }
+
+    // every mesh with its transform in model space, parents before children
+    pub fn instances(&self) -> Vec<(usize, Mat4)> {
+        let mut instances = Vec::new();
+        let mut stack: Vec<_> = self
+            .roots
+            .iter()
+            .rev()
+            .map(|&node| (node, Mat4::IDENTITY))
+            .collect();
+
+        while let Some((index, parent)) = stack.pop() {
+            let node = &self.nodes[index];
+            let transform = parent * node.transform;
+
+            if let Some(mesh) = node.mesh {
+                instances.push((mesh, transform));
+            }
+            stack.extend(node.children.iter().rev().map(|&child| (child, transform)));
+        }
+
+        instances
+    }
+
+    // the box around everything drawn, in model space
+    pub fn bounds(&self) -> Option<(Vec3, Vec3)> {
+        self.instances()
+            .into_iter()
+            .flat_map(|(mesh, transform)| {
+                self.meshes[mesh]
+                    .primitives
+                    .iter()
+                    .flat_map(|primitive| &primitive.vertices)
+                    .map(move |vertex| transform.transform_point3(vertex.position.into()))
+            })
+            .fold(None, |bounds, point| match bounds {
+                Some((min, max)) => Some((point.min(min), point.max(max))),
+                None => Some((point, point)),
+            })
+    }
+
+    // checks the indices between parts, so rendering never has to
+    fn validate(self) -> Result<Self, String> {
+        for mesh in &self.meshes {
+            for primitive in &mesh.primitives {
+                if primitive.indices.len() % 3 != 0 {
+                    return Err(format!("mesh {:?} has a partial triangle", mesh.name));
+                }
+                if primitive
+                    .indices
+                    .iter()
+                    .any(|&i| i as usize >= primitive.vertices.len())
+                {
+                    return Err(format!("mesh {:?} indexes past its vertices", mesh.name));
+                }
+                if primitive
+                    .material
+                    .is_some_and(|m| m >= self.materials.len())
+                {
+                    return Err(format!("mesh {:?} uses a missing material", mesh.name));
+                }
+            }
+        }
+
+        for material in &self.materials {
+            if material
+                .base_color_texture
+                .is_some_and(|image| image >= self.images.len())
+            {
+                return Err(format!("material {:?} uses a missing image", material.name));
+            }
+        }
+
+        let nodes = self.nodes.len();
+        let node_refs = self
+            .nodes
+            .iter()
+            .flat_map(|node| &node.children)
+            .chain(&self.roots);
+        if node_refs.clone().any(|&node| node >= nodes) {
+            return Err("a node refers to a missing one".to_owned());
+        }
+        if self
+            .nodes
+            .iter()
+            .any(|node| node.mesh.is_some_and(|mesh| mesh >= self.meshes.len()))
+        {
+            return Err("a node refers to a missing mesh".to_owned());
+        }
+
+        // a node showing up twice would be drawn twice, or forever in a cycle
+        let mut seen = vec![false; nodes];
+        for &node in node_refs {
+            if std::mem::replace(&mut seen[node], true) {
+                return Err(format!("node {node} has more than one parent"));
+            }
+        }
+
+        Ok(self)
+    }
+}
+
+type ReadFile<'a> = &'a dyn Fn(&str) -> Result<Vec<u8>, String>;
+
+// `path` relative to the directory of `file`. files dropped on windows come
+// with backslashes
+fn relative_to(file: &str, path: &str) -> String {
+    match file.rfind(['/', '\\']) {
+        Some(end) => format!("{}/{path}", &file[..end]),
+        None => path.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vertex(position: [f32; 3], uv: [f32; 2]) -> MeshVertex {
+        MeshVertex {
+            position,
+            normal: [0.0, 0.0, 1.0],
+            uv,
+            ..Default::default()
+        }
+    }
+
+    fn node(transform: Mat4, mesh: Option<usize>, children: Vec<usize>) -> Node {
+        Node {
+            name: String::new(),
+            transform,
+            mesh,
+            children,
+        }
+    }
+
+    #[test]
+    fn flat_normals_split_shared_vertices() {
+        // two triangles folded along the x axis
+        let mut primitive = Primitive {
+            vertices: vec![
+                vertex([0.0, 0.0, 0.0], [0.0; 2]),
+                vertex([1.0, 0.0, 0.0], [0.0; 2]),
+                vertex([0.0, 1.0, 0.0], [0.0; 2]),
+                vertex([0.0, 0.0, 1.0], [0.0; 2]),
+            ],
+            indices: vec![0, 1, 2, 0, 3, 1],
+            material: None,
+        };
+        primitive.flat_normals();
+
+        assert_eq!(primitive.vertices.len(), 6);
+        assert_eq!(primitive.indices, [0, 1, 2, 3, 4, 5]);
+        assert_eq!(primitive.vertices[0].normal, [0.0, 0.0, 1.0]);
+        assert_eq!(primitive.vertices[3].normal, [0.0, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn tangents_follow_u() {
+        let mut primitive = Primitive {
+            vertices: vec![
+                vertex([0.0, 0.0, 0.0], [0.0, 1.0]),
+                vertex([2.0, 0.0, 0.0], [1.0, 1.0]),
+                vertex([0.0, 2.0, 0.0], [0.0, 0.0]),
+            ],
+            indices: vec![0, 1, 2],
+            material: None,
+        };
+        primitive.generate_tangents();
+        assert_eq!(primitive.vertices[0].tangent, [1.0, 0.0, 0.0, -1.0]);
+
+        // mirrored along u, the bitangent flips sides
+        for vertex in &mut primitive.vertices {
+            vertex.uv[1] = 1.0 - vertex.uv[1];
+        }
+        primitive.generate_tangents();
+        assert_eq!(primitive.vertices[2].tangent, [1.0, 0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn instances_walk_the_hierarchy() {
+        let model = Model {
+            meshes: vec![Mesh::default(), Mesh::default()],
+            nodes: vec![
+                node(Mat4::from_translation(Vec3::X), None, vec![1, 2]),
+                node(Mat4::from_scale(Vec3::splat(2.0)), Some(0), vec![3]),
+                node(Mat4::IDENTITY, Some(1), vec![]),
+                node(Mat4::from_translation(Vec3::Y), Some(1), vec![]),
+            ],
+            roots: vec![0],
+            ..Default::default()
+        }
+        .validate()
+        .unwrap();
+
+        let instances = model.instances();
+        let places: Vec<_> = instances
+            .iter()
+            .map(|(mesh, transform)| (*mesh, transform.transform_point3(Vec3::ZERO)))
+            .collect();
+        assert_eq!(
+            places,
+            [(0, Vec3::X), (1, Vec3::new(1.0, 2.0, 0.0)), (1, Vec3::X),]
+        );
+        assert_eq!(
+            instances[1].1.transform_vector3(Vec3::X),
+            Vec3::new(2.0, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn validation_catches_bad_references() {
+        let model = |nodes: Vec<Node>, roots: Vec<usize>| {
+            Model {
+                meshes: vec![Mesh::default()],
+                nodes,
+                roots,
+                ..Default::default()
+            }
+            .validate()
+        };
+        let leaf = || node(Mat4::IDENTITY, Some(0), vec![]);
+
+        assert!(model(vec![leaf()], vec![0]).is_ok());
+        assert!(model(vec![leaf()], vec![1]).is_err());
+        assert!(model(vec![node(Mat4::IDENTITY, Some(1), vec![])], vec![0]).is_err());
+        assert!(model(vec![node(Mat4::IDENTITY, None, vec![0])], vec![0]).is_err());
+        assert!(model(
+            vec![leaf(), node(Mat4::IDENTITY, None, vec![0])],
+            vec![0, 1]
+        )
+        .is_err());
+
+        let mesh = Mesh {
+            name: "broken".to_owned(),
+            primitives: vec![Primitive {
+                vertices: vec![MeshVertex::default(); 3],
+                indices: vec![0, 1, 3],
+                material: None,
+            }],
+        };
+        let broken = Model {
+            meshes: vec![mesh],
+            ..Default::default()
+        };
+        assert!(broken.validate().is_err());
+    }
+}
@@ -0,0 +1,761 @@
+use std::collections::HashMap;
+
+use glam::{Mat4, Vec2, Vec3};
+
+use super::{relative_to, Material, Mesh, MeshVertex, Model, Node, Primitive, ReadFile};
+use crate::texture::decode::DecodedImage;
+
+// one corner of a face, as indices into the lists read so far
+#[derive(Debug, Clone, Copy)]
+struct Corner {
+    position: usize,
+    uv: Option<usize>,
+    normal: Option<usize>,
+}
+
+#[derive(Debug)]
+struct Face {
+    corners: Vec<Corner>,
+    // 0 when smoothing is off
+    smoothing: u32,
+    mesh: usize,
+    material: Option<usize>,
+}
+
+// where a vertex gets its normal from, vertices only merge when it's the same
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum NormalSource {
+    File(usize),
+    // the face's own, for faces without smoothing
+    Face(usize),
+    // averaged over the faces of a smoothing group meeting at the position
+    Group(u32),
+}
+
+// images referenced by materials, each read once
+#[derive(Default)]
+struct Images {
+    images: Vec<DecodedImage>,
+    paths: HashMap<String, usize>,
+}
+
+impl Images {
+    // broken texture references are common enough in the wild not to give up
+    // on the whole model over them
+    fn load(&mut self, path: &str, read: ReadFile) -> Option<usize> {
+        if let Some(&index) = self.paths.get(path) {
+            return Some(index);
+        }
+
+        let image = read(path).and_then(|bytes| DecodedImage::decode(&bytes));
+        match image {
+            Ok(image) => {
+                self.images.push(image);
+                self.paths.insert(path.to_owned(), self.images.len() - 1);
+                Some(self.images.len() - 1)
+            }
+            Err(e) => {
+                log::warn!("skipping texture {path}: {e}");
+                None
+            }
+        }
+    }
+}
+
+// `path` is where `text` was read from, material libraries and their textures
+// are read through `read` relative to it. every object or group becomes a
+// mesh of its own, split into a primitive per material
+pub fn parse(text: &str, path: &str, read: ReadFile) -> Result<Model, String> {
+    let mut positions: Vec<Vec3> = Vec::new();
+    let mut uvs: Vec<Vec2> = Vec::new();
+    let mut normals: Vec<Vec3> = Vec::new();
+    let mut faces = Vec::new();
+
+    let mut meshes = vec![String::new()];
+    let mut materials = Vec::new();
+    let mut material_names = HashMap::new();
+    let mut images = Images::default();
+    let mut material = None;
+    let mut smoothing = 0;
+
+    for (number, line) in lines(text) {
+        let at = |e: String| format!("{path}:{number}: {e}");
+        let mut words = line.split_whitespace();
+        let Some(keyword) = words.next() else {
+            continue;
+        };
+
+        match keyword {
+            "v" => positions.push(
+                floats(words, 3, 3)
+                    .map(|v| Vec3::from_slice(&v))
+                    .map_err(at)?,
+            ),
+            // v is up in obj textures and down in ours, w is ignored
+            "vt" => uvs.push(
+                floats(words, 1, 3)
+                    .map(|uv| Vec2::new(uv[0], 1.0 - uv.get(1).unwrap_or(&0.0)))
+                    .map_err(at)?,
+            ),
+            "vn" => normals.push(
+                floats(words, 3, 3)
+                    .map(|n| Vec3::from_slice(&n))
+                    .map_err(at)?,
+            ),
+            "f" => {
+                let corners = words
+                    .map(|word| corner(word, [positions.len(), uvs.len(), normals.len()]))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(at)?;
+                if corners.len() < 3 {
+                    return Err(at(format!("a face needs 3 corners, not {}", corners.len())));
+                }
+
+                faces.push(Face {
+                    corners,
+                    smoothing,
+                    mesh: meshes.len() - 1,
+                    material,
+                });
+            }
+            "o" | "g" => {
+                let name = rest(&line, keyword);
+                // names given before any face just name the first mesh
+                if faces
+                    .last()
+                    .is_some_and(|face| face.mesh == meshes.len() - 1)
+                {
+                    meshes.push(name.to_owned());
+                } else {
+                    *meshes.last_mut().unwrap() = name.to_owned();
+                }
+            }
+            "s" => {
+                smoothing = match words.next() {
+                    Some("off") | None => 0,
+                    Some(group) => group
+                        .parse()
+                        .map_err(|_| at(format!("invalid smoothing group {group:?}")))?,
+                };
+            }
+            "usemtl" => {
+                let name = rest(&line, keyword);
+                material = material_names.get(name).copied();
+                if material.is_none() {
+                    log::warn!("{path}:{number}: no material named {name:?}");
+                }
+            }
+            "mtllib" => {
+                for file in words {
+                    let file = relative_to(path, file);
+                    let text = match read(&file) {
+                        Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+                        Err(e) => {
+                            log::warn!("skipping material library: {e}");
+                            continue;
+                        }
+                    };
+
+                    for library_material in parse_mtl(&text, &file, read, &mut images)? {
+                        material_names.insert(library_material.name.clone(), materials.len());
+                        materials.push(library_material);
+                    }
+                }
+            }
+            // lines, points, curves and the rest of the format
+            _ => log::debug!("{path}:{number}: skipping {keyword}"),
+        }
+    }
+
+    let smooth_normals = smooth_normals(&faces, &positions);
+
+    let meshes: Vec<_> = meshes
+        .into_iter()
+        .enumerate()
+        .map(|(index, name)| {
+            let faces: Vec<_> = faces
+                .iter()
+                .enumerate()
+                .filter(|(_, face)| face.mesh == index)
+                .collect();
+
+            // a primitive per material, in the order they're first used
+            let mut order = Vec::new();
+            for (_, face) in &faces {
+                if !order.contains(&face.material) {
+                    order.push(face.material);
+                }
+            }
+
+            let primitives = order
+                .into_iter()
+                .map(|material| {
+                    let faces = faces
+                        .iter()
+                        .filter(|(_, face)| face.material == material)
+                        .copied();
+                    primitive(faces, material, &positions, &uvs, &normals, &smooth_normals)
+                })
+                .collect();
+
+            Mesh { name, primitives }
+        })
+        .filter(|mesh: &Mesh| !mesh.primitives.is_empty())
+        .collect();
+
+    Model {
+        nodes: meshes
+            .iter()
+            .enumerate()
+            .map(|(index, mesh)| Node {
+                name: mesh.name.clone(),
+                transform: Mat4::IDENTITY,
+                mesh: Some(index),
+                children: Vec::new(),
+            })
+            .collect(),
+        roots: (0..meshes.len()).collect(),
+        meshes,
+        materials,
+        images: images.images,
+    }
+    .validate()
+}
+
+// the lines that hold something, numbered from 1, with comments cut off and
+// lines ending in a backslash joined to the next one
+fn lines(text: &str) -> Vec<(usize, String)> {
+    let mut lines = Vec::new();
+    let mut pending: Option<(usize, String)> = None;
+
+    for (index, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default();
+        let (number, mut joined) = pending.take().unwrap_or((index + 1, String::new()));
+
+        match line.trim_end().strip_suffix('\\') {
+            Some(line) => {
+                joined.push_str(line);
+                joined.push(' ');
+                pending = Some((number, joined));
+            }
+            None => {
+                joined.push_str(line);
+                lines.push((number, joined));
+            }
+        }
+    }
+    lines.extend(pending);
+
+    lines
+}
+
+// what follows the keyword, for names that may have spaces in them
+fn rest<'a>(line: &'a str, keyword: &str) -> &'a str {
+    line.trim_start()[keyword.len()..].trim()
+}
+
+fn floats<'a>(
+    words: impl Iterator<Item = &'a str>,
+    min: usize,
+    max: usize,
+) -> Result<Vec<f32>, String> {
+    let values = words
+        .map(|word| word.parse().map_err(|_| format!("invalid number {word:?}")))
+        .collect::<Result<Vec<f32>, _>>()?;
+
+    if values.len() < min || values.len() > max {
+        return Err(format!(
+            "expected {min} to {max} numbers, got {}",
+            values.len()
+        ));
+    }
+    Ok(values)
+}
+
+// `v`, `v/vt`, `v//vn` or `v/vt/vn`, counting from 1 or back from the end
+// when negative. `counts` are the lengths of the lists so far
+fn corner(word: &str, counts: [usize; 3]) -> Result<Corner, String> {
+    let mut parts = word.split('/');
+    let mut index = |count: usize, required: bool| -> Result<Option<usize>, String> {
+        let part = parts.next().unwrap_or_default();
+        if part.is_empty() {
+            return match required {
+                true => Err(format!("corner {word:?} has no position")),
+                false => Ok(None),
+            };
+        }
+
+        let index: i64 = part
+            .parse()
+            .map_err(|_| format!("invalid index {part:?} in {word:?}"))?;
+        let resolved = match index {
+            1.. => index - 1,
+            ..0 => count as i64 + index,
+            0 => return Err(format!("indices start at 1, {word:?} has a 0")),
+        };
+
+        if resolved < 0 || resolved >= count as i64 {
+            return Err(format!("{word:?} refers to {index} of {count}"));
+        }
+        Ok(Some(resolved as usize))
+    };
+
+    let position = index(counts[0], true)?.unwrap_or_default();
+    let uv = index(counts[1], false)?;
+    let normal = index(counts[2], false)?;
+    if parts.next().is_some() {
+        return Err(format!("corner {word:?} has too many parts"));
+    }
+
+    Ok(Corner {
+        position,
+        uv,
+        normal,
+    })
+}
+
+fn face_normal(face: &Face, positions: &[Vec3]) -> Vec3 {
+    // newell's method, which copes with faces that aren't quite flat
+    let points: Vec<_> = face.corners.iter().map(|c| positions[c.position]).collect();
+
+    points
+        .iter()
+        .zip(points.iter().cycle().skip(1))
+        .map(|(a, b)| {
+            Vec3::new(
+                (a.y - b.y) * (a.z + b.z),
+                (a.z - b.z) * (a.x + b.x),
+                (a.x - b.x) * (a.y + b.y),
+            )
+        })
+        .sum::<Vec3>()
+}
+
+// per position and smoothing group, the sum of the normals of the faces
+// meeting there, larger faces weighing more
+fn smooth_normals(faces: &[Face], positions: &[Vec3]) -> HashMap<(usize, u32), Vec3> {
+    let mut normals = HashMap::new();
+
+    for face in faces.iter().filter(|face| face.smoothing != 0) {
+        let normal = face_normal(face, positions);
+        for corner in face.corners.iter().filter(|c| c.normal.is_none()) {
+            *normals
+                .entry((corner.position, face.smoothing))
+                .or_insert(Vec3::ZERO) += normal;
+        }
+    }
+
+    normals
+}
+
+// splits a polygon into triangles by cutting off ears, so concave ones come
+// out right. returns indices into `points`
+fn triangulate(points: &[Vec3]) -> Vec<[usize; 3]> {
+    let n = points.len();
+    let fan = |remaining: &[usize]| -> Vec<[usize; 3]> {
+        (1..remaining.len().saturating_sub(1))
+            .map(|i| [remaining[0], remaining[i], remaining[i + 1]])
+            .collect()
+    };
+
+    if n == 3 {
+        return vec![[0, 1, 2]];
+    }
+
+    // flattened onto the plane it's most aligned with, counter clockwise
+    // seen from the front
+    let normal: Vec3 = (0..n).map(|i| points[i].cross(points[(i + 1) % n])).sum();
+    // facing down an axis swaps the coordinates, mirroring the polygon
+    let abs = normal.abs();
+    let (axes, sign) = if abs.x >= abs.y && abs.x >= abs.z {
+        ([1, 2], normal.x)
+    } else if abs.y >= abs.z {
+        ([2, 0], normal.y)
+    } else {
+        ([0, 1], normal.z)
+    };
+    let [u, v] = if sign < 0.0 { [axes[1], axes[0]] } else { axes };
+    let flat: Vec<_> = points.iter().map(|p| Vec2::new(p[u], p[v])).collect();
+
+    let mut remaining: Vec<usize> = (0..n).collect();
+    let mut triangles = Vec::with_capacity(n - 2);
+
+    while remaining.len() > 3 {
+        let count = remaining.len();
+        let ear = (0..count).find(|&i| {
+            let [a, b, c] = [i + count - 1, i, i + 1].map(|j| remaining[j % count]);
+            let [pa, pb, pc] = [a, b, c].map(|j| flat[j]);
+            if (pb - pa).perp_dot(pc - pb) <= 0.0 {
+                return false;
+            }
+
+            // no other corner may be inside the ear
+            remaining
+                .iter()
+                .filter(|&&j| j != a && j != b && j != c)
+                .all(|&j| {
+                    let p = flat[j];
+                    let inside = (pb - pa).perp_dot(p - pa) > 0.0
+                        && (pc - pb).perp_dot(p - pb) > 0.0
+                        && (pa - pc).perp_dot(p - pc) > 0.0;
+                    !inside
+                })
+        });
+
+        // degenerate polygons have no ears left, fan out whatever remains
+        let Some(i) = ear else {
+            break;
+        };
+        triangles.push([i + count - 1, i, i + 1].map(|j| remaining[j % count]));
+        remaining.remove(i);
+    }
+    triangles.extend(fan(&remaining));
+
+    triangles
+}
+
+fn primitive<'a>(
+    faces: impl Iterator<Item = (usize, &'a Face)>,
+    material: Option<usize>,
+    positions: &[Vec3],
+    uvs: &[Vec2],
+    normals: &[Vec3],
+    smooth_normals: &HashMap<(usize, u32), Vec3>,
+) -> Primitive {
+    let mut primitive = Primitive {
+        material,
+        ..Default::default()
+    };
+    let mut merged = HashMap::new();
+
+    for (index, face) in faces {
+        let face_normal = face_normal(face, positions).normalize_or_zero();
+        let points: Vec<_> = face.corners.iter().map(|c| positions[c.position]).collect();
+
+        for triangle in triangulate(&points) {
+            for corner in triangle.map(|i| face.corners[i]) {
+                let source = match corner.normal {
+                    Some(normal) => NormalSource::File(normal),
+                    None if face.smoothing == 0 => NormalSource::Face(index),
+                    None => NormalSource::Group(face.smoothing),
+                };
+                let key = (corner.position, corner.uv, source);
+
+                let vertex = *merged.entry(key).or_insert_with(|| {
+                    let normal = match source {
+                        NormalSource::File(normal) => normals[normal].normalize_or_zero(),
+                        NormalSource::Face(_) => face_normal,
+                        NormalSource::Group(group) => {
+                            smooth_normals[&(corner.position, group)].normalize_or_zero()
+                        }
+                    };
+
+                    primitive.vertices.push(MeshVertex {
+                        position: positions[corner.position].into(),
+                        normal: normal.into(),
+                        uv: corner.uv.map_or([0.0; 2], |uv| uvs[uv].into()),
+                        tangent: [0.0; 4],
+                    });
+                    primitive.vertices.len() as u32 - 1
+                });
+                primitive.indices.push(vertex);
+            }
+        }
+    }
+
+    primitive.generate_tangents();
+    primitive
+}
+
+// the materials of an .mtl file, only the diffuse parts of them
+fn parse_mtl(
+    text: &str,
+    path: &str,
+    read: ReadFile,
+    images: &mut Images,
+) -> Result<Vec<Material>, String> {
+    let mut materials: Vec<Material> = Vec::new();
+
+    for (number, line) in lines(text) {
+        let at = |e: String| format!("{path}:{number}: {e}");
+        let mut words = line.split_whitespace();
+        let Some(keyword) = words.next() else {
+            continue;
+        };
+
+        if keyword == "newmtl" {
+            materials.push(Material {
+                name: rest(&line, keyword).to_owned(),
+                ..Default::default()
+            });
+            continue;
+        }
+        let Some(material) = materials.last_mut() else {
+            return Err(at(format!("{keyword} before any newmtl")));
+        };
+
+        match keyword {
+            "Kd" => {
+                let color = floats(words, 3, 3).map_err(at)?;
+                material.base_color[..3].copy_from_slice(&color);
+            }
+            "d" => material.base_color[3] = floats(words, 1, 1).map_err(at)?[0],
+            "Tr" => material.base_color[3] = 1.0 - floats(words, 1, 1).map_err(at)?[0],
+            "map_Kd" => {
+                let (file, clamp) = texture_file(&line, keyword).map_err(at)?;
+                material.base_color_texture = images.load(&relative_to(path, &file), read);
+                if clamp {
+                    material.sampler.address_mode_u = wgpu::AddressMode::ClampToEdge;
+                    material.sampler.address_mode_v = wgpu::AddressMode::ClampToEdge;
+                }
+            }
+            _ => log::debug!("{path}:{number}: skipping {keyword}"),
+        }
+    }
+
+    Ok(materials)
+}
+
+// the file name after a texture map's options, and whether `-clamp on` was
+// among them. file names may have spaces
+fn texture_file(line: &str, keyword: &str) -> Result<(String, bool), String> {
+    let mut words = rest(line, keyword)
+        .split(' ')
+        .filter(|w| !w.is_empty())
+        .peekable();
+    let mut clamp = false;
+
+    while let Some(option) = words.next_if(|word| word.starts_with('-')) {
+        match option {
+            "-clamp" => clamp = words.next() == Some("on"),
+            "-blendu" | "-blendv" | "-cc" | "-bm" | "-boost" | "-texres" | "-imfchan" | "-type" => {
+                words.next();
+            }
+            "-mm" => {
+                words.next();
+                words.next();
+            }
+            // one to three numbers
+            "-o" | "-s" | "-t" => {
+                for _ in 0..3 {
+                    if words.next_if(|word| word.parse::<f32>().is_ok()).is_none() {
+                        break;
+                    }
+                }
+            }
+            _ => return Err(format!("unknown texture option {option}")),
+        }
+    }
+
+    let file = words.collect::<Vec<_>>().join(" ");
+    if file.is_empty() {
+        return Err(format!("{keyword} without a file"));
+    }
+    Ok((file, clamp))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MTL: &str = "
+        # two materials, one textured
+        newmtl stone
+        Kd 0.5 0.5 0.5
+        map_Kd -s 2 2 -clamp on textures/stone tiles.png
+
+        newmtl glass
+        Kd 0.2 0.4 1.0
+        d 0.25
+    ";
+
+    fn read(path: &str) -> Result<Vec<u8>, String> {
+        match path {
+            "models/things.mtl" => Ok(MTL.as_bytes().to_vec()),
+            "models/textures/stone tiles.png" => Ok(png()),
+            _ => Err(format!("{path} not found")),
+        }
+    }
+
+    // a 1x1 png
+    fn png() -> Vec<u8> {
+        let image = image::RgbaImage::from_pixel(1, 1, image::Rgba([255, 0, 0, 255]));
+        let mut bytes = std::io::Cursor::new(Vec::new());
+        image.write_to(&mut bytes, image::ImageFormat::Png).unwrap();
+        bytes.into_inner()
+    }
+
+    fn parse(text: &str) -> Result<Model, String> {
+        super::parse(text, "models/things.obj", &read)
+    }
+
+    #[test]
+    fn reads_meshes_and_materials() {
+        let model = parse(
+            "mtllib things.mtl
+            o cube side
+            v 0 0 0
+            v 1 0 0
+            v 1 1 0
+            v 0 1 0
+            vt 0 0
+            vt 1 0
+            vt 1 1
+            vt 0 1
+            vn 0 0 1
+            usemtl stone
+            f 1/1/1 2/2/1 3/3/1 4/4/1
+            usemtl glass
+            f -4//1 -2//1 -1//1
+            o empty
+            o plain
+            usemtl missing
+            f 1 2 3",
+        )
+        .unwrap();
+
+        let [side, plain] = &model.meshes[..] else {
+            panic!("expected two meshes");
+        };
+        assert_eq!(side.name, "cube side");
+        let [stone, glass] = &side.primitives[..] else {
+            panic!("expected a primitive per material");
+        };
+        // the quad shares two corners between its triangles
+        assert_eq!((stone.vertices.len(), stone.indices.len()), (4, 6));
+        let origin = stone
+            .vertices
+            .iter()
+            .find(|v| v.position == [0.0; 3])
+            .unwrap();
+        assert_eq!(origin.uv, [0.0, 1.0]);
+        assert_eq!(origin.normal, [0.0, 0.0, 1.0]);
+        assert_eq!(glass.indices.len(), 3);
+        assert_eq!(plain.primitives[0].material, None);
+
+        let [stone, glass] = &model.materials[..] else {
+            panic!("expected two materials");
+        };
+        assert_eq!(stone.base_color_texture, Some(0));
+        assert_eq!(stone.sampler.address_mode_u, wgpu::AddressMode::ClampToEdge);
+        assert_eq!(glass.base_color, [0.2, 0.4, 1.0, 0.25]);
+        assert_eq!(model.images.len(), 1);
+        assert_eq!(model.nodes.len(), 2);
+    }
+
+    #[test]
+    fn generates_flat_or_smooth_normals() {
+        // two triangles folded along x, once per smoothing setting
+        let folded = |smoothing: &str| {
+            let model = parse(&format!(
+                "v 0 0 0
+                v 1 0 0
+                v 0 1 0
+                v 0 0 1
+                s {smoothing}
+                f 1 2 3
+                f 1 4 2"
+            ))
+            .unwrap();
+            model.meshes[0].primitives[0].clone()
+        };
+
+        let flat = folded("off");
+        assert_eq!(flat.vertices.len(), 6);
+        assert_eq!(flat.vertices[0].normal, [0.0, 0.0, 1.0]);
+        assert_eq!(flat.vertices[3].normal, [0.0, 1.0, 0.0]);
+
+        let smooth = folded("1");
+        assert_eq!(smooth.vertices.len(), 4);
+        let shared = Vec3::from(smooth.vertices[0].normal);
+        assert!(shared.abs_diff_eq(Vec3::new(0.0, 1.0, 1.0).normalize(), 1e-6));
+    }
+
+    #[test]
+    fn triangulates_concave_polygons() {
+        // an arrow head pointing up, its notch is the reflex corner
+        let points = [
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(2.0, 1.0, 0.0),
+            Vec3::new(4.0, 0.0, 0.0),
+            Vec3::new(2.0, 3.0, 0.0),
+        ];
+        // signed areas of the triangles, positive when counter clockwise
+        let areas = |points: &[Vec3]| -> Vec<f32> {
+            triangulate(points)
+                .into_iter()
+                .map(|[a, b, c]| (points[b] - points[a]).cross(points[c] - points[a]).z / 2.0)
+                .collect()
+        };
+
+        // the triangles cover the arrow exactly, none reach into the notch
+        let front = areas(&points);
+        assert_eq!(front.len(), 2);
+        assert!(front.iter().all(|&area| area > 0.0));
+        assert_eq!(front.iter().sum::<f32>(), 4.0);
+
+        // the same arrow facing away winds the other way round
+        let back = areas(&[points[0], points[3], points[2], points[1]]);
+        assert!(back.iter().all(|&area| area < 0.0));
+        assert_eq!(back.iter().sum::<f32>(), -4.0);
+    }
+
+    #[test]
+    fn joins_continued_lines() {
+        let model = parse("v 0 0 0\nv 1 0 \\\n 0\nv 0 1 0 # a comment\nf 1 2 \\\n3").unwrap();
+        assert_eq!(
+            model.meshes[0].primitives[0].vertices[1].position,
+            [1.0, 0.0, 0.0]
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_files() {
+        let error = |text: &str| parse(text).err().unwrap();
+        let triangle = "v 0 0 0\nv 1 0 0\nv 0 1 0\n";
+
+        assert!(error("v 1 2").contains("things.obj:1"));
+        assert!(error("v 1 two 3").contains("invalid number"));
+        assert!(error("vn 0 0 1 1").contains("expected 3 to 3"));
+        assert!(error(&format!("{triangle}f 1 2")).contains("3 corners"));
+        assert!(error(&format!("{triangle}f 1 2 4")).contains("things.obj:4"));
+        assert!(error(&format!("{triangle}f 0 1 2")).contains("start at 1"));
+        assert!(error(&format!("{triangle}f -4 1 2")).contains("refers to"));
+        assert!(error(&format!("{triangle}f 1/1 2/1 3/1")).contains("refers to"));
+        assert!(error(&format!("{triangle}f 1/x 2 3")).contains("invalid index"));
+        assert!(error(&format!("{triangle}f 1/// 2 3")).contains("too many parts"));
+        assert!(error(&format!("{triangle}f /1 2 3")).contains("no position"));
+        assert!(error("s smooth").contains("smoothing group"));
+    }
+
+    #[test]
+    fn tolerates_missing_files_but_not_broken_ones() {
+        // libraries and textures that can't be read are skipped
+        let model = super::parse(
+            "mtllib gone.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nusemtl stone\nf 1 2 3",
+            "a.obj",
+            &read,
+        )
+        .unwrap();
+        assert!(model.materials.is_empty());
+
+        let mtl = |text: &'static str| {
+            move |path: &str| match path {
+                "a.mtl" => Ok(text.as_bytes().to_vec()),
+                _ => Err(format!("{path} not found")),
+            }
+        };
+        let with_mtl = |text: &'static str| super::parse("mtllib a.mtl", "a.obj", &mtl(text));
+
+        let model = with_mtl("newmtl a\nmap_Kd missing.png").unwrap();
+        assert_eq!(model.materials[0].base_color_texture, None);
+
+        assert!(with_mtl("Kd 1 1 1")
+            .err()
+            .unwrap()
+            .contains("before any newmtl"));
+        assert!(with_mtl("newmtl a\nKd 1 1").is_err());
+        assert!(with_mtl("newmtl a\nmap_Kd -bogus 1 a.png").is_err());
+        assert!(with_mtl("newmtl a\nmap_Kd -clamp on").is_err());
+    }
+}
@@ -0,0 +1,325 @@
+use glam::{Mat3, Mat4};
+use wgpu::{include_wgsl, util::DeviceExt};
+
+use super::{Material, MeshVertex, Model};
+use crate::{
+    render_target::DEPTH_FORMAT,
+    texture::{
+        decode::{DecodedImage, Pixels},
+        Texture, TextureOptions,
+    },
+    vertices::Vertex,
+};
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct Instance {
+    model: [[f32; 4]; 4],
+    // the inverse transpose, keeping normals perpendicular under non uniform
+    // scaling, padded to vec4 columns
+    normal: [[f32; 4]; 3],
+}
+
+impl Instance {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 7] = wgpu::vertex_attr_array![
+        4 => Float32x4,
+        5 => Float32x4,
+        6 => Float32x4,
+        7 => Float32x4,
+        8 => Float32x4,
+        9 => Float32x4,
+        10 => Float32x4,
+    ];
+
+    fn new(model: Mat4) -> Self {
+        let normal = Mat3::from_mat4(model).inverse().transpose();
+
+        Self {
+            model: model.to_cols_array_2d(),
+            normal: [normal.x_axis, normal.y_axis, normal.z_axis]
+                .map(|axis| axis.extend(0.0).into()),
+        }
+    }
+}
+
+struct GpuPrimitive {
+    vertex_buffer: wgpu::Buffer,
+    index_buffer: wgpu::Buffer,
+    index_count: u32,
+    // the model's default material comes after its own
+    material: usize,
+}
+
+// draws a `Model` with its base colors and a fixed light. the hierarchy is
+// flattened when it's created, every mesh a node shows is one instance
+pub struct ModelRenderer {
+    pipeline: wgpu::RenderPipeline,
+    camera_buffer: wgpu::Buffer,
+    camera_bind_group: wgpu::BindGroup,
+    material_bind_groups: Vec<wgpu::BindGroup>,
+    meshes: Vec<Vec<GpuPrimitive>>,
+    instance_buffer: wgpu::Buffer,
+    // the mesh of every instance, in the order of the instance buffer
+    instances: Vec<usize>,
+}
+
+impl ModelRenderer {
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        view_format: wgpu::TextureFormat,
+        model: &Model,
+    ) -> Self {
+        let shader = device.create_shader_module(include_wgsl!("shader.wgsl"));
+
+        let camera_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("model camera buffer"),
+            size: size_of::<[[f32; 4]; 4]>() as _,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let camera_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("model camera bind group layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("model camera bind group"),
+            layout: &camera_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: camera_buffer.as_entire_binding(),
+            }],
+        });
+
+        let material_layout = material_bind_group_layout(device);
+        let material_bind_groups = material_bind_groups(device, queue, &material_layout, model);
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("model pipeline layout"),
+            bind_group_layouts: &[&camera_layout, &material_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("model pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[
+                    MeshVertex::desc(),
+                    wgpu::VertexBufferLayout {
+                        array_stride: size_of::<Instance>() as _,
+                        step_mode: wgpu::VertexStepMode::Instance,
+                        attributes: &Instance::ATTRIBUTES,
+                    },
+                ],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: view_format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            // nothing is culled, models often have single sided walls
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: Default::default(),
+                bias: Default::default(),
+            }),
+            multisample: Default::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        let meshes = model
+            .meshes
+            .iter()
+            .map(|mesh| {
+                mesh.primitives
+                    .iter()
+                    .map(|primitive| GpuPrimitive {
+                        vertex_buffer: device.create_buffer_init(
+                            &wgpu::util::BufferInitDescriptor {
+                                label: Some("model vertex buffer"),
+                                contents: bytemuck::cast_slice(&primitive.vertices),
+                                usage: wgpu::BufferUsages::VERTEX,
+                            },
+                        ),
+                        index_buffer: device.create_buffer_init(
+                            &wgpu::util::BufferInitDescriptor {
+                                label: Some("model index buffer"),
+                                contents: bytemuck::cast_slice(&primitive.indices),
+                                usage: wgpu::BufferUsages::INDEX,
+                            },
+                        ),
+                        index_count: primitive.indices.len() as u32,
+                        material: primitive.material.unwrap_or(model.materials.len()),
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let (instances, transforms): (Vec<_>, Vec<_>) = model
+            .instances()
+            .into_iter()
+            .map(|(mesh, transform)| (mesh, Instance::new(transform)))
+            .unzip();
+        // buffers can't be empty
+        let instance_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("model instance buffer"),
+            contents: bytemuck::cast_slice(
+                &[transforms, vec![Instance::new(Mat4::IDENTITY)]].concat(),
+            ),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        Self {
+            pipeline,
+            camera_buffer,
+            camera_bind_group,
+            material_bind_groups,
+            meshes,
+            instance_buffer,
+            instances,
+        }
+    }
+
+    pub fn set_camera(&self, view_projection: Mat4, queue: &wgpu::Queue) {
+        queue.write_buffer(
+            &self.camera_buffer,
+            0,
+            bytemuck::cast_slice(&view_projection.transpose().to_cols_array_2d()),
+        );
+    }
+
+    // needs a depth buffer of `DEPTH_FORMAT` in the pass
+    pub fn render(&self, render_pass: &mut wgpu::RenderPass) {
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
+
+        let stride = size_of::<Instance>() as wgpu::BufferAddress;
+        for (index, &mesh) in self.instances.iter().enumerate() {
+            let offset = index as wgpu::BufferAddress * stride;
+            render_pass.set_vertex_buffer(1, self.instance_buffer.slice(offset..offset + stride));
+
+            for primitive in self.meshes[mesh].iter().filter(|p| p.index_count > 0) {
+                render_pass.set_bind_group(1, &self.material_bind_groups[primitive.material], &[]);
+                render_pass.set_vertex_buffer(0, primitive.vertex_buffer.slice(..));
+                render_pass
+                    .set_index_buffer(primitive.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+                render_pass.draw_indexed(0..primitive.index_count, 0, 0..1);
+            }
+        }
+    }
+}
+
+fn material_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    use wgpu::BindGroupLayoutEntry as Entry;
+
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("model material bind group layout"),
+        entries: &[
+            Entry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            Entry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            },
+            Entry {
+                binding: 2,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            },
+        ],
+    })
+}
+
+// one per material and one more for primitives without, untextured
+// materials sample a white texel
+fn material_bind_groups(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    layout: &wgpu::BindGroupLayout,
+    model: &Model,
+) -> Vec<wgpu::BindGroup> {
+    let white = DecodedImage {
+        width: 1,
+        height: 1,
+        pixels: Pixels::Rgba8(vec![255; 4]),
+    };
+    let textures: Vec<_> = model
+        .images
+        .iter()
+        .chain([&white])
+        .map(|image| Texture::from_image(device, queue, image, TextureOptions::default()))
+        .collect();
+    let white = textures.len() - 1;
+
+    model
+        .materials
+        .iter()
+        .chain([&Material::default()])
+        .map(|material| {
+            let texture = &textures[material.base_color_texture.unwrap_or(white)];
+            let uniform = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("model material buffer"),
+                contents: bytemuck::cast_slice(&material.base_color),
+                usage: wgpu::BufferUsages::UNIFORM,
+            });
+
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("model material bind group"),
+                layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: uniform.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(&texture.texture_view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: wgpu::BindingResource::Sampler(
+                            &device.create_sampler(&material.sampler),
+                        ),
+                    },
+                ],
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,60 @@
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) uv: vec2<f32>,
+};
+
+struct InstanceInput {
+    @location(4) model_0: vec4<f32>,
+    @location(5) model_1: vec4<f32>,
+    @location(6) model_2: vec4<f32>,
+    @location(7) model_3: vec4<f32>,
+    @location(8) normal_0: vec4<f32>,
+    @location(9) normal_1: vec4<f32>,
+    @location(10) normal_2: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) normal: vec3<f32>,
+    @location(1) uv: vec2<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> view_projection: mat4x4<f32>;
+
+@group(1) @binding(0)
+var<uniform> base_color: vec4<f32>;
+@group(1) @binding(1)
+var base_color_texture: texture_2d<f32>;
+@group(1) @binding(2)
+var base_color_sampler: sampler;
+
+// towards the light, in world space
+const LIGHT: vec3<f32> = vec3<f32>(0.4, 0.8, 0.45);
+const AMBIENT: f32 = 0.25;
+
+@vertex
+fn vs_main(vertex: VertexInput, instance: InstanceInput) -> VertexOutput {
+    let model = mat4x4<f32>(instance.model_0, instance.model_1, instance.model_2, instance.model_3);
+    let normal_matrix = mat3x3<f32>(instance.normal_0.xyz, instance.normal_1.xyz, instance.normal_2.xyz);
+
+    var out: VertexOutput;
+    out.clip_position = (model * vec4<f32>(vertex.position, 1.0)) * view_projection;
+    out.normal = normal_matrix * vertex.normal;
+    out.uv = vertex.uv;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput, @builtin(front_facing) front_facing: bool) -> @location(0) vec4<f32> {
+    // the back of single sided walls is lit like the front
+    var normal = normalize(in.normal);
+    if !front_facing {
+        normal = -normal;
+    }
+
+    let color = textureSample(base_color_texture, base_color_sampler, in.uv) * base_color;
+    let diffuse = max(dot(normal, normalize(LIGHT)), 0.0);
+    return vec4<f32>(color.rgb * (AMBIENT + diffuse * (1.0 - AMBIENT)), color.a);
+}
@@ -0,0 +1,341 @@
+use glam::Vec2;
+use wgpu::include_wgsl;
+use winit::dpi::PhysicalSize;
+
+use crate::sandbox::camera2d::camera::Camera2D;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Join {
+    #[default]
+    Miter,
+    Bevel,
+    Round,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Cap {
+    #[default]
+    Butt,
+    Square,
+    Round,
+}
+
+// widths and dashes are in pixels and stay the same at every zoom level,
+// only the points go through the camera
+#[derive(Debug, Clone, Copy)]
+pub struct LineStyle {
+    pub width: f32,
+    pub color: [f32; 4],
+    pub join: Join,
+    // also shapes the ends of every dash
+    pub cap: Cap,
+    // the longest miter, relative to the width, before it becomes a bevel
+    pub miter_limit: f32,
+    // dash and gap lengths, a solid line without
+    pub dash: Option<[f32; 2]>,
+    pub dash_offset: f32,
+}
+
+impl Default for LineStyle {
+    fn default() -> Self {
+        Self {
+            width: 2.0,
+            color: [1.0; 4],
+            join: Join::Miter,
+            cap: Cap::Butt,
+            miter_limit: 4.0,
+            dash: None,
+            dash_offset: 0.0,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct Uniforms {
+    view_projection: [[f32; 4]; 4],
+    viewport: [f32; 2],
+    _padding: [f32; 2],
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+struct Instance {
+    a: [f32; 2],
+    b: [f32; 2],
+    prev: [f32; 2],
+    next: [f32; 2],
+    color: [f32; 4],
+    // width, miter limit, dash, gap
+    params: [f32; 4],
+    // world distance along the line up to a, dash offset
+    distance: [f32; 2],
+    // join, cap, has prev, has next
+    style: [u32; 4],
+}
+
+impl Instance {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 8] = wgpu::vertex_attr_array![
+        0 => Float32x2,
+        1 => Float32x2,
+        2 => Float32x2,
+        3 => Float32x2,
+        4 => Float32x4,
+        5 => Float32x4,
+        6 => Float32x2,
+        7 => Uint32x4,
+    ];
+}
+
+// one instance per segment, which knows its neighbours so the shader can
+// join them. repeated points are dropped, as is the last point of a closed
+// line when it repeats the first
+fn segments(points: &[Vec2], closed: bool, style: &LineStyle) -> Vec<Instance> {
+    let mut points = points.to_vec();
+    points.dedup();
+    if closed && points.len() > 2 && points.first() == points.last() {
+        points.pop();
+    }
+
+    let n = points.len();
+    if n < 2 {
+        return Vec::new();
+    }
+
+    let [dash, gap] = style.dash.unwrap_or([0.0; 2]);
+    let count = if closed && n > 2 { n } else { n - 1 };
+    let joined = count == n;
+
+    let mut distance = 0.0;
+    (0..count)
+        .map(|i| {
+            let a = points[i];
+            let b = points[(i + 1) % n];
+            let has_prev = joined || i > 0;
+            let has_next = joined || i + 2 < n;
+
+            let instance = Instance {
+                a: a.into(),
+                b: b.into(),
+                prev: points[(i + n - 1) % n].into(),
+                next: points[(i + 2) % n].into(),
+                color: style.color,
+                params: [style.width, style.miter_limit, dash, gap],
+                distance: [distance, style.dash_offset],
+                style: [
+                    style.join as u32,
+                    style.cap as u32,
+                    has_prev as u32,
+                    has_next as u32,
+                ],
+            };
+            distance += a.distance(b);
+
+            instance
+        })
+        .collect()
+}
+
+// draws thick antialiased lines as screen space quads around each segment.
+// the fragment shader works out the distance to the line, with its joins,
+// caps and dashes, in pixels, and segments are clipped where they meet so
+// translucent lines don't darken at the joins
+pub struct PolylineRenderer {
+    pipeline: wgpu::RenderPipeline,
+    uniforms: Uniforms,
+    uniform_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    instance_buffer: wgpu::Buffer,
+    // in instances
+    capacity: usize,
+    instances: Vec<Instance>,
+    count: u32,
+}
+
+impl PolylineRenderer {
+    pub fn new(device: &wgpu::Device, view_format: wgpu::TextureFormat) -> Self {
+        let shader = device.create_shader_module(include_wgsl!("shader.wgsl"));
+
+        let uniforms = Uniforms {
+            view_projection: Camera2D::new(1, 1, 1.0).camera_matrix().to_cols_array_2d(),
+            viewport: [1.0, 1.0],
+            _padding: [0.0; 2],
+        };
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("polyline uniform buffer"),
+            size: size_of::<Uniforms>() as _,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let layout = Camera2D::bind_group_layout(device);
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("polyline bind group"),
+            layout: &layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("polyline pipeline layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("polyline pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: size_of::<Instance>() as _,
+                    step_mode: wgpu::VertexStepMode::Instance,
+                    attributes: &Instance::ATTRIBUTES,
+                }],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: view_format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            multisample: Default::default(),
+            depth_stencil: None,
+            multiview: None,
+            cache: None,
+        });
+
+        let capacity = 256;
+
+        Self {
+            pipeline,
+            uniforms,
+            uniform_buffer,
+            bind_group,
+            instance_buffer: instance_buffer(device, capacity),
+            capacity,
+            instances: Vec::new(),
+            count: 0,
+        }
+    }
+
+    // widths are in pixels of this size
+    pub fn resize(&mut self, size: PhysicalSize<u32>, queue: &wgpu::Queue) {
+        self.uniforms.viewport = [size.width.max(1) as f32, size.height.max(1) as f32];
+        self.write_uniforms(queue);
+    }
+
+    pub fn set_camera(&mut self, camera: &Camera2D, queue: &wgpu::Queue) {
+        self.uniforms.view_projection = camera.camera_matrix().to_cols_array_2d();
+        self.write_uniforms(queue);
+    }
+
+    fn write_uniforms(&self, queue: &wgpu::Queue) {
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::bytes_of(&self.uniforms));
+    }
+
+    // queues a line through `points`, in world space, for the next `prepare`.
+    // closed lines join their last point back to the first and have no caps
+    pub fn push(&mut self, points: &[Vec2], closed: bool, style: &LineStyle) {
+        self.instances.extend(segments(points, closed, style));
+    }
+
+    pub fn push_line(&mut self, a: Vec2, b: Vec2, style: &LineStyle) {
+        self.push(&[a, b], false, style);
+    }
+
+    // uploads everything pushed since the last call, which is what `render`
+    // draws until the next one
+    pub fn prepare(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        if self.instances.len() > self.capacity {
+            self.capacity = self.instances.len().next_power_of_two();
+            self.instance_buffer = instance_buffer(device, self.capacity);
+        }
+
+        queue.write_buffer(
+            &self.instance_buffer,
+            0,
+            bytemuck::cast_slice(&self.instances),
+        );
+        self.count = self.instances.len() as u32;
+        self.instances.clear();
+    }
+
+    pub fn render(&self, render_pass: &mut wgpu::RenderPass) {
+        if self.count == 0 {
+            return;
+        }
+
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.instance_buffer.slice(..));
+        render_pass.draw(0..6, 0..self.count);
+    }
+}
+
+fn instance_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("polyline instance buffer"),
+        size: (capacity * size_of::<Instance>()) as _,
+        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn open_lines_have_caps_at_both_ends() {
+        let points = [Vec2::ZERO, Vec2::new(3.0, 0.0), Vec2::new(3.0, 4.0)];
+        let segments = segments(&points, false, &LineStyle::default());
+
+        assert_eq!(segments.len(), 2);
+        assert_eq!(segments[0].style[2..], [0, 1]);
+        assert_eq!(segments[1].style[2..], [1, 0]);
+        assert_eq!(segments[1].prev, [0.0, 0.0]);
+        assert_eq!(segments[0].distance[0], 0.0);
+        assert_eq!(segments[1].distance[0], 3.0);
+    }
+
+    #[test]
+    fn closed_lines_wrap_around() {
+        let points = [
+            Vec2::ZERO,
+            Vec2::new(1.0, 0.0),
+            Vec2::new(1.0, 1.0),
+            Vec2::ZERO,
+        ];
+        let segments = segments(&points, true, &LineStyle::default());
+
+        // the repeated first point is dropped, the loop closes by itself
+        assert_eq!(segments.len(), 3);
+        assert!(segments.iter().all(|s| s.style[2..] == [1, 1]));
+        assert_eq!(segments[2].a, [1.0, 1.0]);
+        assert_eq!(segments[2].b, [0.0, 0.0]);
+        assert_eq!(segments[2].next, [1.0, 0.0]);
+        assert_eq!(segments[0].prev, [1.0, 1.0]);
+    }
+
+    #[test]
+    fn degenerate_lines_are_skipped() {
+        let style = LineStyle::default();
+
+        assert!(segments(&[], false, &style).is_empty());
+        assert!(segments(&[Vec2::ONE, Vec2::ONE], false, &style).is_empty());
+
+        let segments = segments(&[Vec2::ZERO, Vec2::ZERO, Vec2::X], false, &style);
+        assert_eq!(segments.len(), 1);
+        assert_eq!(segments[0].style[2..], [0, 0]);
+    }
+}
@@ -0,0 +1,252 @@
+struct Uniforms {
+    view_projection: mat4x4<f32>,
+    // in pixels
+    viewport: vec2<f32>,
+}
+
+struct Instance {
+    // the segment from a to b, in world space
+    @location(0) a: vec2<f32>,
+    @location(1) b: vec2<f32>,
+    // the neighbouring points, used when `style` says there are any
+    @location(2) prev: vec2<f32>,
+    @location(3) next: vec2<f32>,
+    @location(4) color: vec4<f32>,
+    // width in pixels, miter limit, dash and gap length in pixels
+    @location(5) params: vec4<f32>,
+    // distance along the line up to a in world units, dash offset in pixels
+    @location(6) distance: vec2<f32>,
+    // join, cap, has prev, has next
+    @location(7) style: vec4<u32>,
+}
+
+struct VertexOutput {
+    @builtin(position) pos: vec4<f32>,
+    // everything below is in pixels
+    @location(0) pixel: vec2<f32>,
+    @location(1) @interpolate(flat) a: vec2<f32>,
+    @location(2) @interpolate(flat) b: vec2<f32>,
+    // the plane between this segment and its neighbour, pointing away from
+    // this one, and the outer side of the join
+    @location(3) @interpolate(flat) start_clip: vec4<f32>,
+    @location(4) @interpolate(flat) end_clip: vec4<f32>,
+    @location(5) @interpolate(flat) color: vec4<f32>,
+    // half width, dash phase at a, dash and gap length
+    @location(6) @interpolate(flat) params: vec4<f32>,
+    // how the start and the end are shaped, and the cap for dashes
+    @location(7) @interpolate(flat) ends: vec3<u32>,
+}
+
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+
+const JOIN_MITER: u32 = 0u;
+const JOIN_BEVEL: u32 = 1u;
+const JOIN_ROUND: u32 = 2u;
+
+const CAP_BUTT: u32 = 0u;
+const CAP_SQUARE: u32 = 1u;
+const CAP_ROUND: u32 = 2u;
+
+// the end kinds, caps followed by joins
+const END_BUTT: u32 = 0u;
+const END_SQUARE: u32 = 1u;
+const END_ROUND_CAP: u32 = 2u;
+const END_MITER: u32 = 3u;
+const END_BEVEL: u32 = 4u;
+const END_ROUND_JOIN: u32 = 5u;
+
+const CORNERS = array(
+    vec2f(0.0, -1.0),
+    vec2f(1.0, -1.0),
+    vec2f(0.0, 1.0),
+    vec2f(0.0, 1.0),
+    vec2f(1.0, -1.0),
+    vec2f(1.0, 1.0),
+);
+
+fn to_pixels(p: vec2<f32>) -> vec2<f32> {
+    let clip = vec4f(p, 0.0, 1.0) * uniforms.view_projection;
+    return (clip.xy / clip.w * 0.5 + 0.5) * uniforms.viewport;
+}
+
+fn direction(p0: vec2<f32>, p1: vec2<f32>) -> vec2<f32> {
+    let delta = p1 - p0;
+    if dot(delta, delta) < 1e-8 {
+        return vec2f(1.0, 0.0);
+    }
+    return normalize(delta);
+}
+
+fn perp(v: vec2<f32>) -> vec2<f32> {
+    return vec2f(-v.y, v.x);
+}
+
+struct Joint {
+    kind: u32,
+    clip: vec4<f32>,
+}
+
+// the joint between the incoming direction t1 and the outgoing t2, with the
+// clip plane facing along t2. segments ending at the joint flip it
+fn joint(t1: vec2<f32>, t2: vec2<f32>, join: u32, miter_limit: f32) -> Joint {
+    var out: Joint;
+
+    let sum = t1 + t2;
+    // a full turn back has no sensible join
+    if dot(sum, sum) < 1e-6 {
+        out.kind = END_BUTT;
+        out.clip = vec4f(0.0);
+        return out;
+    }
+
+    let n1 = perp(t1);
+    let miter = normalize(n1 + perp(t2));
+    // the outside of the turn is opposite the side it turns to
+    let turn = t1.x * t2.y - t1.y * t2.x;
+    let outer = select(miter, -miter, turn > 0.0);
+
+    out.kind = END_MITER + join;
+    // miters longer than the limit, relative to the width, turn into bevels
+    if join == JOIN_MITER && 1.0 / max(dot(n1, miter), 1e-6) > miter_limit {
+        out.kind = END_BEVEL;
+    }
+    out.clip = vec4f(normalize(sum), outer);
+
+    return out;
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32, in: Instance) -> VertexOutput {
+    var out: VertexOutput;
+
+    let a = to_pixels(in.a);
+    let b = to_pixels(in.b);
+    let t = direction(a, b);
+    let n = perp(t);
+
+    let half_width = in.params.x * 0.5;
+    let miter_limit = in.params.y;
+    let join = in.style.x;
+    let cap = in.style.y;
+
+    var start = Joint(cap, vec4f(0.0));
+    if in.style.z != 0u {
+        start = joint(direction(to_pixels(in.prev), a), t, join, miter_limit);
+        start.clip = vec4f(-start.clip.xy, start.clip.zw);
+    }
+
+    var end = Joint(cap, vec4f(0.0));
+    if in.style.w != 0u {
+        end = joint(t, direction(b, to_pixels(in.next)), join, miter_limit);
+    }
+
+    // long enough for caps and miters, plus a pixel for antialiasing
+    let extend = half_width * max(miter_limit, 1.0) + 1.0;
+    let corner = CORNERS[index];
+    let pixel = mix(a - t * extend, b + t * extend, corner.x) + n * corner.y * (half_width + 1.0);
+
+    // camera2d is a similarity transform, so one ratio covers every direction
+    let world_length = distance(in.a, in.b);
+    let scale = select(0.0, distance(a, b) / world_length, world_length > 0.0);
+
+    out.pos = vec4f(pixel / uniforms.viewport * 2.0 - 1.0, 0.0, 1.0);
+    out.pixel = pixel;
+    out.a = a;
+    out.b = b;
+    out.start_clip = start.clip;
+    out.end_clip = end.clip;
+    out.color = in.color;
+    out.params = vec4f(half_width, in.distance.x * scale + in.distance.y, in.params.zw);
+    out.ends = vec3u(start.kind, end.kind, cap);
+
+    return out;
+}
+
+// signed distance to the line near one of its ends, positive outside, except
+// for the plane between joined segments. `outward` points away from the
+// segment along it
+fn end_distance(
+    p: vec2<f32>,
+    e: vec2<f32>,
+    outward: vec2<f32>,
+    kind: u32,
+    clip: vec4<f32>,
+    strip: f32,
+    half_width: f32,
+) -> f32 {
+    let delta = p - e;
+    let past = dot(delta, outward);
+    let rounded = select(strip, length(delta) - half_width, past > 0.0);
+
+    switch kind {
+        case END_SQUARE: {
+            return max(strip, past - half_width);
+        }
+        case END_ROUND_CAP: {
+            return rounded;
+        }
+        case END_MITER: {
+            return strip;
+        }
+        case END_BEVEL: {
+            return max(strip, dot(delta, clip.zw) - half_width * abs(dot(perp(outward), clip.zw)));
+        }
+        case END_ROUND_JOIN: {
+            return rounded;
+        }
+        default: {
+            return max(strip, past);
+        }
+    }
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let p = in.pixel;
+    let t = direction(in.a, in.b);
+    let half_width = in.params.x;
+
+    // the planes between joined segments cut without antialiasing, so both
+    // sides add up to full coverage instead of leaving a seam. a pixel right
+    // on one goes to the segment ending there
+    let cut_start = in.ends.x >= END_MITER && dot(p - in.a, in.start_clip.xy) >= 0.0;
+    let cut_end = in.ends.y >= END_MITER && dot(p - in.b, in.end_clip.xy) > 0.0;
+    if cut_start || cut_end {
+        discard;
+    }
+
+    let across = dot(p - in.a, perp(t));
+    let strip = abs(across) - half_width;
+
+    var d = max(
+        end_distance(p, in.a, -t, in.ends.x, in.start_clip, strip, half_width),
+        end_distance(p, in.b, t, in.ends.y, in.end_clip, strip, half_width),
+    );
+
+    let dash = in.params.z;
+    let gap = in.params.w;
+    if dash > 0.0 {
+        let period = dash + gap;
+        let along = in.params.y + dot(p - in.a, t);
+        let phase = along - floor(along / period) * period;
+        // distance along the line to the nearest dash, negative inside one
+        let to_dash = select(min(phase - dash, period - phase), -min(phase, dash - phase), phase < dash);
+
+        switch in.ends.z {
+            case CAP_SQUARE: {
+                d = max(d, to_dash - half_width);
+            }
+            case CAP_ROUND: {
+                d = max(d, select(strip, length(vec2f(to_dash, across)) - half_width, to_dash > 0.0));
+            }
+            default: {
+                d = max(d, to_dash);
+            }
+        }
+    }
+
+    // d is in pixels, so coverage ramps over one of them
+    let coverage = clamp(0.5 - d, 0.0, 1.0);
+    return vec4f(in.color.rgb, in.color.a * coverage);
+}
@@ -0,0 +1,16 @@
+// 9 tap gaussian along one axis, taking 5 samples by letting the bilinear
+// filter weigh pairs of texels
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let step = params.direction * params.texel_size * params.strength;
+    let near = step * 1.3846153846;
+    let far = step * 3.2307692308;
+
+    var color = textureSample(input, input_sampler, in.uv) * 0.2270270270;
+    color += textureSample(input, input_sampler, in.uv + near) * 0.3162162162;
+    color += textureSample(input, input_sampler, in.uv - near) * 0.3162162162;
+    color += textureSample(input, input_sampler, in.uv + far) * 0.0702702703;
+    color += textureSample(input, input_sampler, in.uv - far) * 0.0702702703;
+
+    return color;
+}
@@ -0,0 +1,11 @@
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // red and blue drift apart towards the edges
+    let offset = (in.uv - 0.5) * params.strength * 0.02;
+
+    let r = textureSample(input, input_sampler, in.uv + offset).r;
+    let center = textureSample(input, input_sampler, in.uv);
+    let b = textureSample(input, input_sampler, in.uv - offset).b;
+
+    return vec4f(r, center.g, b, center.a);
+}
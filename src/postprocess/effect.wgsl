@@ -0,0 +1,19 @@
+// bindings shared by every effect, the effect's fragment shader is appended to
+// this
+
+struct Params {
+    // one texel in uv units
+    texel_size: vec2<f32>,
+    // step direction of separable effects, in texels
+    direction: vec2<f32>,
+    strength: f32,
+}
+
+@group(0) @binding(0)
+var input: texture_2d<f32>;
+
+@group(0) @binding(1)
+var input_sampler: sampler;
+
+@group(0) @binding(2)
+var<uniform> params: Params;
@@ -0,0 +1,19 @@
+// shared by every fullscreen pass, the pass's own shader is appended to this
+
+struct VertexOutput {
+    @builtin(position) pos: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+// one triangle covering the whole target, uv (0, 0) is the top-left corner
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    var out: VertexOutput;
+
+    let uv = vec2f(f32((index << 1u) & 2u), f32(index & 2u));
+
+    out.pos = vec4f(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    out.uv = uv;
+
+    return out;
+}
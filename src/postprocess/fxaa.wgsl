@@ -0,0 +1,46 @@
+const REDUCE_MIN: f32 = 1.0 / 128.0;
+const REDUCE_MUL: f32 = 1.0 / 8.0;
+const SPAN_MAX: f32 = 8.0;
+
+fn luma(color: vec3<f32>) -> f32 {
+    // edges are judged on perceived brightness, not linear values
+    return dot(sqrt(color), vec3f(0.299, 0.587, 0.114));
+}
+
+fn sample_at(uv: vec2<f32>) -> vec3<f32> {
+    return textureSample(input, input_sampler, uv).rgb;
+}
+
+// blurs along the edge direction estimated from the luma of the diagonal
+// neighbours, unless that overshoots the local contrast
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = params.texel_size;
+    let center = textureSample(input, input_sampler, in.uv);
+
+    let luma_nw = luma(sample_at(in.uv + vec2f(-1.0, -1.0) * texel));
+    let luma_ne = luma(sample_at(in.uv + vec2f(1.0, -1.0) * texel));
+    let luma_sw = luma(sample_at(in.uv + vec2f(-1.0, 1.0) * texel));
+    let luma_se = luma(sample_at(in.uv + vec2f(1.0, 1.0) * texel));
+    let luma_m = luma(center.rgb);
+
+    let luma_min = min(luma_m, min(min(luma_nw, luma_ne), min(luma_sw, luma_se)));
+    let luma_max = max(luma_m, max(max(luma_nw, luma_ne), max(luma_sw, luma_se)));
+
+    var dir = vec2f(
+        (luma_sw + luma_se) - (luma_nw + luma_ne),
+        (luma_nw + luma_sw) - (luma_ne + luma_se),
+    );
+
+    let dir_reduce = max((luma_nw + luma_ne + luma_sw + luma_se) * 0.25 * REDUCE_MUL, REDUCE_MIN);
+    let rcp_dir_min = 1.0 / (min(abs(dir.x), abs(dir.y)) + dir_reduce);
+    dir = clamp(dir * rcp_dir_min, vec2f(-SPAN_MAX), vec2f(SPAN_MAX)) * texel * params.strength;
+
+    let rgb_a = 0.5 * (sample_at(in.uv + dir * (1.0 / 3.0 - 0.5)) + sample_at(in.uv + dir * (2.0 / 3.0 - 0.5)));
+    let rgb_b = rgb_a * 0.5 + 0.25 * (sample_at(in.uv - dir * 0.5) + sample_at(in.uv + dir * 0.5));
+    let luma_b = luma(rgb_b);
+
+    let outside = luma_b < luma_min || luma_b > luma_max;
+
+    return vec4f(select(rgb_b, rgb_a, outside), center.a);
+}
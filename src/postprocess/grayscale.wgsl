@@ -0,0 +1,7 @@
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(input, input_sampler, in.uv);
+    let luma = dot(color.rgb, vec3f(0.2126, 0.7152, 0.0722));
+
+    return vec4f(mix(color.rgb, vec3f(luma), params.strength), color.a);
+}
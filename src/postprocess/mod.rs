@@ -0,0 +1,318 @@
+use std::collections::HashMap;
+
+use bytemuck::{Pod, Zeroable};
+use winit::dpi::PhysicalSize;
+
+use crate::render_target::RenderTarget;
+
+const FULLSCREEN: &str = include_str!("fullscreen.wgsl");
+const EFFECT: &str = include_str!("effect.wgsl");
+
+// every fullscreen pass shares one vertex shader, `source` is appended to it
+pub fn fullscreen_shader(device: &wgpu::Device, label: &str, source: &str) -> wgpu::ShaderModule {
+    device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some(label),
+        source: wgpu::ShaderSource::Wgsl(format!("{FULLSCREEN}\n{source}").into()),
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Effect {
+    Grayscale,
+    Vignette,
+    Blur,
+    ChromaticAberration,
+    Fxaa,
+    Tonemap,
+}
+
+impl Effect {
+    // also the order effects are applied in
+    pub const ALL: [Effect; 6] = [
+        Effect::Tonemap,
+        Effect::Fxaa,
+        Effect::Blur,
+        Effect::ChromaticAberration,
+        Effect::Grayscale,
+        Effect::Vignette,
+    ];
+
+    fn source(self) -> &'static str {
+        match self {
+            Effect::Grayscale => include_str!("grayscale.wgsl"),
+            Effect::Vignette => include_str!("vignette.wgsl"),
+            Effect::Blur => include_str!("blur.wgsl"),
+            Effect::ChromaticAberration => include_str!("chromatic_aberration.wgsl"),
+            Effect::Fxaa => include_str!("fxaa.wgsl"),
+            Effect::Tonemap => include_str!("tonemap.wgsl"),
+        }
+    }
+
+    fn default_strength(self) -> f32 {
+        match self {
+            Effect::Blur => 2.0,
+            _ => 1.0,
+        }
+    }
+
+    // separable effects run once per axis
+    fn directions(self) -> &'static [[f32; 2]] {
+        match self {
+            Effect::Blur => &[[1.0, 0.0], [0.0, 1.0]],
+            _ => &[[0.0, 0.0]],
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+struct Params {
+    texel_size: [f32; 2],
+    direction: [f32; 2],
+    strength: f32,
+    _padding: [f32; 3],
+}
+
+struct Pass {
+    effect: Effect,
+    direction: [f32; 2],
+    params: wgpu::Buffer,
+}
+
+// a stack of full-screen passes, each reading the previous result. effects
+// can be switched on and off at any time, disabled ones cost nothing
+pub struct PostProcess {
+    pipelines: HashMap<Effect, wgpu::RenderPipeline>,
+    passes: Vec<Pass>,
+    enabled: HashMap<Effect, bool>,
+    strengths: HashMap<Effect, f32>,
+    bind_group_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    targets: [RenderTarget; 2],
+}
+
+impl PostProcess {
+    pub fn new(
+        device: &wgpu::Device,
+        size: PhysicalSize<u32>,
+        format: wgpu::TextureFormat,
+    ) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("post process bind group layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("post process pipeline layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipelines = Effect::ALL
+            .into_iter()
+            .map(|effect| {
+                let shader = fullscreen_shader(
+                    device,
+                    &format!("{effect:?} shader"),
+                    &format!("{EFFECT}\n{}", effect.source()),
+                );
+
+                let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                    label: Some(&format!("{effect:?} pipeline")),
+                    layout: Some(&pipeline_layout),
+                    vertex: wgpu::VertexState {
+                        module: &shader,
+                        entry_point: Some("vs_main"),
+                        buffers: &[],
+                        compilation_options: Default::default(),
+                    },
+                    fragment: Some(wgpu::FragmentState {
+                        module: &shader,
+                        entry_point: Some("fs_main"),
+                        compilation_options: Default::default(),
+                        targets: &[Some(wgpu::ColorTargetState {
+                            format,
+                            blend: None,
+                            write_mask: wgpu::ColorWrites::ALL,
+                        })],
+                    }),
+                    primitive: wgpu::PrimitiveState::default(),
+                    multisample: Default::default(),
+                    depth_stencil: None,
+                    multiview: None,
+                    cache: None,
+                });
+
+                (effect, pipeline)
+            })
+            .collect();
+
+        let passes = Effect::ALL
+            .into_iter()
+            .flat_map(|effect| effect.directions().iter().map(move |&dir| (effect, dir)))
+            .map(|(effect, direction)| Pass {
+                effect,
+                direction,
+                params: device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("post process params"),
+                    size: size_of::<Params>() as _,
+                    usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                }),
+            })
+            .collect();
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("post process sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        Self {
+            pipelines,
+            passes,
+            enabled: Effect::ALL.map(|effect| (effect, false)).into(),
+            strengths: Effect::ALL
+                .map(|effect| (effect, effect.default_strength()))
+                .into(),
+            bind_group_layout,
+            sampler,
+            targets: [
+                RenderTarget::new(device, size, format, None),
+                RenderTarget::new(device, size, format, None),
+            ],
+        }
+    }
+
+    pub fn resize(&mut self, device: &wgpu::Device, size: PhysicalSize<u32>) {
+        for target in &mut self.targets {
+            target.resize(device, size);
+        }
+    }
+
+    pub fn is_enabled(&self, effect: Effect) -> bool {
+        self.enabled[&effect]
+    }
+
+    pub fn set_enabled(&mut self, effect: Effect, enabled: bool) {
+        self.enabled.insert(effect, enabled);
+    }
+
+    pub fn toggle(&mut self, effect: Effect) -> bool {
+        let enabled = !self.is_enabled(effect);
+        self.set_enabled(effect, enabled);
+        enabled
+    }
+
+    pub fn set_strength(&mut self, effect: Effect, strength: f32) {
+        self.strengths.insert(effect, strength);
+    }
+
+    // with nothing enabled the scene can be drawn straight to the output
+    pub fn is_active(&self) -> bool {
+        self.enabled.values().any(|&enabled| enabled)
+    }
+
+    // runs every enabled effect, reading `input` and writing the last result
+    // to `output`. both have to match the size the chain was resized to.
+    pub fn run(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        input: &wgpu::TextureView,
+        output: &wgpu::TextureView,
+    ) {
+        let size = self.targets[0].size();
+        let passes: Vec<_> = self
+            .passes
+            .iter()
+            .filter(|pass| self.is_enabled(pass.effect))
+            .collect();
+
+        let mut source = input;
+        for (i, pass) in passes.iter().enumerate() {
+            let target = if i + 1 == passes.len() {
+                output
+            } else {
+                self.targets[i % 2].color_view()
+            };
+
+            let params = Params {
+                texel_size: [1.0 / size.width as f32, 1.0 / size.height as f32],
+                direction: pass.direction,
+                strength: self.strengths[&pass.effect],
+                _padding: [0.0; 3],
+            };
+            queue.write_buffer(&pass.params, 0, bytemuck::bytes_of(&params));
+
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("post process bind group"),
+                layout: &self.bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(source),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&self.sampler),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: pass.params.as_entire_binding(),
+                    },
+                ],
+            });
+
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("post process pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: target,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            render_pass.set_pipeline(&self.pipelines[&pass.effect]);
+            render_pass.set_bind_group(0, &bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+
+            source = target;
+        }
+    }
+}
@@ -0,0 +1,8 @@
+// reinhard, strength is the exposure
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(input, input_sampler, in.uv);
+    let exposed = color.rgb * params.strength;
+
+    return vec4f(exposed / (1.0 + exposed), color.a);
+}
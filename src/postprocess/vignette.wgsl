@@ -0,0 +1,10 @@
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(input, input_sampler, in.uv);
+
+    // 0 in the center, 1 in the corners
+    let distance = length(in.uv - 0.5) * sqrt(2.0);
+    let falloff = 1.0 - smoothstep(0.4, 1.0, distance) * params.strength;
+
+    return vec4f(color.rgb * falloff, color.a);
+}
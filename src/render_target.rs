@@ -0,0 +1,339 @@
+use winit::dpi::PhysicalSize;
+
+use crate::{postprocess::fullscreen_shader, texture::Texture};
+
+// an offscreen color texture with an optional depth buffer that can be drawn
+// into and then sampled. follows the window size through `resize`.
+pub struct RenderTarget {
+    color: wgpu::Texture,
+    color_view: wgpu::TextureView,
+    depth: Option<(wgpu::Texture, wgpu::TextureView)>,
+    format: wgpu::TextureFormat,
+    depth_format: Option<wgpu::TextureFormat>,
+}
+
+impl RenderTarget {
+    pub fn new(
+        device: &wgpu::Device,
+        size: PhysicalSize<u32>,
+        format: wgpu::TextureFormat,
+        depth_format: Option<wgpu::TextureFormat>,
+    ) -> Self {
+        let (color, color_view) = Self::create_texture(
+            device,
+            size,
+            format,
+            wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        );
+        let depth = depth_format.map(|depth_format| {
+            Self::create_texture(
+                device,
+                size,
+                depth_format,
+                wgpu::TextureUsages::RENDER_ATTACHMENT,
+            )
+        });
+
+        Self {
+            color,
+            color_view,
+            depth,
+            format,
+            depth_format,
+        }
+    }
+
+    fn create_texture(
+        device: &wgpu::Device,
+        size: PhysicalSize<u32>,
+        format: wgpu::TextureFormat,
+        usage: wgpu::TextureUsages,
+    ) -> (wgpu::Texture, wgpu::TextureView) {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("render target"),
+            size: wgpu::Extent3d {
+                width: size.width.max(1),
+                height: size.height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&Default::default());
+
+        (texture, view)
+    }
+
+    // recreates the textures when the size changed, views handed out before
+    // keep pointing at the old ones
+    pub fn resize(&mut self, device: &wgpu::Device, size: PhysicalSize<u32>) {
+        if self.size() == PhysicalSize::new(size.width.max(1), size.height.max(1)) {
+            return;
+        }
+
+        *self = Self::new(device, size, self.format, self.depth_format);
+    }
+
+    pub fn size(&self) -> PhysicalSize<u32> {
+        PhysicalSize::new(self.color.width(), self.color.height())
+    }
+
+    pub fn format(&self) -> wgpu::TextureFormat {
+        self.format
+    }
+
+    pub fn depth_format(&self) -> Option<wgpu::TextureFormat> {
+        self.depth_format
+    }
+
+    pub fn color_view(&self) -> &wgpu::TextureView {
+        &self.color_view
+    }
+
+    pub fn depth_view(&self) -> Option<&wgpu::TextureView> {
+        self.depth.as_ref().map(|(_, view)| view)
+    }
+
+    // clears color to `clear` and depth to 1.0
+    pub fn begin_pass<'a>(
+        &self,
+        encoder: &'a mut wgpu::CommandEncoder,
+        clear: wgpu::Color,
+    ) -> wgpu::RenderPass<'a> {
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("render target pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &self.color_view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(clear),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: self.depth_view().map(|view| {
+                wgpu::RenderPassDepthStencilAttachment {
+                    view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: wgpu::StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }
+            }),
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        })
+    }
+}
+
+// the depth buffer of the scene pass, for examples that set `depth`
+pub const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+
+// a depth texture on its own, for passes whose color goes somewhere that
+// isn't a render target, like the swapchain
+pub struct DepthBuffer {
+    view: wgpu::TextureView,
+    size: PhysicalSize<u32>,
+    format: wgpu::TextureFormat,
+}
+
+impl DepthBuffer {
+    pub fn new(
+        device: &wgpu::Device,
+        size: PhysicalSize<u32>,
+        format: wgpu::TextureFormat,
+    ) -> Self {
+        let (_, view) = RenderTarget::create_texture(
+            device,
+            size,
+            format,
+            wgpu::TextureUsages::RENDER_ATTACHMENT,
+        );
+
+        Self {
+            view,
+            size: PhysicalSize::new(size.width.max(1), size.height.max(1)),
+            format,
+        }
+    }
+
+    pub fn resize(&mut self, device: &wgpu::Device, size: PhysicalSize<u32>) {
+        if self.size == PhysicalSize::new(size.width.max(1), size.height.max(1)) {
+            return;
+        }
+
+        *self = Self::new(device, size, self.format);
+    }
+
+    pub fn view(&self) -> &wgpu::TextureView {
+        &self.view
+    }
+}
+
+// what the frame is drawn in when the surface has no srgb view, wide enough
+// for 10 bit and float surfaces
+pub const FRAME_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+// for surfaces without an srgb view, like Rgb10a2Unorm or Rgba16Float. the
+// frame is drawn into a linear target which is then copied onto the surface,
+// encoding to srgb on the way like an srgb view would
+pub struct SrgbBlit {
+    target: RenderTarget,
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    bind_group: wgpu::BindGroup,
+}
+
+impl SrgbBlit {
+    pub fn new(
+        device: &wgpu::Device,
+        size: PhysicalSize<u32>,
+        output_format: wgpu::TextureFormat,
+    ) -> Self {
+        let shader = fullscreen_shader(device, "blit shader", include_str!("texture/blit.wgsl"));
+        let bind_group_layout = Texture::bind_group_layout(device);
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("srgb blit pipeline layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("srgb blit pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_encode_srgb"),
+                compilation_options: Default::default(),
+                targets: &[Some(output_format.into())],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            multisample: Default::default(),
+            depth_stencil: None,
+            multiview: None,
+            cache: None,
+        });
+
+        // same size as the output, every fragment reads its own texel
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("srgb blit sampler"),
+            ..Default::default()
+        });
+
+        let target = RenderTarget::new(device, size, FRAME_FORMAT, None);
+        let bind_group = Self::bind_group(device, &bind_group_layout, &target, &sampler);
+
+        Self {
+            target,
+            pipeline,
+            bind_group_layout,
+            sampler,
+            bind_group,
+        }
+    }
+
+    fn bind_group(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        target: &RenderTarget,
+        sampler: &wgpu::Sampler,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("srgb blit bind group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(target.color_view()),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+            ],
+        })
+    }
+
+    // where the frame is drawn before `run`
+    pub fn target(&self) -> &RenderTarget {
+        &self.target
+    }
+
+    pub fn resize(&mut self, device: &wgpu::Device, size: PhysicalSize<u32>) {
+        self.target.resize(device, size);
+        self.bind_group =
+            Self::bind_group(device, &self.bind_group_layout, &self.target, &self.sampler);
+    }
+
+    pub fn run(&self, encoder: &mut wgpu::CommandEncoder, output: &wgpu::TextureView) {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("srgb blit pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: output,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // linear values cleared into the frame come out of an Rgba8Unorm surface
+    // the same as they would from Rgba8UnormSrgb
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn blit_encodes_like_an_srgb_view() {
+        let (device, queue) = crate::testing::headless_device();
+        let size = PhysicalSize::new(1, 1);
+        let blit = SrgbBlit::new(&device, size, wgpu::TextureFormat::Rgba8Unorm);
+        let (output, output_view) = RenderTarget::create_texture(
+            &device,
+            size,
+            wgpu::TextureFormat::Rgba8Unorm,
+            wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        );
+
+        for (linear, expected) in [(0.0, 0), (0.002, 7), (0.1, 89), (0.5, 188), (1.0, 255)] {
+            let mut encoder = device.create_command_encoder(&Default::default());
+            let color = wgpu::Color {
+                r: linear,
+                g: linear,
+                b: linear,
+                a: 1.0,
+            };
+            drop(blit.target().begin_pass(&mut encoder, color));
+            blit.run(&mut encoder, &output_view);
+
+            let pixel = crate::testing::read_texture(&device, &queue, encoder, &output);
+            assert!(
+                pixel[0].abs_diff(expected) <= 1 && pixel[3] == 255,
+                "{linear} came out as {pixel:?}, expected {expected}"
+            );
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use glam::{vec3, Mat4};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub struct Camera2D {
     pub width: f32,
     pub height: f32,
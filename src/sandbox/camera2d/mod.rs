@@ -1,4 +1,5 @@
 use camera::Camera2D;
+use glam::Vec2;
 use wgpu::{include_wgsl, util::DeviceExt};
 use winit::{
     event::{ElementState, KeyEvent},
@@ -6,6 +7,7 @@ use winit::{
 };
 
 use crate::{
+    debug_draw::DebugDraw,
     graphics::Renderable,
     vertices::{Vertex, VertexPosCol},
 };
@@ -15,6 +17,9 @@ pub mod camera;
 pub struct Sandbox {
     pipeline: wgpu::RenderPipeline,
     camera: Camera2D,
+    // in pixels per key press
+    speed: f32,
+    vertices: [VertexPosCol; 3],
     vertex_buffer: wgpu::Buffer,
     camera_buffer: wgpu::Buffer,
     bind_group: wgpu::BindGroup,
@@ -25,7 +30,7 @@ impl Sandbox {
     pub fn new(device: &wgpu::Device, view_format: wgpu::TextureFormat) -> Self {
         let shader = device.create_shader_module(include_wgsl!("shader.wgsl"));
 
-        let vertices = &[
+        let vertices = [
             VertexPosCol {
                 position: [320.0, 140.0],
                 color: [1.0, 1.0, 1.0],
@@ -76,8 +81,8 @@ impl Sandbox {
 
         let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("vertex buffer"),
-            contents: bytemuck::cast_slice(vertices),
-            usage: wgpu::BufferUsages::VERTEX,
+            contents: bytemuck::cast_slice(&vertices),
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
         });
 
         let camera = Camera2D::new(640, 480, 1.0);
@@ -105,11 +110,21 @@ impl Sandbox {
             pipeline,
             vertex_buffer,
             camera,
+            speed: 3.0,
             bind_group,
             camera_buffer,
             num_vertices: vertices.len() as _,
+            vertices,
         }
     }
+
+    fn write_camera(&self, queue: &wgpu::Queue) {
+        queue.write_buffer(
+            &self.camera_buffer,
+            0,
+            bytemuck::cast_slice(&self.camera.camera_matrix().to_cols_array_2d()),
+        );
+    }
 }
 
 impl Renderable for Sandbox {
@@ -126,27 +141,84 @@ impl Renderable for Sandbox {
 
         match physical_key {
             PhysicalKey::Code(KeyCode::ArrowUp) => {
-                self.camera.y -= 3.0;
+                self.camera.y -= self.speed;
             }
             PhysicalKey::Code(KeyCode::ArrowDown) => {
-                self.camera.y += 3.0;
+                self.camera.y += self.speed;
             }
             PhysicalKey::Code(KeyCode::ArrowLeft) => {
-                self.camera.x -= 3.0;
+                self.camera.x -= self.speed;
             }
             PhysicalKey::Code(KeyCode::ArrowRight) => {
-                self.camera.x += 3.0;
+                self.camera.x += self.speed;
             }
             _ => (),
         }
 
-        queue.write_buffer(
-            &self.camera_buffer,
-            0,
-            bytemuck::cast_slice(&self.camera.camera_matrix().to_cols_array_2d()),
+        self.write_camera(queue);
+    }
+
+    // the world axes and the triangle's bounds, over the scene
+    fn update(&mut self, _delta_time: f32, debug_draw: &mut DebugDraw) {
+        debug_draw.set_camera(&self.camera);
+        debug_draw.grid(50.0, [0.15, 0.15, 0.2]);
+        debug_draw.arrow(Vec2::ZERO, Vec2::new(100.0, 0.0), [1.0, 0.2, 0.2]);
+        debug_draw.arrow(Vec2::ZERO, Vec2::new(0.0, 100.0), [0.2, 1.0, 0.2]);
+
+        let points = self.vertices.map(|vertex| Vec2::from(vertex.position));
+        let min = points.into_iter().reduce(Vec2::min).unwrap();
+        let max = points.into_iter().reduce(Vec2::max).unwrap();
+        debug_draw.rect(min, max - min, [1.0, 0.9, 0.2]);
+
+        let center = points.into_iter().sum::<Vec2>() / points.len() as f32;
+        let radius = points
+            .map(|p| p.distance(center))
+            .into_iter()
+            .fold(0.0, f32::max);
+        debug_draw.circle(center, radius, [0.3, 0.6, 1.0]);
+
+        debug_draw.text(
+            &format!("camera ({:.0}, {:.0})", self.camera.x, self.camera.y),
+            Vec2::new(min.x, max.y + 8.0),
+            [0.8, 0.8, 0.8],
         );
     }
 
+    fn ui(&mut self, ctx: &egui::Context, queue: &wgpu::Queue) {
+        egui::Window::new("Camera").show(ctx, |ui| {
+            ui.add(egui::Slider::new(&mut self.speed, 0.5..=20.0).text("speed"));
+
+            let zoomed = ui
+                .add(
+                    egui::Slider::new(&mut self.camera.scale, 0.1..=10.0)
+                        .logarithmic(true)
+                        .text("zoom"),
+                )
+                .changed();
+            let reset = ui.button("reset position").clicked();
+
+            if reset {
+                (self.camera.x, self.camera.y) = (0.0, 0.0);
+            }
+            if zoomed || reset {
+                self.write_camera(queue);
+            }
+
+            ui.separator();
+
+            let mut changed = false;
+            for (i, vertex) in self.vertices.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    changed |= ui.color_edit_button_rgb(&mut vertex.color).changed();
+                    ui.label(format!("vertex {i}"));
+                });
+            }
+            if changed {
+                queue.write_buffer(&self.vertex_buffer, 0, bytemuck::cast_slice(&self.vertices));
+            }
+        });
+    }
+
     fn render(&self, render_pass: &mut wgpu::RenderPass) {
         render_pass.set_pipeline(&self.pipeline);
         render_pass.set_bind_group(0, &self.bind_group, &[]);
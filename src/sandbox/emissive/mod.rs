@@ -0,0 +1,139 @@
+use bytemuck::{Pod, Zeroable};
+use wgpu::{include_wgsl, util::DeviceExt};
+
+use crate::graphics::Renderable;
+
+const ASPECT: f32 = 640.0 / 480.0;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+struct Shape {
+    center: [f32; 2],
+    size: [f32; 2],
+    color: [f32; 3],
+    kind: u32,
+}
+
+impl Shape {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 4] =
+        wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32x2, 2 => Float32x3, 3 => Uint32];
+
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: size_of::<Shape>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+
+    fn new(kind: u32, center: [f32; 2], radius: [f32; 2], color: [f32; 3], intensity: f32) -> Self {
+        Self {
+            center,
+            size: [radius[0] / ASPECT, radius[1]],
+            color: color.map(|c| c * intensity),
+            kind,
+        }
+    }
+}
+
+// rows of disks, rings and bars from dim to far brighter than white, so the
+// tonemappers and the bloom have something to chew on
+fn shapes() -> Vec<Shape> {
+    const DISK: u32 = 0;
+    const RING: u32 = 1;
+    const BOX: u32 = 2;
+
+    let colors = [
+        [1.0, 0.3, 0.1],
+        [0.2, 1.0, 0.3],
+        [0.2, 0.4, 1.0],
+        [1.0, 0.9, 0.6],
+    ];
+    let intensities = [0.5, 1.0, 4.0, 16.0, 64.0];
+
+    let mut shapes = Vec::new();
+    for (i, &intensity) in intensities.iter().enumerate() {
+        let x = -0.8 + i as f32 * 0.4;
+        let color = colors[i % colors.len()];
+
+        shapes.push(Shape::new(DISK, [x, 0.55], [0.12, 0.12], color, intensity));
+        shapes.push(Shape::new(RING, [x, 0.0], [0.14, 0.14], color, intensity));
+    }
+
+    // thin bars show how far the glow reaches past hard edges
+    for (i, color) in colors.iter().enumerate() {
+        let y = -0.45 - i as f32 * 0.12;
+        shapes.push(Shape::new(BOX, [0.0, y], [0.9, 0.01], *color, 8.0));
+    }
+
+    shapes
+}
+
+pub struct Sandbox {
+    pipeline: wgpu::RenderPipeline,
+    instance_buffer: wgpu::Buffer,
+    instance_count: u32,
+}
+
+impl Sandbox {
+    pub fn new(device: &wgpu::Device, view_format: wgpu::TextureFormat) -> Self {
+        let shader = device.create_shader_module(include_wgsl!("shader.wgsl"));
+        let shapes = shapes();
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("pipeline layout"),
+            bind_group_layouts: &[],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("render pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[Shape::desc()],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: view_format,
+                    blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            multisample: Default::default(),
+            depth_stencil: None,
+            multiview: None,
+            cache: None,
+        });
+
+        let instance_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("instance buffer"),
+            contents: bytemuck::cast_slice(&shapes),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        Self {
+            pipeline,
+            instance_buffer,
+            instance_count: shapes.len() as _,
+        }
+    }
+}
+
+impl Renderable for Sandbox {
+    fn needs_hdr() -> bool {
+        true
+    }
+
+    fn render(&self, render_pass: &mut wgpu::RenderPass) {
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_vertex_buffer(0, self.instance_buffer.slice(..));
+        render_pass.draw(0..6, 0..self.instance_count);
+    }
+}
@@ -0,0 +1,59 @@
+struct InstanceInput {
+    @location(0) center: vec2<f32>,
+    @location(1) size: vec2<f32>,
+    // linear rgb, values above 1 are what makes things glow
+    @location(2) color: vec3<f32>,
+    // 0 disk, 1 ring, 2 box
+    @location(3) shape: u32,
+}
+
+struct VertexOutput {
+    @builtin(position) pos: vec4<f32>,
+    @location(0) local: vec2<f32>,
+    @location(1) color: vec3<f32>,
+    @location(2) @interpolate(flat) shape: u32,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32, instance: InstanceInput) -> VertexOutput {
+    var out: VertexOutput;
+
+    // two triangles spanning -1..1
+    var corners = array(
+        vec2f(-1.0, -1.0), vec2f(1.0, -1.0), vec2f(-1.0, 1.0),
+        vec2f(-1.0, 1.0), vec2f(1.0, -1.0), vec2f(1.0, 1.0),
+    );
+    let corner = corners[index];
+
+    out.pos = vec4f(instance.center + corner * instance.size, 0.0, 1.0);
+    out.local = corner;
+    out.color = instance.color;
+    out.shape = instance.shape;
+
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let aa = fwidth(length(in.local));
+    var coverage: f32;
+
+    switch in.shape {
+        case 1u: {
+            let d = abs(length(in.local) - 0.8);
+            coverage = 1.0 - smoothstep(0.15 - aa, 0.15 + aa, d);
+        }
+        case 2u: {
+            coverage = 1.0;
+        }
+        default: {
+            coverage = 1.0 - smoothstep(1.0 - aa * 2.0, 1.0, length(in.local));
+        }
+    }
+
+    if coverage <= 0.0 {
+        discard;
+    }
+
+    return vec4f(in.color * coverage, coverage);
+}
@@ -0,0 +1,381 @@
+use bytemuck::{Pod, Zeroable};
+use glam::{DVec2, Vec2};
+use wgpu::include_wgsl;
+use winit::{
+    dpi::PhysicalSize,
+    event::{ElementState, KeyEvent, MouseButton},
+    keyboard::{KeyCode, PhysicalKey},
+};
+
+use crate::{
+    graphics::{MouseEvent, Renderable},
+    texture::{sampler::SamplerPreset, ColorSpace, Mipmaps, Texture, TextureOptions},
+};
+
+const PALETTE_WIDTH: usize = 256;
+// (position, srgb color) stops, the last one blends back into the first
+const PALETTES: [&[(f32, [u8; 3])]; 4] = [
+    &[
+        (0.0, [0, 7, 100]),
+        (0.16, [32, 107, 203]),
+        (0.42, [237, 255, 255]),
+        (0.6425, [255, 170, 0]),
+        (0.8575, [0, 2, 0]),
+    ],
+    &[
+        (0.0, [0, 0, 0]),
+        (0.25, [140, 10, 0]),
+        (0.5, [255, 140, 0]),
+        (0.75, [255, 250, 170]),
+    ],
+    &[
+        (0.0, [0, 0, 30]),
+        (0.3, [0, 70, 160]),
+        (0.6, [210, 240, 255]),
+        (0.85, [40, 90, 160]),
+    ],
+    &[(0.0, [10, 10, 10]), (0.5, [245, 245, 245])],
+];
+
+// past this single precision starts to show blocks of equal pixels
+const PRECISE_BELOW: f64 = 1e-3;
+// float-float runs out of bits a bit further down
+const MIN_SCALE: f64 = 1e-13;
+const MAX_SCALE: f64 = 4.0;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+struct Params {
+    center: [f32; 4],
+    julia: [f32; 4],
+    extent: [f32; 2],
+    max_iterations: u32,
+    julia_mode: u32,
+    high_precision: u32,
+    palette: u32,
+    palette_count: u32,
+    color_offset: f32,
+    zero: u32,
+    _padding: [f32; 3],
+}
+
+#[derive(Debug, Clone, Copy)]
+struct View {
+    center: DVec2,
+    // complex units from the center to the top edge
+    scale: f64,
+}
+
+impl View {
+    const MANDELBROT: View = View {
+        center: DVec2::new(-0.5, 0.0),
+        scale: 1.25,
+    };
+    const JULIA: View = View {
+        center: DVec2::ZERO,
+        scale: 1.5,
+    };
+}
+
+// a double as the sum of two floats, the high part holding what an f32 can
+fn split(value: f64) -> [f32; 2] {
+    let hi = value as f32;
+    [hi, (value - hi as f64) as f32]
+}
+
+fn palette_data() -> Vec<u8> {
+    PALETTES
+        .iter()
+        .flat_map(|stops| {
+            (0..PALETTE_WIDTH).flat_map(move |x| {
+                let t = x as f32 / PALETTE_WIDTH as f32;
+                let i = stops
+                    .iter()
+                    .rposition(|&(position, _)| position <= t)
+                    .unwrap();
+                let (from, a) = stops[i];
+                let (to, b) = stops.get(i + 1).copied().unwrap_or((1.0, stops[0].1));
+                let f = (t - from) / (to - from);
+
+                let [r, g, b] =
+                    [0, 1, 2].map(|c| (a[c] as f32 + (b[c] as f32 - a[c] as f32) * f) as u8);
+                [r, g, b, 255]
+            })
+        })
+        .collect()
+}
+
+pub struct Sandbox {
+    pipeline: wgpu::RenderPipeline,
+    params_buffer: wgpu::Buffer,
+    params_bind_group: wgpu::BindGroup,
+    palette_bind_group: wgpu::BindGroup,
+    mandelbrot_view: View,
+    julia_view: View,
+    julia_mode: bool,
+    julia: DVec2,
+    max_iterations: u32,
+    palette: usize,
+    color_offset: f32,
+    aspect: f64,
+    // in normalized device coordinates
+    cursor: Vec2,
+    dragging: bool,
+}
+
+impl Sandbox {
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        view_format: wgpu::TextureFormat,
+    ) -> Self {
+        let shader = device.create_shader_module(include_wgsl!("shader.wgsl"));
+
+        let params_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("fractal params buffer"),
+            size: size_of::<Params>() as _,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let params_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("fractal params bind group layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+
+        let params_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("fractal params bind group"),
+            layout: &params_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: params_buffer.as_entire_binding(),
+            }],
+        });
+
+        // one palette per row
+        let palette = Texture::from_raw_data(
+            device,
+            queue,
+            &palette_data(),
+            (PALETTE_WIDTH as u32, PALETTES.len() as u32),
+            TextureOptions {
+                mipmaps: Mipmaps::None,
+                sampler: SamplerPreset::Bilinear,
+                color_space: ColorSpace::Srgb,
+            },
+        );
+        let texture_layout = Texture::bind_group_layout(device);
+        let palette_bind_group = palette.bind_group(device, &texture_layout);
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("pipeline layout"),
+            bind_group_layouts: &[&params_layout, &texture_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("render pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: view_format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            multisample: Default::default(),
+            depth_stencil: None,
+            multiview: None,
+            cache: None,
+        });
+
+        let sandbox = Self {
+            pipeline,
+            params_buffer,
+            params_bind_group,
+            palette_bind_group,
+            mandelbrot_view: View::MANDELBROT,
+            julia_view: View::JULIA,
+            julia_mode: false,
+            julia: DVec2::new(-0.8, 0.156),
+            max_iterations: 512,
+            palette: 0,
+            color_offset: 0.0,
+            aspect: 640.0 / 480.0,
+            cursor: Vec2::ZERO,
+            dragging: false,
+        };
+        sandbox.write_params(queue);
+
+        sandbox
+    }
+
+    fn view(&self) -> &View {
+        if self.julia_mode {
+            &self.julia_view
+        } else {
+            &self.mandelbrot_view
+        }
+    }
+
+    fn view_mut(&mut self) -> &mut View {
+        if self.julia_mode {
+            &mut self.julia_view
+        } else {
+            &mut self.mandelbrot_view
+        }
+    }
+
+    // the complex number under a point on screen
+    fn point_at(&self, ndc: Vec2) -> DVec2 {
+        let view = self.view();
+        view.center + DVec2::new(ndc.x as f64 * self.aspect, ndc.y as f64) * view.scale
+    }
+
+    // scales the view by `factor`, keeping the point under `ndc` in place
+    fn zoom(&mut self, factor: f64, ndc: Vec2) {
+        let anchor = self.point_at(ndc);
+        let view = self.view_mut();
+        let scale = (view.scale * factor).clamp(MIN_SCALE, MAX_SCALE);
+
+        view.center = anchor + (view.center - anchor) * (scale / view.scale);
+        view.scale = scale;
+    }
+
+    fn write_params(&self, queue: &wgpu::Queue) {
+        let view = self.view();
+        let [x_hi, x_lo] = split(view.center.x);
+        let [y_hi, y_lo] = split(view.center.y);
+        let [julia_x_hi, julia_x_lo] = split(self.julia.x);
+        let [julia_y_hi, julia_y_lo] = split(self.julia.y);
+
+        let params = Params {
+            center: [x_hi, x_lo, y_hi, y_lo],
+            julia: [julia_x_hi, julia_x_lo, julia_y_hi, julia_y_lo],
+            extent: [(view.scale * self.aspect) as f32, view.scale as f32],
+            max_iterations: self.max_iterations,
+            julia_mode: self.julia_mode as u32,
+            high_precision: (view.scale < PRECISE_BELOW) as u32,
+            palette: self.palette as u32,
+            palette_count: PALETTES.len() as u32,
+            color_offset: self.color_offset,
+            zero: 0,
+            _padding: [0.0; 3],
+        };
+        queue.write_buffer(&self.params_buffer, 0, bytemuck::bytes_of(&params));
+    }
+}
+
+impl Renderable for Sandbox {
+    fn handle_input(&mut self, key_event: KeyEvent, queue: &wgpu::Queue) {
+        let KeyEvent {
+            physical_key,
+            state,
+            ..
+        } = key_event;
+
+        if state == ElementState::Released {
+            return;
+        };
+
+        let pan = self.view().scale * 0.1;
+
+        match physical_key {
+            PhysicalKey::Code(KeyCode::ArrowUp) => self.view_mut().center.y += pan,
+            PhysicalKey::Code(KeyCode::ArrowDown) => self.view_mut().center.y -= pan,
+            PhysicalKey::Code(KeyCode::ArrowLeft) => self.view_mut().center.x -= pan,
+            PhysicalKey::Code(KeyCode::ArrowRight) => self.view_mut().center.x += pan,
+            PhysicalKey::Code(KeyCode::KeyE) => self.zoom(0.8, Vec2::ZERO),
+            PhysicalKey::Code(KeyCode::KeyQ) => self.zoom(1.25, Vec2::ZERO),
+            PhysicalKey::Code(KeyCode::BracketRight) => {
+                self.max_iterations = (self.max_iterations * 2).min(1 << 16);
+                log::info!("{} iterations", self.max_iterations);
+            }
+            PhysicalKey::Code(KeyCode::BracketLeft) => {
+                self.max_iterations = (self.max_iterations / 2).max(16);
+                log::info!("{} iterations", self.max_iterations);
+            }
+            // the julia set for the point under the cursor
+            PhysicalKey::Code(KeyCode::KeyJ) => {
+                if !self.julia_mode {
+                    self.julia = self.point_at(self.cursor);
+                    self.julia_view = View::JULIA;
+                    log::info!("julia set for {} {:+}i", self.julia.x, self.julia.y);
+                }
+                self.julia_mode = !self.julia_mode;
+            }
+            PhysicalKey::Code(KeyCode::KeyP) => self.palette = (self.palette + 1) % PALETTES.len(),
+            PhysicalKey::Code(KeyCode::KeyC) => {
+                self.color_offset = (self.color_offset + 0.05).fract()
+            }
+            PhysicalKey::Code(KeyCode::KeyR) => {
+                *self.view_mut() = if self.julia_mode {
+                    View::JULIA
+                } else {
+                    View::MANDELBROT
+                }
+            }
+            _ => return,
+        }
+
+        log::debug!(
+            "center {:?} scale {:e}",
+            self.view().center,
+            self.view().scale
+        );
+        self.write_params(queue);
+    }
+
+    // dragging with the left button pans, the wheel zooms around the cursor
+    fn handle_mouse(&mut self, mouse_event: MouseEvent, queue: &wgpu::Queue) {
+        match mouse_event {
+            MouseEvent::Moved(ndc) => {
+                let delta = self.point_at(ndc) - self.point_at(self.cursor);
+                self.cursor = ndc;
+
+                if !self.dragging {
+                    return;
+                }
+                self.view_mut().center -= delta;
+            }
+            MouseEvent::Button(MouseButton::Left, state) => {
+                self.dragging = state == ElementState::Pressed;
+            }
+            MouseEvent::Wheel(lines) => self.zoom(0.8f64.powf(lines as f64), self.cursor),
+            _ => return,
+        }
+
+        self.write_params(queue);
+    }
+
+    fn resize(&mut self, size: PhysicalSize<u32>, queue: &wgpu::Queue) {
+        self.aspect = size.width.max(1) as f64 / size.height.max(1) as f64;
+        self.write_params(queue);
+    }
+
+    fn render(&self, render_pass: &mut wgpu::RenderPass) {
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.params_bind_group, &[]);
+        render_pass.set_bind_group(1, &self.palette_bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
@@ -0,0 +1,191 @@
+struct Params {
+    // x and y of the view center, each split into a high and a low part
+    center: vec4<f32>,
+    // the julia constant, split the same way
+    julia: vec4<f32>,
+    // complex units from the center to the right and top edges
+    extent: vec2<f32>,
+    max_iterations: u32,
+    julia_mode: u32,
+    // set once single precision can't resolve the pixels anymore
+    high_precision: u32,
+    palette: u32,
+    palette_count: u32,
+    color_offset: f32,
+    // always 0, see `exact`
+    zero: u32,
+}
+
+@group(0) @binding(0)
+var<uniform> params: Params;
+
+@group(1) @binding(0)
+var palette_texture: texture_2d<f32>;
+
+@group(1) @binding(1)
+var palette_sampler: sampler;
+
+struct VertexOutput {
+    @builtin(position) pos: vec4<f32>,
+    @location(0) ndc: vec2<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    var out: VertexOutput;
+
+    // one triangle covering the screen
+    let uv = vec2f(f32((index << 1u) & 2u), f32(index & 2u));
+
+    out.pos = vec4f(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    out.ndc = out.pos.xy;
+
+    return out;
+}
+
+// float-float arithmetic. a number is stored as vec2(hi, lo) with the value
+// hi + lo, which gives about twice the mantissa bits of a single f32
+
+// shader compilers happily simplify (a + b) - a to b or reorder sums, which
+// throws away the rounding errors float-float is made of. every intermediate
+// result takes a round trip through the integer bits, which float rewrites
+// can't see through. multiplying by a uniform one is not enough for mesa.
+fn exact(value: f32) -> f32 {
+    return bitcast<f32>(bitcast<u32>(value) ^ params.zero);
+}
+
+fn two_sum(a: f32, b: f32) -> vec2<f32> {
+    let s = exact(a + b);
+    let v = exact(s - a);
+    let a_err = exact(a - exact(s - v));
+    let b_err = exact(b - v);
+    return vec2f(s, a_err + b_err);
+}
+
+fn quick_two_sum(a: f32, b: f32) -> vec2<f32> {
+    let s = exact(a + b);
+    return vec2f(s, b - exact(s - a));
+}
+
+// splits a into two halves of 12 bits each, so their products are exact
+fn split(a: f32) -> vec2<f32> {
+    let t = exact(4097.0 * a);
+    let hi = exact(t - exact(t - a));
+    return vec2f(hi, a - hi);
+}
+
+// fma isn't guaranteed to be fused, so the exact product is built by hand
+fn two_prod(a: f32, b: f32) -> vec2<f32> {
+    let p = exact(a * b);
+    let a_split = split(a);
+    let b_split = split(b);
+    let high = exact(a_split.x * b_split.x - p);
+    let cross = exact(high + a_split.x * b_split.y + a_split.y * b_split.x);
+    let err = cross + a_split.y * b_split.y;
+    return vec2f(p, err);
+}
+
+fn df_add(a: vec2<f32>, b: vec2<f32>) -> vec2<f32> {
+    let s = two_sum(a.x, b.x);
+    return quick_two_sum(s.x, s.y + a.y + b.y);
+}
+
+fn df_sub(a: vec2<f32>, b: vec2<f32>) -> vec2<f32> {
+    return df_add(a, -b);
+}
+
+fn df_mul(a: vec2<f32>, b: vec2<f32>) -> vec2<f32> {
+    let p = two_prod(a.x, b.x);
+    return quick_two_sum(p.x, p.y + a.x * b.y + a.y * b.x);
+}
+
+// a large bailout radius keeps the smooth coloring free of bands
+const BAILOUT: f32 = 65536.0;
+
+struct Escape {
+    iterations: u32,
+    // squared magnitude of z once it escaped
+    magnitude: f32,
+}
+
+fn iterate(z_start: vec2<f32>, c: vec2<f32>) -> Escape {
+    var z = z_start;
+    var i = 0u;
+    var magnitude = 0.0;
+
+    for (; i < params.max_iterations; i++) {
+        let z2 = z * z;
+        magnitude = z2.x + z2.y;
+        if magnitude > BAILOUT {
+            break;
+        }
+        z = vec2f(z2.x - z2.y + c.x, 2.0 * z.x * z.y + c.y);
+    }
+
+    return Escape(i, magnitude);
+}
+
+fn iterate_precise(z_start: vec4<f32>, c: vec4<f32>) -> Escape {
+    var x = z_start.xy;
+    var y = z_start.zw;
+    var i = 0u;
+    var magnitude = 0.0;
+
+    for (; i < params.max_iterations; i++) {
+        let x2 = df_mul(x, x);
+        let y2 = df_mul(y, y);
+        magnitude = x2.x + y2.x;
+        if magnitude > BAILOUT {
+            break;
+        }
+
+        // 2xy, doubling is exact so both parts scale
+        let xy = df_mul(x, y);
+        y = df_add(xy * 2.0, c.zw);
+        x = df_add(df_sub(x2, y2), c.xy);
+    }
+
+    return Escape(i, magnitude);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let offset = in.ndc * params.extent;
+
+    var escape: Escape;
+    if params.high_precision != 0u {
+        // the pixel offset is tiny next to the center, adding it in float-float
+        // keeps the bits a plain f32 sum would round away
+        let point = vec4f(
+            df_add(params.center.xy, vec2f(offset.x, 0.0)),
+            df_add(params.center.zw, vec2f(offset.y, 0.0)),
+        );
+
+        if params.julia_mode != 0u {
+            escape = iterate_precise(point, params.julia);
+        } else {
+            escape = iterate_precise(vec4f(0.0), point);
+        }
+    } else {
+        let point = params.center.xz + offset;
+
+        if params.julia_mode != 0u {
+            escape = iterate(point, params.julia.xz);
+        } else {
+            escape = iterate(vec2f(0.0), point);
+        }
+    }
+
+    if escape.iterations >= params.max_iterations {
+        return vec4f(0.0, 0.0, 0.0, 1.0);
+    }
+
+    // the fractional iteration count at which z crossed the bailout radius
+    let smooth_iterations = f32(escape.iterations) + 1.0 - log2(0.5 * log2(escape.magnitude));
+
+    let u = fract(smooth_iterations / 48.0 + params.color_offset);
+    let v = (f32(params.palette) + 0.5) / f32(params.palette_count);
+
+    // sampled after a non-uniform branch, so without implicit derivatives
+    return textureSampleLevel(palette_texture, palette_sampler, vec2f(u, v), 0.0);
+}
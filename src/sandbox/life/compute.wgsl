@@ -0,0 +1,76 @@
+struct Params {
+    // bit n set means n live neighbours give birth / let a cell survive
+    birth: u32,
+    survive: u32,
+    // 0 keeps the generation, only applying the brush
+    advance: u32,
+    // 0 is off, 1 paints live cells, 2 erases them
+    brush: u32,
+    // the brush is swept from one point to the other, in cells
+    brush_from: vec2<f32>,
+    brush_to: vec2<f32>,
+    brush_radius: f32,
+}
+
+@group(0) @binding(0)
+var current: texture_2d<f32>;
+
+@group(0) @binding(1)
+var next: texture_storage_2d<rgba8unorm, write>;
+
+@group(0) @binding(2)
+var<uniform> params: Params;
+
+fn is_alive(cell: vec2<i32>, size: vec2<i32>) -> u32 {
+    // the grid wraps around at the edges
+    let wrapped = (cell + size) % size;
+    return u32(textureLoad(current, wrapped, 0).r > 0.5);
+}
+
+fn segment_distance(point: vec2<f32>, a: vec2<f32>, b: vec2<f32>) -> f32 {
+    let ab = b - a;
+    let t = clamp(dot(point - a, ab) / max(dot(ab, ab), 1e-6), 0.0, 1.0);
+    return distance(point, a + ab * t);
+}
+
+@compute @workgroup_size(8, 8)
+fn step(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = vec2<i32>(textureDimensions(current));
+    let cell = vec2<i32>(id.xy);
+    if any(cell >= size) {
+        return;
+    }
+
+    let state = textureLoad(current, cell, 0);
+    var alive = state.r > 0.5;
+    // recently dead cells leave a fading trail in g
+    var trail = state.g;
+
+    if params.advance != 0u {
+        var neighbours = 0u;
+        for (var y = -1; y <= 1; y++) {
+            for (var x = -1; x <= 1; x++) {
+                if x != 0 || y != 0 {
+                    neighbours += is_alive(cell + vec2(x, y), size);
+                }
+            }
+        }
+
+        let rule = select(params.birth, params.survive, alive);
+        alive = ((rule >> neighbours) & 1u) == 1u;
+        trail *= 0.96;
+    }
+
+    if params.brush != 0u {
+        let center = vec2<f32>(cell) + 0.5;
+        if segment_distance(center, params.brush_from, params.brush_to) <= params.brush_radius {
+            alive = params.brush == 1u;
+        }
+    }
+
+    if alive {
+        trail = 1.0;
+    }
+
+    textureStore(next, cell, vec4(f32(alive), trail, 0.0, 1.0));
+}
@@ -0,0 +1,599 @@
+use std::time::SystemTime;
+
+use bytemuck::{Pod, Zeroable};
+use glam::Vec2;
+use wgpu::{include_wgsl, util::DeviceExt};
+use winit::{
+    dpi::PhysicalSize,
+    event::{ElementState, KeyEvent, MouseButton},
+    keyboard::{KeyCode, PhysicalKey},
+};
+
+use crate::{
+    debug_draw::DebugDraw,
+    graphics::{MouseEvent, Renderable},
+    texture::{sampler::SamplerPreset, ColorSpace, Mipmaps, Texture, TextureOptions},
+};
+
+const GRID_SIZE: u32 = 1024;
+const BRUSH_RADIUS: f32 = 6.0;
+// more would stall the frame instead of speeding anything up
+const MAX_STEPS_PER_FRAME: u32 = 32;
+
+// cycled through with r
+const RULES: [(&str, &str); 6] = [
+    ("life", "B3/S23"),
+    ("highlife", "B36/S23"),
+    ("seeds", "B2/S"),
+    ("day & night", "B3678/S34678"),
+    ("maze", "B3/S12345"),
+    ("replicator", "B1357/S1357"),
+];
+
+// a life-like rule, bit n of either mask is set when n live neighbours make a
+// dead cell come alive or keep a live one alive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rule {
+    pub birth: u32,
+    pub survive: u32,
+}
+
+impl Rule {
+    // parses rules written as "B3/S23", in either order and any case
+    pub fn parse(rule: &str) -> Result<Self, String> {
+        let mut birth = None;
+        let mut survive = None;
+
+        for part in rule.split('/') {
+            let mut chars = part.trim().chars();
+            let mask = match chars.next().map(|c| c.to_ascii_uppercase()) {
+                Some('B') => &mut birth,
+                Some('S') => &mut survive,
+                _ => return Err(format!("invalid rule {rule:?}, expected e.g. B3/S23")),
+            };
+
+            if mask.is_some() {
+                return Err(format!("rule {rule:?} repeats {part:?}"));
+            }
+
+            let mut bits = 0;
+            for c in chars {
+                match c.to_digit(10) {
+                    Some(count @ 0..=8) => bits |= 1 << count,
+                    _ => return Err(format!("invalid neighbour count {c:?} in rule {rule:?}")),
+                }
+            }
+            *mask = Some(bits);
+        }
+
+        match (birth, survive) {
+            (Some(birth), Some(survive)) => Ok(Self { birth, survive }),
+            _ => Err(format!("rule {rule:?} needs both a B and an S part")),
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+struct Params {
+    birth: u32,
+    survive: u32,
+    advance: u32,
+    brush: u32,
+    brush_from: [f32; 2],
+    brush_to: [f32; 2],
+    brush_radius: f32,
+    _padding: [f32; 3],
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+struct View {
+    scale: [f32; 2],
+    _padding: [f32; 2],
+}
+
+// the same fit as the vertex shader: the square grid fills the shorter side
+// of the window
+fn grid_scale(size: PhysicalSize<u32>) -> Vec2 {
+    let aspect = size.width.max(1) as f32 / size.height.max(1) as f32;
+
+    if aspect > 1.0 {
+        Vec2::new(1.0 / aspect, 1.0)
+    } else {
+        Vec2::new(1.0, aspect)
+    }
+}
+
+// the cell under a cursor position in normalized device coordinates
+fn cell_at(ndc: Vec2, scale: Vec2) -> Vec2 {
+    let ndc = ndc / scale;
+    let uv = Vec2::new(ndc.x * 0.5 + 0.5, 0.5 - ndc.y * 0.5);
+
+    uv * GRID_SIZE as f32
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Brush {
+    Paint,
+    Erase,
+}
+
+pub struct Sandbox {
+    step_pipeline: wgpu::ComputePipeline,
+    render_pipeline: wgpu::RenderPipeline,
+    // two states, each generation reads one and writes the other
+    state_textures: [wgpu::Texture; 2],
+    // [i] reads state i and writes the other one
+    compute_bind_groups: [wgpu::BindGroup; 2],
+    render_bind_groups: [wgpu::BindGroup; 2],
+    params_buffer: wgpu::Buffer,
+    view_buffer: wgpu::Buffer,
+    view_bind_group: wgpu::BindGroup,
+    scale: Vec2,
+    current: usize,
+    rule: Rule,
+    rule_index: usize,
+    paused: bool,
+    generations_per_second: f32,
+    // fractional generations carried over to the next frame
+    step_budget: f32,
+    // single steps requested while paused
+    pending_steps: u32,
+    brush: Option<Brush>,
+    // in cells
+    cursor: Vec2,
+    brush_from: Vec2,
+}
+
+impl Sandbox {
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        view_format: wgpu::TextureFormat,
+    ) -> Self {
+        let compute_shader = device.create_shader_module(include_wgsl!("compute.wgsl"));
+        let shader = device.create_shader_module(include_wgsl!("shader.wgsl"));
+
+        let state_format = wgpu::TextureFormat::Rgba8Unorm;
+        let state_textures = [0, 1].map(|_| {
+            device.create_texture(&wgpu::TextureDescriptor {
+                label: Some("life state"),
+                size: wgpu::Extent3d {
+                    width: GRID_SIZE,
+                    height: GRID_SIZE,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: state_format,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING
+                    | wgpu::TextureUsages::STORAGE_BINDING
+                    | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            })
+        });
+        let options = TextureOptions {
+            mipmaps: Mipmaps::None,
+            sampler: SamplerPreset::Nearest,
+            color_space: ColorSpace::Linear,
+        };
+        let states = state_textures.each_ref().map(|texture| {
+            Texture::from_texture(device, texture, wgpu::TextureViewDimension::D2, options)
+        });
+
+        let params_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("life params buffer"),
+            size: size_of::<Params>() as _,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let compute_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("life compute bind group layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::StorageTexture {
+                        access: wgpu::StorageTextureAccess::WriteOnly,
+                        format: state_format,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let compute_bind_groups = [0, 1].map(|i| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("life compute bind group"),
+                layout: &compute_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&states[i].texture_view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(&states[1 - i].texture_view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: params_buffer.as_entire_binding(),
+                    },
+                ],
+            })
+        });
+
+        let texture_layout = Texture::bind_group_layout(device);
+        let render_bind_groups = [0, 1].map(|i| states[i].bind_group(device, &texture_layout));
+
+        let view_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("life view buffer"),
+            contents: bytemuck::bytes_of(&View {
+                scale: [1.0; 2],
+                _padding: [0.0; 2],
+            }),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let view_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("life view bind group layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let view_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("life view bind group"),
+            layout: &view_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: view_buffer.as_entire_binding(),
+            }],
+        });
+
+        let compute_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("life compute pipeline layout"),
+                bind_group_layouts: &[&compute_layout],
+                push_constant_ranges: &[],
+            });
+
+        let step_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("life step pipeline"),
+            layout: Some(&compute_pipeline_layout),
+            module: &compute_shader,
+            entry_point: Some("step"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        let render_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("pipeline layout"),
+                bind_group_layouts: &[&texture_layout, &view_layout],
+                push_constant_ranges: &[],
+            });
+
+        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("render pipeline"),
+            layout: Some(&render_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: view_format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            multisample: Default::default(),
+            depth_stencil: None,
+            multiview: None,
+            cache: None,
+        });
+
+        let sandbox = Self {
+            step_pipeline,
+            render_pipeline,
+            state_textures,
+            compute_bind_groups,
+            render_bind_groups,
+            params_buffer,
+            view_buffer,
+            view_bind_group,
+            scale: Vec2::ONE,
+            current: 0,
+            rule: Rule::parse(RULES[0].1).unwrap(),
+            rule_index: 0,
+            paused: false,
+            generations_per_second: 30.0,
+            step_budget: 0.0,
+            pending_steps: 0,
+            brush: None,
+            cursor: Vec2::ZERO,
+            brush_from: Vec2::ZERO,
+        };
+        sandbox.randomize(queue);
+
+        sandbox
+    }
+
+    pub fn rule(&self) -> Rule {
+        self.rule
+    }
+
+    pub fn set_rule(&mut self, rule: &str) -> Result<(), String> {
+        self.rule = Rule::parse(rule)?;
+        Ok(())
+    }
+
+    fn write_state(&self, queue: &wgpu::Queue, cells: &[u8]) {
+        let texture = &self.state_textures[self.current];
+        queue.write_texture(
+            texture.as_image_copy(),
+            cells,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(GRID_SIZE * 4),
+                rows_per_image: None,
+            },
+            texture.size(),
+        );
+    }
+
+    // a quarter of the cells start alive
+    fn randomize(&self, queue: &wgpu::Queue) {
+        let seed = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(1, |time| time.subsec_nanos());
+
+        let mut state = seed | 1;
+        let cells: Vec<u8> = (0..GRID_SIZE * GRID_SIZE)
+            .flat_map(|_| {
+                // xorshift32
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+
+                let alive = if state >> 30 == 0 { 255 } else { 0 };
+                [alive, alive, 0, 255]
+            })
+            .collect();
+
+        self.write_state(queue, &cells);
+    }
+
+    fn clear(&self, queue: &wgpu::Queue) {
+        let cells = [0, 0, 0, 255].repeat((GRID_SIZE * GRID_SIZE) as usize);
+        self.write_state(queue, &cells);
+    }
+}
+
+impl Renderable for Sandbox {
+    fn handle_input(&mut self, key_event: KeyEvent, queue: &wgpu::Queue) {
+        let KeyEvent {
+            physical_key,
+            state,
+            ..
+        } = key_event;
+
+        if state == ElementState::Released {
+            return;
+        };
+
+        match physical_key {
+            PhysicalKey::Code(KeyCode::Space) => {
+                self.paused = !self.paused;
+                self.step_budget = 0.0;
+                log::info!("{}", if self.paused { "paused" } else { "running" });
+            }
+            PhysicalKey::Code(KeyCode::KeyN) if self.paused => {
+                self.pending_steps += 1;
+            }
+            PhysicalKey::Code(KeyCode::ArrowUp) => {
+                self.generations_per_second = (self.generations_per_second * 2.0).min(960.0);
+                log::info!("{} generations per second", self.generations_per_second);
+            }
+            PhysicalKey::Code(KeyCode::ArrowDown) => {
+                self.generations_per_second = (self.generations_per_second / 2.0).max(0.25);
+                log::info!("{} generations per second", self.generations_per_second);
+            }
+            PhysicalKey::Code(KeyCode::KeyR) => {
+                self.rule_index = (self.rule_index + 1) % RULES.len();
+                let (name, rule) = RULES[self.rule_index];
+                self.set_rule(rule).unwrap();
+                log::info!("rule {name} ({rule})");
+            }
+            PhysicalKey::Code(KeyCode::KeyC) => self.clear(queue),
+            PhysicalKey::Code(KeyCode::KeyG) => self.randomize(queue),
+            _ => (),
+        }
+    }
+
+    // left paints live cells, right erases them
+    fn handle_mouse(&mut self, mouse_event: MouseEvent, _queue: &wgpu::Queue) {
+        match mouse_event {
+            MouseEvent::Moved(ndc) => {
+                self.cursor = cell_at(ndc, self.scale);
+            }
+            MouseEvent::Button(button, ElementState::Pressed) => {
+                self.brush = match button {
+                    MouseButton::Left => Some(Brush::Paint),
+                    MouseButton::Right => Some(Brush::Erase),
+                    _ => return,
+                };
+                self.brush_from = self.cursor;
+            }
+            MouseEvent::Button(_, ElementState::Released) => self.brush = None,
+            MouseEvent::Wheel(_) => (),
+        }
+    }
+
+    fn prepare(&mut self, _device: &wgpu::Device, _queue: &wgpu::Queue) -> bool {
+        !self.paused
+    }
+
+    fn resize(&mut self, size: PhysicalSize<u32>, queue: &wgpu::Queue) {
+        self.scale = grid_scale(size);
+
+        let view = View {
+            scale: self.scale.into(),
+            _padding: [0.0; 2],
+        };
+        queue.write_buffer(&self.view_buffer, 0, bytemuck::bytes_of(&view));
+    }
+
+    fn update(&mut self, delta_time: f32, _debug_draw: &mut DebugDraw) {
+        if !self.paused {
+            self.step_budget += delta_time * self.generations_per_second;
+        }
+    }
+
+    fn compute(&mut self, queue: &wgpu::Queue, compute_pass: &mut wgpu::ComputePass) {
+        let budget = self.step_budget as u32;
+        self.step_budget = self.step_budget.fract();
+
+        let steps = (budget + std::mem::take(&mut self.pending_steps)).min(MAX_STEPS_PER_FRAME);
+
+        // painting while nothing advances still needs one pass to apply the brush
+        let dispatches = match (steps, self.brush) {
+            (0, None) => return,
+            (0, Some(_)) => 1,
+            (steps, _) => steps,
+        };
+
+        let params = Params {
+            birth: self.rule.birth,
+            survive: self.rule.survive,
+            advance: (steps > 0) as u32,
+            brush: match self.brush {
+                None => 0,
+                Some(Brush::Paint) => 1,
+                Some(Brush::Erase) => 2,
+            },
+            brush_from: self.brush_from.into(),
+            brush_to: self.cursor.into(),
+            brush_radius: BRUSH_RADIUS,
+            _padding: [0.0; 3],
+        };
+        queue.write_buffer(&self.params_buffer, 0, bytemuck::bytes_of(&params));
+        self.brush_from = self.cursor;
+
+        compute_pass.set_pipeline(&self.step_pipeline);
+        for _ in 0..dispatches {
+            compute_pass.set_bind_group(0, &self.compute_bind_groups[self.current], &[]);
+            compute_pass.dispatch_workgroups(GRID_SIZE.div_ceil(8), GRID_SIZE.div_ceil(8), 1);
+            self.current = 1 - self.current;
+        }
+    }
+
+    fn render(&self, render_pass: &mut wgpu::RenderPass) {
+        render_pass.set_pipeline(&self.render_pipeline);
+        render_pass.set_bind_group(0, &self.render_bind_groups[self.current], &[]);
+        render_pass.set_bind_group(1, &self.view_bind_group, &[]);
+        render_pass.draw(0..6, 0..1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rules_parse_into_masks() {
+        assert_eq!(
+            Rule::parse("B3/S23"),
+            Ok(Rule {
+                birth: 0b1000,
+                survive: 0b1100,
+            })
+        );
+        assert_eq!(
+            Rule::parse("B36/S23"),
+            Ok(Rule {
+                birth: 0b100_1000,
+                survive: 0b1100,
+            })
+        );
+        assert_eq!(Rule::parse("s23/b3"), Rule::parse("B3/S23"));
+        assert_eq!(
+            Rule::parse("B2/S"),
+            Ok(Rule {
+                birth: 0b100,
+                survive: 0,
+            })
+        );
+        assert_eq!(Rule::parse("B012345678/S").unwrap().birth, 0x1ff);
+    }
+
+    #[test]
+    fn invalid_rules_are_rejected() {
+        assert_eq!(
+            Rule::parse("B39/S23").err().unwrap(),
+            "invalid neighbour count '9' in rule \"B39/S23\""
+        );
+        assert_eq!(
+            Rule::parse("B3").err().unwrap(),
+            "rule \"B3\" needs both a B and an S part"
+        );
+        assert!(Rule::parse("S23").is_err());
+        assert!(Rule::parse("B3/S23/B1").is_err());
+        assert!(Rule::parse("B3/S2x").is_err());
+        assert!(Rule::parse("23/3").is_err());
+        assert!(Rule::parse("").is_err());
+    }
+
+    #[test]
+    fn cursor_lands_on_the_cell_under_it() {
+        let grid = GRID_SIZE as f32;
+
+        // a 2:1 window leaves bars left and right of the grid
+        let scale = grid_scale(PhysicalSize::new(800, 400));
+        assert_eq!(scale, Vec2::new(0.5, 1.0));
+        assert_eq!(cell_at(Vec2::new(-0.5, 1.0), scale), Vec2::ZERO);
+        assert_eq!(cell_at(Vec2::new(0.5, -1.0), scale), Vec2::splat(grid));
+        assert_eq!(cell_at(Vec2::ZERO, scale), Vec2::splat(grid / 2.0));
+        assert!(cell_at(Vec2::new(-1.0, 0.0), scale).x < 0.0);
+
+        let scale = grid_scale(PhysicalSize::new(300, 600));
+        assert_eq!(scale, Vec2::new(1.0, 0.5));
+        assert_eq!(cell_at(Vec2::new(1.0, 0.5), scale), Vec2::new(grid, 0.0));
+    }
+}
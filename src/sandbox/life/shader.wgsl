@@ -0,0 +1,49 @@
+struct VertexOutput {
+    @builtin(position) pos: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+struct View {
+    // shrinks the grid along the longer side of the window so cells stay square
+    scale: vec2<f32>,
+}
+
+@group(0) @binding(0)
+var state_texture: texture_2d<f32>;
+
+@group(0) @binding(1)
+var state_sampler: sampler;
+
+@group(1) @binding(0)
+var<uniform> view: View;
+
+// a quad of two triangles, uv (0, 0) is the top-left corner of the grid
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    var out: VertexOutput;
+
+    let corners = array(
+        vec2f(0.0, 0.0),
+        vec2f(1.0, 0.0),
+        vec2f(0.0, 1.0),
+        vec2f(0.0, 1.0),
+        vec2f(1.0, 0.0),
+        vec2f(1.0, 1.0),
+    );
+    let uv = corners[index];
+
+    out.pos = vec4f(vec2f(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0) * view.scale, 0.0, 1.0);
+    out.uv = uv;
+
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let state = textureSample(state_texture, state_sampler, in.uv);
+
+    let trail = vec3f(0.05, 0.15, 0.45) * state.g * state.g;
+    let color = mix(trail, vec3f(0.85, 0.95, 1.0), state.r);
+
+    return vec4f(color, 1.0);
+}
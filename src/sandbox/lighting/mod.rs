@@ -0,0 +1,901 @@
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
+use bytemuck::{Pod, Zeroable};
+use glam::{Mat4, Quat, Vec2, Vec3};
+use wgpu::{include_wgsl, util::DeviceExt};
+use winit::{
+    dpi::PhysicalSize,
+    event::{ElementState, KeyEvent, MouseButton},
+    keyboard::{KeyCode, PhysicalKey},
+};
+
+use crate::{
+    debug_draw::DebugDraw,
+    graphics::{MouseEvent, Renderable},
+    model::{MeshVertex, Primitive},
+    render_target::DEPTH_FORMAT,
+    texture::{
+        decode::{DecodedImage, Pixels},
+        ColorSpace, Texture, TextureOptions,
+    },
+    vertices::Vertex,
+};
+
+const MAX_DIRECTIONAL: usize = 4;
+const MAX_POINT: usize = 128;
+const MAX_SPOT: usize = 32;
+
+// radians per unit of normalized device coordinates dragged across
+const DRAG_SPEED: f32 = 2.0;
+
+const TEXTURE_SIZE: u32 = 256;
+const TILES: u32 = 4;
+
+// matches `Scene` in shader.wgsl, `light_count` sits where a vec4 would have
+// its w
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+struct Scene {
+    view_projection: [[f32; 4]; 4],
+    eye: [f32; 3],
+    light_count: u32,
+    ambient: [f32; 3],
+    shininess: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+enum LightKind {
+    Directional = 0,
+    Point = 1,
+    Spot = 2,
+}
+
+// matches `Light` in shader.wgsl, padded to the 64 bytes of its array stride
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+struct Light {
+    position: [f32; 3],
+    kind: u32,
+    direction: [f32; 3],
+    range: f32,
+    color: [f32; 3],
+    intensity: f32,
+    inner_cos: f32,
+    outer_cos: f32,
+    _padding: [f32; 2],
+}
+
+impl Light {
+    fn directional(direction: Vec3, color: Vec3, intensity: f32) -> Self {
+        Self {
+            kind: LightKind::Directional as u32,
+            direction: direction.normalize().into(),
+            color: color.into(),
+            intensity,
+            ..Zeroable::zeroed()
+        }
+    }
+
+    fn point(position: Vec3, color: Vec3, intensity: f32, range: f32) -> Self {
+        Self {
+            position: position.into(),
+            kind: LightKind::Point as u32,
+            range,
+            color: color.into(),
+            intensity,
+            ..Zeroable::zeroed()
+        }
+    }
+
+    // `inner` and `outer` are the half angles of the cone, in radians
+    fn spot(position: Vec3, direction: Vec3, color: Vec3, [inner, outer]: [f32; 2]) -> Self {
+        Self {
+            kind: LightKind::Spot as u32,
+            direction: direction.normalize().into(),
+            inner_cos: inner.cos(),
+            outer_cos: outer.cos(),
+            ..Self::point(position, color, 12.0, 12.0)
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+struct Instance {
+    model: [[f32; 4]; 4],
+    color: [f32; 4],
+}
+
+impl Instance {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 5] = wgpu::vertex_attr_array![
+        4 => Float32x4,
+        5 => Float32x4,
+        6 => Float32x4,
+        7 => Float32x4,
+        8 => Float32x4,
+    ];
+
+    fn new(translation: Vec3, rotation: Quat, scale: f32, color: [f32; 4]) -> Self {
+        Self {
+            model: Mat4::from_scale_rotation_translation(Vec3::splat(scale), rotation, translation)
+                .to_cols_array_2d(),
+            color,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct LightCounts {
+    directional: usize,
+    point: usize,
+    spot: usize,
+}
+
+impl LightCounts {
+    fn total(&self) -> usize {
+        self.directional + self.point + self.spot
+    }
+}
+
+// a fully saturated color around the hue circle, `hue` from 0 to 1
+fn hue(hue: f32) -> Vec3 {
+    let offsets = Vec3::new(1.0, 2.0 / 3.0, 1.0 / 3.0);
+    let distance = ((offsets + hue).fract() * 6.0 - 3.0).abs();
+    (distance - 1.0).clamp(Vec3::ZERO, Vec3::ONE)
+}
+
+// the lights for `counts` at `time` seconds in, directional ones first. point
+// lights orbit at a few heights, spots sweep their cones over the floor
+fn lights(counts: LightCounts, time: f32) -> Vec<Light> {
+    let directional = (0..counts.directional).map(|i| {
+        let yaw = i as f32 / counts.directional as f32 * TAU + 0.6;
+        let direction = Vec3::new(yaw.sin(), -1.5, yaw.cos());
+        let color = [Vec3::new(1.0, 0.9, 0.75), Vec3::new(0.6, 0.7, 1.0)][i % 2];
+        Light::directional(direction, color, 0.35)
+    });
+
+    let point = (0..counts.point).map(|i| {
+        // golden angle steps spread any count evenly
+        let angle = i as f32 * 2.4 + time * (0.3 + (i % 3) as f32 * 0.15);
+        let radius = 1.5 + (i % 4) as f32 * 1.2;
+        let position = Vec3::new(
+            angle.cos() * radius,
+            0.4 + (i % 3) as f32 * 0.5,
+            angle.sin() * radius,
+        );
+        Light::point(position, hue(i as f32 * 0.618), 3.0, 4.0)
+    });
+
+    let spot = (0..counts.spot).map(|i| {
+        let angle = i as f32 / counts.spot as f32 * TAU;
+        let position = Vec3::new(angle.cos() * 4.0, 4.0, angle.sin() * 4.0);
+        let sweep = angle + time * 0.7;
+        let target = Vec3::new(sweep.cos() * 2.0, 0.0, sweep.sin() * 2.0);
+        let cone = [12f32.to_radians(), 20f32.to_radians()];
+        Light::spot(
+            position,
+            target - position,
+            hue(i as f32 * 0.618 + 0.3),
+            cone,
+        )
+    });
+
+    directional.chain(point).chain(spot).collect()
+}
+
+fn cube() -> Primitive {
+    let mut primitive = Primitive::default();
+
+    for normal in [Vec3::X, Vec3::Y, Vec3::Z, -Vec3::X, -Vec3::Y, -Vec3::Z] {
+        // across the face, u cross v is the normal so the corners go counter
+        // clockwise seen from outside
+        let v = if normal.y == 0.0 { Vec3::Y } else { Vec3::Z };
+        let u = v.cross(normal);
+
+        let first = primitive.vertices.len() as u32;
+        for (s, t) in [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)] {
+            primitive.vertices.push(MeshVertex {
+                position: ((normal + u * s + v * t) * 0.5).into(),
+                normal: normal.into(),
+                uv: [(s + 1.0) / 2.0, (1.0 - t) / 2.0],
+                tangent: [0.0; 4],
+            });
+        }
+        primitive
+            .indices
+            .extend([0, 1, 2, 0, 2, 3].map(|i| first + i));
+    }
+
+    primitive
+}
+
+// a uv sphere of radius 1
+fn sphere(segments: u32, rings: u32) -> Primitive {
+    let mut primitive = Primitive::default();
+
+    for ring in 0..=rings {
+        let theta = ring as f32 / rings as f32 * PI;
+        for segment in 0..=segments {
+            let phi = segment as f32 / segments as f32 * TAU;
+            let normal = Vec3::new(
+                theta.sin() * phi.sin(),
+                theta.cos(),
+                theta.sin() * phi.cos(),
+            );
+            primitive.vertices.push(MeshVertex {
+                position: normal.into(),
+                normal: normal.into(),
+                uv: [
+                    segment as f32 / segments as f32 * 2.0,
+                    ring as f32 / rings as f32,
+                ],
+                tangent: [0.0; 4],
+            });
+        }
+    }
+
+    for ring in 0..rings {
+        for segment in 0..segments {
+            let a = ring * (segments + 1) + segment;
+            let b = a + segments + 1;
+            primitive.indices.extend([a, b, a + 1, a + 1, b, b + 1]);
+        }
+    }
+
+    primitive
+}
+
+// a square on the xz plane facing up, its uvs repeating `repeat` times
+fn plane(repeat: f32) -> Primitive {
+    let corners = [(-0.5, 0.5), (0.5, 0.5), (0.5, -0.5), (-0.5, -0.5)];
+
+    Primitive {
+        vertices: corners
+            .map(|(x, z)| MeshVertex {
+                position: [x, 0.0, z],
+                normal: [0.0, 1.0, 0.0],
+                uv: [(x + 0.5) * repeat, (z + 0.5) * repeat],
+                tangent: [0.0; 4],
+            })
+            .to_vec(),
+        indices: vec![0, 1, 2, 0, 2, 3],
+        material: None,
+    }
+}
+
+// tiles with darker grout between them, as the diffuse color and the specular
+// map. the tiles are glazed, the grout and the worn spots on the tiles are not
+fn tile_textures() -> [DecodedImage; 2] {
+    let tile = TEXTURE_SIZE / TILES;
+    let mut diffuse = Vec::with_capacity((TEXTURE_SIZE * TEXTURE_SIZE * 4) as usize);
+    let mut specular = Vec::with_capacity(diffuse.capacity());
+
+    for y in 0..TEXTURE_SIZE {
+        for x in 0..TEXTURE_SIZE {
+            let grout = x % tile < 3 || y % tile < 3;
+            let shade = if (x / tile + y / tile).is_multiple_of(2) {
+                235
+            } else {
+                200
+            };
+            let worn = ((x as f32 * 0.13).sin() * (y as f32 * 0.07).cos()) > 0.6;
+
+            let (color, shine) = match (grout, worn) {
+                (true, _) => ([90, 85, 80], 0),
+                (false, true) => ([shade - 20, shade - 25, shade - 30], 40),
+                (false, false) => ([shade, shade - 5, shade - 12], 255),
+            };
+            diffuse.extend(color.into_iter().chain([255]));
+            specular.extend([shine, shine, shine, 255]);
+        }
+    }
+
+    [diffuse, specular].map(|pixels| DecodedImage {
+        width: TEXTURE_SIZE,
+        height: TEXTURE_SIZE,
+        pixels: Pixels::Rgba8(pixels),
+    })
+}
+
+struct Shape {
+    vertex_buffer: wgpu::Buffer,
+    index_buffer: wgpu::Buffer,
+    index_count: u32,
+    instances: std::ops::Range<u32>,
+}
+
+impl Shape {
+    fn new(device: &wgpu::Device, primitive: &Primitive, instances: std::ops::Range<u32>) -> Self {
+        Self {
+            vertex_buffer: device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("lighting vertex buffer"),
+                contents: bytemuck::cast_slice(&primitive.vertices),
+                usage: wgpu::BufferUsages::VERTEX,
+            }),
+            index_buffer: device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("lighting index buffer"),
+                contents: bytemuck::cast_slice(&primitive.indices),
+                usage: wgpu::BufferUsages::INDEX,
+            }),
+            index_count: primitive.indices.len() as u32,
+            instances,
+        }
+    }
+}
+
+struct OrbitCamera {
+    distance: f32,
+    yaw: f32,
+    pitch: f32,
+    aspect: f32,
+}
+
+impl OrbitCamera {
+    fn eye(&self) -> Vec3 {
+        Vec3::new(
+            self.pitch.cos() * self.yaw.sin(),
+            self.pitch.sin(),
+            self.pitch.cos() * self.yaw.cos(),
+        ) * self.distance
+    }
+
+    fn view_projection(&self) -> Mat4 {
+        let view = Mat4::look_at_rh(self.eye(), Vec3::ZERO, Vec3::Y);
+        let projection = Mat4::perspective_rh(45f32.to_radians(), self.aspect, 0.1, 100.0);
+
+        projection * view
+    }
+
+    fn orbit(&mut self, delta: Vec2) {
+        self.yaw -= delta.x;
+        self.pitch = (self.pitch - delta.y).clamp(0.05, FRAC_PI_2 - 0.01);
+    }
+}
+
+// blinn-phong shading with any number of directional, point and spot lights
+// read from a storage buffer
+pub struct Sandbox {
+    pipeline: wgpu::RenderPipeline,
+    scene_buffer: wgpu::Buffer,
+    scene_layout: wgpu::BindGroupLayout,
+    scene_bind_group: wgpu::BindGroup,
+    light_buffer: wgpu::Buffer,
+    // in lights
+    light_capacity: usize,
+    // with the specular map and with a white one in its place
+    material_bind_groups: [wgpu::BindGroup; 2],
+    instance_buffer: wgpu::Buffer,
+    // instances of everything but the light markers
+    objects: Vec<Instance>,
+    // the ground, cubes and spheres, light markers are spheres drawn last
+    shapes: [Shape; 3],
+    marker_count: u32,
+    camera: OrbitCamera,
+    drag: Option<Option<Vec2>>,
+    counts: LightCounts,
+    time: f32,
+    animate: bool,
+    specular_map: bool,
+    shininess: f32,
+    ambient: f32,
+    // set when the buffers no longer match the scene
+    dirty: bool,
+}
+
+impl Sandbox {
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        view_format: wgpu::TextureFormat,
+    ) -> Self {
+        let shader = device.create_shader_module(include_wgsl!("shader.wgsl"));
+
+        let scene_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("lighting scene buffer"),
+            size: size_of::<Scene>() as _,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let scene_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("lighting scene bind group layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let light_capacity = 64;
+        let light_buffer = light_buffer(device, light_capacity);
+        let scene_bind_group =
+            scene_bind_group(device, &scene_layout, &scene_buffer, &light_buffer);
+
+        let material_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("lighting material bind group layout"),
+            entries: &[
+                texture_entry(0),
+                texture_entry(1),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+        // the specular map holds strengths, not colors
+        let [diffuse, specular] = tile_textures();
+        let diffuse = Texture::from_image(device, queue, &diffuse, TextureOptions::default());
+        let linear = TextureOptions {
+            color_space: ColorSpace::Linear,
+            ..Default::default()
+        };
+        let specular = Texture::from_image(device, queue, &specular, linear);
+        let white = DecodedImage {
+            width: 1,
+            height: 1,
+            pixels: Pixels::Rgba8(vec![255; 4]),
+        };
+        let white = Texture::from_image(device, queue, &white, linear);
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("lighting sampler"),
+            address_mode_u: wgpu::AddressMode::Repeat,
+            address_mode_v: wgpu::AddressMode::Repeat,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Linear,
+            anisotropy_clamp: 8,
+            ..Default::default()
+        });
+        let material_bind_groups = [&specular, &white].map(|specular| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("lighting material bind group"),
+                layout: &material_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&diffuse.texture_view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(&specular.texture_view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: wgpu::BindingResource::Sampler(&sampler),
+                    },
+                ],
+            })
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("lighting pipeline layout"),
+            bind_group_layouts: &[&scene_layout, &material_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("lighting pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[
+                    MeshVertex::desc(),
+                    wgpu::VertexBufferLayout {
+                        array_stride: size_of::<Instance>() as _,
+                        step_mode: wgpu::VertexStepMode::Instance,
+                        attributes: &Instance::ATTRIBUTES,
+                    },
+                ],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: view_format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                cull_mode: Some(wgpu::Face::Back),
+                ..Default::default()
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: Default::default(),
+                bias: Default::default(),
+            }),
+            multisample: Default::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        // the ground, then a ring of cubes and spheres around a big sphere
+        let white = [1.0; 4];
+        let mut objects = vec![Instance::new(Vec3::ZERO, Quat::IDENTITY, 14.0, white)];
+        for i in 0..6 {
+            let angle = i as f32 / 6.0 * TAU;
+            let position = Vec3::new(angle.cos() * 2.8, 0.5, angle.sin() * 2.8);
+            let rotation = Quat::from_rotation_y(angle * 1.7);
+            objects.push(Instance::new(position, rotation, 0.8, [0.9, 0.6, 0.5, 1.0]));
+        }
+        let cubes = objects.len();
+        for i in 0..6 {
+            let angle = (i as f32 + 0.5) / 6.0 * TAU;
+            let position = Vec3::new(angle.cos() * 2.8, 0.45, angle.sin() * 2.8);
+            objects.push(Instance::new(
+                position,
+                Quat::IDENTITY,
+                0.45,
+                [0.5, 0.7, 0.9, 1.0],
+            ));
+        }
+        objects.push(Instance::new(Vec3::Y, Quat::IDENTITY, 1.0, white));
+
+        let [cubes, spheres] = [cubes, objects.len()].map(|end| end as u32);
+        let shapes = [
+            Shape::new(device, &plane(TILES as f32 * 3.0), 0..1),
+            Shape::new(device, &cube(), 1..cubes),
+            Shape::new(device, &sphere(32, 16), cubes..spheres),
+        ];
+
+        let mut sandbox = Self {
+            pipeline,
+            scene_buffer,
+            scene_layout,
+            scene_bind_group,
+            light_buffer,
+            light_capacity,
+            material_bind_groups,
+            instance_buffer: instance_buffer(device, objects.len() + light_capacity),
+            objects,
+            shapes,
+            marker_count: 0,
+            camera: OrbitCamera {
+                distance: 11.0,
+                yaw: 0.6,
+                pitch: 0.55,
+                aspect: 640.0 / 480.0,
+            },
+            drag: None,
+            counts: LightCounts {
+                directional: 1,
+                point: 8,
+                spot: 3,
+            },
+            time: 0.0,
+            animate: true,
+            specular_map: true,
+            shininess: 48.0,
+            ambient: 0.05,
+            dirty: false,
+        };
+        sandbox.upload(device, queue);
+
+        sandbox
+    }
+
+    // writes this frame's lights, markers and scene, growing the buffers when
+    // there are more lights than fit
+    fn upload(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        let lights = lights(self.counts, self.time);
+
+        if lights.len() > self.light_capacity {
+            self.light_capacity = lights.len().next_power_of_two();
+            self.light_buffer = light_buffer(device, self.light_capacity);
+            self.scene_bind_group = scene_bind_group(
+                device,
+                &self.scene_layout,
+                &self.scene_buffer,
+                &self.light_buffer,
+            );
+            self.instance_buffer =
+                instance_buffer(device, self.objects.len() + self.light_capacity);
+        }
+        queue.write_buffer(&self.light_buffer, 0, bytemuck::cast_slice(&lights));
+
+        // a small unlit sphere where every point and spot light is
+        let markers: Vec<_> = lights
+            .iter()
+            .filter(|light| light.kind != LightKind::Directional as u32)
+            .map(|light| {
+                let position = Vec3::from(light.position);
+                Instance::new(
+                    position,
+                    Quat::IDENTITY,
+                    0.06,
+                    Vec3::from(light.color).extend(0.0).into(),
+                )
+            })
+            .collect();
+        self.marker_count = markers.len() as u32;
+        queue.write_buffer(
+            &self.instance_buffer,
+            0,
+            bytemuck::cast_slice(&[self.objects.as_slice(), &markers].concat()),
+        );
+
+        let scene = Scene {
+            view_projection: self.camera.view_projection().transpose().to_cols_array_2d(),
+            eye: self.camera.eye().into(),
+            light_count: lights.len() as u32,
+            ambient: [self.ambient; 3],
+            shininess: self.shininess,
+        };
+        queue.write_buffer(&self.scene_buffer, 0, bytemuck::bytes_of(&scene));
+    }
+}
+
+impl Renderable for Sandbox {
+    fn needs_depth() -> bool {
+        true
+    }
+
+    fn handle_input(&mut self, key_event: KeyEvent, _queue: &wgpu::Queue) {
+        let KeyEvent {
+            physical_key: PhysicalKey::Code(code),
+            state: ElementState::Pressed,
+            ..
+        } = key_event
+        else {
+            return;
+        };
+
+        let step = 5f32.to_radians();
+        match code {
+            KeyCode::ArrowLeft => self.camera.orbit(Vec2::new(-step, 0.0)),
+            KeyCode::ArrowRight => self.camera.orbit(Vec2::new(step, 0.0)),
+            KeyCode::ArrowUp => self.camera.orbit(Vec2::new(0.0, step)),
+            KeyCode::ArrowDown => self.camera.orbit(Vec2::new(0.0, -step)),
+            KeyCode::Space => self.animate = !self.animate,
+            _ => return,
+        }
+        self.dirty = true;
+    }
+
+    fn handle_mouse(&mut self, mouse_event: MouseEvent, _queue: &wgpu::Queue) {
+        match mouse_event {
+            MouseEvent::Button(MouseButton::Left, state) => {
+                self.drag = (state == ElementState::Pressed).then_some(None);
+            }
+            MouseEvent::Moved(position) => {
+                let Some(last) = &mut self.drag else {
+                    return;
+                };
+
+                if let Some(last) = last.replace(position) {
+                    self.camera.orbit((position - last) * DRAG_SPEED);
+                    self.dirty = true;
+                }
+            }
+            MouseEvent::Wheel(lines) => {
+                self.camera.distance =
+                    (self.camera.distance * 0.9_f32.powf(lines)).clamp(2.0, 40.0);
+                self.dirty = true;
+            }
+            _ => (),
+        }
+    }
+
+    fn ui(&mut self, ctx: &egui::Context, _queue: &wgpu::Queue) {
+        let mut changed = false;
+
+        egui::Window::new("Lights")
+            .default_pos([420.0, 20.0])
+            .show(ctx, |ui| {
+                let counts = &mut self.counts;
+                changed |= ui
+                    .add(
+                        egui::Slider::new(&mut counts.directional, 0..=MAX_DIRECTIONAL)
+                            .text("directional"),
+                    )
+                    .changed();
+                changed |= ui
+                    .add(egui::Slider::new(&mut counts.point, 0..=MAX_POINT).text("point"))
+                    .changed();
+                changed |= ui
+                    .add(egui::Slider::new(&mut counts.spot, 0..=MAX_SPOT).text("spot"))
+                    .changed();
+                ui.label(format!(
+                    "{} lights of {} bytes, room for {}",
+                    counts.total(),
+                    size_of::<Light>(),
+                    self.light_capacity
+                ));
+
+                ui.separator();
+                changed |= ui
+                    .add(
+                        egui::Slider::new(&mut self.shininess, 1.0..=256.0)
+                            .logarithmic(true)
+                            .text("shininess"),
+                    )
+                    .changed();
+                changed |= ui
+                    .add(egui::Slider::new(&mut self.ambient, 0.0..=0.5).text("ambient"))
+                    .changed();
+                changed |= ui
+                    .checkbox(&mut self.specular_map, "specular map")
+                    .changed();
+                changed |= ui.checkbox(&mut self.animate, "animate (space)").changed();
+                ui.label("drag or arrows orbit, the wheel zooms");
+            });
+
+        self.dirty |= changed;
+    }
+
+    fn resize(&mut self, size: PhysicalSize<u32>, _queue: &wgpu::Queue) {
+        self.camera.aspect = size.width.max(1) as f32 / size.height.max(1) as f32;
+        self.dirty = true;
+    }
+
+    fn update(&mut self, delta_time: f32, _debug_draw: &mut DebugDraw) {
+        if self.animate {
+            self.time += delta_time;
+            self.dirty = true;
+        }
+    }
+
+    // the moving lights keep this going while animating
+    fn prepare(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) -> bool {
+        let dirty = std::mem::take(&mut self.dirty);
+        if dirty {
+            self.upload(device, queue);
+        }
+
+        dirty
+    }
+
+    fn render(&self, render_pass: &mut wgpu::RenderPass) {
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.scene_bind_group, &[]);
+        let material = if self.specular_map { 0 } else { 1 };
+        render_pass.set_bind_group(1, &self.material_bind_groups[material], &[]);
+        render_pass.set_vertex_buffer(1, self.instance_buffer.slice(..));
+
+        for (index, shape) in self.shapes.iter().enumerate() {
+            // the markers follow the spheres
+            let mut instances = shape.instances.clone();
+            if index == self.shapes.len() - 1 {
+                instances.end += self.marker_count;
+            }
+
+            render_pass.set_vertex_buffer(0, shape.vertex_buffer.slice(..));
+            render_pass.set_index_buffer(shape.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+            render_pass.draw_indexed(0..shape.index_count, 0, instances);
+        }
+    }
+}
+
+fn texture_entry(binding: u32) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Texture {
+            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+            view_dimension: wgpu::TextureViewDimension::D2,
+            multisampled: false,
+        },
+        count: None,
+    }
+}
+
+fn light_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("lighting light buffer"),
+        size: (capacity * size_of::<Light>()) as _,
+        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+fn instance_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("lighting instance buffer"),
+        size: (capacity * size_of::<Instance>()) as _,
+        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+fn scene_bind_group(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    scene_buffer: &wgpu::Buffer,
+    light_buffer: &wgpu::Buffer,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("lighting scene bind group"),
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: scene_buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: light_buffer.as_entire_binding(),
+            },
+        ],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::mem::offset_of;
+
+    use super::*;
+
+    // where wgsl puts every field, see the structs in shader.wgsl
+    #[test]
+    fn layouts_match_the_shader() {
+        assert_eq!(offset_of!(Scene, eye), 64);
+        assert_eq!(offset_of!(Scene, light_count), 76);
+        assert_eq!(offset_of!(Scene, ambient), 80);
+        assert_eq!(offset_of!(Scene, shininess), 92);
+        assert_eq!(size_of::<Scene>(), 96);
+
+        assert_eq!(offset_of!(Light, kind), 12);
+        assert_eq!(offset_of!(Light, direction), 16);
+        assert_eq!(offset_of!(Light, range), 28);
+        assert_eq!(offset_of!(Light, color), 32);
+        assert_eq!(offset_of!(Light, intensity), 44);
+        assert_eq!(offset_of!(Light, inner_cos), 48);
+        assert_eq!(offset_of!(Light, outer_cos), 52);
+        assert_eq!(size_of::<Light>(), 64);
+    }
+
+    #[test]
+    fn lights_come_in_the_counts_asked_for() {
+        let counts = LightCounts {
+            directional: 2,
+            point: 5,
+            spot: 3,
+        };
+        let lights = lights(counts, 1.5);
+        assert_eq!(lights.len(), counts.total());
+
+        let kinds: Vec<_> = lights.iter().map(|light| light.kind).collect();
+        let expected = [
+            (LightKind::Directional, 2),
+            (LightKind::Point, 5),
+            (LightKind::Spot, 3),
+        ]
+        .into_iter()
+        .flat_map(|(kind, count)| std::iter::repeat_n(kind as u32, count))
+        .collect::<Vec<_>>();
+        assert_eq!(kinds, expected);
+
+        // spots point down at the floor, with the inner cone inside the outer
+        for spot in &lights[7..] {
+            assert!(spot.direction[1] < 0.0);
+            assert!(spot.inner_cos > spot.outer_cos);
+        }
+    }
+}
@@ -0,0 +1,130 @@
+// a uniform, so laid out like std140: vec3s are aligned to 16 bytes, a
+// scalar after one fits in its last 4
+struct Scene {
+    view_projection: mat4x4<f32>,
+    eye: vec3<f32>,
+    light_count: u32,
+    ambient: vec3<f32>,
+    shininess: f32,
+};
+
+const DIRECTIONAL: u32 = 0u;
+const POINT: u32 = 1u;
+const SPOT: u32 = 2u;
+
+// in storage, laid out like std430. the scalars fill the padding after the
+// vec3s, the two at the end are padded up to 64 bytes since the struct is
+// aligned like its vec3s. the stride would be the same in a uniform array
+struct Light {
+    position: vec3<f32>,
+    kind: u32,
+    // the way the light shines
+    direction: vec3<f32>,
+    range: f32,
+    color: vec3<f32>,
+    intensity: f32,
+    // cosines of the half angles of a spot's cone, full inside the inner
+    inner_cos: f32,
+    outer_cos: f32,
+};
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) uv: vec2<f32>,
+};
+
+struct InstanceInput {
+    @location(4) model_0: vec4<f32>,
+    @location(5) model_1: vec4<f32>,
+    @location(6) model_2: vec4<f32>,
+    @location(7) model_3: vec4<f32>,
+    // alpha 0 is drawn unlit, for the light markers
+    @location(8) color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world_position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) uv: vec2<f32>,
+    @location(3) color: vec4<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> scene: Scene;
+// sized by the buffer bound, only the first `scene.light_count` are lit with
+@group(0) @binding(1)
+var<storage, read> lights: array<Light>;
+
+@group(1) @binding(0)
+var diffuse_texture: texture_2d<f32>;
+@group(1) @binding(1)
+var specular_texture: texture_2d<f32>;
+@group(1) @binding(2)
+var material_sampler: sampler;
+
+@vertex
+fn vs_main(vertex: VertexInput, instance: InstanceInput) -> VertexOutput {
+    let model = mat4x4<f32>(instance.model_0, instance.model_1, instance.model_2, instance.model_3);
+    let world_position = model * vec4<f32>(vertex.position, 1.0);
+
+    var out: VertexOutput;
+    out.clip_position = world_position * scene.view_projection;
+    out.world_position = world_position.xyz;
+    // instances are only rotated and uniformly scaled
+    out.normal = (model * vec4<f32>(vertex.normal, 0.0)).xyz;
+    out.uv = vertex.uv;
+    out.color = instance.color;
+    return out;
+}
+
+// how much of the light reaches `position`, with the direction towards it
+fn incoming(light: Light, position: vec3<f32>) -> vec4<f32> {
+    if light.kind == DIRECTIONAL {
+        return vec4<f32>(-normalize(light.direction), 1.0);
+    }
+
+    let offset = light.position - position;
+    let len = length(offset);
+    let towards = offset / len;
+    // inverse square, brought smoothly down to nothing at the range
+    let window = saturate(1.0 - pow(len / light.range, 4.0));
+    var attenuation = window * window / (len * len + 1.0);
+
+    if light.kind == SPOT {
+        let cos_angle = dot(-towards, normalize(light.direction));
+        attenuation *= smoothstep(light.outer_cos, light.inner_cos, cos_angle);
+    }
+
+    return vec4<f32>(towards, attenuation);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    if in.color.a == 0.0 {
+        return vec4<f32>(in.color.rgb, 1.0);
+    }
+
+    let albedo = textureSample(diffuse_texture, material_sampler, in.uv).rgb * in.color.rgb;
+    let specular_strength = textureSample(specular_texture, material_sampler, in.uv).r;
+    let normal = normalize(in.normal);
+    let view = normalize(scene.eye - in.world_position);
+
+    var color = scene.ambient * albedo;
+    for (var i = 0u; i < min(scene.light_count, arrayLength(&lights)); i++) {
+        let light = lights[i];
+        let towards = incoming(light, in.world_position);
+        let diffuse = max(dot(normal, towards.xyz), 0.0);
+        if diffuse == 0.0 || towards.w == 0.0 {
+            continue;
+        }
+
+        // blinn-phong, the half vector against the normal
+        let halfway = normalize(towards.xyz + view);
+        let specular = pow(max(dot(normal, halfway), 0.0), scene.shininess) * specular_strength;
+        color += light.color * light.intensity * towards.w * (albedo * diffuse + specular);
+    }
+
+    return vec4<f32>(color, 1.0);
+}
@@ -1,6 +1,19 @@
 pub mod camera2d;
+pub mod emissive;
+pub mod fractal;
+pub mod life;
+pub mod lighting;
+pub mod particles;
+pub mod polyline;
 pub mod primitives;
 pub mod quad;
+pub mod sdf;
+pub mod shapes;
+pub mod skybox;
+pub mod sprite;
+pub mod text;
 pub mod texture;
+pub mod tilemap;
 pub mod triangle;
 pub mod uniform;
+pub mod viewer;
@@ -0,0 +1,126 @@
+struct Particle {
+    position: vec2<f32>,
+    velocity: vec2<f32>,
+    age: f32,
+    // dead once age passes it
+    lifetime: f32,
+}
+
+// what the render pass draws, one per live particle
+struct Instance {
+    position: vec2<f32>,
+    // 0 when born, 1 when about to die
+    life: f32,
+    speed: f32,
+}
+
+// laid out as draw indirect arguments, followed by the free list size
+struct Counters {
+    vertex_count: u32,
+    instance_count: atomic<u32>,
+    first_vertex: u32,
+    first_instance: u32,
+    dead_count: atomic<i32>,
+}
+
+struct Params {
+    delta_time: f32,
+    time: f32,
+    emit_count: u32,
+    seed: u32,
+}
+
+@group(0) @binding(0)
+var<storage, read_write> particles: array<Particle>;
+
+// indices of dead particles, used as a stack
+@group(0) @binding(1)
+var<storage, read_write> dead: array<u32>;
+
+@group(0) @binding(2)
+var<storage, read_write> counters: Counters;
+
+@group(0) @binding(3)
+var<storage, read_write> instances: array<Instance>;
+
+@group(0) @binding(4)
+var<uniform> params: Params;
+
+fn pcg(value: u32) -> u32 {
+    let state = value * 747796405u + 2891336453u;
+    let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    return (word >> 22u) ^ word;
+}
+
+fn random(seed: ptr<function, u32>) -> f32 {
+    *seed = pcg(*seed);
+    return f32(*seed) / 4294967295.0;
+}
+
+@compute @workgroup_size(64)
+fn emit(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= params.emit_count {
+        return;
+    }
+
+    // pop a free slot, giving it back if another thread got the last one
+    let count = atomicSub(&counters.dead_count, 1);
+    if count <= 0 {
+        atomicAdd(&counters.dead_count, 1);
+        return;
+    }
+
+    let index = dead[count - 1];
+    var seed = pcg(id.x ^ pcg(params.seed));
+
+    // a fountain at the bottom, slowly swaying from side to side
+    let angle = 1.5707963 + sin(params.time * 0.7) * 0.3 + (random(&seed) - 0.5) * 0.5;
+    let speed = 1.2 + random(&seed) * 0.6;
+
+    var particle: Particle;
+    particle.position = vec2f((random(&seed) - 0.5) * 0.02, -0.95);
+    particle.velocity = vec2f(cos(angle), sin(angle)) * speed;
+    particle.age = 0.0;
+    particle.lifetime = 2.0 + random(&seed) * 2.0;
+
+    particles[index] = particle;
+}
+
+@compute @workgroup_size(256)
+fn simulate(@builtin(global_invocation_id) id: vec3<u32>) {
+    let index = id.x;
+    if index >= arrayLength(&particles) {
+        return;
+    }
+
+    var particle = particles[index];
+    if particle.age >= particle.lifetime {
+        return;
+    }
+
+    let dt = params.delta_time;
+    particle.velocity += vec2f(0.0, -1.2) * dt;
+    particle.velocity *= 1.0 - 0.2 * dt;
+    particle.position += particle.velocity * dt;
+    particle.age += dt;
+
+    // bounce off the floor, losing most of the energy
+    if particle.position.y < -1.0 && particle.velocity.y < 0.0 {
+        particle.velocity.y *= -0.4;
+    }
+
+    particles[index] = particle;
+
+    if particle.age >= particle.lifetime {
+        let slot = atomicAdd(&counters.dead_count, 1);
+        dead[slot] = index;
+        return;
+    }
+
+    let slot = atomicAdd(&counters.instance_count, 1u);
+    instances[slot] = Instance(
+        particle.position,
+        particle.age / particle.lifetime,
+        length(particle.velocity),
+    );
+}
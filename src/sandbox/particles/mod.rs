@@ -0,0 +1,282 @@
+use bytemuck::{Pod, Zeroable};
+use wgpu::{include_wgsl, util::DeviceExt};
+
+use crate::{debug_draw::DebugDraw, graphics::Renderable};
+
+const PARTICLE_COUNT: u32 = 1 << 20;
+// particles live 3 seconds on average, this keeps the pool about full
+const EMIT_PER_SECOND: f32 = PARTICLE_COUNT as f32 / 3.0;
+
+// sizes of the structs in compute.wgsl
+const PARTICLE_SIZE: u64 = 24;
+const INSTANCE_SIZE: u64 = 16;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+struct Counters {
+    vertex_count: u32,
+    instance_count: u32,
+    first_vertex: u32,
+    first_instance: u32,
+    dead_count: i32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+struct Params {
+    delta_time: f32,
+    time: f32,
+    emit_count: u32,
+    seed: u32,
+}
+
+pub struct Sandbox {
+    emit_pipeline: wgpu::ComputePipeline,
+    simulate_pipeline: wgpu::ComputePipeline,
+    render_pipeline: wgpu::RenderPipeline,
+    compute_bind_group: wgpu::BindGroup,
+    counters_buffer: wgpu::Buffer,
+    instance_buffer: wgpu::Buffer,
+    params_buffer: wgpu::Buffer,
+    // seconds since the start and since the last frame, from `update`
+    time: f32,
+    delta_time: f32,
+    // fractional particles carried over to the next frame
+    emit_remainder: f32,
+    frame: u32,
+}
+
+impl Sandbox {
+    pub fn new(device: &wgpu::Device, view_format: wgpu::TextureFormat) -> Self {
+        let compute_shader = device.create_shader_module(include_wgsl!("compute.wgsl"));
+        let shader = device.create_shader_module(include_wgsl!("shader.wgsl"));
+
+        // zeroed particles have an age equal to their lifetime, so all start dead
+        let particle_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("particle buffer"),
+            size: PARTICLE_COUNT as u64 * PARTICLE_SIZE,
+            usage: wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+
+        let dead: Vec<u32> = (0..PARTICLE_COUNT).collect();
+        let dead_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("dead list buffer"),
+            contents: bytemuck::cast_slice(&dead),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+
+        let counters_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("counters buffer"),
+            contents: bytemuck::bytes_of(&Counters {
+                vertex_count: 6,
+                instance_count: 0,
+                first_vertex: 0,
+                first_instance: 0,
+                dead_count: PARTICLE_COUNT as i32,
+            }),
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::INDIRECT
+                | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let instance_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("instance buffer"),
+            size: PARTICLE_COUNT as u64 * INSTANCE_SIZE,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::VERTEX,
+            mapped_at_creation: false,
+        });
+
+        let params_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("params buffer"),
+            size: size_of::<Params>() as _,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let storage_entry = |binding| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only: false },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+
+        let compute_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("compute bind group layout"),
+            entries: &[
+                storage_entry(0),
+                storage_entry(1),
+                storage_entry(2),
+                storage_entry(3),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 4,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let compute_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("compute bind group"),
+            layout: &compute_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: particle_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: dead_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: counters_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: instance_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: params_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        let compute_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("compute pipeline layout"),
+                bind_group_layouts: &[&compute_layout],
+                push_constant_ranges: &[],
+            });
+
+        let compute_pipeline = |entry_point| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(entry_point),
+                layout: Some(&compute_pipeline_layout),
+                module: &compute_shader,
+                entry_point: Some(entry_point),
+                compilation_options: Default::default(),
+                cache: None,
+            })
+        };
+
+        let render_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("pipeline layout"),
+                bind_group_layouts: &[],
+                push_constant_ranges: &[],
+            });
+
+        let additive = wgpu::BlendComponent {
+            src_factor: wgpu::BlendFactor::One,
+            dst_factor: wgpu::BlendFactor::One,
+            operation: wgpu::BlendOperation::Add,
+        };
+
+        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("render pipeline"),
+            layout: Some(&render_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: INSTANCE_SIZE,
+                    step_mode: wgpu::VertexStepMode::Instance,
+                    attributes: &wgpu::vertex_attr_array![
+                        0 => Float32x2,
+                        1 => Float32,
+                        2 => Float32,
+                    ],
+                }],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: view_format,
+                    blend: Some(wgpu::BlendState {
+                        color: additive,
+                        alpha: additive,
+                    }),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            multisample: Default::default(),
+            depth_stencil: None,
+            multiview: None,
+            cache: None,
+        });
+
+        Self {
+            emit_pipeline: compute_pipeline("emit"),
+            simulate_pipeline: compute_pipeline("simulate"),
+            render_pipeline,
+            compute_bind_group,
+            counters_buffer,
+            instance_buffer,
+            params_buffer,
+            time: 0.0,
+            delta_time: 0.0,
+            emit_remainder: 0.0,
+            frame: 0,
+        }
+    }
+}
+
+impl Renderable for Sandbox {
+    // always animating
+    fn prepare(&mut self, _device: &wgpu::Device, _queue: &wgpu::Queue) -> bool {
+        true
+    }
+
+    fn update(&mut self, delta_time: f32, _debug_draw: &mut DebugDraw) {
+        self.time += delta_time;
+        self.delta_time = delta_time;
+    }
+
+    fn compute(&mut self, queue: &wgpu::Queue, compute_pass: &mut wgpu::ComputePass) {
+        self.frame = self.frame.wrapping_add(1);
+
+        let emit = EMIT_PER_SECOND * self.delta_time + self.emit_remainder;
+        self.emit_remainder = emit.fract();
+
+        let params = Params {
+            delta_time: self.delta_time,
+            time: self.time,
+            emit_count: emit as u32,
+            seed: self.frame,
+        };
+        queue.write_buffer(&self.params_buffer, 0, bytemuck::bytes_of(&params));
+
+        // live particles append themselves again every frame
+        let instance_count = std::mem::offset_of!(Counters, instance_count) as u64;
+        queue.write_buffer(&self.counters_buffer, instance_count, &0u32.to_ne_bytes());
+
+        compute_pass.set_bind_group(0, &self.compute_bind_group, &[]);
+
+        compute_pass.set_pipeline(&self.emit_pipeline);
+        compute_pass.dispatch_workgroups(params.emit_count.div_ceil(64), 1, 1);
+
+        compute_pass.set_pipeline(&self.simulate_pipeline);
+        compute_pass.dispatch_workgroups(PARTICLE_COUNT.div_ceil(256), 1, 1);
+    }
+
+    fn render(&self, render_pass: &mut wgpu::RenderPass) {
+        render_pass.set_pipeline(&self.render_pipeline);
+        render_pass.set_vertex_buffer(0, self.instance_buffer.slice(..));
+        render_pass.draw_indirect(&self.counters_buffer, 0);
+    }
+}
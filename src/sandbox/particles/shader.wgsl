@@ -0,0 +1,37 @@
+struct InstanceInput {
+    @location(0) position: vec2<f32>,
+    @location(1) life: f32,
+    @location(2) speed: f32,
+}
+
+struct VertexOutput {
+    @builtin(position) pos: vec4<f32>,
+    @location(0) color: vec4<f32>,
+}
+
+const SIZE: vec2<f32> = vec2f(0.003, 0.004);
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32, instance: InstanceInput) -> VertexOutput {
+    var out: VertexOutput;
+
+    // two triangles spanning -1..1
+    var corners = array(
+        vec2f(-1.0, -1.0), vec2f(1.0, -1.0), vec2f(-1.0, 1.0),
+        vec2f(-1.0, 1.0), vec2f(1.0, -1.0), vec2f(1.0, 1.0),
+    );
+
+    out.pos = vec4f(instance.position + corners[index] * SIZE, 0.0, 1.0);
+
+    // fast particles are hot, everything fades out with age
+    let heat = clamp(instance.speed / 1.8, 0.0, 1.0);
+    let color = mix(vec3f(0.9, 0.2, 0.05), vec3f(1.0, 0.9, 0.5), heat);
+    out.color = vec4f(color * (1.0 - instance.life) * 0.15, 1.0);
+
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return in.color;
+}
@@ -0,0 +1,308 @@
+use std::f32::consts::TAU;
+
+use glam::Vec2;
+use winit::{
+    dpi::PhysicalSize,
+    event::{ElementState, KeyEvent},
+    keyboard::{KeyCode, PhysicalKey},
+};
+
+use crate::{
+    debug_draw::DebugDraw,
+    graphics::{MouseEvent, Renderable},
+    polyline::{Cap, Join, LineStyle, PolylineRenderer},
+    sandbox::camera2d::camera::Camera2D,
+    text::{Font, Space, TextRenderer, TextSection},
+};
+
+const JOINS: [Join; 3] = [Join::Miter, Join::Bevel, Join::Round];
+const CAPS: [Cap; 3] = [Cap::Butt, Cap::Square, Cap::Round];
+
+const HELP: &str = "arrows pan, Q/E or the wheel zoom, J join, C cap, D dashes";
+
+// the dash and gap the top line gets when dashes are turned on
+const DASH: [f32; 2] = [20.0, 30.0];
+
+// in pixels per second
+const DASH_SPEED: f32 = 30.0;
+
+fn zigzag(origin: Vec2) -> [Vec2; 5] {
+    [
+        origin,
+        origin + Vec2::new(40.0, -60.0),
+        origin + Vec2::new(80.0, 0.0),
+        origin + Vec2::new(110.0, -70.0),
+        origin + Vec2::new(170.0, -20.0),
+    ]
+}
+
+fn circle(center: Vec2, radius: f32, points: usize) -> Vec<Vec2> {
+    (0..points)
+        .map(|i| center + Vec2::from_angle(i as f32 / points as f32 * TAU) * radius)
+        .collect()
+}
+
+fn star(center: Vec2, outer: f32, inner: f32) -> Vec<Vec2> {
+    (0..10)
+        .map(|i| {
+            let radius = if i % 2 == 0 { outer } else { inner };
+            center + Vec2::from_angle(i as f32 / 10.0 * TAU - TAU / 4.0) * radius
+        })
+        .collect()
+}
+
+fn next<T: Copy + PartialEq>(all: &[T], current: T) -> T {
+    let i = all.iter().position(|&x| x == current).unwrap_or(0);
+    all[(i + 1) % all.len()]
+}
+
+pub struct Sandbox {
+    lines: PolylineRenderer,
+    text: TextRenderer,
+    camera: Camera2D,
+    // the style of the line at the top, changed with the keys and the ui
+    style: LineStyle,
+    // seconds the dashes and lines have been moving for
+    time: f32,
+    // laid out in `update` but not uploaded yet
+    pushed: bool,
+}
+
+impl Sandbox {
+    pub fn new(device: &wgpu::Device, view_format: wgpu::TextureFormat) -> Self {
+        Self {
+            lines: PolylineRenderer::new(device, view_format),
+            text: TextRenderer::new(device, view_format, Font::default()),
+            // zoomed out far enough to show everything at the default size
+            camera: Camera2D::new(640, 480, 0.7),
+            style: LineStyle {
+                width: 20.0,
+                color: [1.0, 0.75, 0.2, 0.8],
+                ..Default::default()
+            },
+            time: 0.0,
+            pushed: false,
+        }
+    }
+
+    fn set_camera(&mut self, queue: &wgpu::Queue) {
+        self.lines.set_camera(&self.camera, queue);
+        self.text.set_camera(&self.camera, queue);
+    }
+
+    fn zoom(&mut self, factor: f32, queue: &wgpu::Queue) {
+        self.camera.scale = (self.camera.scale * factor).clamp(0.1, 20.0);
+        self.set_camera(queue);
+    }
+
+    fn label(&mut self, text: &str, position: Vec2) {
+        self.text.push(&TextSection {
+            text,
+            position,
+            size: 14.0,
+            color: [0.7, 0.7, 0.7, 1.0],
+            space: Space::World,
+            ..Default::default()
+        });
+    }
+
+    fn push_lines(&mut self) {
+        let time = self.time;
+        let thin = LineStyle {
+            width: 1.0,
+            color: [0.2, 0.6, 1.0, 1.0],
+            ..Default::default()
+        };
+
+        // the style being edited, with its centerline on top
+        let top = zigzag(Vec2::new(40.0, 140.0));
+        self.lines.push(&top, false, &self.style);
+        self.lines.push(&top, false, &thin);
+        self.label(
+            &format!("{:?} join, {:?} cap", self.style.join, self.style.cap),
+            Vec2::new(240.0, 80.0),
+        );
+
+        // every join with every cap
+        for (row, cap) in CAPS.into_iter().enumerate() {
+            for (column, join) in JOINS.into_iter().enumerate() {
+                let origin = Vec2::new(40.0 + column as f32 * 200.0, 260.0 + row as f32 * 100.0);
+                let style = LineStyle {
+                    width: 14.0,
+                    color: [0.9, 0.9, 0.95, 1.0],
+                    join,
+                    cap,
+                    ..Default::default()
+                };
+                self.lines.push(&zigzag(origin), false, &style);
+
+                if row == 0 {
+                    self.label(&format!("{join:?}"), Vec2::new(origin.x, 170.0));
+                }
+            }
+            self.label(
+                &format!("{cap:?}"),
+                Vec2::new(620.0, 220.0 + row as f32 * 100.0),
+            );
+        }
+
+        // translucent lines don't overlap where they join
+        let center = Vec2::new(110.0, 600.0);
+        let style = LineStyle {
+            width: 16.0,
+            color: [0.3, 0.9, 0.5, 0.5],
+            join: Join::Round,
+            ..Default::default()
+        };
+        self.lines.push(&star(center, 70.0, 30.0), true, &style);
+
+        // marching dashes
+        let style = LineStyle {
+            width: 6.0,
+            color: [1.0, 0.4, 0.4, 1.0],
+            cap: Cap::Round,
+            dash: Some([16.0, 10.0]),
+            dash_offset: -time * DASH_SPEED,
+            ..Default::default()
+        };
+        self.lines
+            .push(&circle(Vec2::new(310.0, 600.0), 70.0, 64), true, &style);
+
+        // hairlines up to thick ones, in every direction
+        for (i, width) in [1.0, 2.0, 4.0, 8.0].into_iter().enumerate() {
+            let angle = time * 0.2 + i as f32 * TAU / 16.0;
+            let center = Vec2::new(510.0, 600.0);
+            let direction = Vec2::from_angle(angle) * 70.0;
+            let style = LineStyle {
+                width,
+                cap: Cap::Round,
+                ..Default::default()
+            };
+            self.lines
+                .push_line(center - direction, center + direction, &style);
+        }
+
+        self.text.push(&TextSection {
+            text: HELP,
+            position: Vec2::new(10.0, 10.0),
+            size: 16.0,
+            ..Default::default()
+        });
+    }
+}
+
+impl Renderable for Sandbox {
+    fn handle_input(&mut self, key_event: KeyEvent, queue: &wgpu::Queue) {
+        let KeyEvent {
+            physical_key,
+            state,
+            ..
+        } = key_event;
+
+        if state == ElementState::Released {
+            return;
+        };
+
+        // the line style is read again every frame, only the camera needs writing
+        let step = 20.0 / self.camera.scale;
+        match physical_key {
+            PhysicalKey::Code(KeyCode::ArrowUp) => self.camera.y -= step,
+            PhysicalKey::Code(KeyCode::ArrowDown) => self.camera.y += step,
+            PhysicalKey::Code(KeyCode::ArrowLeft) => self.camera.x -= step,
+            PhysicalKey::Code(KeyCode::ArrowRight) => self.camera.x += step,
+            PhysicalKey::Code(KeyCode::KeyE) => return self.zoom(1.25, queue),
+            PhysicalKey::Code(KeyCode::KeyQ) => return self.zoom(1.0 / 1.25, queue),
+            PhysicalKey::Code(KeyCode::KeyJ) => self.style.join = next(&JOINS, self.style.join),
+            PhysicalKey::Code(KeyCode::KeyC) => self.style.cap = next(&CAPS, self.style.cap),
+            PhysicalKey::Code(KeyCode::KeyD) => {
+                self.style.dash = match self.style.dash {
+                    Some(_) => None,
+                    None => Some(DASH),
+                }
+            }
+            _ => (),
+        }
+
+        self.set_camera(queue);
+    }
+
+    fn handle_mouse(&mut self, mouse_event: MouseEvent, queue: &wgpu::Queue) {
+        if let MouseEvent::Wheel(lines) = mouse_event {
+            self.zoom(1.1_f32.powf(lines), queue);
+        }
+    }
+
+    fn ui(&mut self, ctx: &egui::Context, _queue: &wgpu::Queue) {
+        let style = &mut self.style;
+
+        egui::Window::new("Polyline")
+            .default_pos([420.0, 20.0])
+            .show(ctx, |ui| {
+                ui.add(egui::Slider::new(&mut style.width, 0.5..=60.0).text("width"));
+                ui.add(egui::Slider::new(&mut style.miter_limit, 1.0..=10.0).text("miter limit"));
+
+                egui::ComboBox::from_label("join")
+                    .selected_text(format!("{:?}", style.join))
+                    .show_ui(ui, |ui| {
+                        for join in JOINS {
+                            ui.selectable_value(&mut style.join, join, format!("{join:?}"));
+                        }
+                    });
+                egui::ComboBox::from_label("cap")
+                    .selected_text(format!("{:?}", style.cap))
+                    .show_ui(ui, |ui| {
+                        for cap in CAPS {
+                            ui.selectable_value(&mut style.cap, cap, format!("{cap:?}"));
+                        }
+                    });
+
+                let mut dashed = style.dash.is_some();
+                ui.checkbox(&mut dashed, "dashes");
+                let mut dash = style.dash.unwrap_or(DASH);
+                if dashed {
+                    ui.add(egui::Slider::new(&mut dash[0], 1.0..=100.0).text("dash"));
+                    ui.add(egui::Slider::new(&mut dash[1], 0.0..=100.0).text("gap"));
+                }
+                style.dash = dashed.then_some(dash);
+
+                ui.horizontal(|ui| {
+                    ui.color_edit_button_rgba_unmultiplied(&mut style.color);
+                    ui.label("color");
+                });
+            });
+    }
+
+    fn resize(&mut self, size: PhysicalSize<u32>, queue: &wgpu::Queue) {
+        self.camera.width = size.width as f32;
+        self.camera.height = size.height as f32;
+
+        self.lines.resize(size, queue);
+        self.text.resize(size, queue);
+        self.set_camera(queue);
+    }
+
+    fn update(&mut self, delta_time: f32, _debug_draw: &mut DebugDraw) {
+        self.time += delta_time;
+
+        // once per upload, pushing again would draw everything twice
+        if !self.pushed {
+            self.push_lines();
+            self.pushed = true;
+        }
+    }
+
+    // redrawn every frame for the moving dashes
+    fn prepare(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) -> bool {
+        if std::mem::take(&mut self.pushed) {
+            self.lines.prepare(device, queue);
+            self.text.prepare(device, queue);
+        }
+
+        true
+    }
+
+    fn render(&self, render_pass: &mut wgpu::RenderPass) {
+        self.lines.render(render_pass);
+        self.text.render(render_pass);
+    }
+}
@@ -1,7 +1,29 @@
-use wgpu::include_wgsl;
+use std::collections::HashMap;
 
-use crate::graphics::Renderable;
+use glam::Vec2;
+use wgpu::{include_wgsl, util::DeviceExt};
+use winit::{
+    dpi::PhysicalSize,
+    event::{ElementState, KeyEvent},
+    keyboard::{KeyCode, PhysicalKey},
+};
 
+use crate::{
+    graphics::Renderable,
+    text::{Font, TextRenderer, TextSection},
+    vertices::{Vertex, VertexPosCol},
+};
+
+// two ribbons of this many vertices each, zigzagging between a top and a
+// bottom row so the strips wind counter clockwise
+const RIBBON: u16 = 8;
+
+// ends a strip and starts the next one within the same draw. wgpu's gl
+// backend doesn't turn restarts on, desktop gl drivers then draw it as a
+// vertex like any other
+const RESTART: u16 = u16::MAX;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Primitive {
     LineList,
     LineStrip,
@@ -10,6 +32,24 @@ pub enum Primitive {
     TriangleStrip,
 }
 
+impl Primitive {
+    pub const ALL: [Primitive; 5] = [
+        Primitive::PointList,
+        Primitive::LineList,
+        Primitive::LineStrip,
+        Primitive::TriangleList,
+        Primitive::TriangleStrip,
+    ];
+
+    fn is_strip(self) -> bool {
+        matches!(self, Primitive::LineStrip | Primitive::TriangleStrip)
+    }
+
+    fn is_triangles(self) -> bool {
+        matches!(self, Primitive::TriangleList | Primitive::TriangleStrip)
+    }
+}
+
 impl From<Primitive> for wgpu::PrimitiveTopology {
     fn from(value: Primitive) -> Self {
         use Primitive::*;
@@ -24,15 +64,49 @@ impl From<Primitive> for wgpu::PrimitiveTopology {
     }
 }
 
+// everything a pipeline is built for. polygon mode, culling and the front
+// face only change anything for triangles
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct PipelineKey {
+    primitive: Primitive,
+    polygon_mode: wgpu::PolygonMode,
+    cull_mode: Option<wgpu::Face>,
+    front_face: wgpu::FrontFace,
+}
+
+struct IndexBuffers {
+    // every vertex once, for points and lines
+    sequential: (wgpu::Buffer, u32),
+    // the top ribbon wound counter clockwise, the bottom one clockwise
+    triangles: (wgpu::Buffer, u32),
+    // both ribbons in one draw, split by a restart index
+    strips: (wgpu::Buffer, u32),
+    // the same without the restart, the ribbons get joined
+    joined_strips: (wgpu::Buffer, u32),
+}
+
 pub struct Sandbox {
-    pipeline: wgpu::RenderPipeline,
+    shader: wgpu::ShaderModule,
+    pipeline_layout: wgpu::PipelineLayout,
+    view_format: wgpu::TextureFormat,
+    // built the first time a combination is picked
+    pipelines: HashMap<PipelineKey, wgpu::RenderPipeline>,
+    key: PipelineKey,
+    // the last key with a pipeline, drawn until the current one is built
+    shown: PipelineKey,
+    restart: bool,
+    // what the label text was last laid out for
+    labelled: Option<(PipelineKey, bool)>,
+    // laid out but not uploaded yet
+    pushed: bool,
+    polygon_modes: Vec<wgpu::PolygonMode>,
+    vertex_buffer: wgpu::Buffer,
+    indices: IndexBuffers,
+    text: TextRenderer,
 }
 
 impl Sandbox {
     pub fn new(device: &wgpu::Device, view_format: wgpu::TextureFormat) -> Self {
-        // change this to switch between primitives
-        let primitive_topology = Primitive::LineStrip;
-
         let shader = device.create_shader_module(include_wgsl!("shader.wgsl"));
 
         let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
@@ -41,27 +115,146 @@ impl Sandbox {
             push_constant_ranges: &[],
         });
 
+        // line and point rasterization are optional features
+        let mut polygon_modes = vec![wgpu::PolygonMode::Fill];
+        if device
+            .features()
+            .contains(wgpu::Features::POLYGON_MODE_LINE)
+        {
+            polygon_modes.push(wgpu::PolygonMode::Line);
+        }
+        if device
+            .features()
+            .contains(wgpu::Features::POLYGON_MODE_POINT)
+        {
+            polygon_modes.push(wgpu::PolygonMode::Point);
+        }
+
+        let vertices = ribbon(0.55, [0.2, 0.9, 0.3])
+            .chain(ribbon(-0.15, [0.2, 0.6, 1.0]))
+            .collect::<Vec<_>>();
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("vertex buffer"),
+            contents: bytemuck::cast_slice(&vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        let top = 0..RIBBON;
+        let bottom = RIBBON..RIBBON * 2;
+
+        // triangle i of a strip is (i, i + 1, i + 2), with every other one
+        // flipped to keep the winding of the first
+        let strip_triangles = |range: std::ops::Range<u16>| {
+            (range.start..range.end - 2).map(move |i| match (i - range.start) % 2 {
+                0 => [i, i + 1, i + 2],
+                _ => [i + 1, i, i + 2],
+            })
+        };
+        let triangles: Vec<u16> = strip_triangles(top.clone())
+            .chain(strip_triangles(bottom.clone()).map(|[a, b, c]| [a, c, b]))
+            .flatten()
+            .collect();
+
+        let strips: Vec<u16> = top.clone().chain([RESTART]).chain(bottom.clone()).collect();
+        let joined_strips: Vec<u16> = top.chain(bottom).collect();
+        let sequential: Vec<u16> = (0..RIBBON * 2).collect();
+
+        let index_buffer = |label, indices: &[u16]| {
+            let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(label),
+                contents: bytemuck::cast_slice(indices),
+                usage: wgpu::BufferUsages::INDEX,
+            });
+            (buffer, indices.len() as u32)
+        };
+
+        let indices = IndexBuffers {
+            sequential: index_buffer("sequential index buffer", &sequential),
+            triangles: index_buffer("triangle index buffer", &triangles),
+            strips: index_buffer("strip index buffer", &strips),
+            joined_strips: index_buffer("joined strip index buffer", &joined_strips),
+        };
+
+        let key = PipelineKey {
+            primitive: Primitive::TriangleStrip,
+            polygon_mode: wgpu::PolygonMode::Fill,
+            cull_mode: None,
+            front_face: wgpu::FrontFace::Ccw,
+        };
+
+        let mut sandbox = Self {
+            shader,
+            pipeline_layout,
+            view_format,
+            pipelines: HashMap::new(),
+            key,
+            shown: key,
+            restart: true,
+            labelled: None,
+            pushed: false,
+            polygon_modes,
+            vertex_buffer,
+            indices,
+            text: TextRenderer::new(device, view_format, Font::default()),
+        };
+        sandbox.build_pipeline(device);
+        sandbox.push_labels();
+
+        sandbox
+    }
+
+    fn push_labels(&mut self) {
+        let labelled = Some((self.key, self.restart));
+        if self.labelled == labelled {
+            return;
+        }
+        self.labelled = labelled;
+
+        self.text.push(&TextSection {
+            text: &self.labels(),
+            position: Vec2::new(10.0, 10.0),
+            size: 18.0,
+            ..Default::default()
+        });
+        self.pushed = true;
+    }
+
+    fn build_pipeline(&mut self, device: &wgpu::Device) {
+        let key = self.key;
+        if self.pipelines.contains_key(&key) {
+            return;
+        }
+
         let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("render pipeline"),
-            layout: Some(&pipeline_layout),
+            label: Some(&format!("{:?} pipeline", key.primitive)),
+            layout: Some(&self.pipeline_layout),
             vertex: wgpu::VertexState {
-                module: &shader,
+                module: &self.shader,
                 entry_point: Some("vs_main"),
-                buffers: &[],
+                buffers: &[VertexPosCol::desc()],
                 compilation_options: Default::default(),
             },
             fragment: Some(wgpu::FragmentState {
-                module: &shader,
+                module: &self.shader,
                 entry_point: Some("fs_main"),
                 compilation_options: Default::default(),
                 targets: &[Some(wgpu::ColorTargetState {
-                    format: view_format,
+                    format: self.view_format,
                     blend: None,
                     write_mask: wgpu::ColorWrites::ALL,
                 })],
             }),
             primitive: wgpu::PrimitiveState {
-                topology: primitive_topology.into(),
+                topology: key.primitive.into(),
+                // strips have to name the index format their restart value
+                // comes from, lists must leave it out
+                strip_index_format: key
+                    .primitive
+                    .is_strip()
+                    .then_some(wgpu::IndexFormat::Uint16),
+                front_face: key.front_face,
+                cull_mode: key.cull_mode,
+                polygon_mode: key.polygon_mode,
                 ..Default::default()
             },
             multisample: Default::default(),
@@ -70,13 +263,167 @@ impl Sandbox {
             cache: None,
         });
 
-        Self { pipeline }
+        self.pipelines.insert(key, pipeline);
     }
+
+    fn labels(&self) -> String {
+        let key = self.key;
+        let mut labels = format!("[space] topology: {:?}", key.primitive);
+
+        if key.primitive.is_strip() {
+            let restart = if self.restart { "on" } else { "off" };
+            labels += &format!("\n[R] primitive restart: {restart}");
+        }
+
+        if key.primitive.is_triangles() {
+            let polygon_mode = if self.polygon_modes.len() > 1 {
+                format!("{:?}", key.polygon_mode)
+            } else {
+                "Fill (line and point modes unsupported)".to_owned()
+            };
+            let cull_mode = key
+                .cull_mode
+                .map_or("None".to_owned(), |face| format!("{face:?}"));
+
+            labels += &format!(
+                "\n[W] polygon mode: {polygon_mode}\n[C] cull: {cull_mode}\n[F] front face: {:?}",
+                key.front_face
+            );
+        }
+
+        labels
+    }
+}
+
+// a zigzag from left to right, starting at the top row
+fn ribbon(top: f32, color: [f32; 3]) -> impl Iterator<Item = VertexPosCol> {
+    (0..RIBBON).map(move |i| {
+        let x = -0.8 + 1.6 * (i / 2) as f32 / (RIBBON / 2 - 1) as f32;
+        let y = if i % 2 == 0 { top } else { top - 0.5 };
+        // the lower row a little darker, so the zigzag reads
+        let shade = if i % 2 == 0 { 1.0 } else { 0.6 };
+
+        VertexPosCol {
+            position: [x + 0.1 * (i % 2) as f32, y],
+            color: color.map(|channel| channel * shade),
+        }
+    })
 }
 
+fn next<T: Copy + PartialEq>(options: &[T], current: T) -> T {
+    let index = options.iter().position(|&option| option == current);
+    options[index.map_or(0, |i| (i + 1) % options.len())]
+}
+
+const CULL_MODES: [Option<wgpu::Face>; 3] = [None, Some(wgpu::Face::Back), Some(wgpu::Face::Front)];
+const FRONT_FACES: [wgpu::FrontFace; 2] = [wgpu::FrontFace::Ccw, wgpu::FrontFace::Cw];
+
 impl Renderable for Sandbox {
+    fn handle_input(&mut self, key_event: KeyEvent, _queue: &wgpu::Queue) {
+        let KeyEvent {
+            physical_key,
+            state,
+            ..
+        } = key_event;
+
+        if state == ElementState::Released {
+            return;
+        };
+
+        let key = &mut self.key;
+        match physical_key {
+            PhysicalKey::Code(KeyCode::Space) => {
+                key.primitive = next(&Primitive::ALL, key.primitive)
+            }
+            PhysicalKey::Code(KeyCode::KeyR) => self.restart = !self.restart,
+            PhysicalKey::Code(KeyCode::KeyW) => {
+                key.polygon_mode = next(&self.polygon_modes, key.polygon_mode)
+            }
+            PhysicalKey::Code(KeyCode::KeyC) => key.cull_mode = next(&CULL_MODES, key.cull_mode),
+            PhysicalKey::Code(KeyCode::KeyF) => key.front_face = next(&FRONT_FACES, key.front_face),
+            _ => (),
+        }
+
+        self.push_labels();
+    }
+
+    fn ui(&mut self, ctx: &egui::Context, _queue: &wgpu::Queue) {
+        let key = &mut self.key;
+
+        egui::Window::new("Primitives")
+            .default_pos([420.0, 20.0])
+            .show(ctx, |ui| {
+                let combo = |label: &str, selected: String| {
+                    egui::ComboBox::from_label(label).selected_text(selected)
+                };
+
+                combo("topology", format!("{:?}", key.primitive)).show_ui(ui, |ui| {
+                    for primitive in Primitive::ALL {
+                        ui.selectable_value(
+                            &mut key.primitive,
+                            primitive,
+                            format!("{primitive:?}"),
+                        );
+                    }
+                });
+                ui.add_enabled(
+                    key.primitive.is_strip(),
+                    egui::Checkbox::new(&mut self.restart, "primitive restart"),
+                );
+
+                ui.add_enabled_ui(key.primitive.is_triangles(), |ui| {
+                    combo("polygon mode", format!("{:?}", key.polygon_mode)).show_ui(ui, |ui| {
+                        for &mode in &self.polygon_modes {
+                            ui.selectable_value(&mut key.polygon_mode, mode, format!("{mode:?}"));
+                        }
+                    });
+                    combo("cull", format!("{:?}", key.cull_mode)).show_ui(ui, |ui| {
+                        for mode in CULL_MODES {
+                            ui.selectable_value(&mut key.cull_mode, mode, format!("{mode:?}"));
+                        }
+                    });
+                    combo("front face", format!("{:?}", key.front_face)).show_ui(ui, |ui| {
+                        for face in FRONT_FACES {
+                            ui.selectable_value(&mut key.front_face, face, format!("{face:?}"));
+                        }
+                    });
+                });
+            });
+
+        self.push_labels();
+    }
+
+    fn resize(&mut self, size: PhysicalSize<u32>, queue: &wgpu::Queue) {
+        self.text.resize(size, queue);
+    }
+
+    fn prepare(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) -> bool {
+        self.build_pipeline(device);
+        let changed = self.shown != self.key;
+        self.shown = self.key;
+
+        let uploaded = std::mem::take(&mut self.pushed);
+        if uploaded {
+            self.text.prepare(device, queue);
+        }
+
+        changed || uploaded
+    }
+
     fn render(&self, render_pass: &mut wgpu::RenderPass) {
-        render_pass.set_pipeline(&self.pipeline);
-        render_pass.draw(0..6, 0..1);
+        let key = self.shown;
+        let (index_buffer, count) = match key.primitive {
+            Primitive::PointList | Primitive::LineList => &self.indices.sequential,
+            Primitive::TriangleList => &self.indices.triangles,
+            Primitive::LineStrip | Primitive::TriangleStrip if self.restart => &self.indices.strips,
+            Primitive::LineStrip | Primitive::TriangleStrip => &self.indices.joined_strips,
+        };
+
+        render_pass.set_pipeline(&self.pipelines[&key]);
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+        render_pass.draw_indexed(0..*count, 0, 0..1);
+
+        self.text.render(render_pass);
     }
 }
@@ -1,19 +1,30 @@
+struct VertexInput {
+    @location(0) pos: vec2<f32>,
+    @location(1) color: vec3<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) pos: vec4<f32>,
+    @location(0) color: vec3<f32>,
+}
+
 @vertex
-fn vs_main(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
-    var pos = array<vec2<f32>, 6>(
-        vec2f(-0.2, 0.9),
-        vec2f(-0.8, 0.7),
-        vec2f(-0.1, 0.05),
+fn vs_main(in: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
 
-        vec2f(0.9, 0.3),
-        vec2f(-0.3, -0.4),
-        vec2f(0.3, -0.6),
-    );
+    out.pos = vec4f(in.pos, 0.0, 1.0);
+    out.color = in.color;
 
-    return vec4<f32>(pos[index], 0.0, 1.0);
+    return out;
 }
 
 @fragment
-fn fs_main() -> @location(0) vec4<f32> {
-    return vec4<f32>(0.0, 1.0, 0.0, 1.0);
+fn fs_main(in: VertexOutput, @builtin(front_facing) front_facing: bool) -> @location(0) vec4<f32> {
+    // back faces show up red while they aren't culled, lines and points
+    // always count as front facing
+    if !front_facing {
+        return vec4f(0.8, 0.1, 0.1, 1.0);
+    }
+
+    return vec4f(in.color, 1.0);
 }
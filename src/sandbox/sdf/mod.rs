@@ -0,0 +1,333 @@
+use glam::Vec2;
+use winit::{
+    dpi::PhysicalSize,
+    event::{ElementState, KeyEvent, MouseButton},
+    keyboard::{KeyCode, PhysicalKey},
+};
+
+use crate::{
+    graphics::{MouseEvent, Renderable},
+    sandbox::camera2d::camera::Camera2D,
+    text::{
+        sdf::{Effects, SdfFont, SdfRenderer, Shape},
+        Font, Space, TextSection,
+    },
+};
+
+const BUTTON_SIZE: Vec2 = Vec2::new(150.0, 40.0);
+const BUTTON_GAP: f32 = 12.0;
+const MARGIN: f32 = 20.0;
+
+const HELP: &str = "Q/E or the mouse wheel zoom, arrows pan, click the buttons to toggle effects";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Toggle {
+    Outline,
+    Shadow,
+    Glow,
+}
+
+const TOGGLES: [(Toggle, &str); 3] = [
+    (Toggle::Outline, "Outline"),
+    (Toggle::Shadow, "Shadow"),
+    (Toggle::Glow, "Glow"),
+];
+
+pub struct Sandbox {
+    sdf: SdfRenderer,
+    camera: Camera2D,
+    size: PhysicalSize<u32>,
+    // in screen pixels
+    cursor: Vec2,
+    outline: bool,
+    shadow: bool,
+    glow: bool,
+    // set when the next frame has to be laid out again
+    dirty: bool,
+}
+
+impl Sandbox {
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        view_format: wgpu::TextureFormat,
+    ) -> Self {
+        let font = SdfFont::from_font(Font::default());
+
+        Self {
+            sdf: SdfRenderer::new(device, queue, view_format, font),
+            camera: Camera2D::new(640, 480, 1.0),
+            size: PhysicalSize::new(640, 480),
+            cursor: Vec2::splat(-1.0),
+            outline: true,
+            shadow: true,
+            glow: false,
+            dirty: true,
+        }
+    }
+
+    fn button_position(&self, index: usize) -> Vec2 {
+        Vec2::new(
+            MARGIN + index as f32 * (BUTTON_SIZE.x + BUTTON_GAP),
+            self.size.height as f32 - MARGIN - BUTTON_SIZE.y,
+        )
+    }
+
+    fn hovered_button(&self) -> Option<usize> {
+        (0..TOGGLES.len()).find(|&i| {
+            let min = self.button_position(i);
+            let max = min + BUTTON_SIZE;
+            self.cursor.cmpge(min).all() && self.cursor.cmple(max).all()
+        })
+    }
+
+    fn enabled(&self, toggle: Toggle) -> bool {
+        match toggle {
+            Toggle::Outline => self.outline,
+            Toggle::Shadow => self.shadow,
+            Toggle::Glow => self.glow,
+        }
+    }
+
+    fn effects(&self, size: f32) -> Effects {
+        let mut effects = Effects::default();
+
+        if self.outline {
+            effects.outline_width = size * 0.05;
+            effects.outline_color = [0.05, 0.05, 0.1, 1.0];
+        }
+        if self.shadow {
+            effects.shadow_offset = Vec2::splat(size * 0.06);
+            effects.shadow_softness = size * 0.05;
+            effects.shadow_color = [0.0, 0.0, 0.0, 0.7];
+        }
+        if self.glow {
+            effects.glow_width = size * 0.1;
+            effects.glow_color = [1.0, 0.5, 0.1, 0.8];
+        }
+
+        effects
+    }
+
+    fn zoom(&mut self, factor: f32, queue: &wgpu::Queue) {
+        self.camera.scale = (self.camera.scale * factor).clamp(0.05, 50.0);
+        self.sdf.set_camera(&self.camera, queue);
+        self.dirty = true;
+    }
+
+    fn push_world(&mut self) {
+        let lines = [
+            ("Signed distance fields", 48.0, [1.0, 1.0, 1.0, 1.0]),
+            ("stay crisp at every zoom level", 24.0, [0.3, 0.8, 1.0, 1.0]),
+            ("from 8 px ...", 8.0, [0.9, 0.9, 0.9, 1.0]),
+            ("... to 96 px", 96.0, [1.0, 0.85, 0.2, 1.0]),
+        ];
+
+        let mut y = 40.0;
+        for (text, size, color) in lines {
+            let effects = self.effects(size);
+            let layout = self.sdf.push_text(
+                &TextSection {
+                    text,
+                    position: Vec2::new(40.0, y),
+                    size,
+                    color,
+                    space: Space::World,
+                    ..Default::default()
+                },
+                &effects,
+            );
+            y += layout.height + 10.0;
+        }
+
+        // the same field drawing shapes, with the same effects
+        let effects = self.effects(40.0);
+        for (i, color) in [
+            [0.9, 0.3, 0.3, 1.0],
+            [0.3, 0.9, 0.4, 1.0],
+            [0.3, 0.5, 1.0, 1.0],
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            self.sdf.push_shape(
+                Shape::Circle {
+                    center: Vec2::new(80.0 + i as f32 * 90.0, y + 50.0),
+                    radius: 35.0,
+                },
+                color,
+                Space::World,
+                &effects,
+            );
+        }
+        self.sdf.push_shape(
+            Shape::RoundedRect {
+                position: Vec2::new(320.0, y + 15.0),
+                size: Vec2::new(200.0, 70.0),
+                radius: 18.0,
+            },
+            [0.6, 0.4, 0.9, 1.0],
+            Space::World,
+            &effects,
+        );
+    }
+
+    fn push_ui(&mut self) {
+        self.sdf.push_text(
+            &TextSection {
+                text: HELP,
+                position: Vec2::new(MARGIN, 10.0),
+                size: 16.0,
+                color: [0.7, 0.7, 0.7, 1.0],
+                ..Default::default()
+            },
+            &Effects::default(),
+        );
+
+        let hovered = self.hovered_button();
+        let shadow = Effects {
+            shadow_offset: Vec2::new(0.0, 3.0),
+            shadow_softness: 6.0,
+            shadow_color: [0.0, 0.0, 0.0, 0.6],
+            ..Default::default()
+        };
+
+        for (i, (toggle, label)) in TOGGLES.into_iter().enumerate() {
+            let position = self.button_position(i);
+            let background = if hovered == Some(i) {
+                [0.32, 0.34, 0.42, 1.0]
+            } else {
+                [0.22, 0.23, 0.3, 1.0]
+            };
+
+            self.sdf.push_shape(
+                Shape::RoundedRect {
+                    position,
+                    size: BUTTON_SIZE,
+                    radius: 10.0,
+                },
+                background,
+                Space::Screen,
+                &shadow,
+            );
+
+            // an indicator ring, filled while the effect is on
+            let center = position + Vec2::new(22.0, BUTTON_SIZE.y / 2.0);
+            let ring = Effects {
+                outline_width: 2.0,
+                outline_color: [0.9, 0.9, 0.9, 1.0],
+                ..Default::default()
+            };
+            let fill = if self.enabled(toggle) {
+                [0.4, 0.85, 0.5, 1.0]
+            } else {
+                background
+            };
+            self.sdf.push_shape(
+                Shape::Circle {
+                    center,
+                    radius: 7.0,
+                },
+                fill,
+                Space::Screen,
+                &ring,
+            );
+
+            let section = TextSection {
+                text: label,
+                size: 20.0,
+                ..Default::default()
+            };
+            let layout = self.sdf.measure(&section);
+            self.sdf.push_text(
+                &TextSection {
+                    position: position + Vec2::new(40.0, (BUTTON_SIZE.y - layout.height) / 2.0),
+                    ..section
+                },
+                &Effects::default(),
+            );
+        }
+    }
+}
+
+impl Renderable for Sandbox {
+    fn handle_input(&mut self, key_event: KeyEvent, queue: &wgpu::Queue) {
+        let KeyEvent {
+            physical_key,
+            state,
+            ..
+        } = key_event;
+
+        if state == ElementState::Released {
+            return;
+        };
+
+        match physical_key {
+            PhysicalKey::Code(KeyCode::ArrowUp) => self.camera.y -= 20.0 / self.camera.scale,
+            PhysicalKey::Code(KeyCode::ArrowDown) => self.camera.y += 20.0 / self.camera.scale,
+            PhysicalKey::Code(KeyCode::ArrowLeft) => self.camera.x -= 20.0 / self.camera.scale,
+            PhysicalKey::Code(KeyCode::ArrowRight) => self.camera.x += 20.0 / self.camera.scale,
+            PhysicalKey::Code(KeyCode::KeyE) => return self.zoom(1.25, queue),
+            PhysicalKey::Code(KeyCode::KeyQ) => return self.zoom(1.0 / 1.25, queue),
+            _ => return,
+        }
+
+        self.sdf.set_camera(&self.camera, queue);
+        self.dirty = true;
+    }
+
+    fn handle_mouse(&mut self, mouse_event: MouseEvent, queue: &wgpu::Queue) {
+        match mouse_event {
+            MouseEvent::Moved(ndc) => {
+                let hovered = self.hovered_button();
+                self.cursor = Vec2::new(
+                    (ndc.x + 1.0) / 2.0 * self.size.width as f32,
+                    (1.0 - ndc.y) / 2.0 * self.size.height as f32,
+                );
+                self.dirty |= hovered != self.hovered_button();
+            }
+            MouseEvent::Button(MouseButton::Left, ElementState::Pressed) => {
+                let Some(i) = self.hovered_button() else {
+                    return;
+                };
+
+                match TOGGLES[i].0 {
+                    Toggle::Outline => self.outline = !self.outline,
+                    Toggle::Shadow => self.shadow = !self.shadow,
+                    Toggle::Glow => self.glow = !self.glow,
+                }
+                self.dirty = true;
+            }
+            MouseEvent::Wheel(lines) => self.zoom(1.1_f32.powf(lines), queue),
+            _ => (),
+        }
+    }
+
+    fn resize(&mut self, size: PhysicalSize<u32>, queue: &wgpu::Queue) {
+        self.size = size;
+        self.camera.width = size.width as f32;
+        self.camera.height = size.height as f32;
+
+        self.sdf.resize(size, queue);
+        self.sdf.set_camera(&self.camera, queue);
+        self.dirty = true;
+    }
+
+    // the last prepared frame keeps being drawn until something changes
+    fn prepare(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) -> bool {
+        if !self.dirty {
+            return false;
+        }
+
+        self.push_world();
+        self.push_ui();
+        self.sdf.prepare(device, queue);
+        self.dirty = false;
+
+        true
+    }
+
+    fn render(&self, render_pass: &mut wgpu::RenderPass) {
+        self.sdf.render(render_pass);
+    }
+}
@@ -0,0 +1,286 @@
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
+use glam::Vec2;
+use winit::{
+    dpi::PhysicalSize,
+    event::{ElementState, KeyEvent},
+    keyboard::{KeyCode, PhysicalKey},
+};
+
+use crate::{
+    graphics::{MouseEvent, Renderable},
+    polyline::{Cap, Join},
+    sandbox::camera2d::camera::Camera2D,
+    tessellation::{FillRule, Path, ShapeBatch, ShapeId, StrokeOptions, Style},
+    text::{Font, Space, TextRenderer, TextSection},
+};
+
+const HELP: &str = "arrows pan, Q/E or the wheel zoom, O toggles outlines";
+
+const OUTLINE: [f32; 3] = [0.95, 0.95, 0.95];
+
+// drawn in one go, skipping two points each time, so the middle is wound
+// twice
+fn pentagram(center: Vec2, radius: f32) -> Vec<Vec2> {
+    (0..5)
+        .map(|i| center + Vec2::from_angle((i * 2) as f32 * TAU / 5.0 - FRAC_PI_2) * radius)
+        .collect()
+}
+
+fn heart(center: Vec2, size: f32) -> Path {
+    let p = |x: f32, y: f32| center + Vec2::new(x, y) * size;
+
+    Path::new()
+        .move_to(p(0.0, -0.25))
+        .cubic_to(p(0.0, -0.55), p(0.5, -0.65), p(0.5, -0.2))
+        .cubic_to(p(0.5, 0.1), p(0.1, 0.3), p(0.0, 0.5))
+        .cubic_to(p(-0.1, 0.3), p(-0.5, 0.1), p(-0.5, -0.2))
+        .cubic_to(p(-0.5, -0.65), p(0.0, -0.55), p(0.0, -0.25))
+        .close()
+}
+
+fn wave(start: Vec2, length: f32, height: f32, waves: usize) -> Path {
+    let step = length / waves as f32;
+
+    (0..waves).fold(Path::new().move_to(start), |path, i| {
+        let x = start.x + i as f32 * step;
+        let sign = if i % 2 == 0 { -1.0 } else { 1.0 };
+        path.quadratic_to(
+            Vec2::new(x + step / 2.0, start.y + sign * height * 2.0),
+            Vec2::new(x + step, start.y),
+        )
+    })
+}
+
+pub struct Sandbox {
+    shapes: ShapeBatch,
+    text: TextRenderer,
+    camera: Camera2D,
+    // the filled shapes, which get an outline each when it's shown
+    outlined: Vec<Path>,
+    outlines: Vec<ShapeId>,
+}
+
+impl Sandbox {
+    pub fn new(device: &wgpu::Device, view_format: wgpu::TextureFormat) -> Self {
+        let mut sandbox = Self {
+            shapes: ShapeBatch::new(device, view_format),
+            text: TextRenderer::new(device, view_format, Font::default()),
+            camera: Camera2D::new(640, 480, 1.0),
+            outlined: Vec::new(),
+            outlines: Vec::new(),
+        };
+        sandbox.add_shapes();
+        sandbox.toggle_outlines();
+
+        sandbox
+    }
+
+    fn fill(&mut self, path: Path, rule: FillRule, color: [f32; 3]) {
+        self.outlined.push(path.clone());
+        self.shapes.add(path, Style::Fill(rule), color);
+    }
+
+    fn add_shapes(&mut self) {
+        let non_zero = FillRule::NonZero;
+
+        // the basic shapes
+        self.fill(
+            Path::new().circle(Vec2::new(80.0, 100.0), 45.0),
+            non_zero,
+            [0.9, 0.3, 0.3],
+        );
+        self.fill(
+            Path::new().ellipse(Vec2::new(210.0, 100.0), Vec2::new(65.0, 35.0)),
+            non_zero,
+            [0.3, 0.8, 0.4],
+        );
+        self.fill(
+            Path::new().rounded_rect(Vec2::new(300.0, 60.0), Vec2::new(120.0, 80.0), 20.0),
+            non_zero,
+            [0.3, 0.5, 0.95],
+        );
+
+        // a pie chart of wedges
+        let center = Vec2::new(520.0, 100.0);
+        let mut start = -FRAC_PI_2;
+        for (share, color) in [
+            (0.45, [0.95, 0.75, 0.2]),
+            (0.3, [0.6, 0.35, 0.85]),
+            (0.25, [0.2, 0.75, 0.8]),
+        ] {
+            let sweep = share * TAU;
+            self.fill(Path::new().pie(center, 50.0, start, sweep), non_zero, color);
+            start += sweep;
+        }
+
+        // the same self intersecting star and the same pair of nested
+        // squares, one with each fill rule
+        for (i, rule) in [FillRule::NonZero, FillRule::EvenOdd]
+            .into_iter()
+            .enumerate()
+        {
+            let x = 80.0 + i as f32 * 280.0;
+            self.fill(
+                Path::new().polygon(&pentagram(Vec2::new(x, 250.0), 60.0)),
+                rule,
+                [0.95, 0.6, 0.2],
+            );
+            self.fill(
+                Path::new()
+                    .rect(Vec2::new(x + 80.0, 195.0), Vec2::splat(110.0))
+                    .rect(Vec2::new(x + 110.0, 225.0), Vec2::splat(50.0)),
+                rule,
+                [0.5, 0.7, 0.95],
+            );
+        }
+
+        // curves
+        self.fill(
+            heart(Vec2::new(90.0, 400.0), 120.0),
+            non_zero,
+            [0.9, 0.2, 0.4],
+        );
+
+        let stroke = StrokeOptions {
+            width: 8.0,
+            join: Join::Round,
+            cap: Cap::Round,
+            ..Default::default()
+        };
+        self.shapes.add(
+            wave(Vec2::new(180.0, 400.0), 240.0, 25.0, 4),
+            Style::Stroke(stroke),
+            [0.4, 0.9, 0.9],
+        );
+        self.shapes.add(
+            Path::new().arc(Vec2::new(520.0, 410.0), 50.0, PI, PI * 1.5),
+            Style::Stroke(StrokeOptions {
+                width: 14.0,
+                ..stroke
+            }),
+            [0.95, 0.85, 0.3],
+        );
+    }
+
+    // removed and added again rather than hidden, the batch only keeps what
+    // it draws
+    fn toggle_outlines(&mut self) {
+        if !self.outlines.is_empty() {
+            for id in self.outlines.drain(..) {
+                self.shapes.remove(id);
+            }
+            return;
+        }
+
+        let outline = Style::Stroke(StrokeOptions {
+            width: 2.0,
+            join: Join::Round,
+            ..Default::default()
+        });
+        for path in &self.outlined {
+            let id = self.shapes.add(path.clone(), outline, OUTLINE);
+            self.outlines.push(id);
+        }
+    }
+
+    fn set_camera(&mut self, queue: &wgpu::Queue) {
+        self.shapes.set_camera(&self.camera, queue);
+        self.text.set_camera(&self.camera, queue);
+    }
+
+    fn zoom(&mut self, factor: f32, queue: &wgpu::Queue) {
+        self.camera.scale = (self.camera.scale * factor).clamp(0.1, 100.0);
+        self.set_camera(queue);
+    }
+
+    fn label(&mut self, text: &str, position: Vec2) {
+        self.text.push(&TextSection {
+            text,
+            position,
+            size: 14.0,
+            color: [0.7, 0.7, 0.7, 1.0],
+            space: Space::World,
+            ..Default::default()
+        });
+    }
+}
+
+impl Renderable for Sandbox {
+    fn handle_input(&mut self, key_event: KeyEvent, queue: &wgpu::Queue) {
+        let KeyEvent {
+            physical_key,
+            state,
+            ..
+        } = key_event;
+
+        if state == ElementState::Released {
+            return;
+        };
+
+        let step = 20.0 / self.camera.scale;
+        match physical_key {
+            PhysicalKey::Code(KeyCode::ArrowUp) => self.camera.y -= step,
+            PhysicalKey::Code(KeyCode::ArrowDown) => self.camera.y += step,
+            PhysicalKey::Code(KeyCode::ArrowLeft) => self.camera.x -= step,
+            PhysicalKey::Code(KeyCode::ArrowRight) => self.camera.x += step,
+            PhysicalKey::Code(KeyCode::KeyE) => return self.zoom(1.25, queue),
+            PhysicalKey::Code(KeyCode::KeyQ) => return self.zoom(1.0 / 1.25, queue),
+            PhysicalKey::Code(KeyCode::KeyO) => return self.toggle_outlines(),
+            _ => return,
+        }
+
+        self.set_camera(queue);
+    }
+
+    fn handle_mouse(&mut self, mouse_event: MouseEvent, queue: &wgpu::Queue) {
+        if let MouseEvent::Wheel(lines) = mouse_event {
+            self.zoom(1.1_f32.powf(lines), queue);
+        }
+    }
+
+    fn ui(&mut self, ctx: &egui::Context, _queue: &wgpu::Queue) {
+        let mut outlines = !self.outlines.is_empty();
+
+        egui::Window::new("Shapes")
+            .default_pos([420.0, 20.0])
+            .show(ctx, |ui| {
+                ui.label(format!("{} triangles", self.shapes.triangle_count()));
+                ui.label(format!("tolerance {:.4}", self.shapes.tolerance()));
+                ui.checkbox(&mut outlines, "outlines");
+            });
+
+        if outlines == self.outlines.is_empty() {
+            self.toggle_outlines();
+        }
+    }
+
+    fn resize(&mut self, size: PhysicalSize<u32>, queue: &wgpu::Queue) {
+        self.camera.width = size.width as f32;
+        self.camera.height = size.height as f32;
+
+        self.text.resize(size, queue);
+        self.set_camera(queue);
+    }
+
+    fn prepare(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) -> bool {
+        self.shapes.prepare(device, queue);
+
+        self.label("non zero", Vec2::new(20.0, 320.0));
+        self.label("even odd", Vec2::new(300.0, 320.0));
+        self.text.push(&TextSection {
+            text: HELP,
+            position: Vec2::new(10.0, 10.0),
+            size: 16.0,
+            ..Default::default()
+        });
+        self.text.prepare(device, queue);
+
+        false
+    }
+
+    fn render(&self, render_pass: &mut wgpu::RenderPass) {
+        self.shapes.render(render_pass);
+        self.text.render(render_pass);
+    }
+}
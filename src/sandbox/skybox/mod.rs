@@ -0,0 +1,238 @@
+use glam::{vec3, Mat4, Quat, Vec3};
+use half::f16;
+use wgpu::{include_wgsl, util::DeviceExt};
+use winit::{
+    dpi::PhysicalSize,
+    event::{ElementState, KeyEvent},
+    keyboard::{KeyCode, PhysicalKey},
+};
+
+use crate::{
+    graphics::Renderable,
+    texture::{
+        decode::{DecodedImage, Pixels},
+        Texture, TextureOptions,
+    },
+};
+
+const SKY_WIDTH: u32 = 1024;
+const SKY_HEIGHT: u32 = 512;
+
+// equirectangular sky with a sun and a 30 degree grid so the orientation of
+// every cube face is easy to check
+fn procedural_sky() -> DecodedImage {
+    use std::f32::consts::PI;
+
+    let sun = vec3(0.0, 0.4, 1.0).normalize();
+    let zenith = vec3(0.1, 0.3, 0.8);
+    let horizon = vec3(0.8, 0.85, 0.9);
+    let ground = vec3(0.2, 0.16, 0.12);
+
+    let mut data = Vec::with_capacity((SKY_WIDTH * SKY_HEIGHT * 4) as usize);
+
+    for y in 0..SKY_HEIGHT {
+        for x in 0..SKY_WIDTH {
+            let longitude = ((x as f32 + 0.5) / SKY_WIDTH as f32 - 0.5) * 2.0 * PI;
+            let latitude = (0.5 - (y as f32 + 0.5) / SKY_HEIGHT as f32) * PI;
+            let dir = vec3(
+                latitude.cos() * longitude.cos(),
+                latitude.sin(),
+                latitude.cos() * longitude.sin(),
+            );
+
+            let mut color = if latitude > 0.0 {
+                horizon.lerp(zenith, (latitude / (PI / 2.0)).sqrt())
+            } else {
+                ground * (1.0 + latitude)
+            };
+
+            color += Vec3::splat(dir.dot(sun).max(0.0).powf(512.0) * 8.0);
+
+            let grid = (PI / 6.0, 0.004);
+            if longitude.rem_euclid(grid.0) < grid.1 * 2.0 || latitude.rem_euclid(grid.0) < grid.1 {
+                color *= 0.6;
+            }
+
+            data.extend([color.x, color.y, color.z, 1.0].map(f16::from_f32));
+        }
+    }
+
+    DecodedImage {
+        width: SKY_WIDTH,
+        height: SKY_HEIGHT,
+        pixels: Pixels::Rgba16Float(data),
+    }
+}
+
+struct OrbitCamera {
+    yaw: f32,
+    pitch: f32,
+    aspect: f32,
+}
+
+impl OrbitCamera {
+    fn inv_view_projection(&self) -> Mat4 {
+        let rotation = Quat::from_rotation_y(self.yaw) * Quat::from_rotation_x(self.pitch);
+        let view = Mat4::from_quat(rotation).inverse();
+        let projection = Mat4::perspective_rh(70f32.to_radians(), self.aspect, 0.1, 10.0);
+
+        (projection * view).inverse().transpose()
+    }
+}
+
+pub struct Sandbox {
+    pipeline: wgpu::RenderPipeline,
+    camera: OrbitCamera,
+    camera_buffer: wgpu::Buffer,
+    sky_bind_group: wgpu::BindGroup,
+    camera_bind_group: wgpu::BindGroup,
+}
+
+impl Sandbox {
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        view_format: wgpu::TextureFormat,
+    ) -> Self {
+        let shader = device.create_shader_module(include_wgsl!("shader.wgsl"));
+
+        let sky = Texture::from_equirectangular(
+            device,
+            queue,
+            &procedural_sky(),
+            512,
+            TextureOptions::default(),
+        )
+        .expect("the procedural sky is a valid panorama");
+
+        let sky_layout = Texture::bind_group_layout_for(device, sky.view_dimension);
+        let camera_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: None,
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("pipeline layout"),
+            bind_group_layouts: &[&sky_layout, &camera_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("render pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: view_format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            multisample: Default::default(),
+            depth_stencil: None,
+            multiview: None,
+            cache: None,
+        });
+
+        let camera = OrbitCamera {
+            yaw: 0.0,
+            pitch: 0.0,
+            aspect: 640.0 / 480.0,
+        };
+
+        let camera_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("camera buffer"),
+            contents: bytemuck::cast_slice(&camera.inv_view_projection().to_cols_array_2d()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("camera bind group"),
+            layout: &camera_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: camera_buffer.as_entire_binding(),
+            }],
+        });
+
+        Self {
+            pipeline,
+            camera,
+            camera_buffer,
+            sky_bind_group: sky.bind_group(device, &sky_layout),
+            camera_bind_group,
+        }
+    }
+
+    fn update_camera(&self, queue: &wgpu::Queue) {
+        queue.write_buffer(
+            &self.camera_buffer,
+            0,
+            bytemuck::cast_slice(&self.camera.inv_view_projection().to_cols_array_2d()),
+        );
+    }
+}
+
+impl Renderable for Sandbox {
+    fn handle_input(&mut self, key_event: KeyEvent, queue: &wgpu::Queue) {
+        let KeyEvent {
+            physical_key,
+            state,
+            ..
+        } = key_event;
+
+        if state == ElementState::Released {
+            return;
+        };
+
+        let step = 3f32.to_radians();
+
+        match physical_key {
+            PhysicalKey::Code(KeyCode::ArrowUp) => {
+                self.camera.pitch = (self.camera.pitch + step).min(1.5);
+            }
+            PhysicalKey::Code(KeyCode::ArrowDown) => {
+                self.camera.pitch = (self.camera.pitch - step).max(-1.5);
+            }
+            PhysicalKey::Code(KeyCode::ArrowLeft) => {
+                self.camera.yaw += step;
+            }
+            PhysicalKey::Code(KeyCode::ArrowRight) => {
+                self.camera.yaw -= step;
+            }
+            _ => (),
+        }
+
+        self.update_camera(queue);
+    }
+
+    fn resize(&mut self, size: PhysicalSize<u32>, queue: &wgpu::Queue) {
+        self.camera.aspect = size.width.max(1) as f32 / size.height.max(1) as f32;
+        self.update_camera(queue);
+    }
+
+    fn render(&self, render_pass: &mut wgpu::RenderPass) {
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.sky_bind_group, &[]);
+        render_pass.set_bind_group(1, &self.camera_bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
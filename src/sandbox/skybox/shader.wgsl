@@ -0,0 +1,34 @@
+struct VertexOutput {
+    @builtin(position) pos: vec4<f32>,
+    @location(0) ndc: vec2<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    var out: VertexOutput;
+
+    let uv = vec2f(f32((index << 1u) & 2u), f32(index & 2u));
+    let ndc = vec2f(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0);
+
+    out.pos = vec4f(ndc, 0.0, 1.0);
+    out.ndc = ndc;
+
+    return out;
+}
+
+@group(0) @binding(0)
+var sky: texture_cube<f32>;
+
+@group(0) @binding(1)
+var sky_sampler: sampler;
+
+@group(1) @binding(0)
+var<uniform> inv_view_projection: mat4x4<f32>;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let world = vec4f(in.ndc, 1.0, 1.0) * inv_view_projection;
+    let dir = normalize(world.xyz / world.w);
+
+    return textureSample(sky, sky_sampler, dir);
+}
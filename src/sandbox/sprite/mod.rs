@@ -0,0 +1,284 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use glam::Vec2;
+use winit::{
+    dpi::PhysicalSize,
+    event::{ElementState, KeyEvent},
+    keyboard::{KeyCode, PhysicalKey},
+};
+
+use crate::{
+    assets::AssetSource,
+    debug_draw::DebugDraw,
+    embed,
+    graphics::Renderable,
+    sandbox::camera2d::camera::Camera2D,
+    sprite::{AnimationEvent, Animator, Clip, PlayMode, Sprite, SpriteBatch},
+    text::{Font, TextRenderer, TextSection},
+    texture::{
+        atlas::{AtlasOptions, TextureAtlas},
+        decode::DecodedImage,
+        sampler::SamplerPreset,
+        sheet::SpriteSheet,
+        TextureOptions,
+    },
+};
+
+const SHEET: &str = "src/sandbox/sprite/hero.json";
+const HELP: &str = "arrows walk, space waves";
+
+// the sheet's pixels are this big on screen
+const SCALE: f32 = 6.0;
+// in pixels per second
+const WALK_SPEED: f32 = 240.0;
+// for frames the sheet has no duration for, in seconds
+const FRAME_DURATION: f32 = 0.1;
+const LOG_LENGTH: usize = 12;
+
+fn load(device: &wgpu::Device, queue: &wgpu::Queue) -> Result<(TextureAtlas, Animator), String> {
+    let source = AssetSource::default().with_embedded(&[
+        embed!("src/sandbox/sprite/hero.json"),
+        embed!("src/sandbox/sprite/hero.png"),
+    ]);
+
+    let json = String::from_utf8(source.read(SHEET)?).map_err(|e| e.to_string())?;
+    let sheet = SpriteSheet::from_json(&json)?;
+    let image = DecodedImage::decode(&source.read("src/sandbox/sprite/hero.png")?)?;
+
+    // filtering would blur the pixel art
+    let mut atlas = TextureAtlas::new(AtlasOptions {
+        page_size: 256,
+        texture: TextureOptions {
+            sampler: SamplerPreset::Nearest,
+            ..AtlasOptions::default().texture
+        },
+        ..Default::default()
+    });
+    atlas.add_sheet(&image, &sheet)?;
+    atlas.upload(device, queue);
+
+    let clips = Clip::from_tags(&sheet, FRAME_DURATION)?
+        .into_iter()
+        .map(|clip| match clip.name.as_str() {
+            // a foot comes down on the second and fourth frames
+            "walk" => clip.with_event(1, "footstep").with_event(3, "footstep"),
+            "wave" => Clip {
+                mode: PlayMode::Once,
+                ..clip
+            },
+            _ => clip,
+        })
+        .collect();
+
+    Ok((atlas, Animator::new(clips)?))
+}
+
+pub struct Sandbox {
+    atlas: Option<TextureAtlas>,
+    animator: Option<Animator>,
+    batch: SpriteBatch,
+    text: TextRenderer,
+    camera: Camera2D,
+    position: Vec2,
+    facing_left: bool,
+    waving: bool,
+    // arrow keys held down, as a direction
+    held: BTreeMap<KeyCode, Vec2>,
+    events: Vec<AnimationEvent>,
+    log: VecDeque<String>,
+    frames: u64,
+    // pushed in `update` or `new` but not uploaded yet
+    sprite_pushed: bool,
+    text_pushed: bool,
+}
+
+impl Sandbox {
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        view_format: wgpu::TextureFormat,
+    ) -> Self {
+        let (atlas, animator) = load(device, queue)
+            .inspect_err(|e| log::error!("failed to load the sprite sheet: {e}"))
+            .ok()
+            .unzip();
+
+        let mut batch = SpriteBatch::new(device, view_format);
+        if let Some(page) = atlas.as_ref().and_then(|atlas| atlas.page(0)) {
+            batch.set_texture(device, page);
+        }
+
+        let mut text = TextRenderer::new(device, view_format, Font::default());
+        text.push(&TextSection {
+            text: HELP,
+            position: Vec2::new(10.0, 10.0),
+            size: 16.0,
+            ..Default::default()
+        });
+
+        Self {
+            atlas,
+            animator,
+            batch,
+            text,
+            camera: Camera2D::new(640, 480, 1.0),
+            position: Vec2::new(320.0, 240.0),
+            facing_left: false,
+            waving: false,
+            held: BTreeMap::new(),
+            events: Vec::new(),
+            log: VecDeque::new(),
+            frames: 0,
+            sprite_pushed: false,
+            text_pushed: true,
+        }
+    }
+
+    fn push_sprite(&mut self) {
+        let region = self
+            .animator
+            .as_ref()
+            .zip(self.atlas.as_ref())
+            .and_then(|(animator, atlas)| atlas.region(animator.region()));
+
+        // standing on `position`, in the middle
+        if let Some(region) = region {
+            let source_size = Vec2::new(region.source_size.0 as f32, region.source_size.1 as f32);
+            let top_left = self.position - source_size * SCALE * Vec2::new(0.5, 1.0);
+            let sprite = Sprite::from_region(&region, top_left, SCALE, self.facing_left);
+            self.batch.push(&sprite);
+        }
+        self.sprite_pushed = true;
+    }
+
+    // the clip that fits what the character is doing
+    fn clip(&self) -> &'static str {
+        if self.waving {
+            "wave"
+        } else if self.held.is_empty() {
+            "idle"
+        } else {
+            "walk"
+        }
+    }
+}
+
+impl Renderable for Sandbox {
+    fn handle_input(&mut self, key_event: KeyEvent, _queue: &wgpu::Queue) {
+        let KeyEvent {
+            physical_key: PhysicalKey::Code(code),
+            state,
+            repeat,
+            ..
+        } = key_event
+        else {
+            return;
+        };
+
+        let direction = match code {
+            KeyCode::ArrowUp => Vec2::NEG_Y,
+            KeyCode::ArrowDown => Vec2::Y,
+            KeyCode::ArrowLeft => Vec2::NEG_X,
+            KeyCode::ArrowRight => Vec2::X,
+            _ => Vec2::ZERO,
+        };
+        // walking happens in `update`, for as long as the keys are held
+        if direction != Vec2::ZERO {
+            match state {
+                ElementState::Pressed => self.held.insert(code, direction),
+                ElementState::Released => self.held.remove(&code),
+            };
+            return;
+        }
+
+        if code == KeyCode::Space && state == ElementState::Pressed && !repeat {
+            self.waving = true;
+        }
+    }
+
+    fn ui(&mut self, ctx: &egui::Context, _queue: &wgpu::Queue) {
+        egui::Window::new("Animation")
+            .default_pos([420.0, 20.0])
+            .show(ctx, |ui| {
+                let Some(animator) = &mut self.animator else {
+                    ui.label("no sprite sheet loaded, see the log");
+                    return;
+                };
+
+                ui.label(format!(
+                    "{} frame {} ({})",
+                    animator.clip().name,
+                    animator.frame(),
+                    animator.region()
+                ));
+                ui.add(egui::Slider::new(&mut animator.speed, 0.1..=3.0).text("speed"));
+                ui.separator();
+                for line in &self.log {
+                    ui.monospace(line);
+                }
+            });
+    }
+
+    fn update(&mut self, delta_time: f32, _debug_draw: &mut DebugDraw) {
+        // waving stands still
+        let direction = self.held.values().sum::<Vec2>();
+        if direction != Vec2::ZERO && !self.waving {
+            self.position += direction.normalize_or_zero() * WALK_SPEED * delta_time;
+            if direction.x != 0.0 {
+                self.facing_left = direction.x < 0.0;
+            }
+        }
+
+        let clip = self.clip();
+        let Some(animator) = &mut self.animator else {
+            return;
+        };
+        if let Err(e) = animator.play(clip) {
+            log::error!("{e}");
+        }
+        animator.advance(delta_time, &mut self.events);
+
+        self.frames += 1;
+        for event in self.events.drain(..) {
+            match event {
+                AnimationEvent::Finished => self.waving = false,
+                // every frame change would drown out the rest
+                AnimationEvent::Frame(_) => continue,
+                _ => (),
+            }
+
+            self.log.push_front(format!("{:>6} {event:?}", self.frames));
+            self.log.truncate(LOG_LENGTH);
+        }
+
+        // once per upload, pushing again would draw the sprite twice
+        if !self.sprite_pushed {
+            self.push_sprite();
+        }
+    }
+
+    fn resize(&mut self, size: PhysicalSize<u32>, queue: &wgpu::Queue) {
+        self.camera.width = size.width as f32;
+        self.camera.height = size.height as f32;
+
+        self.batch.set_camera(&self.camera, queue);
+        self.text.resize(size, queue);
+    }
+
+    // always animating
+    fn prepare(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) -> bool {
+        if std::mem::take(&mut self.sprite_pushed) {
+            self.batch.prepare(device, queue);
+        }
+        if std::mem::take(&mut self.text_pushed) {
+            self.text.prepare(device, queue);
+        }
+
+        true
+    }
+
+    fn render(&self, render_pass: &mut wgpu::RenderPass) {
+        self.batch.render(render_pass);
+        self.text.render(render_pass);
+    }
+}
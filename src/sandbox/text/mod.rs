@@ -0,0 +1,226 @@
+use glam::Vec2;
+use winit::{
+    dpi::PhysicalSize,
+    event::{ElementState, KeyEvent},
+    keyboard::{KeyCode, PhysicalKey},
+};
+
+use crate::{
+    debug_draw::DebugDraw,
+    graphics::Renderable,
+    sandbox::camera2d::camera::Camera2D,
+    text::{Align, Font, LayoutOptions, Space, TextRenderer, TextSection},
+};
+
+const MARGIN: f32 = 20.0;
+
+const PARAGRAPH: &str = "The quick brown fox jumps over the lazy dog. \
+AVATAR, WAVE, Type, To, Yo and LT pull their letters together where the font \
+asks for kerning.\n\
+Größe, déjà vu, Ελληνικά, Кириллица and ½ ≠ ∞ come from the same atlas as \
+everything else, each glyph is rasterized once per size and reused after.";
+
+const HELP: &str = "A align, +/- text size, arrows pan and Q/E zoom the world labels";
+
+// labels at fixed world positions, they move with the camera
+const LABELS: [(&str, Vec2); 3] = [
+    ("world (100, 320)", Vec2::new(100.0, 320.0)),
+    ("world (360, 380)", Vec2::new(360.0, 380.0)),
+    ("world (-200, 250)", Vec2::new(-200.0, 250.0)),
+];
+
+pub struct Sandbox {
+    text: TextRenderer,
+    camera: Camera2D,
+    size: PhysicalSize<u32>,
+    align: Align,
+    text_size: f32,
+    text_color: [f32; 4],
+    frames: u32,
+    // seconds since the fps counter was last updated
+    fps_elapsed: f32,
+    fps: String,
+    // set when the layout has to be rebuilt
+    changed: bool,
+    // laid out in `update` but not uploaded yet
+    pushed: bool,
+}
+
+impl Sandbox {
+    pub fn new(device: &wgpu::Device, view_format: wgpu::TextureFormat) -> Self {
+        Self {
+            text: TextRenderer::new(device, view_format, Font::default()),
+            camera: Camera2D::new(640, 480, 1.0),
+            size: PhysicalSize::new(640, 480),
+            align: Align::Left,
+            text_size: 22.0,
+            text_color: [1.0; 4],
+            frames: 0,
+            fps_elapsed: 0.0,
+            fps: "fps: -".to_owned(),
+            changed: true,
+            pushed: false,
+        }
+    }
+
+    // averaged over half a second so the number stays readable
+    fn count_frame(&mut self, delta_time: f32) {
+        self.frames += 1;
+        self.fps_elapsed += delta_time;
+
+        if self.fps_elapsed >= 0.5 {
+            self.fps = format!("fps: {:.0}", self.frames as f32 / self.fps_elapsed);
+            self.frames = 0;
+            self.fps_elapsed = 0.0;
+            self.changed = true;
+        }
+    }
+
+    fn layout(&mut self) {
+        self.text.push(&TextSection {
+            text: &self.fps,
+            position: Vec2::new(MARGIN, 10.0),
+            size: 18.0,
+            color: [1.0, 0.85, 0.2, 1.0],
+            ..Default::default()
+        });
+
+        let paragraph = self.text.push(&TextSection {
+            text: PARAGRAPH,
+            position: Vec2::new(MARGIN, 40.0),
+            size: self.text_size,
+            color: self.text_color,
+            layout: LayoutOptions {
+                max_width: Some(self.size.width as f32 - MARGIN * 2.0),
+                align: self.align,
+                line_spacing: 1.1,
+            },
+            ..Default::default()
+        });
+
+        // right under the paragraph, wherever it ends after wrapping
+        self.text.push(&TextSection {
+            text: HELP,
+            position: Vec2::new(MARGIN, 40.0 + paragraph.height + 10.0),
+            size: 16.0,
+            color: [0.6, 0.6, 0.6, 1.0],
+            layout: LayoutOptions {
+                max_width: Some(self.size.width as f32 - MARGIN * 2.0),
+                align: self.align,
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+
+        for (label, position) in LABELS {
+            self.text.push(&TextSection {
+                text: label,
+                position,
+                size: 24.0,
+                color: [0.3, 0.8, 1.0, 1.0],
+                space: Space::World,
+                ..Default::default()
+            });
+        }
+    }
+}
+
+impl Renderable for Sandbox {
+    fn handle_input(&mut self, key_event: KeyEvent, queue: &wgpu::Queue) {
+        let KeyEvent {
+            physical_key,
+            state,
+            ..
+        } = key_event;
+
+        if state == ElementState::Released {
+            return;
+        };
+
+        match physical_key {
+            PhysicalKey::Code(KeyCode::ArrowUp) => self.camera.y -= 10.0,
+            PhysicalKey::Code(KeyCode::ArrowDown) => self.camera.y += 10.0,
+            PhysicalKey::Code(KeyCode::ArrowLeft) => self.camera.x -= 10.0,
+            PhysicalKey::Code(KeyCode::ArrowRight) => self.camera.x += 10.0,
+            PhysicalKey::Code(KeyCode::KeyE) => self.camera.scale *= 1.25,
+            PhysicalKey::Code(KeyCode::KeyQ) => self.camera.scale /= 1.25,
+            PhysicalKey::Code(KeyCode::KeyA) => {
+                self.align = match self.align {
+                    Align::Left => Align::Center,
+                    Align::Center => Align::Right,
+                    Align::Right => Align::Left,
+                };
+                self.changed = true;
+            }
+            PhysicalKey::Code(KeyCode::Equal) => {
+                self.text_size = (self.text_size + 2.0).min(96.0);
+                self.changed = true;
+            }
+            PhysicalKey::Code(KeyCode::Minus) => {
+                self.text_size = (self.text_size - 2.0).max(8.0);
+                self.changed = true;
+            }
+            _ => (),
+        }
+
+        self.text.set_camera(&self.camera, queue);
+    }
+
+    fn ui(&mut self, ctx: &egui::Context, _queue: &wgpu::Queue) {
+        let before = (self.align, self.text_size, self.text_color);
+
+        egui::Window::new("Text")
+            .default_pos([MARGIN, 300.0])
+            .show(ctx, |ui| {
+                egui::ComboBox::from_label("align")
+                    .selected_text(format!("{:?}", self.align))
+                    .show_ui(ui, |ui| {
+                        for align in [Align::Left, Align::Center, Align::Right] {
+                            ui.selectable_value(&mut self.align, align, format!("{align:?}"));
+                        }
+                    });
+
+                ui.add(egui::Slider::new(&mut self.text_size, 8.0..=96.0).text("size"));
+
+                ui.horizontal(|ui| {
+                    ui.color_edit_button_rgba_unmultiplied(&mut self.text_color);
+                    ui.label("color");
+                });
+            });
+
+        self.changed |= before != (self.align, self.text_size, self.text_color);
+    }
+
+    fn resize(&mut self, size: PhysicalSize<u32>, queue: &wgpu::Queue) {
+        self.size = size;
+        self.changed = true;
+        self.camera.width = size.width as f32;
+        self.camera.height = size.height as f32;
+
+        self.text.resize(size, queue);
+        self.text.set_camera(&self.camera, queue);
+    }
+
+    fn update(&mut self, delta_time: f32, _debug_draw: &mut DebugDraw) {
+        self.count_frame(delta_time);
+
+        if std::mem::take(&mut self.changed) {
+            self.layout();
+            self.pushed = true;
+        }
+    }
+
+    // keeps redrawing so every frame is counted, the text itself is only
+    // uploaded after its layout changed
+    fn prepare(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) -> bool {
+        if std::mem::take(&mut self.pushed) {
+            self.text.prepare(device, queue);
+        }
+
+        true
+    }
+
+    fn render(&self, render_pass: &mut wgpu::RenderPass) {
+        self.text.render(render_pass);
+    }
+}
@@ -1,18 +1,105 @@
 use crate::{
+    assets::{AssetLoader, AssetSource, Handle},
+    embed,
     graphics::Renderable,
+    texture::{
+        atlas::{AtlasOptions, AtlasRegion, TextureAtlas},
+        decode::DecodedImage,
+        sheet::SpriteSheet,
+        Texture, TextureOptions,
+    },
     vertices::{Vertex, VertexPosTex},
 };
-use texture::Texture;
 use wgpu::{include_wgsl, util::DeviceExt};
 
-pub mod texture;
+const PHOTO: &str = "src/sandbox/texture/funyarinpa.jpg";
+const SHAPES: &str = "src/sandbox/texture/shapes.png";
+const SHAPES_SHEET: &str = "src/sandbox/texture/shapes.json";
+
+// the photo on the left, the sheet frames in a 2x2 grid on the right
+const PHOTO_QUAD: ([f32; 2], [f32; 2]) = ([-1.0, 1.0], [0.0, -1.0]);
+const SHAPE_QUADS: [(&str, [f32; 2], [f32; 2]); 4] = [
+    ("circle", [0.0, 1.0], [0.5, 0.0]),
+    ("diamond", [0.5, 1.0], [1.0, 0.0]),
+    ("ring", [0.0, 0.0], [0.5, -1.0]),
+    ("star", [0.5, 0.0], [1.0, -1.0]),
+];
+
+const FULL: AtlasRegion = AtlasRegion {
+    page: 0,
+    uv_min: [0.0, 0.0],
+    uv_max: [1.0, 1.0],
+    size: (1, 1),
+    offset: (0, 0),
+    source_size: (1, 1),
+};
+
+// the photo first, then the shapes. shapes that aren't in the atlas yet get
+// the whole page, `render` skips them anyway
+fn vertices(atlas: &TextureAtlas) -> Vec<VertexPosTex> {
+    let shapes = SHAPE_QUADS.iter().map(|&(name, top_left, bottom_right)| {
+        quad(atlas.region(name).unwrap_or(FULL), top_left, bottom_right)
+    });
+
+    std::iter::once(quad(FULL, PHOTO_QUAD.0, PHOTO_QUAD.1))
+        .chain(shapes)
+        .flatten()
+        .collect()
+}
+
+// trimmed frames only cover their part of the untrimmed image's quad
+fn quad(region: AtlasRegion, top_left: [f32; 2], bottom_right: [f32; 2]) -> [VertexPosTex; 4] {
+    let (min, max) = (region.uv_min, region.uv_max);
+    let corner = |texels: (u32, u32)| {
+        let (x, y) = (
+            texels.0 as f32 / region.source_size.0 as f32,
+            texels.1 as f32 / region.source_size.1 as f32,
+        );
+        [
+            top_left[0] + (bottom_right[0] - top_left[0]) * x,
+            top_left[1] + (bottom_right[1] - top_left[1]) * y,
+        ]
+    };
+    let top_left = corner(region.offset);
+    let bottom_right = corner((
+        region.offset.0 + region.size.0,
+        region.offset.1 + region.size.1,
+    ));
+
+    [
+        VertexPosTex {
+            position: top_left,
+            texture_coord: min,
+        },
+        VertexPosTex {
+            position: [bottom_right[0], top_left[1]],
+            texture_coord: [max[0], min[1]],
+        },
+        VertexPosTex {
+            position: [top_left[0], bottom_right[1]],
+            texture_coord: [min[0], max[1]],
+        },
+        VertexPosTex {
+            position: bottom_right,
+            texture_coord: max,
+        },
+    ]
+}
 
 pub struct Sandbox {
     pipeline: wgpu::RenderPipeline,
     vertex_buffer: wgpu::Buffer,
     index_buffer: wgpu::Buffer,
-    bind_group: wgpu::BindGroup,
-    indices_len: u32,
+    bind_group_layout: wgpu::BindGroupLayout,
+    // one per atlas page
+    bind_groups: Vec<wgpu::BindGroup>,
+    loader: AssetLoader,
+    photo: Handle<Texture>,
+    shapes: Handle<DecodedImage>,
+    sheet: Handle<Vec<u8>>,
+    // the loader revisions of the shapes and sheet last added to the atlas
+    added: (u32, u32),
+    atlas: TextureAtlas,
 }
 
 impl Sandbox {
@@ -22,32 +109,23 @@ impl Sandbox {
         view_format: wgpu::TextureFormat,
     ) -> Self {
         let shader = device.create_shader_module(include_wgsl!("shader.wgsl"));
-        let texture =
-            Texture::from_jpeg(device, queue, "src/sandbox/texture/funyarinpa.jpg").unwrap();
-
-        let vertices = &[
-            // top-left
-            VertexPosTex {
-                position: [-1.0, 1.0],
-                texture_coord: [0.0, 0.0],
-            },
-            // top-right
-            VertexPosTex {
-                position: [1.0, 1.0],
-                texture_coord: [1.0, 0.0],
-            },
-            // bottom-left
-            VertexPosTex {
-                position: [-1.0, -1.0],
-                texture_coord: [0.0, 1.0],
-            },
-            // bottom-right
-            VertexPosTex {
-                position: [1.0, -1.0],
-                texture_coord: [1.0, 1.0],
-            },
-        ];
-        let indices: &[u16] = &[0, 2, 1, 1, 2, 3];
+        let source = AssetSource::default().with_embedded(&[
+            embed!("src/sandbox/texture/funyarinpa.jpg"),
+            embed!("src/sandbox/texture/shapes.png"),
+            embed!("src/sandbox/texture/shapes.json"),
+        ]);
+
+        let mut loader = AssetLoader::new(device, queue, source);
+        let photo = loader.load_texture(PHOTO, TextureOptions::default());
+        // the shapes are cut up on the cpu by the sheet, and both reload
+        let shapes = loader.load_image(SHAPES);
+        let sheet = loader.load_bytes(SHAPES_SHEET);
+
+        let atlas = TextureAtlas::new(AtlasOptions::default());
+        let vertices = vertices(&atlas);
+        let indices: Vec<u16> = (0..1 + SHAPE_QUADS.len() as u16)
+            .flat_map(|i| [0, 2, 1, 1, 2, 3].map(|index| i * 4 + index))
+            .collect();
 
         let bind_group_layout = Texture::bind_group_layout(device);
 
@@ -72,7 +150,7 @@ impl Sandbox {
                 compilation_options: Default::default(),
                 targets: &[Some(wgpu::ColorTargetState {
                     format: view_format,
-                    blend: None,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
                     write_mask: wgpu::ColorWrites::ALL,
                 })],
             }),
@@ -85,47 +163,112 @@ impl Sandbox {
 
         let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("vertex buffer"),
-            contents: bytemuck::cast_slice(vertices),
-            usage: wgpu::BufferUsages::VERTEX,
+            contents: bytemuck::cast_slice(&vertices),
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
         });
 
         let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("index buffer"),
-            contents: bytemuck::cast_slice(indices),
+            contents: bytemuck::cast_slice(&indices),
             usage: wgpu::BufferUsages::INDEX,
         });
 
-        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("texture bind group"),
-            layout: &bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::TextureView(&texture.texture_view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::Sampler(&texture.sampler),
-                },
-            ],
-        });
-
         Self {
             pipeline,
             vertex_buffer,
             index_buffer,
-            bind_group,
-            indices_len: indices.len() as _,
+            bind_group_layout,
+            bind_groups: Vec::new(),
+            loader,
+            photo,
+            shapes,
+            sheet,
+            added: (0, 0),
+            atlas,
         }
     }
 }
 
+impl Sandbox {
+    // cuts the shapes into the atlas again when the image or the sheet was
+    // reloaded. the space of the old frames is not reclaimed, so reloads
+    // spill onto new pages eventually
+    fn add_changed(&mut self) -> Result<bool, String> {
+        let revisions = (
+            self.loader.revision(self.shapes),
+            self.loader.revision(self.sheet),
+        );
+        let (Some(shapes), Some(json)) = (
+            self.loader.image(self.shapes),
+            self.loader.bytes(self.sheet),
+        ) else {
+            return Ok(false);
+        };
+        if revisions == self.added {
+            return Ok(false);
+        }
+
+        // recorded first, so a broken sheet is reported once
+        self.added = revisions;
+        let sheet = SpriteSheet::from_json(&String::from_utf8_lossy(json))?;
+        self.atlas.add_sheet(shapes, &sheet)?;
+
+        Ok(true)
+    }
+}
+
 impl Renderable for Sandbox {
+    fn prepare(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) -> bool {
+        if !self.loader.poll(device, queue) {
+            return false;
+        }
+
+        match self.add_changed() {
+            Ok(true) => (),
+            // the photo's bind group is looked up every frame
+            Ok(false) => return true,
+            Err(e) => {
+                log::error!("failed to add the shapes to the atlas: {e}");
+                return true;
+            }
+        }
+
+        // bind groups of the existing pages stay valid, new pages need one
+        self.atlas.upload(device, queue);
+        for page in self.bind_groups.len()..self.atlas.page_count() {
+            let texture = self.atlas.page(page).unwrap();
+            self.bind_groups
+                .push(texture.bind_group(device, &self.bind_group_layout));
+        }
+
+        queue.write_buffer(
+            &self.vertex_buffer,
+            0,
+            bytemuck::cast_slice(&vertices(&self.atlas)),
+        );
+
+        true
+    }
+
     fn render(&self, render_pass: &mut wgpu::RenderPass) {
         render_pass.set_pipeline(&self.pipeline);
-        render_pass.set_bind_group(0, &self.bind_group, &[]);
         render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
         render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
-        render_pass.draw_indexed(0..self.indices_len, 0, 0..1);
+
+        // the placeholder until the photo is loaded, the new version once it
+        // is reloaded
+        render_pass.set_bind_group(0, self.loader.bind_group(self.photo), &[]);
+        render_pass.draw_indexed(0..6, 0, 0..1);
+
+        // shapes that aren't in the atlas yet aren't drawn
+        for (i, &(name, ..)) in SHAPE_QUADS.iter().enumerate() {
+            let Some(region) = self.atlas.region(name) else {
+                continue;
+            };
+
+            let first = (i as u32 + 1) * 6;
+            render_pass.set_bind_group(0, &self.bind_groups[region.page], &[]);
+            render_pass.draw_indexed(first..first + 6, 0, 0..1);
+        }
     }
 }
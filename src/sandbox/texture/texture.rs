@@ -1,108 +0,0 @@
-use std::path::Path;
-
-use wgpu::util::DeviceExt;
-use zune_jpeg::{
-    zune_core::{colorspace::ColorSpace, options::DecoderOptions},
-    JpegDecoder,
-};
-
-pub struct Texture {
-    pub texture_view: wgpu::TextureView,
-    pub sampler: wgpu::Sampler,
-}
-
-impl Texture {
-    pub fn from_jpeg(
-        device: &wgpu::Device,
-        queue: &wgpu::Queue,
-        path: impl AsRef<Path>,
-    ) -> Result<Self, String> {
-        let content = std::fs::read(path.as_ref()).map_err(|_| "failed to read file".to_owned())?;
-        let options = DecoderOptions::default().jpeg_set_out_colorspace(ColorSpace::RGBA);
-        let mut decoder = JpegDecoder::new_with_options(content, options);
-
-        let raw_data = decoder
-            .decode()
-            .map_err(|_| "failed to decode jpeg".to_owned())?;
-
-        let info = decoder.info().ok_or("failed to get info".to_owned())?;
-
-        Ok(Self::from_raw_data(
-            device,
-            queue,
-            &raw_data,
-            (info.width as _, info.height as _),
-        ))
-    }
-
-    pub fn from_raw_data(
-        device: &wgpu::Device,
-        queue: &wgpu::Queue,
-        data: &[u8],
-        dimensions: (u32, u32),
-    ) -> Self {
-        let size = wgpu::Extent3d {
-            width: dimensions.0,
-            height: dimensions.1,
-            depth_or_array_layers: 1,
-        };
-
-        let texture = device.create_texture_with_data(
-            queue,
-            &wgpu::TextureDescriptor {
-                label: None,
-                size,
-                mip_level_count: 1,
-                sample_count: 1,
-                dimension: wgpu::TextureDimension::D2,
-                format: wgpu::TextureFormat::Rgba8Unorm,
-                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
-                view_formats: &[],
-            },
-            wgpu::util::TextureDataOrder::LayerMajor,
-            data,
-        );
-
-        let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
-        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
-            label: None,
-            address_mode_u: wgpu::AddressMode::ClampToEdge,
-            address_mode_v: wgpu::AddressMode::ClampToEdge,
-            address_mode_w: wgpu::AddressMode::ClampToEdge,
-            mag_filter: wgpu::FilterMode::Linear,
-            min_filter: wgpu::FilterMode::Linear,
-            ..Default::default()
-        });
-
-        Self {
-            texture_view,
-            sampler,
-        }
-    }
-
-    pub fn bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
-        use wgpu::BindGroupLayoutEntry as Entry;
-
-        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: Some("texture bind group layout"),
-            entries: &[
-                Entry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Texture {
-                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                        view_dimension: wgpu::TextureViewDimension::D2,
-                        multisampled: false,
-                    },
-                    count: None,
-                },
-                Entry {
-                    binding: 1,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
-                    count: None,
-                },
-            ],
-        })
-    }
-}
@@ -0,0 +1,446 @@
+use std::collections::BTreeMap;
+
+use glam::Vec2;
+use winit::{
+    dpi::PhysicalSize,
+    event::{ElementState, KeyEvent},
+    keyboard::{KeyCode, PhysicalKey},
+};
+
+use crate::{
+    assets::AssetSource,
+    debug_draw::DebugDraw,
+    embed,
+    graphics::{MouseEvent, Renderable},
+    sandbox::camera2d::camera::Camera2D,
+    text::{Font, TextRenderer, TextSection},
+    texture::decode::DecodedImage,
+    tilemap::{
+        renderer::CHUNK_SIZE, Tile, TileLayer, TileMap, TileMapRenderer, Tileset,
+        FLIPPED_HORIZONTALLY,
+    },
+};
+
+const ISLAND: &str = "src/sandbox/tilemap/island.tmx";
+const HELP: &str = "arrows scroll, Q/E or the wheel zoom, M switches maps";
+
+// in tiles along each side
+const WORLD_SIZE: u32 = 1000;
+// in pixels per second at a zoom of one
+const SCROLL_SPEED: f32 = 800.0;
+
+// local ids in terrain.tsx
+const GRASS: u32 = 0;
+const FLOWERS: u32 = 1;
+const DIRT: u32 = 2;
+const SAND: u32 = 3;
+const WATER: u32 = 4;
+const TREE: u32 = 8;
+const BUSH: u32 = 9;
+const ROCK: u32 = 10;
+const TUFT: u32 = 11;
+const TORCH: u32 = 14;
+
+fn hash(x: u32, y: u32, seed: u32) -> f32 {
+    let mut h = x.wrapping_mul(0x27d4_eb2d) ^ y.wrapping_mul(0x1656_67b1) ^ seed;
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x85eb_ca6b);
+    h ^= h >> 13;
+    h = h.wrapping_mul(0xc2b2_ae35);
+    h ^= h >> 16;
+
+    h as f32 / u32::MAX as f32
+}
+
+// smooth value noise in 0..1, a few octaves of it
+fn noise(p: Vec2, seed: u32) -> f32 {
+    let mut total = 0.0;
+    let mut weight = 0.5;
+    let mut p = p;
+
+    for octave in 0..4 {
+        let cell = p.floor();
+        let f = p - cell;
+        let f = f * f * (3.0 - 2.0 * f);
+        let corner = |dx: u32, dy: u32| {
+            hash(
+                (cell.x as i32 as u32).wrapping_add(dx),
+                (cell.y as i32 as u32).wrapping_add(dy),
+                seed.wrapping_add(octave),
+            )
+        };
+
+        let top = corner(0, 0) + (corner(1, 0) - corner(0, 0)) * f.x;
+        let bottom = corner(0, 1) + (corner(1, 1) - corner(0, 1)) * f.x;
+        total += (top + (bottom - top) * f.y) * weight;
+
+        weight *= 0.5;
+        p *= 2.0;
+    }
+
+    total / (1.0 - weight * 2.0)
+}
+
+// islands, beaches and forests with the island map's tileset, too large to
+// draw in one go
+fn generate_world(tileset: &Tileset) -> TileMap {
+    let gid = |local: u32| Tile(tileset.first_gid + local);
+    let mut ground = Vec::with_capacity((WORLD_SIZE * WORLD_SIZE) as usize);
+    let mut decoration = Vec::with_capacity(ground.capacity());
+
+    for y in 0..WORLD_SIZE {
+        for x in 0..WORLD_SIZE {
+            let p = Vec2::new(x as f32, y as f32);
+            let height = noise(p / 48.0, 1);
+            let forest = noise(p / 24.0, 2);
+            let roll = hash(x, y, 3);
+
+            let (tile, decor) = if height < 0.45 {
+                (WATER, None)
+            } else if height < 0.5 {
+                (SAND, (roll < 0.03).then_some(ROCK))
+            } else if noise(p / 12.0, 4) > 0.68 {
+                (DIRT, (roll < 0.002).then_some(TORCH))
+            } else if forest > 0.55 && roll < 0.6 {
+                (GRASS, Some(TREE))
+            } else {
+                let decor = match roll {
+                    r if r < 0.02 => Some(BUSH),
+                    r if r < 0.06 => Some(TUFT),
+                    _ => None,
+                };
+                (if roll > 0.9 { FLOWERS } else { GRASS }, decor)
+            };
+
+            ground.push(gid(tile));
+            // half the trees face the other way
+            decoration.push(decor.map_or(Tile::EMPTY, |decor| {
+                let flip = if hash(x, y, 5) < 0.5 {
+                    FLIPPED_HORIZONTALLY
+                } else {
+                    0
+                };
+                Tile(gid(decor).0 | flip)
+            }));
+        }
+    }
+
+    let layer = |name: &str, tiles| TileLayer {
+        name: name.to_owned(),
+        width: WORLD_SIZE,
+        height: WORLD_SIZE,
+        tiles,
+        visible: true,
+        opacity: 1.0,
+        offset: [0.0, 0.0],
+    };
+
+    TileMap {
+        width: WORLD_SIZE,
+        height: WORLD_SIZE,
+        tile_width: tileset.tile_width,
+        tile_height: tileset.tile_height,
+        tilesets: vec![tileset.clone()],
+        layers: vec![layer("ground", ground), layer("decoration", decoration)],
+    }
+}
+
+struct Map {
+    name: String,
+    // in tiles, then in pixels
+    size: [u32; 2],
+    tile_size: Vec2,
+    layers: Vec<(String, bool)>,
+    renderer: TileMapRenderer,
+}
+
+impl Map {
+    fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        view_format: wgpu::TextureFormat,
+        name: &str,
+        map: &TileMap,
+        images: &[DecodedImage],
+    ) -> Result<Self, String> {
+        Ok(Self {
+            name: name.to_owned(),
+            size: [map.width, map.height],
+            tile_size: Vec2::new(map.tile_width as f32, map.tile_height as f32),
+            layers: map
+                .layers
+                .iter()
+                .map(|layer| (layer.name.clone(), layer.visible))
+                .collect(),
+            renderer: TileMapRenderer::new(device, queue, view_format, map, images)?,
+        })
+    }
+
+    fn pixel_size(&self) -> Vec2 {
+        Vec2::new(self.size[0] as f32, self.size[1] as f32) * self.tile_size
+    }
+}
+
+fn load_maps(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    view_format: wgpu::TextureFormat,
+) -> Result<Vec<Map>, String> {
+    let source = AssetSource::default().with_embedded(&[
+        embed!("src/sandbox/tilemap/island.tmx"),
+        embed!("src/sandbox/tilemap/terrain.tsx"),
+        embed!("src/sandbox/tilemap/terrain.png"),
+    ]);
+
+    let island = TileMap::load(&source, ISLAND)?;
+    let images = island
+        .tilesets
+        .iter()
+        .map(|tileset| DecodedImage::decode(&source.read(&tileset.image)?))
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let tileset = island
+        .tilesets
+        .first()
+        .ok_or_else(|| format!("{ISLAND} has no tileset"))?;
+    let world = generate_world(tileset);
+
+    Ok(vec![
+        Map::new(device, queue, view_format, "island.tmx", &island, &images)?,
+        Map::new(
+            device,
+            queue,
+            view_format,
+            "generated",
+            &world,
+            &images[..1],
+        )?,
+    ])
+}
+
+pub struct Sandbox {
+    maps: Vec<Map>,
+    current: usize,
+    camera: Camera2D,
+    // arrow keys held down, as a direction
+    held: BTreeMap<KeyCode, Vec2>,
+    // scrolled in `update`, written in the next `prepare`
+    camera_moved: bool,
+    // seconds since the sandbox started, tile animations play from then
+    time: f32,
+    text: TextRenderer,
+    // the help text is laid out once and uploaded in the first `prepare`
+    text_pushed: bool,
+    show_chunks: bool,
+}
+
+impl Sandbox {
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        view_format: wgpu::TextureFormat,
+    ) -> Self {
+        let maps = load_maps(device, queue, view_format)
+            .inspect_err(|e| log::error!("failed to load tile maps: {e}"))
+            .unwrap_or_default();
+
+        let mut sandbox = Self {
+            maps,
+            current: 0,
+            camera: Camera2D::new(640, 480, 1.0),
+            held: BTreeMap::new(),
+            camera_moved: false,
+            time: 0.0,
+            text: TextRenderer::new(device, view_format, Font::default()),
+            text_pushed: true,
+            show_chunks: false,
+        };
+        sandbox.center_camera();
+        sandbox.text.push(&TextSection {
+            text: HELP,
+            position: Vec2::new(10.0, 10.0),
+            size: 16.0,
+            ..Default::default()
+        });
+
+        sandbox
+    }
+
+    fn center_camera(&mut self) {
+        let Some(map) = self.maps.get(self.current) else {
+            return;
+        };
+
+        let view = Vec2::new(self.camera.width, self.camera.height) / self.camera.scale;
+        let corner = (map.pixel_size() - view) / 2.0;
+        (self.camera.x, self.camera.y) = (corner.x, corner.y);
+    }
+
+    fn set_camera(&mut self, queue: &wgpu::Queue) {
+        if let Some(map) = self.maps.get_mut(self.current) {
+            map.renderer.set_camera(&self.camera, queue);
+        }
+    }
+
+    // around the middle of the view
+    fn zoom(&mut self, factor: f32, queue: &wgpu::Queue) {
+        let view = Vec2::new(self.camera.width, self.camera.height);
+        let center = Vec2::new(self.camera.x, self.camera.y) + view / self.camera.scale / 2.0;
+
+        self.camera.scale = (self.camera.scale * factor).clamp(0.05, 8.0);
+
+        let corner = center - view / self.camera.scale / 2.0;
+        (self.camera.x, self.camera.y) = (corner.x, corner.y);
+        self.set_camera(queue);
+    }
+
+    fn switch_map(&mut self, queue: &wgpu::Queue) {
+        if self.maps.is_empty() {
+            return;
+        }
+
+        self.current = (self.current + 1) % self.maps.len();
+        self.center_camera();
+        self.set_camera(queue);
+    }
+}
+
+impl Renderable for Sandbox {
+    fn handle_input(&mut self, key_event: KeyEvent, queue: &wgpu::Queue) {
+        let KeyEvent {
+            physical_key: PhysicalKey::Code(code),
+            state,
+            repeat,
+            ..
+        } = key_event
+        else {
+            return;
+        };
+
+        let direction = match code {
+            KeyCode::ArrowUp => Vec2::NEG_Y,
+            KeyCode::ArrowDown => Vec2::Y,
+            KeyCode::ArrowLeft => Vec2::NEG_X,
+            KeyCode::ArrowRight => Vec2::X,
+            _ => Vec2::ZERO,
+        };
+        // scrolling happens in `update`, for as long as the keys are held
+        if direction != Vec2::ZERO {
+            match state {
+                ElementState::Pressed => self.held.insert(code, direction),
+                ElementState::Released => self.held.remove(&code),
+            };
+            return;
+        }
+
+        if state == ElementState::Released || repeat {
+            return;
+        }
+
+        match code {
+            KeyCode::KeyE => self.zoom(1.25, queue),
+            KeyCode::KeyQ => self.zoom(1.0 / 1.25, queue),
+            KeyCode::KeyM => self.switch_map(queue),
+            _ => (),
+        }
+    }
+
+    fn handle_mouse(&mut self, mouse_event: MouseEvent, queue: &wgpu::Queue) {
+        if let MouseEvent::Wheel(lines) = mouse_event {
+            self.zoom(1.1_f32.powf(lines), queue);
+        }
+    }
+
+    fn ui(&mut self, ctx: &egui::Context, queue: &wgpu::Queue) {
+        let mut switch = false;
+
+        egui::Window::new("Tile map")
+            .default_pos([420.0, 20.0])
+            .show(ctx, |ui| {
+                let Some(map) = self.maps.get_mut(self.current) else {
+                    ui.label("no map loaded, see the log");
+                    return;
+                };
+
+                ui.label(format!(
+                    "{}, {}x{} tiles",
+                    map.name, map.size[0], map.size[1]
+                ));
+                ui.label(format!(
+                    "{} chunks of {CHUNK_SIZE}x{CHUNK_SIZE} drawn",
+                    map.renderer.visible_chunks()
+                ));
+                for (index, (name, visible)) in map.layers.iter_mut().enumerate() {
+                    if ui.checkbox(visible, name.as_str()).changed() {
+                        map.renderer.set_layer_visible(index, *visible);
+                    }
+                }
+                ui.checkbox(&mut self.show_chunks, "chunk bounds");
+                switch = ui.button("switch map").clicked();
+            });
+
+        if switch {
+            self.switch_map(queue);
+        }
+    }
+
+    fn update(&mut self, delta_time: f32, debug_draw: &mut DebugDraw) {
+        self.time += delta_time;
+
+        let direction = self.held.values().sum::<Vec2>();
+        if direction != Vec2::ZERO {
+            let step = direction.normalize() * SCROLL_SPEED * delta_time / self.camera.scale;
+            self.camera.x += step.x;
+            self.camera.y += step.y;
+            self.camera_moved = true;
+        }
+
+        let Some(map) = self.maps.get(self.current) else {
+            return;
+        };
+        if !self.show_chunks {
+            return;
+        }
+
+        debug_draw.set_camera(&self.camera);
+        debug_draw.grid(map.tile_size.x * CHUNK_SIZE as f32, [1.0, 0.4, 0.2]);
+        debug_draw.rect(Vec2::ZERO, map.pixel_size(), [1.0, 1.0, 0.3]);
+    }
+
+    fn resize(&mut self, size: PhysicalSize<u32>, queue: &wgpu::Queue) {
+        self.camera.width = size.width as f32;
+        self.camera.height = size.height as f32;
+
+        self.text.resize(size, queue);
+        self.set_camera(queue);
+    }
+
+    // keeps redrawing while scrolling or showing animated tiles, `update`
+    // only advances them on drawn frames
+    fn prepare(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) -> bool {
+        let mut changed = std::mem::take(&mut self.camera_moved);
+        if changed {
+            self.set_camera(queue);
+        }
+
+        let mut animated = false;
+        if let Some(map) = self.maps.get_mut(self.current) {
+            changed |= map.renderer.set_time((self.time * 1000.0) as u64, queue);
+            animated = map.renderer.is_animated();
+        }
+
+        if std::mem::take(&mut self.text_pushed) {
+            self.text.prepare(device, queue);
+            changed = true;
+        }
+
+        changed || animated || !self.held.is_empty()
+    }
+
+    fn render(&self, render_pass: &mut wgpu::RenderPass) {
+        if let Some(map) = self.maps.get(self.current) {
+            map.renderer.render(render_pass);
+        }
+        self.text.render(render_pass);
+    }
+}
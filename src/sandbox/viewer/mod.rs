@@ -0,0 +1,322 @@
+use std::{
+    f32::consts::FRAC_PI_2,
+    path::{Path, PathBuf},
+};
+
+use glam::{Mat4, Vec2, Vec3};
+use winit::{
+    dpi::PhysicalSize,
+    event::{ElementState, KeyEvent, MouseButton},
+    keyboard::{KeyCode, PhysicalKey},
+};
+
+use crate::{
+    assets::AssetSource,
+    embed,
+    graphics::{MouseEvent, Renderable},
+    model::{Model, ModelRenderer},
+};
+
+// the models that come with the sandbox
+const SAMPLES: [&str; 2] = [
+    "src/sandbox/viewer/robot.gltf",
+    "src/sandbox/viewer/pedestal.obj",
+];
+
+// radians per unit of normalized device coordinates dragged across
+const DRAG_SPEED: f32 = 2.0;
+
+struct OrbitCamera {
+    target: Vec3,
+    distance: f32,
+    yaw: f32,
+    pitch: f32,
+    aspect: f32,
+}
+
+impl OrbitCamera {
+    fn eye(&self) -> Vec3 {
+        let direction = Vec3::new(
+            self.pitch.cos() * self.yaw.sin(),
+            self.pitch.sin(),
+            self.pitch.cos() * self.yaw.cos(),
+        );
+
+        self.target + direction * self.distance
+    }
+
+    fn view_projection(&self) -> Mat4 {
+        let view = Mat4::look_at_rh(self.eye(), self.target, Vec3::Y);
+        // near and far follow the distance, so small and huge models both fit
+        let projection = Mat4::perspective_rh(
+            45f32.to_radians(),
+            self.aspect,
+            self.distance * 0.01,
+            self.distance * 100.0,
+        );
+
+        projection * view
+    }
+
+    fn orbit(&mut self, delta: Vec2) {
+        self.yaw -= delta.x;
+        self.pitch = (self.pitch - delta.y).clamp(-FRAC_PI_2 + 0.01, FRAC_PI_2 - 0.01);
+    }
+
+    // looks at the middle of the box from far enough to see all of it
+    fn frame(&mut self, (min, max): (Vec3, Vec3)) {
+        self.target = (min + max) / 2.0;
+        self.distance = ((max - min).length() * 1.2).max(0.01);
+    }
+}
+
+struct Loaded {
+    name: String,
+    nodes: usize,
+    triangles: usize,
+    bounds: Option<(Vec3, Vec3)>,
+    renderer: ModelRenderer,
+}
+
+impl Loaded {
+    fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        view_format: wgpu::TextureFormat,
+        name: &str,
+        model: &Model,
+    ) -> Self {
+        let triangles = model
+            .instances()
+            .iter()
+            .flat_map(|&(mesh, _)| &model.meshes[mesh].primitives)
+            .map(|primitive| primitive.indices.len() / 3)
+            .sum();
+
+        Self {
+            name: name.to_owned(),
+            nodes: model.nodes.len(),
+            triangles,
+            bounds: model.bounds(),
+            renderer: ModelRenderer::new(device, queue, view_format, model),
+        }
+    }
+}
+
+// a model viewer, drop a .gltf, .glb or .obj file on the window to open it
+pub struct Sandbox {
+    view_format: wgpu::TextureFormat,
+    source: AssetSource,
+    model: Option<Loaded>,
+    camera: OrbitCamera,
+    // where the cursor was while the left button is down
+    drag: Option<Option<Vec2>>,
+    // dropped or picked, to be opened in `prepare` where the device is
+    dropped: Option<PathBuf>,
+    sample: Option<&'static str>,
+    error: Option<String>,
+}
+
+impl Sandbox {
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        view_format: wgpu::TextureFormat,
+    ) -> Self {
+        let source = AssetSource::default().with_embedded(&[
+            embed!("src/sandbox/viewer/robot.gltf"),
+            embed!("src/sandbox/viewer/robot.bin"),
+            embed!("src/sandbox/viewer/robot.png"),
+            embed!("src/sandbox/viewer/pedestal.obj"),
+            embed!("src/sandbox/viewer/pedestal.mtl"),
+            embed!("src/sandbox/viewer/marble.png"),
+        ]);
+
+        let mut sandbox = Self {
+            view_format,
+            source,
+            model: None,
+            camera: OrbitCamera {
+                target: Vec3::ZERO,
+                distance: 5.0,
+                yaw: 0.5,
+                pitch: 0.3,
+                aspect: 640.0 / 480.0,
+            },
+            drag: None,
+            dropped: None,
+            sample: None,
+            error: None,
+        };
+        sandbox.show_sample(device, queue, SAMPLES[0]);
+
+        sandbox
+    }
+
+    fn show(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        name: &str,
+        model: Result<Model, String>,
+    ) {
+        match model {
+            Ok(model) => {
+                let loaded = Loaded::new(device, queue, self.view_format, name, &model);
+                if let Some(bounds) = loaded.bounds {
+                    self.camera.frame(bounds);
+                }
+
+                self.model = Some(loaded);
+                self.error = None;
+                self.update_camera(queue);
+            }
+            // the last model stays up
+            Err(e) => {
+                log::error!("failed to open {name}: {e}");
+                self.error = Some(e);
+            }
+        }
+    }
+
+    fn show_sample(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, path: &str) {
+        let model = Model::load(&self.source, path);
+        self.show(device, queue, file_name(path), model);
+    }
+
+    fn update_camera(&self, queue: &wgpu::Queue) {
+        if let Some(model) = &self.model {
+            model
+                .renderer
+                .set_camera(self.camera.view_projection(), queue);
+        }
+    }
+}
+
+impl Renderable for Sandbox {
+    fn needs_depth() -> bool {
+        true
+    }
+
+    fn handle_input(&mut self, key_event: KeyEvent, queue: &wgpu::Queue) {
+        let KeyEvent {
+            physical_key: PhysicalKey::Code(code),
+            state: ElementState::Pressed,
+            ..
+        } = key_event
+        else {
+            return;
+        };
+
+        let step = 5f32.to_radians();
+        match code {
+            KeyCode::ArrowLeft => self.camera.orbit(Vec2::new(-step, 0.0)),
+            KeyCode::ArrowRight => self.camera.orbit(Vec2::new(step, 0.0)),
+            KeyCode::ArrowUp => self.camera.orbit(Vec2::new(0.0, step)),
+            KeyCode::ArrowDown => self.camera.orbit(Vec2::new(0.0, -step)),
+            KeyCode::KeyF => {
+                if let Some(bounds) = self.model.as_ref().and_then(|model| model.bounds) {
+                    self.camera.frame(bounds);
+                }
+            }
+            _ => return,
+        }
+
+        self.update_camera(queue);
+    }
+
+    fn handle_mouse(&mut self, mouse_event: MouseEvent, queue: &wgpu::Queue) {
+        match mouse_event {
+            MouseEvent::Button(MouseButton::Left, state) => {
+                self.drag = (state == ElementState::Pressed).then_some(None);
+            }
+            MouseEvent::Moved(position) => {
+                let Some(last) = &mut self.drag else {
+                    return;
+                };
+
+                if let Some(last) = last.replace(position) {
+                    self.camera.orbit((position - last) * DRAG_SPEED);
+                    self.update_camera(queue);
+                }
+            }
+            MouseEvent::Wheel(lines) => {
+                self.camera.distance *= 0.9_f32.powf(lines);
+                self.update_camera(queue);
+            }
+            _ => (),
+        }
+    }
+
+    fn handle_dropped_file(&mut self, path: &Path, _queue: &wgpu::Queue) {
+        self.dropped = Some(path.to_owned());
+    }
+
+    fn ui(&mut self, ctx: &egui::Context, _queue: &wgpu::Queue) {
+        egui::Window::new("Model")
+            .default_pos([420.0, 20.0])
+            .show(ctx, |ui| {
+                match &self.model {
+                    Some(model) => {
+                        ui.label(&model.name);
+                        ui.label(format!(
+                            "{} nodes, {} triangles drawn",
+                            model.nodes, model.triangles
+                        ));
+                    }
+                    None => {
+                        ui.label("no model loaded");
+                    }
+                }
+                if let Some(error) = &self.error {
+                    ui.colored_label(egui::Color32::LIGHT_RED, error);
+                }
+
+                ui.horizontal(|ui| {
+                    for sample in SAMPLES {
+                        if ui.button(file_name(sample)).clicked() {
+                            self.sample = Some(sample);
+                        }
+                    }
+                });
+
+                ui.separator();
+                ui.label("drop a .gltf, .glb or .obj file on the window to open it");
+                ui.label("drag or arrows orbit, the wheel zooms, F frames the model");
+            });
+    }
+
+    fn resize(&mut self, size: PhysicalSize<u32>, queue: &wgpu::Queue) {
+        self.camera.aspect = size.width.max(1) as f32 / size.height.max(1) as f32;
+        self.update_camera(queue);
+    }
+
+    fn prepare(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) -> bool {
+        if let Some(sample) = self.sample.take() {
+            self.show_sample(device, queue, sample);
+            return true;
+        }
+
+        let Some(path) = self.dropped.take() else {
+            return false;
+        };
+
+        let name = path
+            .file_name()
+            .map_or_else(|| path.to_string_lossy(), |name| name.to_string_lossy())
+            .into_owned();
+        self.show(device, queue, &name, Model::open(&path));
+
+        true
+    }
+
+    fn render(&self, render_pass: &mut wgpu::RenderPass) {
+        if let Some(model) = &self.model {
+            model.renderer.render(render_pass);
+        }
+    }
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
@@ -0,0 +1,408 @@
+use std::ops::RangeInclusive;
+
+use crate::texture::sheet::{SpriteSheet, TagDirection};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PlayMode {
+    #[default]
+    Loop,
+    // back and forth, without showing the end frames twice in a row
+    PingPong,
+    // stops on the last frame
+    Once,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClipFrame {
+    // the frame's name in the sheet, or in the atlas it went into
+    pub region: String,
+    // in seconds
+    pub duration: f32,
+    // raised whenever the frame is shown
+    pub event: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Clip {
+    pub name: String,
+    pub frames: Vec<ClipFrame>,
+    pub mode: PlayMode,
+}
+
+impl Clip {
+    pub fn new(name: impl Into<String>, mode: PlayMode) -> Self {
+        Self {
+            name: name.into(),
+            frames: Vec::new(),
+            mode,
+        }
+    }
+
+    pub fn frame(mut self, region: impl Into<String>, duration: f32) -> Self {
+        self.frames.push(ClipFrame {
+            region: region.into(),
+            duration,
+            event: None,
+        });
+        self
+    }
+
+    // frames of the sheet by index, with the durations it was exported with,
+    // `duration` for those without
+    pub fn from_range(
+        name: impl Into<String>,
+        sheet: &SpriteSheet,
+        range: RangeInclusive<usize>,
+        duration: f32,
+        mode: PlayMode,
+    ) -> Result<Self, String> {
+        let name = name.into();
+        let frames = sheet.frames.get(range.clone()).ok_or_else(|| {
+            format!(
+                "clip {name:?} wants frames {range:?} of a sheet with {}",
+                sheet.frames.len()
+            )
+        })?;
+
+        Ok(frames.iter().fold(Self::new(name, mode), |clip, frame| {
+            let duration = frame.duration.map_or(duration, |ms| ms as f32 / 1000.0);
+            clip.frame(&frame.name, duration)
+        }))
+    }
+
+    // one clip per tag of an aseprite export. reversed tags play their
+    // frames backwards, aseprite's repeat counts are not exported, so every
+    // clip repeats
+    pub fn from_tags(sheet: &SpriteSheet, duration: f32) -> Result<Vec<Self>, String> {
+        sheet
+            .tags
+            .iter()
+            .map(|tag| {
+                let mode = match tag.direction {
+                    TagDirection::Forward | TagDirection::Reverse => PlayMode::Loop,
+                    TagDirection::Pingpong | TagDirection::PingpongReverse => PlayMode::PingPong,
+                };
+                let mut clip =
+                    Self::from_range(&tag.name, sheet, tag.from..=tag.to, duration, mode)?;
+
+                if matches!(
+                    tag.direction,
+                    TagDirection::Reverse | TagDirection::PingpongReverse
+                ) {
+                    clip.frames.reverse();
+                }
+
+                Ok(clip)
+            })
+            .collect()
+    }
+
+    // names an event raised every time `frame` comes up, e.g. a footstep
+    pub fn with_event(mut self, frame: usize, event: impl Into<String>) -> Self {
+        if let Some(frame) = self.frames.get_mut(frame) {
+            frame.event = Some(event.into());
+        }
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AnimationEvent {
+    // the current clip moved on to this frame
+    Frame(usize),
+    // the event of the frame that came up
+    Event(String),
+    // a looping clip is back at its first frame
+    Looped,
+    // a clip played once reached its end
+    Finished,
+}
+
+// plays one clip at a time out of a set
+#[derive(Debug, Clone)]
+pub struct Animator {
+    clips: Vec<Clip>,
+    clip: usize,
+    frame: usize,
+    // into the current frame, in seconds
+    time: f32,
+    // ping pong clips play backwards half the time
+    forward: bool,
+    // the first frame of a clip hasn't been announced yet
+    started: bool,
+    finished: bool,
+    pub speed: f32,
+}
+
+impl Animator {
+    pub fn new(clips: Vec<Clip>) -> Result<Self, String> {
+        if clips.is_empty() {
+            return Err("an animator needs at least one clip".to_owned());
+        }
+        if let Some(clip) = clips.iter().find(|clip| clip.frames.is_empty()) {
+            return Err(format!("clip {:?} has no frames", clip.name));
+        }
+
+        Ok(Self {
+            clips,
+            clip: 0,
+            frame: 0,
+            time: 0.0,
+            forward: true,
+            started: false,
+            finished: false,
+            speed: 1.0,
+        })
+    }
+
+    // switches clips from the start, keeps playing if it's the current one
+    pub fn play(&mut self, name: &str) -> Result<(), String> {
+        if self.clip().name == name {
+            return Ok(());
+        }
+
+        self.clip = self
+            .clips
+            .iter()
+            .position(|clip| clip.name == name)
+            .ok_or_else(|| format!("no clip named {name:?}"))?;
+        self.restart();
+
+        Ok(())
+    }
+
+    pub fn restart(&mut self) {
+        self.frame = 0;
+        self.time = 0.0;
+        self.forward = true;
+        self.started = false;
+        self.finished = false;
+    }
+
+    pub fn clip(&self) -> &Clip {
+        &self.clips[self.clip]
+    }
+
+    pub fn frame(&self) -> usize {
+        self.frame
+    }
+
+    // what to draw right now
+    pub fn region(&self) -> &str {
+        &self.clip().frames[self.frame].region
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    // moves time on, collecting what happened on the way in `events`.
+    // frames shorter than `delta_time` are skipped over but still reported
+    pub fn advance(&mut self, delta_time: f32, events: &mut Vec<AnimationEvent>) {
+        if !self.started {
+            self.started = true;
+            self.enter(self.frame, events);
+        }
+        if self.finished {
+            return;
+        }
+
+        self.time += delta_time * self.speed;
+
+        loop {
+            let clip = &self.clips[self.clip];
+            // zero length frames would never let time run out
+            let duration = clip.frames[self.frame].duration.max(0.001);
+            if self.time < duration {
+                return;
+            }
+            self.time -= duration;
+
+            let last = clip.frames.len() - 1;
+            let next = match clip.mode {
+                PlayMode::Loop if self.frame == last => {
+                    events.push(AnimationEvent::Looped);
+                    0
+                }
+                PlayMode::Once if self.frame == last => {
+                    self.finished = true;
+                    self.time = 0.0;
+                    events.push(AnimationEvent::Finished);
+                    return;
+                }
+                PlayMode::Loop | PlayMode::Once => self.frame + 1,
+                PlayMode::PingPong if last == 0 => {
+                    events.push(AnimationEvent::Looped);
+                    0
+                }
+                PlayMode::PingPong => {
+                    if self.forward && self.frame == last {
+                        self.forward = false;
+                    } else if !self.forward && self.frame == 0 {
+                        self.forward = true;
+                        events.push(AnimationEvent::Looped);
+                    }
+
+                    if self.forward {
+                        self.frame + 1
+                    } else {
+                        self.frame - 1
+                    }
+                }
+            };
+
+            self.enter(next, events);
+        }
+    }
+
+    fn enter(&mut self, frame: usize, events: &mut Vec<AnimationEvent>) {
+        self.frame = frame;
+        events.push(AnimationEvent::Frame(frame));
+
+        if let Some(event) = &self.clip().frames[frame].event {
+            events.push(AnimationEvent::Event(event.clone()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ASEPRITE: &str = r#"{
+        "frames": [
+            { "filename": "hero 0.aseprite", "frame": { "x": 0, "y": 0, "w": 8, "h": 8 }, "duration": 100 },
+            { "filename": "hero 1.aseprite", "frame": { "x": 8, "y": 0, "w": 8, "h": 8 }, "duration": 200 },
+            { "filename": "hero 2.aseprite", "frame": { "x": 16, "y": 0, "w": 8, "h": 8 }, "duration": 300 },
+            { "filename": "hero 3.aseprite", "frame": { "x": 24, "y": 0, "w": 8, "h": 8 } }
+        ],
+        "meta": {
+            "image": "hero.png",
+            "frameTags": [
+                { "name": "run", "from": 0, "to": 2, "direction": "forward" },
+                { "name": "back", "from": 1, "to": 3, "direction": "reverse" },
+                { "name": "bob", "from": 0, "to": 2, "direction": "pingpong" }
+            ]
+        }
+    }"#;
+
+    fn clip(mode: PlayMode, frames: usize) -> Clip {
+        (0..frames).fold(Clip::new("clip", mode), |clip, i| {
+            clip.frame(i.to_string(), 1.0)
+        })
+    }
+
+    // the frames shown after each of `steps` one second steps
+    fn play(clip: Clip, steps: usize) -> Vec<usize> {
+        let mut animator = Animator::new(vec![clip]).unwrap();
+        let mut events = Vec::new();
+
+        (0..steps)
+            .map(|_| {
+                animator.advance(1.0, &mut events);
+                animator.frame()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn modes_step_through_frames() {
+        assert_eq!(play(clip(PlayMode::Loop, 3), 7), [1, 2, 0, 1, 2, 0, 1]);
+        assert_eq!(play(clip(PlayMode::Once, 3), 5), [1, 2, 2, 2, 2]);
+        assert_eq!(
+            play(clip(PlayMode::PingPong, 3), 8),
+            [1, 2, 1, 0, 1, 2, 1, 0]
+        );
+        assert_eq!(play(clip(PlayMode::PingPong, 1), 3), [0, 0, 0]);
+    }
+
+    #[test]
+    fn events_are_raised_on_the_way() {
+        let clip = clip(PlayMode::Once, 3)
+            .with_event(0, "start")
+            .with_event(2, "hit");
+        let mut animator = Animator::new(vec![clip]).unwrap();
+        let mut events = Vec::new();
+
+        // one long step passes every frame
+        animator.advance(10.0, &mut events);
+
+        assert_eq!(
+            events,
+            [
+                AnimationEvent::Frame(0),
+                AnimationEvent::Event("start".to_owned()),
+                AnimationEvent::Frame(1),
+                AnimationEvent::Frame(2),
+                AnimationEvent::Event("hit".to_owned()),
+                AnimationEvent::Finished,
+            ]
+        );
+        assert!(animator.is_finished());
+
+        events.clear();
+        animator.advance(1.0, &mut events);
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn durations_are_kept_per_frame() {
+        let clip = Clip::new("clip", PlayMode::Loop)
+            .frame("short", 0.1)
+            .frame("long", 0.5);
+        let mut animator = Animator::new(vec![clip]).unwrap();
+        let mut events = Vec::new();
+
+        animator.advance(0.15, &mut events);
+        assert_eq!(animator.region(), "long");
+        animator.advance(0.4, &mut events);
+        assert_eq!(animator.region(), "long");
+        animator.advance(0.1, &mut events);
+        assert_eq!(animator.region(), "short");
+        assert!(events.contains(&AnimationEvent::Looped));
+    }
+
+    #[test]
+    fn switching_clips_starts_them_over() {
+        let walk = clip(PlayMode::Loop, 4);
+        let idle = Clip::new("idle", PlayMode::Loop).frame("idle", 1.0);
+        let mut animator = Animator::new(vec![walk, idle]).unwrap();
+        let mut events = Vec::new();
+
+        animator.advance(2.5, &mut events);
+        animator.play("clip").unwrap();
+        assert_eq!(animator.frame(), 2);
+
+        animator.play("idle").unwrap();
+        assert_eq!((animator.frame(), animator.region()), (0, "idle"));
+        assert!(animator.play("run").is_err());
+    }
+
+    #[test]
+    fn aseprite_tags_become_clips() {
+        let sheet = SpriteSheet::from_json(ASEPRITE).unwrap();
+        let clips = Clip::from_tags(&sheet, 0.25).unwrap();
+        let [run, back, bob] = &clips[..] else {
+            panic!("expected three clips");
+        };
+
+        let regions =
+            |clip: &Clip| -> Vec<String> { clip.frames.iter().map(|f| f.region.clone()).collect() };
+        assert_eq!(
+            regions(run),
+            ["hero 0.aseprite", "hero 1.aseprite", "hero 2.aseprite"]
+        );
+        assert_eq!(
+            run.frames.iter().map(|f| f.duration).collect::<Vec<_>>(),
+            [0.1, 0.2, 0.3]
+        );
+        assert_eq!(
+            regions(back),
+            ["hero 3.aseprite", "hero 2.aseprite", "hero 1.aseprite"]
+        );
+        assert_eq!(back.frames[0].duration, 0.25);
+        assert_eq!((run.mode, bob.mode), (PlayMode::Loop, PlayMode::PingPong));
+
+        assert!(Clip::from_range("too far", &sheet, 2..=4, 0.1, PlayMode::Loop).is_err());
+    }
+}
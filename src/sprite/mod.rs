@@ -0,0 +1,267 @@
+pub mod animation;
+
+pub use animation::{AnimationEvent, Animator, Clip, ClipFrame, PlayMode};
+
+use glam::Vec2;
+use wgpu::include_wgsl;
+
+use crate::{
+    sandbox::camera2d::camera::Camera2D,
+    texture::{atlas::AtlasRegion, Texture},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sprite {
+    // where the top left corner goes, in world space
+    pub position: Vec2,
+    pub size: Vec2,
+    // the part of the texture to show
+    pub uv_min: [f32; 2],
+    pub uv_max: [f32; 2],
+    pub color: [f32; 4],
+    // mirrors the image, in place
+    pub flip_x: bool,
+    pub flip_y: bool,
+}
+
+impl Sprite {
+    // an atlas region at its size in texels times `scale`. `position` is the
+    // top left corner of the untrimmed image, mirrored ones keep their trimmed
+    // part on the mirrored side
+    pub fn from_region(region: &AtlasRegion, position: Vec2, scale: f32, flip_x: bool) -> Self {
+        let mut offset = Vec2::new(region.offset.0 as f32, region.offset.1 as f32);
+        if flip_x {
+            offset.x = (region.source_size.0 - region.offset.0 - region.size.0) as f32;
+        }
+
+        Self {
+            position: position + offset * scale,
+            size: Vec2::new(region.size.0 as f32, region.size.1 as f32) * scale,
+            uv_min: region.uv_min,
+            uv_max: region.uv_max,
+            color: [1.0; 4],
+            flip_x,
+            flip_y: false,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+struct Instance {
+    position: [f32; 2],
+    size: [f32; 2],
+    uv_min: [f32; 2],
+    uv_max: [f32; 2],
+    color: [f32; 4],
+}
+
+impl Instance {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 5] = wgpu::vertex_attr_array![
+        0 => Float32x2,
+        1 => Float32x2,
+        2 => Float32x2,
+        3 => Float32x2,
+        4 => Float32x4,
+    ];
+}
+
+// flips swap the uv corners, the quad itself stays where it is
+impl From<&Sprite> for Instance {
+    fn from(sprite: &Sprite) -> Self {
+        let mut uv_min = sprite.uv_min;
+        let mut uv_max = sprite.uv_max;
+        if sprite.flip_x {
+            std::mem::swap(&mut uv_min[0], &mut uv_max[0]);
+        }
+        if sprite.flip_y {
+            std::mem::swap(&mut uv_min[1], &mut uv_max[1]);
+        }
+
+        Self {
+            position: sprite.position.into(),
+            size: sprite.size.into(),
+            uv_min,
+            uv_max,
+            color: sprite.color,
+        }
+    }
+}
+
+// draws textured quads out of a single texture, usually an atlas page, one
+// instance each in the order they were pushed
+pub struct SpriteBatch {
+    pipeline: wgpu::RenderPipeline,
+    camera_buffer: wgpu::Buffer,
+    camera_bind_group: wgpu::BindGroup,
+    texture_layout: wgpu::BindGroupLayout,
+    texture_bind_group: Option<wgpu::BindGroup>,
+    instance_buffer: wgpu::Buffer,
+    // in instances
+    capacity: usize,
+    instances: Vec<Instance>,
+    count: u32,
+}
+
+impl SpriteBatch {
+    pub fn new(device: &wgpu::Device, view_format: wgpu::TextureFormat) -> Self {
+        let shader = device.create_shader_module(include_wgsl!("shader.wgsl"));
+
+        let camera_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("sprite camera buffer"),
+            size: size_of::<[[f32; 4]; 4]>() as _,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let camera_layout = Camera2D::bind_group_layout(device);
+        let camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("sprite camera bind group"),
+            layout: &camera_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: camera_buffer.as_entire_binding(),
+            }],
+        });
+        let texture_layout = Texture::bind_group_layout(device);
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("sprite pipeline layout"),
+            bind_group_layouts: &[&camera_layout, &texture_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("sprite pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: size_of::<Instance>() as _,
+                    step_mode: wgpu::VertexStepMode::Instance,
+                    attributes: &Instance::ATTRIBUTES,
+                }],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: view_format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleStrip,
+                ..Default::default()
+            },
+            multisample: Default::default(),
+            depth_stencil: None,
+            multiview: None,
+            cache: None,
+        });
+
+        let capacity = 64;
+
+        Self {
+            pipeline,
+            camera_buffer,
+            camera_bind_group,
+            texture_layout,
+            texture_bind_group: None,
+            instance_buffer: instance_buffer(device, capacity),
+            capacity,
+            instances: Vec::new(),
+            count: 0,
+        }
+    }
+
+    // nothing is drawn until there is one
+    pub fn set_texture(&mut self, device: &wgpu::Device, texture: &Texture) {
+        self.texture_bind_group = Some(texture.bind_group(device, &self.texture_layout));
+    }
+
+    pub fn set_camera(&self, camera: &Camera2D, queue: &wgpu::Queue) {
+        queue.write_buffer(
+            &self.camera_buffer,
+            0,
+            bytemuck::cast_slice(&camera.camera_matrix().to_cols_array_2d()),
+        );
+    }
+
+    // queues a sprite for the next `prepare`, later ones are drawn on top
+    pub fn push(&mut self, sprite: &Sprite) {
+        self.instances.push(sprite.into());
+    }
+
+    // uploads everything pushed since the last call, which is what `render`
+    // draws until the next one
+    pub fn prepare(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        if self.instances.len() > self.capacity {
+            self.capacity = self.instances.len().next_power_of_two();
+            self.instance_buffer = instance_buffer(device, self.capacity);
+        }
+
+        queue.write_buffer(
+            &self.instance_buffer,
+            0,
+            bytemuck::cast_slice(&self.instances),
+        );
+        self.count = self.instances.len() as u32;
+        self.instances.clear();
+    }
+
+    pub fn render(&self, render_pass: &mut wgpu::RenderPass) {
+        let Some(texture_bind_group) = &self.texture_bind_group else {
+            return;
+        };
+        if self.count == 0 {
+            return;
+        }
+
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
+        render_pass.set_bind_group(1, texture_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.instance_buffer.slice(..));
+        render_pass.draw(0..4, 0..self.count);
+    }
+}
+
+fn instance_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("sprite instance buffer"),
+        size: (capacity * size_of::<Instance>()) as _,
+        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trimmed_regions_keep_their_place() {
+        // a 4x2 frame trimmed out of an 8x8 image, 1 texel from the left
+        let region = AtlasRegion {
+            page: 0,
+            uv_min: [0.0, 0.0],
+            uv_max: [0.5, 0.25],
+            size: (4, 2),
+            offset: (1, 3),
+            source_size: (8, 8),
+        };
+
+        let sprite = Sprite::from_region(&region, Vec2::new(10.0, 20.0), 2.0, false);
+        assert_eq!(sprite.position, Vec2::new(12.0, 26.0));
+        assert_eq!(sprite.size, Vec2::new(8.0, 4.0));
+
+        // mirrored, the 3 texels on the right become the gap on the left
+        let flipped = Sprite::from_region(&region, Vec2::new(10.0, 20.0), 2.0, true);
+        assert_eq!(flipped.position, Vec2::new(16.0, 26.0));
+        assert!(flipped.flip_x);
+    }
+}
@@ -0,0 +1,224 @@
+use wgpu::{include_wgsl, util::DeviceExt};
+
+use super::{tessellate, Mesh, Path, Style, TOLERANCE};
+use crate::{
+    sandbox::camera2d::camera::Camera2D,
+    vertices::{Vertex, VertexPosCol},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ShapeId(usize);
+
+struct Shape {
+    path: Path,
+    style: Style,
+    color: [f32; 3],
+}
+
+// keeps its shapes from frame to frame and only tessellates them again when
+// they change, or when the camera zooms far enough that the curves would
+// look too coarse or be needlessly fine
+pub struct ShapeBatch {
+    pipeline: wgpu::RenderPipeline,
+    camera_buffer: wgpu::Buffer,
+    camera_bind_group: wgpu::BindGroup,
+    vertex_buffer: wgpu::Buffer,
+    index_buffer: wgpu::Buffer,
+    // in vertices and indices
+    vertex_capacity: usize,
+    index_capacity: usize,
+    index_count: u32,
+    // removed shapes leave a hole, so ids stay valid
+    shapes: Vec<Option<Shape>>,
+    mesh: Mesh,
+    tolerance: f32,
+    dirty: bool,
+}
+
+impl ShapeBatch {
+    pub fn new(device: &wgpu::Device, view_format: wgpu::TextureFormat) -> Self {
+        let shader = device.create_shader_module(include_wgsl!("shader.wgsl"));
+
+        let camera = Camera2D::new(1, 1, 1.0);
+        let camera_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("shape batch camera buffer"),
+            contents: bytemuck::cast_slice(&camera.camera_matrix().to_cols_array_2d()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let camera_layout = Camera2D::bind_group_layout(device);
+        let camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("shape batch camera bind group"),
+            layout: &camera_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: camera_buffer.as_entire_binding(),
+            }],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("shape batch pipeline layout"),
+            bind_group_layouts: &[&camera_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("shape batch pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[VertexPosCol::desc()],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: view_format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            // fills and strokes come out in either winding
+            primitive: wgpu::PrimitiveState::default(),
+            multisample: Default::default(),
+            depth_stencil: None,
+            multiview: None,
+            cache: None,
+        });
+
+        let (vertex_capacity, index_capacity) = (1024, 4096);
+
+        Self {
+            pipeline,
+            camera_buffer,
+            camera_bind_group,
+            vertex_buffer: buffer::<VertexPosCol>(
+                device,
+                vertex_capacity,
+                wgpu::BufferUsages::VERTEX,
+            ),
+            index_buffer: buffer::<u32>(device, index_capacity, wgpu::BufferUsages::INDEX),
+            vertex_capacity,
+            index_capacity,
+            index_count: 0,
+            shapes: Vec::new(),
+            mesh: Mesh::default(),
+            tolerance: TOLERANCE,
+            dirty: false,
+        }
+    }
+
+    // shapes are drawn in the order they were added
+    pub fn add(&mut self, path: Path, style: Style, color: [f32; 3]) -> ShapeId {
+        self.shapes.push(Some(Shape { path, style, color }));
+        self.dirty = true;
+
+        ShapeId(self.shapes.len() - 1)
+    }
+
+    pub fn remove(&mut self, id: ShapeId) {
+        if let Some(shape) = self.shapes.get_mut(id.0) {
+            self.dirty |= shape.take().is_some();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.shapes.clear();
+        self.dirty = true;
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.index_count as usize / 3
+    }
+
+    // in world units, see `set_camera`
+    pub fn tolerance(&self) -> f32 {
+        self.tolerance
+    }
+
+    pub fn set_camera(&mut self, camera: &Camera2D, queue: &wgpu::Queue) {
+        queue.write_buffer(
+            &self.camera_buffer,
+            0,
+            bytemuck::cast_slice(&camera.camera_matrix().to_cols_array_2d()),
+        );
+
+        // the tolerance follows the zoom in steps, so zooming smoothly
+        // doesn't tessellate everything on every frame
+        let tolerance = TOLERANCE / camera.scale.max(f32::EPSILON);
+        if tolerance < self.tolerance / 2.0 || tolerance > self.tolerance * 2.0 {
+            self.tolerance = tolerance;
+            self.dirty = true;
+        }
+    }
+
+    // tessellates and uploads the shapes if anything changed since the last
+    // call. shapes that fail to tessellate are left out
+    pub fn prepare(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        if !self.dirty {
+            return;
+        }
+        self.dirty = false;
+
+        self.mesh.clear();
+        for shape in self.shapes.iter().flatten() {
+            let result = tessellate(
+                &shape.path,
+                &shape.style,
+                shape.color,
+                self.tolerance,
+                &mut self.mesh,
+            );
+            if let Err(e) = result {
+                log::warn!("{e}");
+            }
+        }
+
+        if self.mesh.vertices.len() > self.vertex_capacity {
+            self.vertex_capacity = self.mesh.vertices.len().next_power_of_two();
+            self.vertex_buffer =
+                buffer::<VertexPosCol>(device, self.vertex_capacity, wgpu::BufferUsages::VERTEX);
+        }
+        if self.mesh.indices.len() > self.index_capacity {
+            self.index_capacity = self.mesh.indices.len().next_power_of_two();
+            self.index_buffer =
+                buffer::<u32>(device, self.index_capacity, wgpu::BufferUsages::INDEX);
+        }
+
+        queue.write_buffer(
+            &self.vertex_buffer,
+            0,
+            bytemuck::cast_slice(&self.mesh.vertices),
+        );
+        queue.write_buffer(
+            &self.index_buffer,
+            0,
+            bytemuck::cast_slice(&self.mesh.indices),
+        );
+        self.index_count = self.mesh.indices.len() as u32;
+    }
+
+    pub fn render(&self, render_pass: &mut wgpu::RenderPass) {
+        if self.index_count == 0 {
+            return;
+        }
+
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+        render_pass.draw_indexed(0..self.index_count, 0, 0..1);
+    }
+}
+
+fn buffer<T>(device: &wgpu::Device, capacity: usize, usage: wgpu::BufferUsages) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("shape batch buffer"),
+        size: (capacity * size_of::<T>()) as _,
+        usage: usage | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
@@ -0,0 +1,347 @@
+use lyon::tessellation::{
+    BuffersBuilder, FillOptions, FillTessellator, FillVertex, LineCap, LineJoin, StrokeTessellator,
+    StrokeVertex, VertexBuffers,
+};
+
+use crate::{
+    polyline::{Cap, Join},
+    vertices::VertexPosCol,
+};
+
+pub use batch::{ShapeBatch, ShapeId};
+pub use path::Path;
+
+pub mod batch;
+pub mod path;
+
+// how far the flattened curves may stray from the real ones, in world units
+// at a zoom of one
+pub const TOLERANCE: f32 = 0.1;
+
+// where subpaths overlap, even odd leaves a hole wherever an even number of
+// them do, non zero only where they wind in opposite directions
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FillRule {
+    EvenOdd,
+    #[default]
+    NonZero,
+}
+
+// widths are in world units, unlike `polyline::LineStyle`, since the stroke
+// becomes part of the mesh
+#[derive(Debug, Clone, Copy)]
+pub struct StrokeOptions {
+    pub width: f32,
+    pub join: Join,
+    pub cap: Cap,
+    pub miter_limit: f32,
+}
+
+impl Default for StrokeOptions {
+    fn default() -> Self {
+        Self {
+            width: 1.0,
+            join: Join::Miter,
+            cap: Cap::Butt,
+            miter_limit: 4.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Style {
+    Fill(FillRule),
+    Stroke(StrokeOptions),
+}
+
+// an indexed triangle list
+#[derive(Debug, Clone, Default)]
+pub struct Mesh {
+    pub vertices: Vec<VertexPosCol>,
+    pub indices: Vec<u32>,
+}
+
+impl Mesh {
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+        self.indices.clear();
+    }
+}
+
+// appends the triangles covering `path` to `mesh`, in a single color
+pub fn tessellate(
+    path: &Path,
+    style: &Style,
+    color: [f32; 3],
+    tolerance: f32,
+    mesh: &mut Mesh,
+) -> Result<(), String> {
+    let path = path.to_lyon();
+    let mut buffers: VertexBuffers<VertexPosCol, u32> = VertexBuffers::new();
+
+    match style {
+        Style::Fill(rule) => {
+            let rule = match rule {
+                FillRule::EvenOdd => lyon::tessellation::FillRule::EvenOdd,
+                FillRule::NonZero => lyon::tessellation::FillRule::NonZero,
+            };
+            let options = FillOptions::tolerance(tolerance).with_fill_rule(rule);
+
+            FillTessellator::new().tessellate_path(
+                &path,
+                &options,
+                &mut BuffersBuilder::new(&mut buffers, |vertex: FillVertex| VertexPosCol {
+                    position: vertex.position().to_array(),
+                    color,
+                }),
+            )
+        }
+        Style::Stroke(stroke) => {
+            let join = match stroke.join {
+                Join::Miter => LineJoin::Miter,
+                Join::Bevel => LineJoin::Bevel,
+                Join::Round => LineJoin::Round,
+            };
+            let cap = match stroke.cap {
+                Cap::Butt => LineCap::Butt,
+                Cap::Square => LineCap::Square,
+                Cap::Round => LineCap::Round,
+            };
+            let options = lyon::tessellation::StrokeOptions::tolerance(tolerance)
+                .with_line_width(stroke.width)
+                .with_line_join(join)
+                .with_line_cap(cap)
+                .with_miter_limit(stroke.miter_limit.max(1.0));
+
+            StrokeTessellator::new().tessellate_path(
+                &path,
+                &options,
+                &mut BuffersBuilder::new(&mut buffers, |vertex: StrokeVertex| VertexPosCol {
+                    position: vertex.position().to_array(),
+                    color,
+                }),
+            )
+        }
+    }
+    .map_err(|e| format!("failed to tessellate path: {e}"))?;
+
+    let offset = mesh.vertices.len() as u32;
+    mesh.vertices.extend(buffers.vertices);
+    mesh.indices
+        .extend(buffers.indices.into_iter().map(|i| i + offset));
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use glam::Vec2;
+
+    use super::*;
+
+    const WHITE: [f32; 3] = [1.0; 3];
+
+    fn mesh(path: &Path, style: Style) -> Mesh {
+        let mut mesh = Mesh::default();
+        tessellate(path, &style, WHITE, 0.01, &mut mesh).unwrap();
+        mesh
+    }
+
+    fn triangles(mesh: &Mesh) -> impl Iterator<Item = [Vec2; 3]> + '_ {
+        assert_eq!(mesh.indices.len() % 3, 0);
+        mesh.indices.chunks_exact(3).map(|triangle| {
+            [0, 1, 2].map(|i| Vec2::from(mesh.vertices[triangle[i] as usize].position))
+        })
+    }
+
+    fn signed_area([a, b, c]: [Vec2; 3]) -> f32 {
+        (b - a).perp_dot(c - a) / 2.0
+    }
+
+    // triangles shouldn't overlap, so their areas add up to the shape's
+    fn area(mesh: &Mesh) -> f32 {
+        triangles(mesh).map(|t| signed_area(t).abs()).sum()
+    }
+
+    fn shoelace(points: &[Vec2]) -> f32 {
+        let n = points.len();
+        (0..n)
+            .map(|i| points[i].perp_dot(points[(i + 1) % n]) / 2.0)
+            .sum::<f32>()
+            .abs()
+    }
+
+    fn assert_close(actual: f32, expected: f32, epsilon: f32) {
+        assert!(
+            (actual - expected).abs() <= epsilon,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn square_is_two_triangles() {
+        let mesh = mesh(
+            &Path::new().rect(Vec2::ZERO, Vec2::ONE),
+            Style::Fill(FillRule::NonZero),
+        );
+
+        assert_eq!(mesh.vertices.len(), 4);
+        assert_eq!(mesh.indices.len(), 6);
+        assert_close(area(&mesh), 1.0, 1e-6);
+        assert!(mesh.vertices.iter().all(|v| v.color == WHITE));
+    }
+
+    #[test]
+    fn concave_polygon_is_covered_exactly() {
+        // a star and an l shape, each triangle keeps to the inside
+        let star: Vec<_> = (0..10)
+            .map(|i| {
+                let radius = if i % 2 == 0 { 10.0 } else { 4.0 };
+                Vec2::from_angle(i as f32 * PI / 5.0) * radius
+            })
+            .collect();
+        let l_shape = [
+            Vec2::new(0.0, 0.0),
+            Vec2::new(4.0, 0.0),
+            Vec2::new(4.0, 1.0),
+            Vec2::new(1.0, 1.0),
+            Vec2::new(1.0, 3.0),
+            Vec2::new(0.0, 3.0),
+        ];
+
+        for polygon in [&star[..], &l_shape] {
+            let mesh = mesh(
+                &Path::new().polygon(polygon),
+                Style::Fill(FillRule::NonZero),
+            );
+
+            assert_eq!(mesh.indices.len() / 3, polygon.len() - 2);
+            assert_close(area(&mesh), shoelace(polygon), 1e-4);
+            assert!(triangles(&mesh).all(|t| signed_area(t).abs() > 1e-6));
+        }
+    }
+
+    #[test]
+    fn fill_rules_decide_holes() {
+        // two squares winding the same way, one inside the other
+        let path = Path::new()
+            .rect(Vec2::ZERO, Vec2::splat(4.0))
+            .rect(Vec2::ONE, Vec2::splat(2.0));
+
+        let even_odd = mesh(&path, Style::Fill(FillRule::EvenOdd));
+        let non_zero = mesh(&path, Style::Fill(FillRule::NonZero));
+
+        assert_close(area(&even_odd), 12.0, 1e-4);
+        assert_close(area(&non_zero), 16.0, 1e-4);
+    }
+
+    #[test]
+    fn curves_are_flattened_within_tolerance() {
+        let circle = mesh(
+            &Path::new().circle(Vec2::ZERO, 10.0),
+            Style::Fill(FillRule::NonZero),
+        );
+        assert_close(area(&circle), PI * 100.0, 0.5);
+
+        // the area between a parabola and its chord is two thirds of the
+        // triangle its control point makes with the chord. flattening cuts a
+        // little off, at most the tolerance along the length of the curve
+        let quadratic = mesh(
+            &Path::new()
+                .move_to(Vec2::ZERO)
+                .quadratic_to(Vec2::new(1.0, 2.0), Vec2::new(2.0, 0.0))
+                .close(),
+            Style::Fill(FillRule::NonZero),
+        );
+        assert_close(area(&quadratic), 4.0 / 3.0, 0.03);
+
+        // a cubic with both controls at the same point is that quadratic
+        // again, raised a degree
+        let cubic = mesh(
+            &Path::new()
+                .move_to(Vec2::ZERO)
+                .cubic_to(
+                    Vec2::new(2.0 / 3.0, 4.0 / 3.0),
+                    Vec2::new(4.0 / 3.0, 4.0 / 3.0),
+                    Vec2::new(2.0, 0.0),
+                )
+                .close(),
+            Style::Fill(FillRule::NonZero),
+        );
+        assert_close(area(&cubic), 4.0 / 3.0, 0.03);
+    }
+
+    #[test]
+    fn shape_helpers_have_their_area() {
+        let fill = Style::Fill(FillRule::NonZero);
+
+        let rounded = mesh(
+            &Path::new().rounded_rect(Vec2::ZERO, Vec2::new(10.0, 6.0), 2.0),
+            fill,
+        );
+        assert_close(area(&rounded), 60.0 - (4.0 - PI) * 4.0, 0.1);
+
+        let ellipse = mesh(&Path::new().ellipse(Vec2::ZERO, Vec2::new(8.0, 3.0)), fill);
+        assert_close(area(&ellipse), PI * 24.0, 0.3);
+
+        let quarter = mesh(&Path::new().pie(Vec2::ZERO, 10.0, 0.0, PI / 2.0), fill);
+        assert_close(area(&quarter), PI * 25.0, 0.3);
+    }
+
+    #[test]
+    fn strokes_cover_their_width() {
+        let line = Path::new()
+            .move_to(Vec2::ZERO)
+            .line_to(Vec2::new(10.0, 0.0));
+        let butt = mesh(
+            &line,
+            Style::Stroke(StrokeOptions {
+                width: 2.0,
+                ..Default::default()
+            }),
+        );
+        assert_close(area(&butt), 20.0, 1e-4);
+
+        let square = mesh(
+            &line,
+            Style::Stroke(StrokeOptions {
+                width: 2.0,
+                cap: Cap::Square,
+                ..Default::default()
+            }),
+        );
+        assert_close(area(&square), 24.0, 1e-4);
+
+        // an outline around a square, the inside stays empty
+        let outline = mesh(
+            &Path::new().rect(Vec2::ZERO, Vec2::splat(10.0)),
+            Style::Stroke(StrokeOptions {
+                width: 2.0,
+                ..Default::default()
+            }),
+        );
+        assert_close(area(&outline), 12.0 * 12.0 - 8.0 * 8.0, 1e-3);
+    }
+
+    #[test]
+    fn meshes_append() {
+        let mut mesh = Mesh::default();
+        let square = Path::new().rect(Vec2::ZERO, Vec2::ONE);
+        let style = Style::Fill(FillRule::NonZero);
+
+        tessellate(&square, &style, WHITE, TOLERANCE, &mut mesh).unwrap();
+        tessellate(&square, &style, [1.0, 0.0, 0.0], TOLERANCE, &mut mesh).unwrap();
+
+        assert_eq!(mesh.vertices.len(), 8);
+        assert!(mesh.indices[6..].iter().all(|&i| (4..8).contains(&i)));
+        assert_eq!(mesh.vertices[4].color, [1.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn empty_paths_make_no_triangles() {
+        let mesh = mesh(&Path::new(), Style::Fill(FillRule::NonZero));
+        assert!(mesh.indices.is_empty());
+    }
+}
@@ -0,0 +1,225 @@
+use std::f32::consts::{FRAC_PI_2, TAU};
+
+use glam::Vec2;
+use lyon::math::point;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Verb {
+    MoveTo(Vec2),
+    LineTo(Vec2),
+    QuadraticTo(Vec2, Vec2),
+    CubicTo(Vec2, Vec2, Vec2),
+    Close,
+}
+
+// a list of subpaths made of lines and bezier curves, in world units. the
+// shape helpers each add a closed subpath of their own, so several of them
+// in one path can cut holes into each other depending on the fill rule
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Path {
+    verbs: Vec<Verb>,
+}
+
+impl Path {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn move_to(mut self, to: Vec2) -> Self {
+        self.verbs.push(Verb::MoveTo(to));
+        self
+    }
+
+    pub fn line_to(mut self, to: Vec2) -> Self {
+        self.verbs.push(Verb::LineTo(to));
+        self
+    }
+
+    pub fn quadratic_to(mut self, control: Vec2, to: Vec2) -> Self {
+        self.verbs.push(Verb::QuadraticTo(control, to));
+        self
+    }
+
+    pub fn cubic_to(mut self, control1: Vec2, control2: Vec2, to: Vec2) -> Self {
+        self.verbs.push(Verb::CubicTo(control1, control2, to));
+        self
+    }
+
+    pub fn close(mut self) -> Self {
+        self.verbs.push(Verb::Close);
+        self
+    }
+
+    pub fn polygon(self, points: &[Vec2]) -> Self {
+        let Some((&first, rest)) = points.split_first() else {
+            return self;
+        };
+
+        rest.iter()
+            .fold(self.move_to(first), |path, &p| path.line_to(p))
+            .close()
+    }
+
+    // `position` is the top left corner
+    pub fn rect(self, position: Vec2, size: Vec2) -> Self {
+        self.polygon(&[
+            position,
+            position + Vec2::new(size.x, 0.0),
+            position + size,
+            position + Vec2::new(0.0, size.y),
+        ])
+    }
+
+    // the radius is clamped to half the shorter side
+    pub fn rounded_rect(self, position: Vec2, size: Vec2, radius: f32) -> Self {
+        let radius = radius.clamp(0.0, size.x.min(size.y) / 2.0);
+        let inner_min = position + radius;
+        let inner_max = position + size - radius;
+
+        // clockwise on screen, starting from the top left corner
+        let corners = [
+            (Vec2::new(inner_min.x, inner_min.y), 2.0),
+            (Vec2::new(inner_max.x, inner_min.y), 3.0),
+            (Vec2::new(inner_max.x, inner_max.y), 0.0),
+            (Vec2::new(inner_min.x, inner_max.y), 1.0),
+        ];
+
+        let mut path = self;
+        for (i, (center, quarter)) in corners.into_iter().enumerate() {
+            path = path.arc_segment(center, radius, quarter * FRAC_PI_2, FRAC_PI_2, i == 0);
+        }
+        path.close()
+    }
+
+    pub fn circle(self, center: Vec2, radius: f32) -> Self {
+        self.ellipse(center, Vec2::splat(radius))
+    }
+
+    pub fn ellipse(self, center: Vec2, radii: Vec2) -> Self {
+        let mut path = self.move_to(center + Vec2::new(radii.x, 0.0));
+        for quarter in 0..4 {
+            let [c1, c2, end] = arc_controls(quarter as f32 * FRAC_PI_2, FRAC_PI_2);
+            path = path.cubic_to(
+                center + c1 * radii,
+                center + c2 * radii,
+                center + end * radii,
+            );
+        }
+
+        path.close()
+    }
+
+    // an open arc from `start` radians, sweeping clockwise on screen for
+    // positive angles, since world y points down
+    pub fn arc(self, center: Vec2, radius: f32, start: f32, sweep: f32) -> Self {
+        self.arc_segment(center, radius, start, sweep, true)
+    }
+
+    // a closed wedge from the center, like a slice of a pie chart
+    pub fn pie(self, center: Vec2, radius: f32, start: f32, sweep: f32) -> Self {
+        self.move_to(center)
+            .arc_segment(center, radius, start, sweep, false)
+            .close()
+    }
+
+    // follows the circle with one cubic per quarter turn at most, starting a
+    // new subpath or drawing a line to the start of the arc first
+    fn arc_segment(
+        mut self,
+        center: Vec2,
+        radius: f32,
+        start: f32,
+        sweep: f32,
+        new_subpath: bool,
+    ) -> Self {
+        let sweep = sweep.clamp(-TAU, TAU);
+        let first = center + Vec2::from_angle(start) * radius;
+        self = match new_subpath {
+            true => self.move_to(first),
+            false => self.line_to(first),
+        };
+
+        let pieces = (sweep.abs() / FRAC_PI_2).ceil().max(1.0);
+        let step = sweep / pieces;
+        for i in 0..pieces as usize {
+            let [c1, c2, end] = arc_controls(start + i as f32 * step, step);
+            self = self.cubic_to(
+                center + c1 * radius,
+                center + c2 * radius,
+                center + end * radius,
+            );
+        }
+
+        self
+    }
+
+    pub(super) fn to_lyon(&self) -> lyon::path::Path {
+        let p = |v: Vec2| point(v.x, v.y);
+        let mut builder = lyon::path::Path::builder();
+
+        // lyon wants every subpath begun and ended explicitly. like svg,
+        // drawing without a move starts where the last subpath stopped, or
+        // where it started when it was closed
+        let mut open = false;
+        let mut current = Vec2::ZERO;
+        let mut first = Vec2::ZERO;
+
+        for verb in &self.verbs {
+            let to = match *verb {
+                Verb::MoveTo(to) => {
+                    if open {
+                        builder.end(false);
+                    }
+                    builder.begin(p(to));
+                    open = true;
+                    current = to;
+                    first = to;
+                    continue;
+                }
+                Verb::Close => {
+                    if open {
+                        builder.end(true);
+                    }
+                    open = false;
+                    current = first;
+                    continue;
+                }
+                Verb::LineTo(to) | Verb::QuadraticTo(_, to) | Verb::CubicTo(_, _, to) => to,
+            };
+
+            if !open {
+                builder.begin(p(current));
+                open = true;
+                first = current;
+            }
+
+            match *verb {
+                Verb::QuadraticTo(control, _) => {
+                    builder.quadratic_bezier_to(p(control), p(to));
+                }
+                Verb::CubicTo(control1, control2, _) => {
+                    builder.cubic_bezier_to(p(control1), p(control2), p(to));
+                }
+                _ => {
+                    builder.line_to(p(to));
+                }
+            }
+            current = to;
+        }
+        if open {
+            builder.end(false);
+        }
+
+        builder.build()
+    }
+}
+
+// control points and end of a cubic following a unit circle from `start` for
+// `sweep` radians, a quarter turn at most to stay close to it
+fn arc_controls(start: f32, sweep: f32) -> [Vec2; 3] {
+    let k = 4.0 / 3.0 * (sweep / 4.0).tan();
+    let from = Vec2::from_angle(start);
+    let to = Vec2::from_angle(start + sweep);
+
+    [from + from.perp() * k, to - to.perp() * k, to]
+}
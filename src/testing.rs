@@ -0,0 +1,63 @@
+// helpers for tests that need a gpu. those tests are marked
+// #[ignore = "needs a GPU adapter"], run them with `cargo test -- --ignored`
+
+pub fn headless_device() -> (wgpu::Device, wgpu::Queue) {
+    let instance = wgpu::Instance::new(Default::default());
+    let adapter = pollster::block_on(instance.request_adapter(&Default::default()))
+        .expect("no GPU adapter available");
+
+    let req_device = adapter.request_device(
+        &wgpu::DeviceDescriptor {
+            label: None,
+            required_features: wgpu::Features::empty(),
+            required_limits: wgpu::Limits::downlevel_defaults(),
+            memory_hints: Default::default(),
+        },
+        None,
+    );
+
+    pollster::block_on(req_device).expect("create device failed")
+}
+
+// copies a single layer, single mip texture into a tightly packed vec
+pub fn read_texture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    mut encoder: wgpu::CommandEncoder,
+    texture: &wgpu::Texture,
+) -> Vec<u8> {
+    let block_size = texture.format().block_copy_size(None).unwrap();
+    let row_size = texture.width() * block_size;
+    let padded_row_size = row_size.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("readback buffer"),
+        size: (padded_row_size * texture.height()) as _,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    encoder.copy_texture_to_buffer(
+        texture.as_image_copy(),
+        wgpu::ImageCopyBuffer {
+            buffer: &buffer,
+            layout: wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_row_size),
+                rows_per_image: None,
+            },
+        },
+        texture.size(),
+    );
+    queue.submit(Some(encoder.finish()));
+
+    let slice = buffer.slice(..);
+    slice.map_async(wgpu::MapMode::Read, |result| result.unwrap());
+    device.poll(wgpu::Maintain::Wait);
+
+    let data = slice.get_mapped_range();
+    data.chunks(padded_row_size as _)
+        .flat_map(|row| &row[..row_size as usize])
+        .copied()
+        .collect()
+}
@@ -0,0 +1,85 @@
+use ab_glyph::{Font as _, FontArc, GlyphId, PxScale, PxScaleFont, ScaleFont};
+
+use super::layout::Metrics;
+
+// a coverage bitmap of one glyph, `offset` goes from the pen position on the
+// baseline to its top left corner
+pub struct RasterizedGlyph {
+    pub width: u32,
+    pub height: u32,
+    pub offset: [f32; 2],
+    pub coverage: Vec<u8>,
+}
+
+// a truetype or opentype font. cheap to clone, the font data is shared
+#[derive(Clone)]
+pub struct Font {
+    font: FontArc,
+}
+
+impl Default for Font {
+    // dejavu sans, bundled so text works without any files around
+    fn default() -> Self {
+        Self::from_bytes(include_bytes!("DejaVuSans.ttf").to_vec()).unwrap()
+    }
+}
+
+impl Font {
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self, String> {
+        let font = FontArc::try_from_vec(bytes).map_err(|e| format!("invalid font: {e}"))?;
+
+        Ok(Self { font })
+    }
+
+    // `size` is the distance from the highest ascender to the lowest
+    // descender in pixels
+    pub fn scaled(&self, size: f32) -> ScaledFont<'_> {
+        ScaledFont(self.font.as_scaled(PxScale::from(size)))
+    }
+}
+
+pub struct ScaledFont<'a>(PxScaleFont<&'a FontArc>);
+
+impl ScaledFont<'_> {
+    // none for glyphs without an outline, like spaces
+    pub fn rasterize(&self, glyph: GlyphId) -> Option<RasterizedGlyph> {
+        let outlined = self.0.outline_glyph(glyph.with_scale(self.0.scale))?;
+        let bounds = outlined.px_bounds();
+
+        let width = bounds.width() as u32;
+        let height = bounds.height() as u32;
+        let mut coverage = vec![0; (width * height) as usize];
+        outlined.draw(|x, y, value| {
+            coverage[(y * width + x) as usize] = (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+        });
+
+        Some(RasterizedGlyph {
+            width,
+            height,
+            offset: [bounds.min.x, bounds.min.y],
+            coverage,
+        })
+    }
+}
+
+impl Metrics for ScaledFont<'_> {
+    fn glyph_id(&self, c: char) -> GlyphId {
+        self.0.glyph_id(c)
+    }
+
+    fn advance(&self, glyph: GlyphId) -> f32 {
+        self.0.h_advance(glyph)
+    }
+
+    fn kern(&self, first: GlyphId, second: GlyphId) -> f32 {
+        self.0.kern(first, second)
+    }
+
+    fn ascent(&self) -> f32 {
+        self.0.ascent()
+    }
+
+    fn line_height(&self) -> f32 {
+        self.0.ascent() - self.0.descent() + self.0.line_gap()
+    }
+}
@@ -0,0 +1,388 @@
+use std::ops::Range;
+
+use ab_glyph::GlyphId;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Align {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+// what laying out text needs to know about a font at one size, in pixels
+pub trait Metrics {
+    fn glyph_id(&self, c: char) -> GlyphId;
+    fn advance(&self, glyph: GlyphId) -> f32;
+    fn kern(&self, first: GlyphId, second: GlyphId) -> f32;
+    // from the top of a line down to its baseline
+    fn ascent(&self) -> f32;
+    // from one baseline to the next
+    fn line_height(&self) -> f32;
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct LayoutOptions {
+    // longer lines wrap, after whitespace where possible and inside a word
+    // when it doesn't fit a line on its own
+    pub max_width: Option<f32>,
+    // lines are aligned within `max_width`, or the widest line without one
+    pub align: Align,
+    // scales the font's line height
+    pub line_spacing: f32,
+}
+
+impl Default for LayoutOptions {
+    fn default() -> Self {
+        Self {
+            max_width: None,
+            align: Align::Left,
+            line_spacing: 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PositionedGlyph {
+    pub glyph: GlyphId,
+    // the pen position on the baseline, relative to the top left of the text
+    pub x: f32,
+    pub y: f32,
+    // byte offset of the character in the text
+    pub index: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TextLayout {
+    pub glyphs: Vec<PositionedGlyph>,
+    pub width: f32,
+    pub height: f32,
+    pub line_count: usize,
+}
+
+#[derive(Clone, Copy)]
+struct Item {
+    index: usize,
+    whitespace: bool,
+    glyph: GlyphId,
+}
+
+struct Line {
+    glyphs: Vec<PositionedGlyph>,
+    // trailing whitespace doesn't count
+    width: f32,
+}
+
+pub fn layout(metrics: &impl Metrics, text: &str, options: &LayoutOptions) -> TextLayout {
+    let mut lines = Vec::new();
+    let mut offset = 0;
+
+    for paragraph in text.split('\n') {
+        let items: Vec<_> = paragraph
+            .char_indices()
+            .filter(|&(_, c)| c != '\r')
+            .map(|(index, c)| Item {
+                index: offset + index,
+                whitespace: c.is_whitespace(),
+                glyph: metrics.glyph_id(c),
+            })
+            .collect();
+        offset += paragraph.len() + 1;
+
+        for range in break_lines(metrics, &items, options.max_width) {
+            lines.push(place(metrics, &items[range]));
+        }
+    }
+
+    let widest = lines.iter().map(|line| line.width).fold(0.0, f32::max);
+    let align_width = options.max_width.unwrap_or(widest);
+    let line_advance = metrics.line_height() * options.line_spacing;
+
+    let mut glyphs = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        let x = match options.align {
+            Align::Left => 0.0,
+            Align::Center => (align_width - line.width) / 2.0,
+            Align::Right => align_width - line.width,
+        };
+        let y = metrics.ascent() + i as f32 * line_advance;
+
+        glyphs.extend(line.glyphs.iter().map(|glyph| PositionedGlyph {
+            x: glyph.x + x,
+            y,
+            ..*glyph
+        }));
+    }
+
+    TextLayout {
+        glyphs,
+        width: widest,
+        height: lines.len() as f32 * line_advance,
+        line_count: lines.len(),
+    }
+}
+
+// greedy breaking, every line takes as many items as fit
+fn break_lines(
+    metrics: &impl Metrics,
+    items: &[Item],
+    max_width: Option<f32>,
+) -> Vec<Range<usize>> {
+    let mut lines = Vec::new();
+    let mut start = 0;
+
+    // runs at least once, an empty paragraph still takes up a line
+    loop {
+        let mut end = start;
+        let mut pen = 0.0;
+        let mut previous = None;
+        // index of the first item after the last whitespace
+        let mut last_break = None;
+
+        while let Some(item) = items.get(end) {
+            let kern = previous.map_or(0.0, |previous| metrics.kern(previous, item.glyph));
+            let right = pen + kern + metrics.advance(item.glyph);
+
+            // whitespace may hang past the edge, the first item always fits
+            let overflows = max_width.is_some_and(|max_width| right > max_width);
+            if overflows && !item.whitespace && end > start {
+                break;
+            }
+
+            if item.whitespace {
+                last_break = Some(end + 1);
+            }
+
+            pen = right;
+            previous = Some(item.glyph);
+            end += 1;
+        }
+
+        if end < items.len() {
+            end = last_break.unwrap_or(end);
+        }
+
+        lines.push(start..end);
+        start = end;
+
+        if start >= items.len() {
+            return lines;
+        }
+    }
+}
+
+fn place(metrics: &impl Metrics, items: &[Item]) -> Line {
+    let mut glyphs = Vec::with_capacity(items.len());
+    let mut pen = 0.0;
+    let mut width = 0.0;
+    let mut previous = None;
+
+    for item in items {
+        if let Some(previous) = previous {
+            pen += metrics.kern(previous, item.glyph);
+        }
+
+        glyphs.push(PositionedGlyph {
+            glyph: item.glyph,
+            x: pen,
+            y: 0.0,
+            index: item.index,
+        });
+
+        pen += metrics.advance(item.glyph);
+        if !item.whitespace {
+            width = pen;
+        }
+        previous = Some(item.glyph);
+    }
+
+    Line { glyphs, width }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // every character is 10 wide except 'i', and "AV" kerns by -3
+    struct Monospace;
+
+    impl Metrics for Monospace {
+        fn glyph_id(&self, c: char) -> GlyphId {
+            GlyphId(c as u16)
+        }
+
+        fn advance(&self, glyph: GlyphId) -> f32 {
+            if glyph == GlyphId('i' as u16) {
+                4.0
+            } else {
+                10.0
+            }
+        }
+
+        fn kern(&self, first: GlyphId, second: GlyphId) -> f32 {
+            if (first, second) == (GlyphId('A' as u16), GlyphId('V' as u16)) {
+                -3.0
+            } else {
+                0.0
+            }
+        }
+
+        fn ascent(&self) -> f32 {
+            15.0
+        }
+
+        fn line_height(&self) -> f32 {
+            20.0
+        }
+    }
+
+    fn wrapped(width: f32, align: Align) -> LayoutOptions {
+        LayoutOptions {
+            max_width: Some(width),
+            align,
+            ..Default::default()
+        }
+    }
+
+    // the text of every line, rebuilt from the glyph positions
+    fn lines(text: &str, layout: &TextLayout) -> Vec<String> {
+        let mut lines: Vec<(f32, String)> = Vec::new();
+        for glyph in &layout.glyphs {
+            match lines.last_mut() {
+                Some((y, line)) if *y == glyph.y => line.push_str(&text[glyph.index..][..1]),
+                _ => lines.push((glyph.y, text[glyph.index..][..1].to_owned())),
+            }
+        }
+
+        lines.into_iter().map(|(_, line)| line).collect()
+    }
+
+    fn xs(layout: &TextLayout) -> Vec<f32> {
+        layout.glyphs.iter().map(|glyph| glyph.x).collect()
+    }
+
+    #[test]
+    fn advances_the_pen_by_each_glyph() {
+        let layout = layout(&Monospace, "hint", &LayoutOptions::default());
+
+        assert_eq!(xs(&layout), [0.0, 10.0, 14.0, 24.0]);
+        assert_eq!(layout.width, 34.0);
+        assert_eq!(layout.line_count, 1);
+    }
+
+    #[test]
+    fn applies_kerning_between_pairs() {
+        let layout = layout(&Monospace, "AVA", &LayoutOptions::default());
+
+        assert_eq!(xs(&layout), [0.0, 7.0, 17.0]);
+        assert_eq!(layout.width, 27.0);
+    }
+
+    #[test]
+    fn puts_glyphs_on_the_baseline() {
+        let layout = layout(&Monospace, "a\nb", &LayoutOptions::default());
+
+        let ys: Vec<_> = layout.glyphs.iter().map(|glyph| glyph.y).collect();
+        assert_eq!(ys, [15.0, 35.0]);
+        assert_eq!(layout.height, 40.0);
+    }
+
+    #[test]
+    fn scales_the_line_height_by_the_spacing() {
+        let options = LayoutOptions {
+            line_spacing: 1.5,
+            ..Default::default()
+        };
+        let layout = layout(&Monospace, "a\nb", &options);
+
+        assert_eq!(layout.glyphs[1].y, 45.0);
+        assert_eq!(layout.height, 60.0);
+    }
+
+    #[test]
+    fn breaks_lines_at_newlines() {
+        let text = "one\n\ntwo\r\nthree";
+        let layout = layout(&Monospace, text, &LayoutOptions::default());
+
+        assert_eq!(layout.line_count, 4);
+        assert_eq!(lines(text, &layout), ["one", "two", "three"]);
+        assert_eq!(layout.width, 50.0);
+    }
+
+    #[test]
+    fn wraps_after_whitespace() {
+        let text = "aaa bbb ccc";
+        let layout = layout(&Monospace, text, &wrapped(75.0, Align::Left));
+
+        assert_eq!(lines(text, &layout), ["aaa bbb ", "ccc"]);
+        assert_eq!(layout.width, 70.0);
+        assert_eq!(layout.glyphs[8].x, 0.0);
+    }
+
+    #[test]
+    fn lets_whitespace_hang_past_the_edge() {
+        let text = "aaa   bbb";
+        let layout = layout(&Monospace, text, &wrapped(30.0, Align::Left));
+
+        assert_eq!(lines(text, &layout), ["aaa   ", "bbb"]);
+    }
+
+    #[test]
+    fn splits_words_longer_than_a_line() {
+        let text = "aaaaaaa bb";
+        let layout = layout(&Monospace, text, &wrapped(30.0, Align::Left));
+
+        assert_eq!(lines(text, &layout), ["aaa", "aaa", "a ", "bb"]);
+    }
+
+    #[test]
+    fn keeps_a_glyph_wider_than_the_line() {
+        let text = "ab";
+        let layout = layout(&Monospace, text, &wrapped(5.0, Align::Left));
+
+        assert_eq!(lines(text, &layout), ["a", "b"]);
+    }
+
+    #[test]
+    fn aligns_within_the_wrap_width() {
+        let text = "aa bbbb";
+
+        let center = layout(&Monospace, text, &wrapped(60.0, Align::Center));
+        assert_eq!(xs(&center)[0], 20.0);
+        assert_eq!(xs(&center)[3], 10.0);
+
+        let right = layout(&Monospace, text, &wrapped(60.0, Align::Right));
+        assert_eq!(xs(&right)[0], 40.0);
+        assert_eq!(xs(&right)[3], 20.0);
+    }
+
+    #[test]
+    fn aligns_within_the_widest_line_without_wrapping() {
+        let text = "a\nbbb";
+        let options = LayoutOptions {
+            align: Align::Right,
+            ..Default::default()
+        };
+        let layout = layout(&Monospace, text, &options);
+
+        assert_eq!(xs(&layout), [20.0, 0.0, 10.0, 20.0]);
+    }
+
+    #[test]
+    fn records_byte_offsets_of_characters() {
+        let text = "é\nx";
+        let layout = layout(&Monospace, text, &LayoutOptions::default());
+
+        let indices: Vec<_> = layout.glyphs.iter().map(|glyph| glyph.index).collect();
+        assert_eq!(indices, [0, 3]);
+    }
+
+    #[test]
+    fn lays_out_empty_text_as_one_empty_line() {
+        let layout = layout(&Monospace, "", &LayoutOptions::default());
+
+        assert!(layout.glyphs.is_empty());
+        assert_eq!(layout.line_count, 1);
+        assert_eq!(layout.width, 0.0);
+    }
+}
@@ -0,0 +1,418 @@
+use std::{collections::HashMap, ops::Range};
+
+use ab_glyph::GlyphId;
+use glam::Vec2;
+use wgpu::{include_wgsl, util::DeviceExt};
+use winit::dpi::PhysicalSize;
+
+use crate::{
+    sandbox::camera2d::camera::Camera2D,
+    texture::{
+        atlas::{AtlasOptions, AtlasRegion, TextureAtlas},
+        decode::{DecodedImage, Pixels},
+        sampler::SamplerPreset,
+        ColorSpace, Mipmaps, Texture, TextureOptions,
+    },
+    vertices::{Vertex, VertexPosTexCol},
+};
+use font::ScaledFont;
+
+pub use font::Font;
+pub use layout::{Align, LayoutOptions, TextLayout};
+
+pub mod font;
+pub mod layout;
+pub mod sdf;
+
+// screen space is in pixels from the top left corner of the window, world
+// space goes through the camera given to `set_camera`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Space {
+    Screen,
+    World,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TextSection<'a> {
+    pub text: &'a str,
+    // the top left corner of the text
+    pub position: Vec2,
+    // see `Font::scaled`
+    pub size: f32,
+    pub color: [f32; 4],
+    pub layout: LayoutOptions,
+    pub space: Space,
+}
+
+impl Default for TextSection<'_> {
+    fn default() -> Self {
+        Self {
+            text: "",
+            position: Vec2::ZERO,
+            size: 20.0,
+            color: [1.0; 4],
+            layout: LayoutOptions::default(),
+            space: Space::Screen,
+        }
+    }
+}
+
+// a view projection uniform for each space
+struct Cameras {
+    screen_buffer: wgpu::Buffer,
+    world_buffer: wgpu::Buffer,
+    screen_bind_group: wgpu::BindGroup,
+    world_bind_group: wgpu::BindGroup,
+}
+
+impl Cameras {
+    fn new(device: &wgpu::Device, layout: &wgpu::BindGroupLayout, label: &str) -> Self {
+        let buffer = |space| {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(&format!("{label} {space} buffer")),
+                size: size_of::<[[f32; 4]; 4]>() as _,
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            })
+        };
+        let bind_group = |buffer: &wgpu::Buffer| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some(&format!("{label} camera bind group")),
+                layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: buffer.as_entire_binding(),
+                }],
+            })
+        };
+
+        let screen_buffer = buffer("screen");
+        let world_buffer = buffer("world");
+
+        Self {
+            screen_bind_group: bind_group(&screen_buffer),
+            world_bind_group: bind_group(&world_buffer),
+            screen_buffer,
+            world_buffer,
+        }
+    }
+
+    fn resize(&self, size: PhysicalSize<u32>, queue: &wgpu::Queue) {
+        let screen = Camera2D::new(size.width.max(1), size.height.max(1), 1.0);
+
+        queue.write_buffer(
+            &self.screen_buffer,
+            0,
+            bytemuck::cast_slice(&screen.camera_matrix().to_cols_array_2d()),
+        );
+    }
+
+    fn set_camera(&self, camera: &Camera2D, queue: &wgpu::Queue) {
+        queue.write_buffer(
+            &self.world_buffer,
+            0,
+            bytemuck::cast_slice(&camera.camera_matrix().to_cols_array_2d()),
+        );
+    }
+
+    fn bind_group(&self, space: Space) -> &wgpu::BindGroup {
+        match space {
+            Space::Screen => &self.screen_bind_group,
+            Space::World => &self.world_bind_group,
+        }
+    }
+}
+
+struct CachedGlyph {
+    region: AtlasRegion,
+    offset: [f32; 2],
+}
+
+struct Quad {
+    space: Space,
+    page: usize,
+    vertices: [VertexPosTexCol; 4],
+}
+
+struct Batch {
+    space: Space,
+    page: usize,
+    indices: Range<u32>,
+}
+
+// draws text from a glyph atlas. glyphs are rasterized the first time they
+// show up at a size and stay cached, sections pushed during a frame are
+// batched into as few draws as there are atlas pages per space
+pub struct TextRenderer {
+    font: Font,
+    pipeline: wgpu::RenderPipeline,
+    atlas: TextureAtlas,
+    // keyed by glyph and size bits, none for glyphs with nothing to draw
+    glyphs: HashMap<(GlyphId, u32), Option<CachedGlyph>>,
+    texture_layout: wgpu::BindGroupLayout,
+    page_bind_groups: Vec<wgpu::BindGroup>,
+    cameras: Cameras,
+    vertex_buffer: wgpu::Buffer,
+    index_buffer: wgpu::Buffer,
+    // in quads
+    capacity: usize,
+    quads: Vec<Quad>,
+    batches: Vec<Batch>,
+}
+
+impl TextRenderer {
+    pub fn new(device: &wgpu::Device, view_format: wgpu::TextureFormat, font: Font) -> Self {
+        let shader = device.create_shader_module(include_wgsl!("shader.wgsl"));
+
+        let camera_layout = Camera2D::bind_group_layout(device);
+        let texture_layout = Texture::bind_group_layout(device);
+
+        let cameras = Cameras::new(device, &camera_layout, "text");
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("text pipeline layout"),
+            bind_group_layouts: &[&camera_layout, &texture_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("text pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[VertexPosTexCol::desc()],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: view_format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            multisample: Default::default(),
+            depth_stencil: None,
+            multiview: None,
+            cache: None,
+        });
+
+        // glyphs are drawn at their rasterized size, so no mipmaps and no
+        // extruded edges, the padding between them is enough to keep bilinear
+        // filtering from bleeding
+        let atlas = TextureAtlas::new(AtlasOptions {
+            page_size: 1024,
+            padding: 1,
+            extrude: 0,
+            texture: TextureOptions {
+                mipmaps: Mipmaps::None,
+                sampler: SamplerPreset::Bilinear,
+                color_space: ColorSpace::Linear,
+            },
+        });
+
+        let capacity = 256;
+        let (vertex_buffer, index_buffer) = quad_buffers(device, capacity);
+
+        Self {
+            font,
+            pipeline,
+            atlas,
+            glyphs: HashMap::new(),
+            texture_layout,
+            page_bind_groups: Vec::new(),
+            cameras,
+            vertex_buffer,
+            index_buffer,
+            capacity,
+            quads: Vec::new(),
+            batches: Vec::new(),
+        }
+    }
+
+    pub fn font(&self) -> &Font {
+        &self.font
+    }
+
+    pub fn measure(&self, section: &TextSection) -> TextLayout {
+        layout::layout(
+            &self.font.scaled(section.size),
+            section.text,
+            &section.layout,
+        )
+    }
+
+    pub fn resize(&self, size: PhysicalSize<u32>, queue: &wgpu::Queue) {
+        self.cameras.resize(size, queue);
+    }
+
+    pub fn set_camera(&self, camera: &Camera2D, queue: &wgpu::Queue) {
+        self.cameras.set_camera(camera, queue);
+    }
+
+    // lays out a section for the next `prepare`, the layout is returned for
+    // placing things around the text
+    pub fn push(&mut self, section: &TextSection) -> TextLayout {
+        let scaled = self.font.scaled(section.size);
+        let layout = layout::layout(&scaled, section.text, &section.layout);
+
+        for glyph in &layout.glyphs {
+            let key = (glyph.glyph, section.size.to_bits());
+            let cached = self
+                .glyphs
+                .entry(key)
+                .or_insert_with(|| cache_glyph(&mut self.atlas, &scaled, glyph.glyph, key.1));
+
+            let Some(CachedGlyph { region, offset }) = cached else {
+                continue;
+            };
+
+            let mut top_left = section.position + Vec2::new(glyph.x, glyph.y) + Vec2::from(*offset);
+            // whole pixels keep glyphs from getting blurred by filtering
+            if section.space == Space::Screen {
+                top_left = top_left.round();
+            }
+            let bottom_right = top_left + Vec2::new(region.size.0 as f32, region.size.1 as f32);
+
+            let vertex = |x: f32, y: f32, u: f32, v: f32| VertexPosTexCol {
+                position: [x, y],
+                texture_coord: [u, v],
+                color: section.color,
+            };
+            let (min, max) = (region.uv_min, region.uv_max);
+
+            self.quads.push(Quad {
+                space: section.space,
+                page: region.page,
+                vertices: [
+                    vertex(top_left.x, top_left.y, min[0], min[1]),
+                    vertex(bottom_right.x, top_left.y, max[0], min[1]),
+                    vertex(top_left.x, bottom_right.y, min[0], max[1]),
+                    vertex(bottom_right.x, bottom_right.y, max[0], max[1]),
+                ],
+            });
+        }
+
+        layout
+    }
+
+    // uploads new glyphs and everything pushed since the last call, which is
+    // what `render` draws until the next one
+    pub fn prepare(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        if self.atlas.upload(device, queue) {
+            self.page_bind_groups = (0..self.atlas.page_count())
+                .map(|page| {
+                    self.atlas
+                        .page(page)
+                        .unwrap()
+                        .bind_group(device, &self.texture_layout)
+                })
+                .collect();
+        }
+
+        if self.quads.len() > self.capacity {
+            self.capacity = self.quads.len().next_power_of_two();
+            (self.vertex_buffer, self.index_buffer) = quad_buffers(device, self.capacity);
+        }
+
+        self.quads.sort_by_key(|quad| (quad.space, quad.page));
+
+        self.batches.clear();
+        for (i, quad) in self.quads.iter().enumerate() {
+            let indices = i as u32 * 6..i as u32 * 6 + 6;
+
+            match self.batches.last_mut() {
+                Some(batch) if (batch.space, batch.page) == (quad.space, quad.page) => {
+                    batch.indices.end = indices.end;
+                }
+                _ => self.batches.push(Batch {
+                    space: quad.space,
+                    page: quad.page,
+                    indices,
+                }),
+            }
+        }
+
+        let vertices: Vec<_> = self
+            .quads
+            .drain(..)
+            .flat_map(|quad| quad.vertices)
+            .collect();
+        queue.write_buffer(&self.vertex_buffer, 0, bytemuck::cast_slice(&vertices));
+    }
+
+    pub fn render(&self, render_pass: &mut wgpu::RenderPass) {
+        if self.batches.is_empty() {
+            return;
+        }
+
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+
+        for batch in &self.batches {
+            render_pass.set_bind_group(0, self.cameras.bind_group(batch.space), &[]);
+            render_pass.set_bind_group(1, &self.page_bind_groups[batch.page], &[]);
+            render_pass.draw_indexed(batch.indices.clone(), 0, 0..1);
+        }
+    }
+}
+
+fn cache_glyph(
+    atlas: &mut TextureAtlas,
+    font: &ScaledFont,
+    glyph: GlyphId,
+    size_bits: u32,
+) -> Option<CachedGlyph> {
+    let rasterized = font.rasterize(glyph)?;
+    if rasterized.width == 0 || rasterized.height == 0 {
+        return None;
+    }
+
+    // white texels with the coverage in alpha
+    let image = DecodedImage {
+        width: rasterized.width,
+        height: rasterized.height,
+        pixels: Pixels::Rgba8(
+            rasterized
+                .coverage
+                .iter()
+                .flat_map(|&coverage| [255, 255, 255, coverage])
+                .collect(),
+        ),
+    };
+
+    let region = atlas
+        .add(format!("{}@{size_bits:x}", glyph.0), &image)
+        .inspect_err(|e| log::error!("failed to cache glyph: {e}"))
+        .ok()?;
+
+    Some(CachedGlyph {
+        region,
+        offset: rasterized.offset,
+    })
+}
+
+fn quad_buffers(device: &wgpu::Device, capacity: usize) -> (wgpu::Buffer, wgpu::Buffer) {
+    let vertex_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("text vertex buffer"),
+        size: (capacity * 4 * size_of::<VertexPosTexCol>()) as _,
+        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+
+    let indices: Vec<u32> = (0..capacity as u32)
+        .flat_map(|i| [0, 2, 1, 1, 2, 3].map(|index| i * 4 + index))
+        .collect();
+    let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("text index buffer"),
+        contents: bytemuck::cast_slice(&indices),
+        usage: wgpu::BufferUsages::INDEX,
+    });
+
+    (vertex_buffer, index_buffer)
+}
@@ -0,0 +1,180 @@
+// signed distance fields from coverage bitmaps, the approach of mapbox's
+// tiny-sdf: partially covered texels seed sub-texel distances to the edge,
+// then an exact euclidean distance transform spreads them out
+
+const INF: f64 = 1e20;
+
+// pads the bitmap by `spread` texels on every side and encodes the distance
+// to the outline as 0.5 + distance / (2 * spread), positive inside. the full
+// 0..1 range covers 2 * spread texels
+pub fn distance_field(coverage: &[u8], width: u32, height: u32, spread: u32) -> Vec<u8> {
+    let padded_width = (width + spread * 2) as usize;
+    let padded_height = (height + spread * 2) as usize;
+    let len = padded_width * padded_height;
+
+    // squared distances to the nearest texel inside and outside the glyph
+    let mut outer = vec![INF; len];
+    let mut inner = vec![0.0; len];
+
+    for y in 0..height as usize {
+        for x in 0..width as usize {
+            let a = coverage[y * width as usize + x] as f64 / 255.0;
+            if a == 0.0 {
+                continue;
+            }
+
+            let i = (y + spread as usize) * padded_width + x + spread as usize;
+            if a == 1.0 {
+                outer[i] = 0.0;
+                inner[i] = INF;
+            } else {
+                // the edge runs through the texel at 50% coverage
+                let d = 0.5 - a;
+                outer[i] = if d > 0.0 { d * d } else { 0.0 };
+                inner[i] = if d < 0.0 { d * d } else { 0.0 };
+            }
+        }
+    }
+
+    let mut scratch = Scratch::new(padded_width.max(padded_height));
+    transform(&mut outer, padded_width, padded_height, &mut scratch);
+    transform(&mut inner, padded_width, padded_height, &mut scratch);
+
+    outer
+        .iter()
+        .zip(&inner)
+        .map(|(outer, inner)| {
+            let inside = inner.sqrt() - outer.sqrt();
+            let value = 0.5 + inside / (2.0 * spread as f64);
+            (value.clamp(0.0, 1.0) * 255.0).round() as u8
+        })
+        .collect()
+}
+
+struct Scratch {
+    f: Vec<f64>,
+    v: Vec<usize>,
+    z: Vec<f64>,
+}
+
+impl Scratch {
+    fn new(len: usize) -> Self {
+        Self {
+            f: vec![0.0; len],
+            v: vec![0; len],
+            z: vec![0.0; len + 1],
+        }
+    }
+}
+
+// 2d squared distance transform, columns then rows
+fn transform(grid: &mut [f64], width: usize, height: usize, scratch: &mut Scratch) {
+    for x in 0..width {
+        transform_1d(grid, x, width, height, scratch);
+    }
+    for y in 0..height {
+        transform_1d(grid, y * width, 1, width, scratch);
+    }
+}
+
+// felzenszwalb and huttenlocher, the lower envelope of the parabolas rooted at
+// every sample
+fn transform_1d(grid: &mut [f64], offset: usize, stride: usize, len: usize, scratch: &mut Scratch) {
+    let Scratch { f, v, z } = scratch;
+
+    v[0] = 0;
+    z[0] = -INF;
+    z[1] = INF;
+    f[0] = grid[offset];
+
+    let mut k = 0;
+    for q in 1..len {
+        f[q] = grid[offset + q * stride];
+
+        // drop parabolas hidden by the new one, z[0] is -inf so k stays valid
+        let qf = q as f64;
+        let s = loop {
+            let r = v[k] as f64;
+            let s = (f[q] - f[v[k]] + qf * qf - r * r) / (qf - r) / 2.0;
+            if s > z[k] {
+                break s;
+            }
+            k -= 1;
+        };
+
+        k += 1;
+        v[k] = q;
+        z[k] = s;
+        z[k + 1] = INF;
+    }
+
+    k = 0;
+    for q in 0..len {
+        while z[k + 1] < q as f64 {
+            k += 1;
+        }
+
+        let r = v[k];
+        let d = q as f64 - r as f64;
+        grid[offset + q * stride] = f[r] + d * d;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a 10x10 filled square in a 20x20 bitmap
+    fn square() -> Vec<u8> {
+        let mut coverage = vec![0; 400];
+        for y in 5..15 {
+            for x in 5..15 {
+                coverage[y * 20 + x] = 255;
+            }
+        }
+        coverage
+    }
+
+    #[test]
+    fn grows_the_bitmap_by_the_spread() {
+        let field = distance_field(&square(), 20, 20, 4);
+
+        assert_eq!(field.len(), 28 * 28);
+    }
+
+    #[test]
+    fn is_half_at_the_edge_and_rises_inside() {
+        let field = distance_field(&square(), 20, 20, 4);
+        let row = |y: usize| &field[y * 28..(y + 1) * 28];
+
+        // the middle row crosses the square from texel 9 to 18 of the padded
+        // bitmap, its edges lie between those and their outside neighbours
+        let middle = row(14);
+        assert!(middle[8] < 128 && middle[9] > 128, "{middle:?}");
+        assert!(middle[18] > 128 && middle[19] < 128, "{middle:?}");
+        assert!(middle[11] > middle[10] && middle[10] > middle[9]);
+        assert_eq!(middle[8], 255 - middle[9]);
+    }
+
+    #[test]
+    fn measures_euclidean_distance_outside() {
+        let field = distance_field(&square(), 20, 20, 4);
+
+        // two texels left of the square
+        let expected = 0.5 - 2.0 / 8.0;
+        assert_eq!(field[14 * 28 + 7], (expected * 255.0_f64).round() as u8);
+
+        // diagonal from the corner texel (9, 9), sqrt(2) * 2 texels from it
+        let corner = 0.5 - 2.0 * 2.0_f64.sqrt() / 8.0;
+        let value = field[7 * 28 + 7] as f64 / 255.0;
+        assert!((value - corner).abs() < 0.03, "{value} vs {corner}");
+    }
+
+    #[test]
+    fn clamps_beyond_the_spread() {
+        let field = distance_field(&square(), 20, 20, 2);
+
+        assert_eq!(field[0], 0);
+        assert_eq!(field[12 * 24 + 12], 255);
+    }
+}
@@ -0,0 +1,293 @@
+use std::collections::HashMap;
+
+use ab_glyph::GlyphId;
+
+use super::{
+    distance::distance_field,
+    msdf::{FieldType, MsdfAtlas},
+};
+use crate::{
+    text::{
+        font::{Font, ScaledFont},
+        layout::Metrics,
+    },
+    texture::{
+        atlas::{AtlasOptions, TextureAtlas},
+        decode::{DecodedImage, Pixels},
+        sampler::SamplerPreset,
+        ColorSpace, Mipmaps, Texture, TextureOptions,
+    },
+};
+
+// size glyphs are rasterized at before their distance field is taken. fields
+// reach SPREAD texels past the outline, which bounds how far outlines, glows
+// and shadows can extend: SPREAD / SIZE of the text size
+const SIZE: f32 = 48.0;
+const SPREAD: u32 = 8;
+
+const OPTIONS: TextureOptions = TextureOptions {
+    mipmaps: Mipmaps::None,
+    sampler: SamplerPreset::Bilinear,
+    color_space: ColorSpace::Linear,
+};
+
+// a glyph quad in units of the text size, x right and y down from the pen
+// position on the baseline
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SdfGlyph {
+    pub page: usize,
+    pub uv_min: [f32; 2],
+    pub uv_max: [f32; 2],
+    pub offset: [f32; 2],
+    pub size: [f32; 2],
+}
+
+enum Source {
+    // distance fields made from the outlines the first time a glyph is used
+    Outline {
+        font: Font,
+        atlas: TextureAtlas,
+    },
+    Msdf {
+        atlas: MsdfAtlas,
+        // uploaded on the first `upload`
+        image: Option<DecodedImage>,
+        texture: Option<Texture>,
+    },
+}
+
+// glyph distance fields, either generated from a truetype / opentype font or
+// loaded from an msdf-atlas-gen atlas. sizes follow `Font::scaled`, ascender
+// to descender
+pub struct SdfFont {
+    source: Source,
+    glyphs: HashMap<GlyphId, Option<SdfGlyph>>,
+}
+
+impl SdfFont {
+    pub fn from_font(font: Font) -> Self {
+        let atlas = TextureAtlas::new(AtlasOptions {
+            page_size: 1024,
+            padding: 1,
+            extrude: 0,
+            texture: OPTIONS,
+        });
+
+        Self {
+            source: Source::Outline { font, atlas },
+            glyphs: HashMap::new(),
+        }
+    }
+
+    pub fn from_msdf(json: &str, image: DecodedImage) -> Result<Self, String> {
+        let atlas = MsdfAtlas::from_json(json)?;
+
+        if (image.width, image.height) != (atlas.width, atlas.height) {
+            return Err(format!(
+                "msdf atlas image is {}x{}, the json describes {}x{}",
+                image.width, image.height, atlas.width, atlas.height
+            ));
+        }
+        if atlas.field_type == FieldType::Mtsdf {
+            log::info!("mtsdf atlas, the true distance in alpha goes unused");
+        }
+
+        Ok(Self {
+            source: Source::Msdf {
+                atlas,
+                image: Some(image),
+                texture: None,
+            },
+            glyphs: HashMap::new(),
+        })
+    }
+
+    // the distance covered by the 0..1 range of field values, in units of the
+    // text size
+    pub fn distance_range(&self) -> f32 {
+        match &self.source {
+            Source::Outline { .. } => (SPREAD * 2) as f32 / SIZE,
+            Source::Msdf { atlas, .. } => {
+                atlas.distance_range / atlas.texels_per_em / (atlas.ascender - atlas.descender)
+            }
+        }
+    }
+
+    pub fn metrics(&self, size: f32) -> SdfMetrics<'_> {
+        match &self.source {
+            Source::Outline { font, .. } => SdfMetrics::Outline(font.scaled(size)),
+            Source::Msdf { atlas, .. } => SdfMetrics::Msdf {
+                atlas,
+                pixels_per_em: size / (atlas.ascender - atlas.descender),
+            },
+        }
+    }
+
+    // none for glyphs with nothing to draw
+    pub fn glyph(&mut self, glyph: GlyphId) -> Option<SdfGlyph> {
+        if let Some(cached) = self.glyphs.get(&glyph) {
+            return *cached;
+        }
+
+        let sdf_glyph = match &mut self.source {
+            Source::Outline { font, atlas } => generate(font, atlas, glyph),
+            Source::Msdf { atlas, .. } => msdf_glyph(atlas, glyph),
+        };
+        self.glyphs.insert(glyph, sdf_glyph);
+
+        sdf_glyph
+    }
+
+    // returns true when new pages were created
+    pub fn upload(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) -> bool {
+        match &mut self.source {
+            Source::Outline { atlas, .. } => atlas.upload(device, queue),
+            Source::Msdf { image, texture, .. } => match image.take() {
+                Some(image) => {
+                    *texture = Some(Texture::from_image(device, queue, &image, OPTIONS));
+                    true
+                }
+                None => false,
+            },
+        }
+    }
+
+    pub fn page_count(&self) -> usize {
+        match &self.source {
+            Source::Outline { atlas, .. } => atlas.page_count(),
+            Source::Msdf { .. } => 1,
+        }
+    }
+
+    // only available once uploaded
+    pub fn page(&self, index: usize) -> Option<&Texture> {
+        match &self.source {
+            Source::Outline { atlas, .. } => atlas.page(index),
+            Source::Msdf { texture, .. } => texture.as_ref().filter(|_| index == 0),
+        }
+    }
+}
+
+fn generate(font: &Font, atlas: &mut TextureAtlas, glyph: GlyphId) -> Option<SdfGlyph> {
+    let rasterized = font.scaled(SIZE).rasterize(glyph)?;
+    if rasterized.width == 0 || rasterized.height == 0 {
+        return None;
+    }
+
+    let field = distance_field(
+        &rasterized.coverage,
+        rasterized.width,
+        rasterized.height,
+        SPREAD,
+    );
+    let width = rasterized.width + SPREAD * 2;
+    let height = rasterized.height + SPREAD * 2;
+
+    // the distance in every channel, so msdf and sdf sample the same way
+    let image = DecodedImage {
+        width,
+        height,
+        pixels: Pixels::Rgba8(field.iter().flat_map(|&value| [value; 4]).collect()),
+    };
+
+    let region = atlas
+        .add(glyph.0.to_string(), &image)
+        .inspect_err(|e| log::error!("failed to cache glyph: {e}"))
+        .ok()?;
+
+    Some(SdfGlyph {
+        page: region.page,
+        uv_min: region.uv_min,
+        uv_max: region.uv_max,
+        offset: [
+            (rasterized.offset[0] - SPREAD as f32) / SIZE,
+            (rasterized.offset[1] - SPREAD as f32) / SIZE,
+        ],
+        size: [width as f32 / SIZE, height as f32 / SIZE],
+    })
+}
+
+fn msdf_glyph(atlas: &MsdfAtlas, glyph: GlyphId) -> Option<SdfGlyph> {
+    let msdf = atlas.glyphs.get(glyph.0 as usize)?;
+    let (plane, bounds) = (msdf.plane?, msdf.atlas?);
+
+    let ems = 1.0 / (atlas.ascender - atlas.descender);
+    let (width, height) = (atlas.width as f32, atlas.height as f32);
+
+    Some(SdfGlyph {
+        page: 0,
+        uv_min: [bounds.left / width, bounds.top / height],
+        uv_max: [bounds.right / width, bounds.bottom / height],
+        offset: [plane.left * ems, -plane.top * ems],
+        size: [
+            (plane.right - plane.left) * ems,
+            (plane.top - plane.bottom) * ems,
+        ],
+    })
+}
+
+pub enum SdfMetrics<'a> {
+    Outline(ScaledFont<'a>),
+    Msdf {
+        atlas: &'a MsdfAtlas,
+        pixels_per_em: f32,
+    },
+}
+
+impl Metrics for SdfMetrics<'_> {
+    // characters missing from an msdf atlas map to an id past its glyphs
+    fn glyph_id(&self, c: char) -> GlyphId {
+        match self {
+            SdfMetrics::Outline(font) => font.glyph_id(c),
+            SdfMetrics::Msdf { atlas, .. } => {
+                GlyphId(atlas.chars.get(&c).map_or(u16::MAX, |&i| i as u16))
+            }
+        }
+    }
+
+    fn advance(&self, glyph: GlyphId) -> f32 {
+        match self {
+            SdfMetrics::Outline(font) => font.advance(glyph),
+            SdfMetrics::Msdf {
+                atlas,
+                pixels_per_em,
+            } => atlas
+                .glyphs
+                .get(glyph.0 as usize)
+                .map_or(0.0, |glyph| glyph.advance * pixels_per_em),
+        }
+    }
+
+    fn kern(&self, first: GlyphId, second: GlyphId) -> f32 {
+        match self {
+            SdfMetrics::Outline(font) => font.kern(first, second),
+            SdfMetrics::Msdf {
+                atlas,
+                pixels_per_em,
+            } => atlas
+                .kerning
+                .get(&(first.0 as usize, second.0 as usize))
+                .map_or(0.0, |kern| kern * pixels_per_em),
+        }
+    }
+
+    fn ascent(&self) -> f32 {
+        match self {
+            SdfMetrics::Outline(font) => font.ascent(),
+            SdfMetrics::Msdf {
+                atlas,
+                pixels_per_em,
+            } => atlas.ascender * pixels_per_em,
+        }
+    }
+
+    fn line_height(&self) -> f32 {
+        match self {
+            SdfMetrics::Outline(font) => font.line_height(),
+            SdfMetrics::Msdf {
+                atlas,
+                pixels_per_em,
+            } => atlas.line_height * pixels_per_em,
+        }
+    }
+}
@@ -0,0 +1,369 @@
+use std::ops::Range;
+
+use glam::Vec2;
+use wgpu::include_wgsl;
+use winit::dpi::PhysicalSize;
+
+use super::{layout, Cameras, Space, TextLayout, TextSection};
+use crate::{
+    sandbox::camera2d::camera::Camera2D,
+    texture::{
+        decode::{DecodedImage, Pixels},
+        sampler::SamplerPreset,
+        ColorSpace, Mipmaps, Texture, TextureOptions,
+    },
+};
+
+pub use font::{SdfFont, SdfGlyph, SdfMetrics};
+
+pub mod distance;
+pub mod font;
+pub mod msdf;
+
+const GLYPH: f32 = 0.0;
+const ROUNDED_RECT: f32 = 1.0;
+
+// all lengths are in the units of the space things are drawn in. glyph fields
+// only reach so far past the outline, see `SdfFont::distance_range`: outlines
+// and glows wider than half of it, relative to the text size, get cut short
+#[derive(Debug, Clone, Copy)]
+pub struct Effects {
+    pub outline_width: f32,
+    pub outline_color: [f32; 4],
+    pub shadow_offset: Vec2,
+    // blur of the shadow edge, zero for a hard shadow
+    pub shadow_softness: f32,
+    // a transparent color disables the shadow
+    pub shadow_color: [f32; 4],
+    pub glow_width: f32,
+    pub glow_color: [f32; 4],
+}
+
+impl Default for Effects {
+    fn default() -> Self {
+        Self {
+            outline_width: 0.0,
+            outline_color: [0.0, 0.0, 0.0, 1.0],
+            shadow_offset: Vec2::ZERO,
+            shadow_softness: 0.0,
+            shadow_color: [0.0; 4],
+            glow_width: 0.0,
+            glow_color: [1.0; 4],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Shape {
+    RoundedRect {
+        // the top left corner
+        position: Vec2,
+        size: Vec2,
+        // clamped to half the shorter side
+        radius: f32,
+    },
+    Circle {
+        center: Vec2,
+        radius: f32,
+    },
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct Instance {
+    rect: [f32; 4],
+    uv: [f32; 4],
+    fill: [f32; 4],
+    outline_color: [f32; 4],
+    shadow_color: [f32; 4],
+    glow_color: [f32; 4],
+    // kind, corner radius, distance range, shadow softness
+    shape: [f32; 4],
+    // outline width, glow width, shadow offset
+    effects: [f32; 4],
+}
+
+impl Instance {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 8] = wgpu::vertex_attr_array![
+        0 => Float32x4,
+        1 => Float32x4,
+        2 => Float32x4,
+        3 => Float32x4,
+        4 => Float32x4,
+        5 => Float32x4,
+        6 => Float32x4,
+        7 => Float32x4,
+    ];
+
+    fn new(rect: [f32; 4], fill: [f32; 4], effects: &Effects) -> Self {
+        Self {
+            rect,
+            uv: [0.0; 4],
+            fill,
+            outline_color: effects.outline_color,
+            shadow_color: effects.shadow_color,
+            glow_color: effects.glow_color,
+            shape: [GLYPH, 0.0, 0.0, effects.shadow_softness],
+            effects: [
+                effects.outline_width,
+                effects.glow_width,
+                effects.shadow_offset.x,
+                effects.shadow_offset.y,
+            ],
+        }
+    }
+}
+
+// shapes have no page
+type BatchKey = (Space, Option<usize>);
+
+struct Batch {
+    key: BatchKey,
+    instances: Range<u32>,
+}
+
+// draws text and ui shapes through signed distance fields, which stay sharp
+// at any scale and make outlines, shadows and glows cheap. everything pushed
+// during a frame is batched per space and atlas page, shapes go under the
+// text of the same space
+pub struct SdfRenderer {
+    font: SdfFont,
+    pipeline: wgpu::RenderPipeline,
+    texture_layout: wgpu::BindGroupLayout,
+    page_bind_groups: Vec<wgpu::BindGroup>,
+    // shapes don't sample, but the pipeline always has a texture bound
+    blank_bind_group: wgpu::BindGroup,
+    cameras: Cameras,
+    instance_buffer: wgpu::Buffer,
+    // in instances
+    capacity: usize,
+    instances: Vec<(BatchKey, Instance)>,
+    batches: Vec<Batch>,
+}
+
+impl SdfRenderer {
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        view_format: wgpu::TextureFormat,
+        font: SdfFont,
+    ) -> Self {
+        let shader = device.create_shader_module(include_wgsl!("shader.wgsl"));
+
+        let camera_layout = Camera2D::bind_group_layout(device);
+        let texture_layout = Texture::bind_group_layout(device);
+        let cameras = Cameras::new(device, &camera_layout, "sdf");
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("sdf pipeline layout"),
+            bind_group_layouts: &[&camera_layout, &texture_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("sdf pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: size_of::<Instance>() as _,
+                    step_mode: wgpu::VertexStepMode::Instance,
+                    attributes: &Instance::ATTRIBUTES,
+                }],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: view_format,
+                    blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            multisample: Default::default(),
+            depth_stencil: None,
+            multiview: None,
+            cache: None,
+        });
+
+        let blank = DecodedImage {
+            width: 1,
+            height: 1,
+            pixels: Pixels::Rgba8(vec![0; 4]),
+        };
+        let blank_bind_group = Texture::from_image(
+            device,
+            queue,
+            &blank,
+            TextureOptions {
+                mipmaps: Mipmaps::None,
+                sampler: SamplerPreset::Bilinear,
+                color_space: ColorSpace::Linear,
+            },
+        )
+        .bind_group(device, &texture_layout);
+
+        let capacity = 256;
+
+        Self {
+            font,
+            pipeline,
+            texture_layout,
+            page_bind_groups: Vec::new(),
+            blank_bind_group,
+            cameras,
+            instance_buffer: instance_buffer(device, capacity),
+            capacity,
+            instances: Vec::new(),
+            batches: Vec::new(),
+        }
+    }
+
+    pub fn font(&self) -> &SdfFont {
+        &self.font
+    }
+
+    pub fn measure(&self, section: &TextSection) -> TextLayout {
+        layout::layout(
+            &self.font.metrics(section.size),
+            section.text,
+            &section.layout,
+        )
+    }
+
+    pub fn resize(&self, size: PhysicalSize<u32>, queue: &wgpu::Queue) {
+        self.cameras.resize(size, queue);
+    }
+
+    pub fn set_camera(&self, camera: &Camera2D, queue: &wgpu::Queue) {
+        self.cameras.set_camera(camera, queue);
+    }
+
+    // lays out a section for the next `prepare`, like `TextRenderer::push`.
+    // positions aren't snapped to pixels, there is no need with a field
+    pub fn push_text(&mut self, section: &TextSection, effects: &Effects) -> TextLayout {
+        let layout = self.measure(section);
+        let range = self.font.distance_range() * section.size;
+
+        for positioned in &layout.glyphs {
+            let Some(glyph) = self.font.glyph(positioned.glyph) else {
+                continue;
+            };
+
+            let pen = section.position + Vec2::new(positioned.x, positioned.y);
+            let top_left = pen + Vec2::from(glyph.offset) * section.size;
+            let size = Vec2::from(glyph.size) * section.size;
+
+            let mut instance = Instance::new(
+                [top_left.x, top_left.y, size.x, size.y],
+                section.color,
+                effects,
+            );
+            instance.uv = [glyph.uv_min, glyph.uv_max].concat().try_into().unwrap();
+            instance.shape[2] = range;
+
+            self.instances
+                .push(((section.space, Some(glyph.page)), instance));
+        }
+
+        layout
+    }
+
+    pub fn push_shape(&mut self, shape: Shape, color: [f32; 4], space: Space, effects: &Effects) {
+        let (position, size, radius) = match shape {
+            Shape::RoundedRect {
+                position,
+                size,
+                radius,
+            } => (position, size, radius),
+            Shape::Circle { center, radius } => {
+                (center - radius, Vec2::splat(radius * 2.0), radius)
+            }
+        };
+
+        let mut instance = Instance::new([position.x, position.y, size.x, size.y], color, effects);
+        instance.shape[0] = ROUNDED_RECT;
+        instance.shape[1] = radius;
+
+        self.instances.push(((space, None), instance));
+    }
+
+    // uploads new glyphs and everything pushed since the last call, which is
+    // what `render` draws until the next one
+    pub fn prepare(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        // indexed by page in `render`, every page has its texture after the
+        // upload so none is skipped
+        if self.font.upload(device, queue) {
+            self.page_bind_groups = (0..self.font.page_count())
+                .map(|page| {
+                    self.font
+                        .page(page)
+                        .unwrap()
+                        .bind_group(device, &self.texture_layout)
+                })
+                .collect();
+        }
+
+        if self.instances.len() > self.capacity {
+            self.capacity = self.instances.len().next_power_of_two();
+            self.instance_buffer = instance_buffer(device, self.capacity);
+        }
+
+        // stable, so things keep the order they were pushed in within a batch
+        self.instances.sort_by_key(|(key, _)| *key);
+
+        self.batches.clear();
+        for (i, (key, _)) in self.instances.iter().enumerate() {
+            let i = i as u32;
+
+            match self.batches.last_mut() {
+                Some(batch) if batch.key == *key => batch.instances.end = i + 1,
+                _ => self.batches.push(Batch {
+                    key: *key,
+                    instances: i..i + 1,
+                }),
+            }
+        }
+
+        let instances: Vec<_> = self
+            .instances
+            .drain(..)
+            .map(|(_, instance)| instance)
+            .collect();
+        queue.write_buffer(&self.instance_buffer, 0, bytemuck::cast_slice(&instances));
+    }
+
+    pub fn render(&self, render_pass: &mut wgpu::RenderPass) {
+        if self.batches.is_empty() {
+            return;
+        }
+
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_vertex_buffer(0, self.instance_buffer.slice(..));
+
+        for batch in &self.batches {
+            let (space, page) = batch.key;
+            let texture_bind_group = match page {
+                Some(page) => &self.page_bind_groups[page],
+                None => &self.blank_bind_group,
+            };
+
+            render_pass.set_bind_group(0, self.cameras.bind_group(space), &[]);
+            render_pass.set_bind_group(1, texture_bind_group, &[]);
+            render_pass.draw(0..6, batch.instances.clone());
+        }
+    }
+}
+
+fn instance_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("sdf instance buffer"),
+        size: (capacity * size_of::<Instance>()) as _,
+        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
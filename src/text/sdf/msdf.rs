@@ -0,0 +1,264 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldType {
+    // one channel, r = g = b
+    Sdf,
+    // three channels, the distance is their median
+    Msdf,
+    // msdf with a true sdf in alpha
+    Mtsdf,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct Bounds {
+    pub left: f32,
+    pub bottom: f32,
+    pub right: f32,
+    pub top: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MsdfGlyph {
+    pub advance: f32,
+    // the quad around the pen position in ems, y up. none for empty glyphs
+    pub plane: Option<Bounds>,
+    // the same quad in the atlas, in texels from the top left
+    pub atlas: Option<Bounds>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawAtlas {
+    #[serde(rename = "type")]
+    kind: String,
+    distance_range: f32,
+    // texels per em
+    size: f32,
+    width: u32,
+    height: u32,
+    #[serde(default)]
+    y_origin: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawMetrics {
+    #[serde(default = "one")]
+    em_size: f32,
+    line_height: f32,
+    ascender: f32,
+    descender: f32,
+}
+
+fn one() -> f32 {
+    1.0
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawGlyph {
+    unicode: u32,
+    advance: f32,
+    plane_bounds: Option<Bounds>,
+    atlas_bounds: Option<Bounds>,
+}
+
+#[derive(Deserialize)]
+struct RawKerning {
+    unicode1: u32,
+    unicode2: u32,
+    advance: f32,
+}
+
+#[derive(Deserialize)]
+struct RawFont {
+    atlas: RawAtlas,
+    metrics: RawMetrics,
+    glyphs: Vec<RawGlyph>,
+    #[serde(default)]
+    kerning: Vec<RawKerning>,
+}
+
+// glyph metrics of an atlas made by msdf-atlas-gen with `-json`, for a single
+// font. all lengths are in ems
+#[derive(Debug, Clone)]
+pub struct MsdfAtlas {
+    pub field_type: FieldType,
+    // distance range across the whole 0..1 value range, in texels
+    pub distance_range: f32,
+    pub texels_per_em: f32,
+    pub width: u32,
+    pub height: u32,
+    pub line_height: f32,
+    pub ascender: f32,
+    pub descender: f32,
+    pub glyphs: Vec<MsdfGlyph>,
+    pub chars: HashMap<char, usize>,
+    pub kerning: HashMap<(usize, usize), f32>,
+}
+
+impl MsdfAtlas {
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let font: RawFont =
+            serde_json::from_str(json).map_err(|e| format!("invalid msdf atlas: {e}"))?;
+        let RawFont {
+            atlas,
+            metrics,
+            glyphs,
+            kerning,
+        } = font;
+
+        let field_type = match atlas.kind.as_str() {
+            "sdf" | "psdf" => FieldType::Sdf,
+            "msdf" => FieldType::Msdf,
+            "mtsdf" => FieldType::Mtsdf,
+            kind => return Err(format!("unsupported msdf atlas type {kind:?}")),
+        };
+
+        // bounds count from the bottom unless told otherwise
+        let flip = match atlas.y_origin.as_deref() {
+            None | Some("bottom") => true,
+            Some("top") => false,
+            Some(origin) => return Err(format!("invalid msdf atlas y origin {origin:?}")),
+        };
+
+        let em = metrics.em_size;
+        let scale = |bounds: Bounds| Bounds {
+            left: bounds.left / em,
+            bottom: bounds.bottom / em,
+            right: bounds.right / em,
+            top: bounds.top / em,
+        };
+
+        let mut chars = HashMap::new();
+        let glyphs = glyphs
+            .into_iter()
+            .enumerate()
+            .map(|(i, glyph)| {
+                if let Some(c) = char::from_u32(glyph.unicode) {
+                    chars.insert(c, i);
+                }
+
+                let atlas = glyph.atlas_bounds.map(|bounds| {
+                    if flip {
+                        Bounds {
+                            top: atlas.height as f32 - bounds.top,
+                            bottom: atlas.height as f32 - bounds.bottom,
+                            ..bounds
+                        }
+                    } else {
+                        bounds
+                    }
+                });
+
+                MsdfGlyph {
+                    advance: glyph.advance / em,
+                    plane: glyph.plane_bounds.map(scale),
+                    atlas,
+                }
+            })
+            .collect();
+
+        let kerning = kerning
+            .into_iter()
+            .filter_map(|pair| {
+                let first = chars.get(&char::from_u32(pair.unicode1)?)?;
+                let second = chars.get(&char::from_u32(pair.unicode2)?)?;
+                Some(((*first, *second), pair.advance / em))
+            })
+            .collect();
+
+        Ok(Self {
+            field_type,
+            distance_range: atlas.distance_range,
+            texels_per_em: atlas.size,
+            width: atlas.width,
+            height: atlas.height,
+            line_height: metrics.line_height / em,
+            ascender: metrics.ascender / em,
+            descender: metrics.descender / em,
+            glyphs,
+            chars,
+            kerning,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ATLAS: &str = r#"{
+        "atlas": {
+            "type": "msdf", "distanceRange": 4, "size": 32,
+            "width": 128, "height": 64, "yOrigin": "bottom"
+        },
+        "metrics": {
+            "emSize": 1, "lineHeight": 1.2, "ascender": 0.9,
+            "descender": -0.25, "underlineY": -0.1, "underlineThickness": 0.05
+        },
+        "glyphs": [
+            { "unicode": 32, "advance": 0.3 },
+            {
+                "unicode": 65, "advance": 0.7,
+                "planeBounds": { "left": -0.05, "bottom": -0.06, "right": 0.75, "top": 0.8 },
+                "atlasBounds": { "left": 0.5, "bottom": 30.5, "right": 26.5, "top": 58.5 }
+            },
+            { "unicode": 86, "advance": 0.68 }
+        ],
+        "kerning": [{ "unicode1": 65, "unicode2": 86, "advance": -0.08 }]
+    }"#;
+
+    #[test]
+    fn reads_glyphs_and_metrics() {
+        let atlas = MsdfAtlas::from_json(ATLAS).unwrap();
+
+        assert_eq!(atlas.field_type, FieldType::Msdf);
+        assert_eq!(atlas.distance_range, 4.0);
+        assert_eq!(atlas.texels_per_em, 32.0);
+        assert_eq!(atlas.line_height, 1.2);
+        assert_eq!(atlas.glyphs.len(), 3);
+
+        let a = atlas.glyphs[atlas.chars[&'A']];
+        assert_eq!(a.advance, 0.7);
+        assert_eq!(a.plane.unwrap().top, 0.8);
+
+        let space = atlas.glyphs[atlas.chars[&' ']];
+        assert!(space.plane.is_none() && space.atlas.is_none());
+    }
+
+    #[test]
+    fn flips_bottom_origin_atlas_bounds() {
+        let atlas = MsdfAtlas::from_json(ATLAS).unwrap();
+        let bounds = atlas.glyphs[1].atlas.unwrap();
+
+        assert_eq!(bounds.top, 5.5);
+        assert_eq!(bounds.bottom, 33.5);
+    }
+
+    #[test]
+    fn maps_kerning_to_glyph_indices() {
+        let atlas = MsdfAtlas::from_json(ATLAS).unwrap();
+
+        assert_eq!(atlas.kerning[&(1, 2)], -0.08);
+    }
+
+    #[test]
+    fn scales_by_the_em_size() {
+        let json = ATLAS.replace(r#""emSize": 1"#, r#""emSize": 2"#);
+        let atlas = MsdfAtlas::from_json(&json).unwrap();
+
+        assert_eq!(atlas.ascender, 0.45);
+        assert_eq!(atlas.glyphs[1].advance, 0.35);
+    }
+
+    #[test]
+    fn rejects_bitmap_atlases() {
+        let json = ATLAS.replace(r#""type": "msdf""#, r#""type": "hardmask""#);
+
+        assert!(MsdfAtlas::from_json(&json).is_err());
+    }
+}
@@ -0,0 +1,145 @@
+struct Instance {
+    // the glyph or shape bounds, x, y, width and height
+    @location(0) rect: vec4<f32>,
+    // min and max texture coordinates of the glyph
+    @location(1) uv: vec4<f32>,
+    @location(2) fill: vec4<f32>,
+    @location(3) outline_color: vec4<f32>,
+    @location(4) shadow_color: vec4<f32>,
+    @location(5) glow_color: vec4<f32>,
+    // kind (0 glyph, 1 rounded rect), corner radius, distance range and
+    // shadow softness
+    @location(6) shape: vec4<f32>,
+    // outline width, glow width and shadow offset
+    @location(7) effects: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) pos: vec4<f32>,
+    @location(0) local: vec2<f32>,
+    @location(1) @interpolate(flat) rect: vec4<f32>,
+    @location(2) @interpolate(flat) uv: vec4<f32>,
+    @location(3) @interpolate(flat) fill: vec4<f32>,
+    @location(4) @interpolate(flat) outline_color: vec4<f32>,
+    @location(5) @interpolate(flat) shadow_color: vec4<f32>,
+    @location(6) @interpolate(flat) glow_color: vec4<f32>,
+    @location(7) @interpolate(flat) shape: vec4<f32>,
+    @location(8) @interpolate(flat) effects: vec4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> view_projection: mat4x4<f32>;
+
+@group(1) @binding(0)
+var field_texture: texture_2d<f32>;
+
+@group(1) @binding(1)
+var field_sampler: sampler;
+
+const CORNERS = array(
+    vec2f(0.0, 0.0),
+    vec2f(1.0, 0.0),
+    vec2f(0.0, 1.0),
+    vec2f(0.0, 1.0),
+    vec2f(1.0, 0.0),
+    vec2f(1.0, 1.0),
+);
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32, in: Instance) -> VertexOutput {
+    var out: VertexOutput;
+
+    // grown so outlines, glows and shadows aren't cut off, plus a little
+    // room for antialiasing
+    let offset = in.effects.zw;
+    let reach = max(in.effects.y, length(offset) + in.shape.w);
+    let margin = in.effects.x + reach + 1.0;
+
+    let corner = CORNERS[index];
+    let local = in.rect.xy - margin + corner * (in.rect.zw + margin * 2.0);
+
+    out.pos = vec4f(local, 0.0, 1.0) * view_projection;
+    out.local = local;
+    out.rect = in.rect;
+    out.uv = in.uv;
+    out.fill = in.fill;
+    out.outline_color = in.outline_color;
+    out.shadow_color = in.shadow_color;
+    out.glow_color = in.glow_color;
+    out.shape = in.shape;
+    out.effects = in.effects;
+
+    return out;
+}
+
+fn median(a: f32, b: f32, c: f32) -> f32 {
+    return max(min(a, b), min(max(a, b), c));
+}
+
+// signed distance to the outline in local units, positive inside
+fn signed_distance(in: VertexOutput, p: vec2<f32>) -> f32 {
+    let top_left = in.rect.xy;
+    let bottom_right = in.rect.xy + in.rect.zw;
+
+    if in.shape.x == 0.0 {
+        // outside the glyph's texels the field is continued by the distance
+        // to them, so glows and shadows fade out instead of ending in a box
+        let inside = clamp(p, top_left, bottom_right);
+        let uv = mix(in.uv.xy, in.uv.zw, (inside - top_left) / in.rect.zw);
+        // sampled in non-uniform control flow, so without derivatives
+        let texel = textureSampleLevel(field_texture, field_sampler, uv, 0.0);
+
+        let field = median(texel.r, texel.g, texel.b) - 0.5;
+        return field * in.shape.z - length(p - inside);
+    }
+
+    let extent = in.rect.zw * 0.5;
+    let radius = min(in.shape.y, min(extent.x, extent.y));
+    let q = abs(p - (top_left + extent)) - extent + radius;
+
+    return radius - length(max(q, vec2f(0.0))) - min(max(q.x, q.y), 0.0);
+}
+
+// premultiplied src over dst
+fn over(src: vec4<f32>, dst: vec4<f32>) -> vec4<f32> {
+    return src + dst * (1.0 - src.a);
+}
+
+fn premultiply(color: vec4<f32>, coverage: f32) -> vec4<f32> {
+    let alpha = color.a * coverage;
+    return vec4f(color.rgb * alpha, alpha);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // the size of a pixel in local units, derivatives need uniform control
+    // flow so this comes before any branching
+    let pixel = length(fwidth(in.local)) * 0.7071;
+
+    let d = signed_distance(in, in.local);
+    let outline_width = in.effects.x;
+    let glow_width = in.effects.y;
+    let softness = in.shape.w;
+
+    var color = vec4f(0.0);
+
+    if in.shadow_color.a > 0.0 {
+        let shadow = signed_distance(in, in.local - in.effects.zw);
+        let blur = softness + pixel;
+        let coverage = smoothstep(-blur * 0.5, blur * 0.5, shadow + outline_width);
+        color = premultiply(in.shadow_color, coverage);
+    }
+
+    if glow_width > 0.0 {
+        let falloff = clamp(1.0 + (d + outline_width) / glow_width, 0.0, 1.0);
+        color = over(premultiply(in.glow_color, falloff * falloff), color);
+    }
+
+    if outline_width > 0.0 {
+        let coverage = clamp((d + outline_width) / pixel + 0.5, 0.0, 1.0);
+        color = over(premultiply(in.outline_color, coverage), color);
+    }
+
+    let coverage = clamp(d / pixel + 0.5, 0.0, 1.0);
+    return over(premultiply(in.fill, coverage), color);
+}
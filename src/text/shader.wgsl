@@ -0,0 +1,39 @@
+struct VertexInput {
+    @location(0) pos: vec2<f32>,
+    @location(1) tex_coord: vec2<f32>,
+    @location(2) color: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) pos: vec4<f32>,
+    @location(0) tex_coord: vec2<f32>,
+    @location(1) color: vec4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> view_projection: mat4x4<f32>;
+
+@group(1) @binding(0)
+var glyph_texture: texture_2d<f32>;
+
+@group(1) @binding(1)
+var glyph_sampler: sampler;
+
+@vertex
+fn vs_main(in: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+
+    out.pos = vec4f(in.pos, 0.0, 1.0) * view_projection;
+    out.tex_coord = in.tex_coord;
+    out.color = in.color;
+
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // the atlas only holds coverage, in alpha
+    let coverage = textureSample(glyph_texture, glyph_sampler, in.tex_coord).a;
+
+    return vec4f(in.color.rgb, in.color.a * coverage);
+}
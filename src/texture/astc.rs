@@ -0,0 +1,673 @@
+// cpu decoder for ldr astc blocks, used when the adapter has no
+// TEXTURE_COMPRESSION_ASTC support. blocks an ldr decoder can't handle (hdr
+// endpoints, hdr void extents, reserved encodings) decode to the error color
+
+const ERROR_COLOR: [u8; 4] = [255, 0, 255, 255];
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Digits {
+    None,
+    Trits,
+    Quints,
+}
+
+// every quantization range as its low bit count and whether a trit or quint
+// sits above them, smallest first
+#[rustfmt::skip]
+const RANGES: [(u32, Digits); 21] = [
+    (1, Digits::None), (0, Digits::Trits), (2, Digits::None), (0, Digits::Quints),
+    (1, Digits::Trits), (3, Digits::None), (1, Digits::Quints), (2, Digits::Trits),
+    (4, Digits::None), (2, Digits::Quints), (3, Digits::Trits), (5, Digits::None),
+    (3, Digits::Quints), (4, Digits::Trits), (6, Digits::None), (4, Digits::Quints),
+    (5, Digits::Trits), (7, Digits::None), (5, Digits::Quints), (6, Digits::Trits),
+    (8, Digits::None),
+];
+
+// color endpoints need at least 6 levels
+const MIN_COLOR_RANGE: usize = 4;
+
+fn ise_bits(count: u32, range: usize) -> u32 {
+    let (bits, digits) = RANGES[range];
+
+    count * bits
+        + match digits {
+            Digits::None => 0,
+            Digits::Trits => (count * 8).div_ceil(5),
+            Digits::Quints => (count * 7).div_ceil(3),
+        }
+}
+
+fn read(bits: u128, position: u32, count: u32) -> u32 {
+    ((bits >> position) & ((1 << count) - 1)) as u32
+}
+
+fn decode_trits(t: u32) -> [u32; 5] {
+    let bit = |value: u32, i: u32| (value >> i) & 1;
+
+    let (c, t3, t4) = if (t >> 2) & 0b111 == 0b111 {
+        ((t >> 5) << 2 | (t & 0b11), 2, 2)
+    } else if (t >> 5) & 0b11 == 0b11 {
+        (t & 0x1f, bit(t, 7), 2)
+    } else {
+        (t & 0x1f, (t >> 5) & 0b11, bit(t, 7))
+    };
+
+    let (t0, t1, t2) = if c & 0b11 == 0b11 {
+        (bit(c, 3) << 1 | (bit(c, 2) & !bit(c, 3) & 1), bit(c, 4), 2)
+    } else if (c >> 2) & 0b11 == 0b11 {
+        (c & 0b11, 2, 2)
+    } else {
+        (
+            bit(c, 1) << 1 | (bit(c, 0) & !bit(c, 1) & 1),
+            (c >> 2) & 0b11,
+            bit(c, 4),
+        )
+    };
+
+    [t0, t1, t2, t3, t4]
+}
+
+fn decode_quints(q: u32) -> [u32; 3] {
+    let bit = |value: u32, i: u32| (value >> i) & 1;
+
+    if (q >> 1) & 0b11 == 0b11 && (q >> 5) & 0b11 == 0 {
+        let q2 = bit(q, 0) << 2 | (bit(q, 4) & !bit(q, 0) & 1) << 1 | (bit(q, 3) & !bit(q, 0) & 1);
+        return [4, 4, q2];
+    }
+
+    let (c, q2) = if (q >> 1) & 0b11 == 0b11 {
+        (
+            ((q >> 3) & 0b11) << 3 | (!(q >> 5) & 0b11) << 1 | bit(q, 0),
+            4,
+        )
+    } else {
+        (q & 0x1f, (q >> 5) & 0b11)
+    };
+
+    if c & 0b111 == 0b101 {
+        [(c >> 3) & 0b11, 4, q2]
+    } else {
+        [c & 0b111, (c >> 3) & 0b11, q2]
+    }
+}
+
+// integer sequence encoded values as (trit or quint, low bits). bits past
+// the end of the sequence read as zero
+fn decode_ise(bits: u128, start: u32, count: usize, range: usize) -> Vec<(u32, u32)> {
+    let (low_bits, digits) = RANGES[range];
+    let end = start + ise_bits(count as u32, range);
+
+    let mut position = start;
+    let mut next = |count: u32| {
+        let available = end.saturating_sub(position).min(count);
+        let value = read(bits, position.min(127), available);
+        position += count;
+        value
+    };
+
+    let mut values = Vec::with_capacity(count + 4);
+    while values.len() < count {
+        match digits {
+            Digits::None => values.push((0, next(low_bits))),
+            Digits::Trits => {
+                let mut low = [0; 5];
+                let mut t = 0;
+                for (i, (shift, count)) in [(0, 2), (2, 2), (4, 1), (5, 2), (7, 1)]
+                    .into_iter()
+                    .enumerate()
+                {
+                    low[i] = next(low_bits);
+                    t |= next(count) << shift;
+                }
+                values.extend(decode_trits(t).into_iter().zip(low));
+            }
+            Digits::Quints => {
+                let mut low = [0; 3];
+                let mut q = 0;
+                for (i, (shift, count)) in [(0, 3), (3, 2), (5, 2)].into_iter().enumerate() {
+                    low[i] = next(low_bits);
+                    q |= next(count) << shift;
+                }
+                values.extend(decode_quints(q).into_iter().zip(low));
+            }
+        }
+    }
+
+    values.truncate(count);
+    values
+}
+
+fn replicate(value: u32, bits: u32, to: u32) -> u32 {
+    let mut result = 0;
+    let mut filled = 0;
+    while filled < to {
+        result = result << bits | value;
+        filled += bits;
+    }
+
+    result >> (filled - to)
+}
+
+// spreads a quantized endpoint value over 0..=255
+fn unquantize_color((digit, low): (u32, u32), range: usize) -> i32 {
+    let (bits, digits) = RANGES[range];
+    if digits == Digits::None {
+        return replicate(low, bits, 8) as i32;
+    }
+
+    let bit = |i: u32| (low >> i) & 1;
+    let (a, b, c, d) = (bit(1), bit(2), bit(3), bit(4));
+    let (pattern, scale) = match (digits, bits) {
+        (Digits::Trits, 1) => (0, 204),
+        (Digits::Trits, 2) => (a << 8 | a << 4 | a << 2 | a << 1, 93),
+        (Digits::Trits, 3) => (b << 8 | a << 7 | b << 3 | a << 2 | b << 1 | a, 44),
+        (Digits::Trits, 4) => (c << 8 | b << 7 | a << 6 | c << 2 | b << 1 | a, 22),
+        (Digits::Trits, 5) => (d << 8 | c << 7 | b << 6 | a << 5 | d << 1 | c, 11),
+        (Digits::Trits, _) => {
+            let e = bit(5);
+            (e << 8 | d << 7 | c << 6 | b << 5 | a << 4 | e, 5)
+        }
+        (_, 1) => (0, 113),
+        (_, 2) => (a << 8 | a << 3 | a << 2, 54),
+        (_, 3) => (b << 8 | a << 7 | b << 2 | a << 1 | b, 26),
+        (_, 4) => (c << 8 | b << 7 | a << 6 | c << 1 | b, 13),
+        (_, _) => (d << 8 | c << 7 | b << 6 | a << 5 | d, 6),
+    };
+
+    let mask = if low & 1 == 1 { 0x1ff } else { 0 };
+    let value = (digit * scale + pattern) ^ mask;
+    ((mask & 0x80) | (value >> 2)) as i32
+}
+
+// spreads a quantized weight over 0..=64
+fn unquantize_weight((digit, low): (u32, u32), range: usize) -> u32 {
+    let (bits, digits) = RANGES[range];
+
+    let bit = |i: u32| (low >> i) & 1;
+    let (a, b) = (bit(1), bit(2));
+    let value = match (digits, bits) {
+        (Digits::None, _) => replicate(low, bits, 6),
+        (Digits::Trits, 0) => [0, 32, 63][digit as usize],
+        (Digits::Quints, 0) => [0, 16, 32, 47, 63][digit as usize],
+        _ => {
+            let (pattern, scale) = match (digits, bits) {
+                (Digits::Trits, 1) => (0, 50),
+                (Digits::Trits, 2) => (a << 6 | a << 2 | a, 23),
+                (Digits::Trits, _) => (b << 6 | a << 5 | b << 1 | a, 11),
+                (_, 1) => (0, 28),
+                (_, _) => (a << 6 | a << 1, 13),
+            };
+            let mask = if low & 1 == 1 { 0x7f } else { 0 };
+            let value = (digit * scale + pattern) ^ mask;
+            (mask & 0x20) | (value >> 2)
+        }
+    };
+
+    if value > 32 {
+        value + 1
+    } else {
+        value
+    }
+}
+
+struct BlockMode {
+    grid: (u32, u32),
+    dual_plane: bool,
+    weight_range: usize,
+}
+
+fn block_mode(mode: u32) -> Option<BlockMode> {
+    let bit = |i: u32| (mode >> i) & 1;
+    let a = (mode >> 5) & 0b11;
+    let (mut dual_plane, mut high_precision) = (bit(10) == 1, bit(9) == 1);
+
+    let (range, grid) = if mode & 0b11 != 0 {
+        let b = (mode >> 7) & 0b11;
+        let grid = match (mode >> 2) & 0b11 {
+            0 => (b + 4, a + 2),
+            1 => (b + 8, a + 2),
+            2 => (a + 2, b + 8),
+            _ if bit(8) == 0 => (a + 2, (b & 1) + 6),
+            _ => ((b & 1) + 2, a + 2),
+        };
+        ((mode & 0b11) << 1 | bit(4), grid)
+    } else {
+        let range = ((mode >> 2) & 0b11) << 1 | bit(4);
+        if range < 2 {
+            return None;
+        }
+        let b = (mode >> 9) & 0b11;
+        let grid = match ((mode >> 7) & 0b11, a) {
+            (0, _) => (12, a + 2),
+            (1, _) => (a + 2, 12),
+            (2, _) => {
+                (dual_plane, high_precision) = (false, false);
+                (a + 6, b + 6)
+            }
+            (_, 0) => (6, 10),
+            (_, 1) => (10, 6),
+            _ => return None,
+        };
+        (range, grid)
+    };
+
+    let weight_range = (range - 2) as usize + if high_precision { 6 } else { 0 };
+    let weights = grid.0 * grid.1 * (1 + dual_plane as u32);
+    let weight_bits = ise_bits(weights, weight_range);
+    if weights > 64 || !(24..=96).contains(&weight_bits) {
+        return None;
+    }
+
+    Some(BlockMode {
+        grid,
+        dual_plane,
+        weight_range,
+    })
+}
+
+// the offset bits of a base + offset pair are stolen from the top of the base
+fn bit_transfer_signed(offset: i32, base: i32) -> (i32, i32) {
+    let base = (base >> 1) | (offset & 0x80);
+    let offset = (offset >> 1) & 0x3f;
+    let offset = if offset & 0x20 != 0 {
+        offset - 0x40
+    } else {
+        offset
+    };
+
+    (offset, base)
+}
+
+fn blue_contract([r, g, b, a]: [i32; 4]) -> [i32; 4] {
+    [(r + b) >> 1, (g + b) >> 1, b, a]
+}
+
+// the two rgba endpoints of a partition, hdr modes have no ldr decoding
+fn endpoints(mode: u32, v: &[i32]) -> Option<[[i32; 4]; 2]> {
+    let pair = match mode {
+        0 => [[v[0], v[0], v[0], 255], [v[1], v[1], v[1], 255]],
+        1 => {
+            let l0 = (v[0] >> 2) | (v[1] & 0xc0);
+            let l1 = (l0 + (v[1] & 0x3f)).min(255);
+            [[l0, l0, l0, 255], [l1, l1, l1, 255]]
+        }
+        4 => [[v[0], v[0], v[0], v[2]], [v[1], v[1], v[1], v[3]]],
+        5 => {
+            let (o1, b0) = bit_transfer_signed(v[1], v[0]);
+            let (o3, b2) = bit_transfer_signed(v[3], v[2]);
+            let l1 = b0 + o1;
+            [[b0, b0, b0, b2], [l1, l1, l1, b2 + o3]]
+        }
+        6 | 10 => {
+            let (a0, a1) = match mode {
+                6 => (255, 255),
+                _ => (v[4], v[5]),
+            };
+            let scaled = |c: i32| (c * v[3]) >> 8;
+            [
+                [scaled(v[0]), scaled(v[1]), scaled(v[2]), a0],
+                [v[0], v[1], v[2], a1],
+            ]
+        }
+        8 | 12 => {
+            let (a0, a1) = match mode {
+                8 => (255, 255),
+                _ => (v[6], v[7]),
+            };
+            let e0 = [v[0], v[2], v[4], a0];
+            let e1 = [v[1], v[3], v[5], a1];
+            if v[1] + v[3] + v[5] >= v[0] + v[2] + v[4] {
+                [e0, e1]
+            } else {
+                [blue_contract(e1), blue_contract(e0)]
+            }
+        }
+        9 | 13 => {
+            let (or, br) = bit_transfer_signed(v[1], v[0]);
+            let (og, bg) = bit_transfer_signed(v[3], v[2]);
+            let (ob, bb) = bit_transfer_signed(v[5], v[4]);
+            let (oa, ba) = match mode {
+                9 => (0, 255),
+                _ => bit_transfer_signed(v[7], v[6]),
+            };
+            let e0 = [br, bg, bb, ba];
+            let e1 = [br + or, bg + og, bb + ob, ba + oa];
+            if or + og + ob >= 0 {
+                [e0, e1]
+            } else {
+                [blue_contract(e1), blue_contract(e0)]
+            }
+        }
+        _ => return None,
+    };
+
+    Some(pair.map(|e| e.map(|c| c.clamp(0, 255))))
+}
+
+fn hash52(mut p: u32) -> u32 {
+    p ^= p >> 15;
+    p = p.wrapping_sub(p << 17);
+    p = p.wrapping_add(p << 7);
+    p = p.wrapping_add(p << 4);
+    p ^= p >> 5;
+    p = p.wrapping_add(p << 16);
+    p ^= p >> 7;
+    p ^= p >> 3;
+    p ^= p << 6;
+    p ^= p >> 17;
+    p
+}
+
+fn partition(seed: u32, partitions: u32, x: u32, y: u32, small_block: bool) -> usize {
+    let (x, y) = if small_block {
+        (x << 1, y << 1)
+    } else {
+        (x, y)
+    };
+    let seed = seed + (partitions - 1) * 1024;
+    let random = hash52(seed);
+
+    let mut seeds: [u32; 8] = std::array::from_fn(|i| {
+        let nibble = (random >> (i * 4)) & 0xf;
+        nibble * nibble
+    });
+
+    let (sh1, sh2) = match (seed & 1 == 1, seed & 2 == 2) {
+        (true, high) => (
+            if high { 4 } else { 5 },
+            if partitions == 3 { 6 } else { 5 },
+        ),
+        (false, high) => (
+            if partitions == 3 { 6 } else { 5 },
+            if high { 4 } else { 5 },
+        ),
+    };
+    for (i, seed) in seeds.iter_mut().enumerate() {
+        *seed >>= if i % 2 == 0 { sh1 } else { sh2 };
+    }
+
+    let a = (seeds[0] * x + seeds[1] * y + (random >> 14)) & 0x3f;
+    let b = (seeds[2] * x + seeds[3] * y + (random >> 10)) & 0x3f;
+    let c = if partitions >= 3 {
+        (seeds[4] * x + seeds[5] * y + (random >> 6)) & 0x3f
+    } else {
+        0
+    };
+    let d = if partitions >= 4 {
+        (seeds[6] * x + seeds[7] * y + (random >> 2)) & 0x3f
+    } else {
+        0
+    };
+
+    if a >= b && a >= c && a >= d {
+        0
+    } else if b >= c && b >= d {
+        1
+    } else if c >= d {
+        2
+    } else {
+        3
+    }
+}
+
+fn decode_texels(block: &[u8], (width, height): (u32, u32), srgb: bool) -> Option<Vec<[u8; 4]>> {
+    let bits = u128::from_le_bytes(block[..16].try_into().unwrap());
+    let texel_count = (width * height) as usize;
+
+    let mode = read(bits, 0, 11);
+    if mode & 0x1ff == 0x1fc {
+        // a void extent block holds one unorm16 color, the hdr variant
+        // holds half floats. the extent has to be empty or all ones
+        let [s0, s1, t0, t1] = [12, 25, 38, 51].map(|position| read(bits, position, 13));
+        let no_extent = [s0, s1, t0, t1] == [0x1fff; 4];
+        if mode & 0x200 != 0 || read(bits, 10, 2) != 0b11 || !no_extent && (s0 >= s1 || t0 >= t1) {
+            return None;
+        }
+        let color = [0, 1, 2, 3].map(|c| (read(bits, 64 + c * 16, 16) >> 8) as u8);
+        return Some(vec![color; texel_count]);
+    }
+
+    let mode = block_mode(mode)?;
+    let (grid_width, grid_height) = mode.grid;
+    if grid_width > width || grid_height > height {
+        return None;
+    }
+
+    let partitions = read(bits, 11, 2) + 1;
+    if mode.dual_plane && partitions == 4 {
+        return None;
+    }
+
+    let planes = 1 + mode.dual_plane as u32;
+    let weight_count = grid_width * grid_height * planes;
+    let mut below_weights = 128 - ise_bits(weight_count, mode.weight_range);
+
+    // endpoint modes, either one shared by every partition or a class per
+    // block and a mode per partition, spilling below the weights
+    let mut modes = [0; 4];
+    let color_start = if partitions == 1 {
+        modes[0] = read(bits, 13, 4);
+        17
+    } else {
+        let encoded = read(bits, 23, 6);
+        if encoded & 0b11 == 0 {
+            modes = [encoded >> 2; 4];
+        } else {
+            let extra = 3 * partitions - 4;
+            below_weights -= extra;
+            let encoded = encoded | read(bits, below_weights, extra) << 6;
+            let class = (encoded & 0b11) - 1;
+            for (i, mode) in modes[..partitions as usize].iter_mut().enumerate() {
+                let class = class + ((encoded >> (2 + i)) & 1);
+                let low = (encoded >> (2 + partitions as usize + i * 2)) & 0b11;
+                *mode = class << 2 | low;
+            }
+        }
+        29
+    };
+
+    let plane2_channel = if mode.dual_plane {
+        below_weights -= 2;
+        Some(read(bits, below_weights, 2) as usize)
+    } else {
+        None
+    };
+
+    let modes = &modes[..partitions as usize];
+    let value_count: u32 = modes.iter().map(|mode| ((mode >> 2) + 1) * 2).sum();
+    let color_bits = below_weights.checked_sub(color_start)?;
+    if value_count > 18 {
+        return None;
+    }
+    let color_range = (MIN_COLOR_RANGE..RANGES.len())
+        .rev()
+        .find(|&range| ise_bits(value_count, range) <= color_bits)?;
+
+    let values: Vec<i32> = decode_ise(bits, color_start, value_count as usize, color_range)
+        .into_iter()
+        .map(|value| unquantize_color(value, color_range))
+        .collect();
+
+    let mut partition_endpoints = Vec::with_capacity(modes.len());
+    let mut offset = 0;
+    for &mode in modes {
+        let count = (((mode >> 2) + 1) * 2) as usize;
+        partition_endpoints.push(endpoints(mode, &values[offset..offset + count])?);
+        offset += count;
+    }
+
+    // weights are stored from the top of the block down
+    let weights: Vec<u32> = decode_ise(
+        bits.reverse_bits(),
+        0,
+        weight_count as usize,
+        mode.weight_range,
+    )
+    .into_iter()
+    .map(|value| unquantize_weight(value, mode.weight_range))
+    .collect();
+
+    let seed = read(bits, 13, 10);
+    let small_block = texel_count < 31;
+    let scale_x = (1024 + width / 2) / (width - 1);
+    let scale_y = (1024 + height / 2) / (height - 1);
+
+    let texels = (0..texel_count as u32)
+        .map(|i| {
+            let (x, y) = (i % width, i / width);
+
+            // bilinear infill from the weight grid
+            let gx = (scale_x * x * (grid_width - 1) + 32) >> 6;
+            let gy = (scale_y * y * (grid_height - 1) + 32) >> 6;
+            let (jx, fx) = (gx >> 4, gx & 0xf);
+            let (jy, fy) = (gy >> 4, gy & 0xf);
+            let w11 = (fx * fy + 8) >> 4;
+            let taps = [
+                (0, 0, 16 + w11 - fx - fy),
+                (1, 0, fx - w11),
+                (0, 1, fy - w11),
+                (1, 1, w11),
+            ];
+            let weight = |plane: u32| {
+                let sum: u32 = taps
+                    .iter()
+                    .filter(|&&(_, _, w)| w > 0)
+                    .map(|&(dx, dy, w)| {
+                        let index = ((jy + dy) * grid_width + jx + dx) * planes + plane;
+                        weights[index as usize] * w
+                    })
+                    .sum();
+                (sum + 8) >> 4
+            };
+            let (w0, w1) = match plane2_channel {
+                Some(_) => (weight(0), weight(1)),
+                None => (weight(0), 0),
+            };
+
+            let p = match partitions {
+                1 => 0,
+                _ => partition(seed, partitions, x, y, small_block),
+            };
+            let [e0, e1] = partition_endpoints[p];
+
+            std::array::from_fn(|c| {
+                let w = if plane2_channel == Some(c) { w1 } else { w0 } as i32;
+                let expand = |value: i32| match srgb {
+                    true => value << 8 | 0x80,
+                    false => value << 8 | value,
+                };
+                let value = (expand(e0[c]) * (64 - w) + expand(e1[c]) * w + 32) >> 6;
+                (value >> 8) as u8
+            })
+        })
+        .collect();
+
+    Some(texels)
+}
+
+pub fn is_supported(format: wgpu::TextureFormat) -> bool {
+    matches!(
+        format,
+        wgpu::TextureFormat::Astc {
+            channel: wgpu::AstcChannel::Unorm | wgpu::AstcChannel::UnormSrgb,
+            ..
+        }
+    )
+}
+
+// writes the texels of one block row by row as rgba8
+pub fn decode_block(format: wgpu::TextureFormat, block: &[u8], texels: &mut [u8]) {
+    let dimensions = format.block_dimensions();
+    let decoded = decode_texels(block, dimensions, format.is_srgb());
+
+    match decoded {
+        Some(decoded) => texels.copy_from_slice(decoded.as_flattened()),
+        None => {
+            for texel in texels.chunks_exact_mut(4) {
+                texel.copy_from_slice(&ERROR_COLOR);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wgpu::{AstcBlock, AstcChannel, TextureFormat};
+
+    use super::*;
+
+    const ASTC_4X4: TextureFormat = TextureFormat::Astc {
+        block: AstcBlock::B4x4,
+        channel: AstcChannel::Unorm,
+    };
+
+    const ASTC_6X6: TextureFormat = TextureFormat::Astc {
+        block: AstcBlock::B6x6,
+        channel: AstcChannel::Unorm,
+    };
+
+    fn decode(format: TextureFormat, block: u128) -> Vec<[u8; 4]> {
+        let (width, height) = format.block_dimensions();
+        let mut texels = vec![[0; 4]; (width * height) as usize];
+        decode_block(format, &block.to_le_bytes(), texels.as_flattened_mut());
+        texels
+    }
+
+    #[test]
+    fn void_extent_fills_the_block() {
+        let color = 0xffff_8080_4000_1234u128 << 64;
+        let extent = ((1u128 << 52) - 1) << 12;
+        let texels = decode(ASTC_4X4, color | extent | 0xdfc);
+        assert_eq!(texels, vec![[0x12, 0x40, 0x80, 0xff]; 16]);
+
+        // the hdr variant holds half floats
+        let texels = decode(ASTC_4X4, color | extent | 0xffc);
+        assert_eq!(texels, vec![ERROR_COLOR; 16]);
+    }
+
+    #[test]
+    fn reserved_blocks_decode_to_the_error_color() {
+        assert_eq!(decode(ASTC_4X4, 0), vec![ERROR_COLOR; 16]);
+    }
+
+    #[test]
+    fn hdr_endpoints_decode_to_the_error_color() {
+        // a 4x4 weight grid with one partition, ldr rgba and then hdr rgba
+        let ldr = decode(ASTC_4X4, 8 << 13 | 0x53);
+        assert_ne!(ldr, vec![ERROR_COLOR; 16]);
+
+        let hdr = decode(ASTC_4X4, 15 << 13 | 0x53);
+        assert_eq!(hdr, vec![ERROR_COLOR; 16]);
+    }
+
+    // checked against mesa's decoder
+    #[test]
+    fn partitioned_blocks_match_a_reference_block() {
+        let block = u128::from_le_bytes([
+            0x92, 0x72, 0xff, 0xbc, 0x22, 0x48, 0x4c, 0x75, 0xf7, 0x68, 0xe6, 0xf9, 0x92, 0xdf,
+            0xb4, 0xe2,
+        ]);
+        let texels = decode(ASTC_6X6, block);
+
+        assert_eq!(texels[0], [206, 206, 206, 141]);
+        assert_eq!(texels[4], [120, 30, 44, 255]);
+        assert_eq!(texels[5], [106, 26, 39, 255]);
+        assert_eq!(texels[21], [201, 201, 201, 145]);
+        assert_eq!(texels[35], [114, 28, 42, 255]);
+    }
+
+    // checked against mesa's decoder
+    #[test]
+    fn dual_plane_blocks_match_a_reference_block() {
+        let block = u128::from_le_bytes([
+            0x0f, 0x24, 0x75, 0x58, 0xcd, 0x43, 0xa7, 0xca, 0x87, 0x2d, 0x03, 0x20, 0x08, 0xa3,
+            0xbf, 0x88,
+        ]);
+        let texels = decode(ASTC_6X6, block);
+
+        assert_eq!(texels[0], [137, 191, 217, 255]);
+        assert_eq!(texels[7], [134, 189, 214, 255]);
+        assert_eq!(texels[18], [117, 179, 195, 255]);
+        assert_eq!(texels[31], [130, 187, 210, 255]);
+    }
+}
@@ -0,0 +1,530 @@
+use std::collections::HashMap;
+
+use super::{
+    decode::{DecodedImage, Pixels},
+    extent,
+    mipmap::{mip_level_count, MipmapGenerator},
+    sampler::SamplerPreset,
+    sheet::SpriteSheet,
+    ColorSpace, Mipmaps, Texture, TextureOptions,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AtlasRegion {
+    pub page: usize,
+    pub uv_min: [f32; 2],
+    pub uv_max: [f32; 2],
+    pub size: (u32, u32),
+    // sheet frames trimmed of their transparent border sit at `offset` in an
+    // image of `source_size`
+    pub offset: (u32, u32),
+    pub source_size: (u32, u32),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct AtlasOptions {
+    pub page_size: u32,
+    // empty texels between neighbouring images
+    pub padding: u32,
+    // texels of repeated edge around every image, so filtering at the border
+    // never picks up a neighbour
+    pub extrude: u32,
+    pub texture: TextureOptions,
+}
+
+impl Default for AtlasOptions {
+    fn default() -> Self {
+        Self {
+            page_size: 1024,
+            padding: 2,
+            extrude: 1,
+            texture: TextureOptions {
+                mipmaps: Mipmaps::None,
+                sampler: SamplerPreset::Bilinear,
+                color_space: ColorSpace::Srgb,
+            },
+        }
+    }
+}
+
+// bottom-left skyline packer, every node is a horizontal segment (x, y, width)
+// of the top edge of the packed area
+struct Skyline {
+    size: u32,
+    nodes: Vec<(u32, u32, u32)>,
+}
+
+impl Skyline {
+    fn new(size: u32) -> Self {
+        Self {
+            size,
+            nodes: vec![(0, 0, size)],
+        }
+    }
+
+    // the lowest y a rect starting at node `index` can sit at
+    fn fit(&self, index: usize, width: u32, height: u32) -> Option<u32> {
+        let x = self.nodes[index].0;
+        if x + width > self.size {
+            return None;
+        }
+
+        let mut y = 0;
+        let mut remaining = width;
+        for &(_, node_y, node_width) in &self.nodes[index..] {
+            if remaining == 0 {
+                break;
+            }
+
+            y = y.max(node_y);
+            if y + height > self.size {
+                return None;
+            }
+
+            remaining = remaining.saturating_sub(node_width);
+        }
+
+        Some(y)
+    }
+
+    fn insert(&mut self, width: u32, height: u32) -> Option<(u32, u32)> {
+        let (index, y) = (0..self.nodes.len())
+            .filter_map(|i| self.fit(i, width, height).map(|y| (i, y)))
+            .min_by_key(|&(i, y)| (y + height, self.nodes[i].2))?;
+
+        let x = self.nodes[index].0;
+        self.nodes.insert(index, (x, y + height, width));
+
+        // cut the nodes the new one now covers
+        let end = x + width;
+        while let Some(node) = self.nodes.get_mut(index + 1) {
+            if node.0 >= end {
+                break;
+            }
+
+            let covered = end - node.0;
+            if node.2 <= covered {
+                self.nodes.remove(index + 1);
+            } else {
+                node.0 += covered;
+                node.2 -= covered;
+                break;
+            }
+        }
+
+        // merge neighbours at the same height
+        let mut i = 0;
+        while i + 1 < self.nodes.len() {
+            if self.nodes[i].1 == self.nodes[i + 1].1 {
+                self.nodes[i].2 += self.nodes[i + 1].2;
+                self.nodes.remove(i + 1);
+            } else {
+                i += 1;
+            }
+        }
+
+        Some((x, y))
+    }
+}
+
+struct Page {
+    skyline: Skyline,
+    texels: Vec<u8>,
+    gpu: Option<(wgpu::Texture, Texture)>,
+    dirty: bool,
+}
+
+// packs many rgba8 images into one or more square pages. images can be added
+// at any time, `upload` sends the changed pages to the gpu
+pub struct TextureAtlas {
+    options: AtlasOptions,
+    pages: Vec<Page>,
+    regions: HashMap<String, AtlasRegion>,
+}
+
+impl TextureAtlas {
+    pub fn new(options: AtlasOptions) -> Self {
+        Self {
+            options,
+            pages: Vec::new(),
+            regions: HashMap::new(),
+        }
+    }
+
+    // adding a name twice points it at the new image, the space of the old
+    // one is not reclaimed
+    pub fn add(
+        &mut self,
+        name: impl Into<String>,
+        image: &DecodedImage,
+    ) -> Result<AtlasRegion, String> {
+        let Pixels::Rgba8(texels) = &image.pixels else {
+            return Err("atlas images must be 8 bit rgba".to_owned());
+        };
+
+        let size = (image.width, image.height);
+        self.insert(name.into(), texels, size, (0, 0), size)
+    }
+
+    // adds every frame of a packed sheet under its frame name
+    pub fn add_sheet(&mut self, image: &DecodedImage, sheet: &SpriteSheet) -> Result<(), String> {
+        let Pixels::Rgba8(texels) = &image.pixels else {
+            return Err("atlas images must be 8 bit rgba".to_owned());
+        };
+
+        for frame in &sheet.frames {
+            let rect = frame.rect;
+            let (width, height) = if frame.rotated {
+                (rect.h, rect.w)
+            } else {
+                (rect.w, rect.h)
+            };
+
+            if rect.x + width > image.width || rect.y + height > image.height {
+                return Err(format!(
+                    "frame {:?} lies outside the {}x{} sheet",
+                    frame.name, image.width, image.height
+                ));
+            }
+
+            let mut cropped = Vec::with_capacity((rect.w * rect.h * 4) as usize);
+            for y in 0..rect.h {
+                for x in 0..rect.w {
+                    // rotated frames are stored turned 90 degrees clockwise
+                    let (sx, sy) = if frame.rotated {
+                        (rect.x + rect.h - 1 - y, rect.y + x)
+                    } else {
+                        (rect.x + x, rect.y + y)
+                    };
+
+                    let offset = ((sy * image.width + sx) * 4) as usize;
+                    cropped.extend_from_slice(&texels[offset..offset + 4]);
+                }
+            }
+
+            self.insert(
+                frame.name.clone(),
+                &cropped,
+                (rect.w, rect.h),
+                frame.offset,
+                frame.source_size,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn insert(
+        &mut self,
+        name: String,
+        texels: &[u8],
+        (width, height): (u32, u32),
+        offset: (u32, u32),
+        source_size: (u32, u32),
+    ) -> Result<AtlasRegion, String> {
+        let AtlasOptions {
+            page_size,
+            padding,
+            extrude,
+            ..
+        } = self.options;
+
+        let cell = (
+            width + extrude * 2 + padding,
+            height + extrude * 2 + padding,
+        );
+        if cell.0 > page_size || cell.1 > page_size {
+            return Err(format!(
+                "{name:?} is {width}x{height}, which doesn't fit a {page_size}x{page_size} page"
+            ));
+        }
+
+        let placed = self
+            .pages
+            .iter_mut()
+            .enumerate()
+            .find_map(|(i, page)| page.skyline.insert(cell.0, cell.1).map(|pos| (i, pos)));
+
+        let (index, (x, y)) = match placed {
+            Some(placed) => placed,
+            None => {
+                let mut skyline = Skyline::new(page_size);
+                let pos = skyline.insert(cell.0, cell.1).unwrap();
+
+                self.pages.push(Page {
+                    skyline,
+                    texels: vec![0; (page_size * page_size * 4) as usize],
+                    gpu: None,
+                    dirty: true,
+                });
+
+                (self.pages.len() - 1, pos)
+            }
+        };
+
+        let page = &mut self.pages[index];
+        for dy in 0..height + extrude * 2 {
+            for dx in 0..width + extrude * 2 {
+                let sx = dx.saturating_sub(extrude).min(width - 1);
+                let sy = dy.saturating_sub(extrude).min(height - 1);
+                let src = ((sy * width + sx) * 4) as usize;
+                let dst = (((y + dy) * page_size + x + dx) * 4) as usize;
+
+                page.texels[dst..dst + 4].copy_from_slice(&texels[src..src + 4]);
+            }
+        }
+        page.dirty = true;
+
+        let uv = |x: u32, y: u32| [x as f32 / page_size as f32, y as f32 / page_size as f32];
+        let region = AtlasRegion {
+            page: index,
+            uv_min: uv(x + extrude, y + extrude),
+            uv_max: uv(x + extrude + width, y + extrude + height),
+            size: (width, height),
+            offset,
+            source_size,
+        };
+
+        self.regions.insert(name, region);
+
+        Ok(region)
+    }
+
+    pub fn region(&self, name: &str) -> Option<AtlasRegion> {
+        self.regions.get(name).copied()
+    }
+
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    // only available once the page has been uploaded
+    pub fn page(&self, index: usize) -> Option<&Texture> {
+        self.pages
+            .get(index)?
+            .gpu
+            .as_ref()
+            .map(|(_, texture)| texture)
+    }
+
+    // writes every changed page to the gpu. pages start out changed, so
+    // afterwards each one has its texture. returns true when new pages were
+    // created, so bind groups of the previous pages stay valid but new ones
+    // need to be made
+    pub fn upload(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) -> bool {
+        let options = self.options.texture;
+        let page_size = self.options.page_size;
+        let mip_level_count = match options.mipmaps {
+            Mipmaps::Generate => mip_level_count(page_size, page_size),
+            _ => 1,
+        };
+
+        let mut created = false;
+        for page in self.pages.iter_mut().filter(|page| page.dirty) {
+            let (texture, _) = page.gpu.get_or_insert_with(|| {
+                created = true;
+
+                let mut usage =
+                    wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST;
+                if mip_level_count > 1 {
+                    usage |= wgpu::TextureUsages::RENDER_ATTACHMENT;
+                }
+
+                let texture = device.create_texture(&wgpu::TextureDescriptor {
+                    label: Some("atlas page"),
+                    size: extent(page_size, page_size, 1),
+                    mip_level_count,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format: options.color_space.apply(wgpu::TextureFormat::Rgba8Unorm),
+                    usage,
+                    view_formats: &[],
+                });
+                let view = Texture::from_texture(
+                    device,
+                    &texture,
+                    wgpu::TextureViewDimension::D2,
+                    options,
+                );
+
+                (texture, view)
+            });
+
+            queue.write_texture(
+                texture.as_image_copy(),
+                &page.texels,
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(page_size * 4),
+                    rows_per_image: None,
+                },
+                extent(page_size, page_size, 1),
+            );
+
+            if mip_level_count > 1 {
+                MipmapGenerator::shared(device).generate(
+                    device,
+                    queue,
+                    texture,
+                    wgpu::TextureViewDimension::D2,
+                );
+            }
+
+            page.dirty = false;
+        }
+
+        created
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::texture::sheet::{Rect, SheetFrame};
+
+    fn overlaps(a: (u32, u32, u32, u32), b: (u32, u32, u32, u32)) -> bool {
+        a.0 < b.0 + b.2 && b.0 < a.0 + a.2 && a.1 < b.1 + b.3 && b.1 < a.1 + a.3
+    }
+
+    fn solid(width: u32, height: u32, texel: [u8; 4]) -> DecodedImage {
+        DecodedImage {
+            width,
+            height,
+            pixels: Pixels::Rgba8(texel.repeat((width * height) as usize)),
+        }
+    }
+
+    #[test]
+    fn skyline_rects_stay_apart_and_inside() {
+        let mut skyline = Skyline::new(64);
+        let sizes = [(20, 10), (7, 30), (33, 5), (12, 12), (40, 8), (5, 5)];
+
+        let mut placed: Vec<(u32, u32, u32, u32)> = Vec::new();
+        for _ in 0..3 {
+            for (width, height) in sizes {
+                let Some((x, y)) = skyline.insert(width, height) else {
+                    continue;
+                };
+                let rect = (x, y, width, height);
+
+                assert!(
+                    x + width <= 64 && y + height <= 64,
+                    "{rect:?} leaves the page"
+                );
+                for other in &placed {
+                    assert!(!overlaps(rect, *other), "{rect:?} overlaps {other:?}");
+                }
+                placed.push(rect);
+            }
+        }
+
+        assert!(placed.len() > sizes.len());
+    }
+
+    #[test]
+    fn skyline_refuses_what_doesnt_fit() {
+        let mut skyline = Skyline::new(16);
+
+        assert_eq!(skyline.insert(17, 1), None);
+        assert_eq!(skyline.insert(16, 10), Some((0, 0)));
+        assert_eq!(skyline.insert(4, 7), None);
+        assert_eq!(skyline.insert(4, 6), Some((0, 10)));
+    }
+
+    #[test]
+    fn skyline_segments_merge() {
+        let mut skyline = Skyline::new(16);
+        skyline.insert(8, 4);
+        skyline.insert(4, 2);
+        assert_eq!(skyline.nodes, [(0, 4, 8), (8, 2, 4), (12, 0, 4)]);
+
+        // the lowest gap fills first and joins its neighbour
+        assert_eq!(skyline.insert(4, 2), Some((12, 0)));
+        assert_eq!(skyline.nodes, [(0, 4, 8), (8, 2, 8)]);
+
+        assert_eq!(skyline.insert(8, 2), Some((8, 2)));
+        assert_eq!(skyline.nodes, [(0, 4, 16)]);
+    }
+
+    #[test]
+    fn images_are_padded_and_extruded() {
+        let mut atlas = TextureAtlas::new(AtlasOptions {
+            page_size: 16,
+            padding: 1,
+            extrude: 1,
+            ..Default::default()
+        });
+
+        let red = atlas.add("red", &solid(4, 4, [255, 0, 0, 255])).unwrap();
+        let blue = atlas.add("blue", &solid(3, 2, [0, 0, 255, 255])).unwrap();
+
+        // 1 texel of extrusion on each side and 1 of padding after
+        assert_eq!(red.uv_min, [1.0 / 16.0, 1.0 / 16.0]);
+        assert_eq!(red.uv_max, [5.0 / 16.0, 5.0 / 16.0]);
+        assert_eq!(blue.uv_min, [8.0 / 16.0, 1.0 / 16.0]);
+        assert_eq!(
+            (blue.size, blue.offset, blue.source_size),
+            ((3, 2), (0, 0), (3, 2))
+        );
+
+        let texel = |x: u32, y: u32| {
+            let offset = ((y * 16 + x) * 4) as usize;
+            &atlas.pages[0].texels[offset..offset + 4]
+        };
+        // the border repeats the edge, the padding stays empty
+        assert_eq!(texel(0, 0), [255, 0, 0, 255]);
+        assert_eq!(texel(5, 5), [255, 0, 0, 255]);
+        assert_eq!(texel(6, 3), [0, 0, 0, 0]);
+        assert_eq!(texel(7, 0), [0, 0, 255, 255]);
+        assert_eq!(texel(11, 3), [0, 0, 255, 255]);
+        assert_eq!(texel(12, 3), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn full_pages_spill_onto_new_ones() {
+        let mut atlas = TextureAtlas::new(AtlasOptions {
+            page_size: 8,
+            padding: 0,
+            extrude: 0,
+            ..Default::default()
+        });
+
+        for i in 0..5 {
+            let region = atlas.add(i.to_string(), &solid(4, 4, [0; 4])).unwrap();
+            assert_eq!(region.page, i / 4);
+        }
+        assert_eq!(atlas.page_count(), 2);
+
+        assert!(atlas.add("big", &solid(9, 1, [0; 4])).is_err());
+    }
+
+    #[test]
+    fn sheet_frames_carry_their_trim() {
+        let mut atlas = TextureAtlas::new(AtlasOptions::default());
+        let sheet = SpriteSheet {
+            image: None,
+            frames: vec![SheetFrame {
+                name: "coin".to_owned(),
+                rect: Rect {
+                    x: 1,
+                    y: 0,
+                    w: 2,
+                    h: 1,
+                },
+                rotated: false,
+                offset: (3, 5),
+                source_size: (8, 8),
+                duration: None,
+            }],
+            tags: Vec::new(),
+        };
+
+        atlas.add_sheet(&solid(4, 1, [9; 4]), &sheet).unwrap();
+        let region = atlas.region("coin").unwrap();
+
+        assert_eq!(
+            (region.size, region.offset, region.source_size),
+            ((2, 1), (3, 5), (8, 8))
+        );
+    }
+}
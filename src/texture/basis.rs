@@ -0,0 +1,831 @@
+// transcodes the etc1s flavor of basis universal, stored in ktx2 files with
+// BasisLZ supercompression. etc1s blocks are etc1 blocks with one color and
+// modifier table for both halves, so they carry over to etc2 unchanged. alpha
+// slices are decoded and re-encoded as eac
+
+use super::{encode, etc};
+
+// basis selectors run from the most negative modifier up, etc1 indices are
+// ordered small positive, large positive, small negative, large negative
+const ETC1_INDICES: [u64; 4] = [3, 2, 0, 1];
+
+// the order code length code lengths are stored in
+const CODE_LENGTH_ORDER: [usize; 21] = [
+    17, 18, 19, 20, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15, 16,
+];
+
+const IMAGE_DESC_LENGTH: usize = 20;
+const IS_P_FRAME: u32 = 0x02;
+
+const ENDPOINT_PRED_REPEAT_LAST: usize = 256;
+const ENDPOINT_PRED_MIN_REPEAT: u32 = 3;
+const SELECTOR_RLE_MIN: u32 = 3;
+const SELECTOR_RLE_LONG: usize = 63;
+
+// reads lsb first, past the end of the data as zeros
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    fn bits(&mut self, count: u32) -> u32 {
+        let mut value = 0;
+        for i in 0..count {
+            let byte = self.data.get(self.position / 8).copied().unwrap_or(0);
+            value |= ((byte >> (self.position % 8)) as u32 & 1) << i;
+            self.position += 1;
+        }
+        value
+    }
+
+    // groups of `chunk_bits` bits, each followed by a bit saying whether
+    // another group follows
+    fn vlc(&mut self, chunk_bits: u32) -> u32 {
+        let mut value = 0;
+        for shift in (0..32).step_by(chunk_bits as usize) {
+            let chunk = self.bits(chunk_bits + 1);
+            value |= (chunk & ((1 << chunk_bits) - 1)) << shift;
+            if chunk >> chunk_bits == 0 {
+                break;
+            }
+        }
+        value
+    }
+
+    fn huffman(&mut self) -> Result<Huffman, String> {
+        let symbol_count = self.bits(14) as usize;
+        if symbol_count == 0 {
+            return Huffman::new(&[]);
+        }
+
+        let mut code_lengths = [0; 21];
+        let stored = self.bits(5) as usize;
+        if !(1..=21).contains(&stored) {
+            return Err("invalid basis huffman table".to_owned());
+        }
+        for &symbol in &CODE_LENGTH_ORDER[..stored] {
+            code_lengths[symbol] = self.bits(3) as u8;
+        }
+        let code_lengths = Huffman::new(&code_lengths)?;
+
+        // code lengths 0 to 16, and runs of zeros or of the previous length
+        let mut lengths = vec![0; symbol_count];
+        let mut i = 0;
+        while i < symbol_count {
+            let (length, run) = match self.symbol(&code_lengths)? {
+                length @ 0..=16 => (length as u8, 1),
+                17 => (0, self.bits(3) as usize + 3),
+                18 => (0, self.bits(7) as usize + 11),
+                symbol if i > 0 => {
+                    let run = match symbol {
+                        19 => self.bits(2) as usize + 3,
+                        _ => self.bits(6) as usize + 7,
+                    };
+                    (lengths[i - 1], run)
+                }
+                _ => return Err("invalid basis huffman table".to_owned()),
+            };
+            if i + run > symbol_count {
+                return Err("invalid basis huffman table".to_owned());
+            }
+            lengths[i..i + run].fill(length);
+            i += run;
+        }
+
+        Huffman::new(&lengths)
+    }
+
+    // codes are read one bit at a time, the first bit read being the most
+    // significant bit of the canonical code
+    fn symbol(&mut self, table: &Huffman) -> Result<usize, String> {
+        let (mut code, mut first, mut index) = (0, 0, 0);
+        for &count in &table.counts[1..] {
+            code |= self.bits(1) as usize;
+            if code - first < count {
+                return Ok(table.symbols[index + code - first] as usize);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+
+        Err("invalid basis huffman code".to_owned())
+    }
+}
+
+// a canonical huffman code, as the number of codes of each length and the
+// symbols sorted by code
+struct Huffman {
+    counts: [usize; 17],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Result<Self, String> {
+        let mut counts = [0; 17];
+        for &length in lengths {
+            counts[length as usize] += 1;
+        }
+        counts[0] = 0;
+
+        let mut symbols = Vec::new();
+        for length in 1..=16 {
+            for (symbol, _) in lengths.iter().enumerate().filter(|(_, &l)| l == length) {
+                symbols.push(symbol as u16);
+            }
+        }
+
+        // a single symbol takes a one bit code, anything else has to fill
+        // the code space exactly
+        let mut space = 1i32;
+        for &count in &counts[1..] {
+            space = space * 2 - count as i32;
+        }
+        if symbols.len() > 1 && space != 0 {
+            return Err("invalid basis huffman table".to_owned());
+        }
+
+        Ok(Self { counts, symbols })
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Endpoint {
+    color: [u8; 3],
+    table: u8,
+}
+
+fn decode_endpoints(data: &[u8], count: usize) -> Result<Vec<Endpoint>, String> {
+    let mut reader = BitReader::new(data);
+    // color deltas are coded with one of three tables, picked by the range
+    // the previous value of the channel lies in
+    let color_deltas = [reader.huffman()?, reader.huffman()?, reader.huffman()?];
+    let table_deltas = reader.huffman()?;
+    let grayscale = reader.bits(1) == 1;
+
+    let mut previous = Endpoint {
+        color: [16; 3],
+        table: 0,
+    };
+    let mut endpoints = Vec::with_capacity(count);
+    for _ in 0..count {
+        let mut endpoint = previous;
+        endpoint.table = ((reader.symbol(&table_deltas)? + previous.table as usize) & 7) as u8;
+
+        for c in 0..if grayscale { 1 } else { 3 } {
+            let deltas = match previous.color[c] {
+                0..=9 => &color_deltas[0],
+                10..=21 => &color_deltas[1],
+                _ => &color_deltas[2],
+            };
+            let delta = reader.symbol(deltas)?;
+            endpoint.color[c] = ((previous.color[c] as usize + delta) & 31) as u8;
+        }
+        if grayscale {
+            endpoint.color = [endpoint.color[0]; 3];
+        }
+
+        endpoints.push(endpoint);
+        previous = endpoint;
+    }
+
+    Ok(endpoints)
+}
+
+// each selector is 4 rows of 4 two bit values, the leftmost texel lowest
+fn decode_selectors(data: &[u8], count: usize) -> Result<Vec<[u8; 4]>, String> {
+    let mut reader = BitReader::new(data);
+    if reader.bits(1) == 1 || reader.bits(1) == 1 {
+        return Err("basis global selector codebooks are not supported".to_owned());
+    }
+
+    if reader.bits(1) == 1 {
+        return Ok((0..count)
+            .map(|_| std::array::from_fn(|_| reader.bits(8) as u8))
+            .collect());
+    }
+
+    // every selector after the first is xored with the one before it
+    let deltas = reader.huffman()?;
+    let mut selectors = Vec::with_capacity(count);
+    let mut selector = [0u8; 4];
+    for i in 0..count {
+        for row in &mut selector {
+            *row = match i {
+                0 => reader.bits(8) as u8,
+                _ => *row ^ reader.symbol(&deltas)? as u8,
+            };
+        }
+        selectors.push(selector);
+    }
+
+    Ok(selectors)
+}
+
+struct Tables {
+    endpoint_preds: Huffman,
+    endpoint_deltas: Huffman,
+    selectors: Huffman,
+    selector_runs: Huffman,
+    history_size: usize,
+}
+
+impl Tables {
+    fn decode(data: &[u8]) -> Result<Self, String> {
+        let mut reader = BitReader::new(data);
+        let tables = Self {
+            endpoint_preds: reader.huffman()?,
+            endpoint_deltas: reader.huffman()?,
+            selectors: reader.huffman()?,
+            selector_runs: reader.huffman()?,
+            history_size: reader.bits(13) as usize,
+        };
+        if tables.history_size == 0 {
+            return Err("invalid basis selector history size".to_owned());
+        }
+
+        Ok(tables)
+    }
+}
+
+// recently used selectors. new ones go in the middle, and looking one up
+// swaps it halfway to the front
+struct SelectorHistory {
+    values: Vec<usize>,
+    rover: usize,
+}
+
+impl SelectorHistory {
+    fn new(size: usize) -> Self {
+        Self {
+            values: vec![0; size],
+            rover: size / 2,
+        }
+    }
+
+    fn add(&mut self, value: usize) {
+        self.values[self.rover] = value;
+        self.rover += 1;
+        if self.rover == self.values.len() {
+            self.rover = self.values.len() / 2;
+        }
+    }
+
+    fn take(&mut self, index: usize) -> usize {
+        let value = self.values[index];
+        self.values.swap(index / 2, index);
+        value
+    }
+}
+
+struct Codebooks {
+    endpoints: Vec<Endpoint>,
+    selectors: Vec<[u8; 4]>,
+    tables: Tables,
+}
+
+#[derive(Clone, Copy, Default)]
+struct Prediction {
+    endpoint: usize,
+    // the predictors of the next row's two blocks, when on an even row
+    bits: usize,
+}
+
+// the endpoint and selector of every block of one slice, row by row
+fn decode_slice(
+    codebooks: &Codebooks,
+    data: &[u8],
+    blocks_x: usize,
+    blocks_y: usize,
+) -> Result<Vec<(Endpoint, [u8; 4])>, String> {
+    let Codebooks {
+        endpoints,
+        selectors,
+        tables,
+    } = codebooks;
+    let invalid = || "invalid basis slice".to_owned();
+
+    let mut reader = BitReader::new(data);
+    let mut history = SelectorHistory::new(tables.history_size);
+    let history_start = selectors.len();
+    let run_symbol = history_start + tables.history_size;
+
+    let mut rows = [
+        vec![Prediction::default(); blocks_x],
+        vec![Prediction::default(); blocks_x],
+    ];
+    let (mut pred_bits, mut previous_bits, mut pred_repeats) = (0, 0, 0);
+    let (mut previous_endpoint, mut selector_run) = (0, 0);
+
+    let mut blocks = Vec::with_capacity(blocks_x * blocks_y);
+    for y in 0..blocks_y {
+        let (current, above) = match y % 2 {
+            0 => (0, 1),
+            _ => (1, 0),
+        };
+
+        for x in 0..blocks_x {
+            // each 2x2 group of blocks shares one symbol holding the 2 bit
+            // endpoint predictor of every block in it
+            if x % 2 == 0 {
+                if y % 2 == 0 {
+                    if pred_repeats > 0 {
+                        pred_repeats -= 1;
+                        pred_bits = previous_bits;
+                    } else {
+                        pred_bits = reader.symbol(&tables.endpoint_preds)?;
+                        if pred_bits == ENDPOINT_PRED_REPEAT_LAST {
+                            pred_repeats = reader.vlc(4) + ENDPOINT_PRED_MIN_REPEAT - 1;
+                            pred_bits = previous_bits;
+                        } else {
+                            previous_bits = pred_bits;
+                        }
+                    }
+                    rows[above][x].bits = pred_bits >> 4;
+                } else {
+                    pred_bits = rows[current][x].bits;
+                }
+            }
+
+            let endpoint = match pred_bits & 3 {
+                0 if x > 0 => previous_endpoint,
+                1 if y > 0 => rows[above][x].endpoint,
+                2 if x > 0 && y > 0 => rows[above][x - 1].endpoint,
+                3 => {
+                    let endpoint = reader.symbol(&tables.endpoint_deltas)? + previous_endpoint;
+                    match endpoint >= endpoints.len() {
+                        true => endpoint - endpoints.len(),
+                        false => endpoint,
+                    }
+                }
+                _ => return Err(invalid()),
+            };
+            pred_bits >>= 2;
+            rows[current][x].endpoint = endpoint;
+            previous_endpoint = endpoint;
+
+            // selectors are either new, or picked from the history, which
+            // can repeat its first entry for a run of blocks
+            let symbol = if selector_run > 0 {
+                selector_run -= 1;
+                history_start
+            } else {
+                let symbol = reader.symbol(&tables.selectors)?;
+                if symbol == run_symbol {
+                    let run = match reader.symbol(&tables.selector_runs)? {
+                        SELECTOR_RLE_LONG => reader.vlc(7) + SELECTOR_RLE_MIN,
+                        run => run as u32 + SELECTOR_RLE_MIN,
+                    };
+                    if run as usize > blocks_x * blocks_y {
+                        return Err(invalid());
+                    }
+                    selector_run = run - 1;
+                    history_start
+                } else {
+                    symbol
+                }
+            };
+            let selector = if symbol >= history_start {
+                let index = symbol - history_start;
+                if index >= tables.history_size {
+                    return Err(invalid());
+                }
+                history.take(index)
+            } else {
+                history.add(symbol);
+                symbol
+            };
+
+            let endpoint = *endpoints.get(endpoint).ok_or_else(invalid)?;
+            let selector = *selectors.get(selector).ok_or_else(invalid)?;
+            blocks.push((endpoint, selector));
+        }
+    }
+
+    Ok(blocks)
+}
+
+fn etc1_block(endpoint: Endpoint, selector: [u8; 4]) -> [u8; 8] {
+    let [r, g, b] = endpoint.color.map(u64::from);
+    let table = endpoint.table as u64;
+    let mut word = r << 59 | g << 51 | b << 43 | table << 37 | table << 34 | 0b11 << 32;
+
+    for (y, row) in selector.iter().enumerate() {
+        for x in 0..4 {
+            let index = ETC1_INDICES[(row >> (x * 2)) as usize & 3];
+            let j = x * 4 + y;
+            word |= (index >> 1) << (16 + j) | (index & 1) << j;
+        }
+    }
+
+    word.to_be_bytes()
+}
+
+fn alpha_block(endpoint: Endpoint, selector: [u8; 4]) -> [u8; 8] {
+    let mut texels = [[0u8; 4]; 16];
+    etc::decode_block(
+        wgpu::TextureFormat::Etc2Rgb8Unorm,
+        &etc1_block(endpoint, selector),
+        texels.as_flattened_mut(),
+    );
+
+    encode::eac_alpha_block(&texels.map(|texel| texel[1]))
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> Result<usize, String> {
+    bytes
+        .get(offset..offset + 2)
+        .map(|bytes| u16::from_le_bytes(bytes.try_into().unwrap()) as usize)
+        .ok_or("basis global data is truncated".to_owned())
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Result<usize, String> {
+    bytes
+        .get(offset..offset + 4)
+        .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()) as usize)
+        .ok_or("basis global data is truncated".to_owned())
+}
+
+// transcodes every image of every level to etc2 rgb8 blocks, or rgba8 blocks
+// when the images come with alpha slices. `global_data` is the ktx2
+// supercompression global data, `images` the number of layers times faces
+pub fn transcode_etc1s(
+    global_data: &[u8],
+    levels: &[&[u8]],
+    (width, height): (u32, u32),
+    images: usize,
+    alpha: bool,
+) -> Result<Vec<u8>, String> {
+    let endpoint_count = read_u16(global_data, 0)?;
+    let selector_count = read_u16(global_data, 2)?;
+
+    let descs_start = 20;
+    let endpoints_start = descs_start + levels.len() * images * IMAGE_DESC_LENGTH;
+    let selectors_start = endpoints_start + read_u32(global_data, 4)?;
+    let tables_start = selectors_start + read_u32(global_data, 8)?;
+    let tables_end = tables_start + read_u32(global_data, 12)?;
+    let section = |start: usize, end: usize| {
+        global_data
+            .get(start..end)
+            .ok_or("basis global data is truncated".to_owned())
+    };
+
+    let codebooks = Codebooks {
+        endpoints: decode_endpoints(section(endpoints_start, selectors_start)?, endpoint_count)?,
+        selectors: decode_selectors(section(selectors_start, tables_start)?, selector_count)?,
+        tables: Tables::decode(section(tables_start, tables_end)?)?,
+    };
+
+    let mut data = Vec::new();
+    for (level, level_data) in levels.iter().enumerate() {
+        let blocks_x = ((width >> level).max(1)).div_ceil(4) as usize;
+        let blocks_y = ((height >> level).max(1)).div_ceil(4) as usize;
+
+        for image in 0..images {
+            let desc = descs_start + (level * images + image) * IMAGE_DESC_LENGTH;
+            let field = |offset: usize| read_u32(global_data, desc + offset);
+            if field(0)? as u32 & IS_P_FRAME != 0 {
+                return Err("basis video frames are not supported".to_owned());
+            }
+
+            let slice = |offset: usize, length: usize| {
+                let data = level_data
+                    .get(offset..offset + length)
+                    .ok_or("basis slice is out of bounds".to_owned())?;
+                decode_slice(&codebooks, data, blocks_x, blocks_y)
+            };
+            let colors = slice(field(4)?, field(8)?)?;
+
+            if alpha {
+                let alphas = slice(field(12)?, field(16)?)?;
+                for ((endpoint, selector), alpha) in colors.into_iter().zip(alphas) {
+                    data.extend(alpha_block(alpha.0, alpha.1));
+                    data.extend(etc1_block(endpoint, selector));
+                }
+            } else {
+                for (endpoint, selector) in colors {
+                    data.extend(etc1_block(endpoint, selector));
+                }
+            }
+        }
+    }
+
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct BitWriter {
+        bytes: Vec<u8>,
+        position: usize,
+    }
+
+    impl BitWriter {
+        fn bits(&mut self, value: u32, count: u32) -> &mut Self {
+            for i in 0..count {
+                if self.position.is_multiple_of(8) {
+                    self.bytes.push(0);
+                }
+                *self.bytes.last_mut().unwrap() |= ((value >> i) as u8 & 1) << (self.position % 8);
+                self.position += 1;
+            }
+            self
+        }
+
+        // huffman codes go out most significant bit first
+        fn code(&mut self, lengths: &[u8], symbol: usize) -> &mut Self {
+            let length = lengths[symbol] as u32;
+            let code = canonical(lengths)[symbol];
+            for i in (0..length).rev() {
+                self.bits(code >> i, 1);
+            }
+            self
+        }
+
+        // stores every length with a complete code length code: 4 bits for
+        // lengths up to 10, 5 bits above
+        fn table(&mut self, lengths: &[u8]) -> &mut Self {
+            let code_lengths: Vec<u8> = (0..21).map(|i| if i <= 10 { 4 } else { 5 }).collect();
+
+            self.bits(lengths.len() as u32, 14).bits(21, 5);
+            for symbol in CODE_LENGTH_ORDER {
+                self.bits(code_lengths[symbol] as u32, 3);
+            }
+            for &length in lengths {
+                self.code(&code_lengths, length as usize);
+            }
+            self
+        }
+    }
+
+    fn canonical(lengths: &[u8]) -> Vec<u32> {
+        let mut codes = vec![0; lengths.len()];
+        let mut code = 0;
+        for length in 1..=16 {
+            for (symbol, _) in lengths.iter().enumerate().filter(|(_, &l)| l == length) {
+                codes[symbol] = code;
+                code += 1;
+            }
+            code <<= 1;
+        }
+        codes
+    }
+
+    // 2 endpoints and 2 selectors, with a history of 4
+    const TABLE_DELTAS: [u8; 8] = [3; 8];
+    const WIDE_DELTAS: [u8; 32] = [5; 32];
+    const NARROW_DELTAS: [u8; 16] = [4; 16];
+    const SELECTOR_DELTAS: [u8; 256] = [8; 256];
+    const ENDPOINT_DELTAS: [u8; 2] = [1, 1];
+    const SELECTOR_SYMBOLS: [u8; 7] = [3, 3, 3, 3, 3, 3, 2];
+    const SELECTOR_RUNS: [u8; 64] = [6; 64];
+
+    // the repeat symbol takes one bit, the 256 predictor groups nine
+    fn endpoint_preds() -> Vec<u8> {
+        let mut lengths = vec![9; 257];
+        lengths[256] = 1;
+        lengths
+    }
+
+    fn endpoint_data() -> Vec<u8> {
+        let mut writer = BitWriter::default();
+        // channels after values of 10 to 21 take the narrow table
+        writer
+            .table(&WIDE_DELTAS)
+            .table(&NARROW_DELTAS)
+            .table(&WIDE_DELTAS)
+            .table(&TABLE_DELTAS)
+            .bits(0, 1);
+
+        // table 2 and color 20, 16, 30
+        writer
+            .code(&TABLE_DELTAS, 2)
+            .code(&NARROW_DELTAS, 4)
+            .code(&NARROW_DELTAS, 0)
+            .code(&NARROW_DELTAS, 14);
+        // table 1 and color 3, 31, 14, wrapping around
+        writer
+            .code(&TABLE_DELTAS, 7)
+            .code(&NARROW_DELTAS, 15)
+            .code(&NARROW_DELTAS, 15)
+            .code(&WIDE_DELTAS, 16);
+        writer.bytes
+    }
+
+    fn selector_data() -> Vec<u8> {
+        let mut writer = BitWriter::default();
+        writer.bits(0, 3).table(&SELECTOR_DELTAS);
+        // rows of selectors 0 to 3, then their reverse
+        for _ in 0..4 {
+            writer.bits(0b11_10_01_00, 8);
+        }
+        for _ in 0..4 {
+            writer.code(&SELECTOR_DELTAS, 0b11_10_01_00 ^ 0b00_01_10_11);
+        }
+        writer.bytes
+    }
+
+    fn table_data() -> Vec<u8> {
+        let mut writer = BitWriter::default();
+        writer
+            .table(&endpoint_preds())
+            .table(&ENDPOINT_DELTAS)
+            .table(&SELECTOR_SYMBOLS)
+            .table(&SELECTOR_RUNS)
+            .bits(4, 13);
+        writer.bytes
+    }
+
+    // a 3x2 block slice exercising every predictor and selector source
+    fn slice_data() -> Vec<u8> {
+        let preds = endpoint_preds();
+        let mut writer = BitWriter::default();
+
+        // delta and left, then up and up left on the next row
+        writer.code(&preds, 3 | 1 << 4 | 2 << 6);
+        writer.code(&ENDPOINT_DELTAS, 1).code(&SELECTOR_SYMBOLS, 0);
+        writer.code(&SELECTOR_SYMBOLS, 1);
+        // the same predictors again, so delta then up
+        writer.code(&preds, 256).bits(0, 5);
+        writer
+            .code(&ENDPOINT_DELTAS, 1)
+            .code(&SELECTOR_SYMBOLS, 2 + 3);
+
+        writer.code(&SELECTOR_SYMBOLS, 2 + 1);
+        writer.code(&SELECTOR_SYMBOLS, 6).code(&SELECTOR_RUNS, 0);
+        writer.bytes
+    }
+
+    fn codebooks() -> Codebooks {
+        Codebooks {
+            endpoints: decode_endpoints(&endpoint_data(), 2).unwrap(),
+            selectors: decode_selectors(&selector_data(), 2).unwrap(),
+            tables: Tables::decode(&table_data()).unwrap(),
+        }
+    }
+
+    #[test]
+    fn huffman_tables_round_trip() {
+        let lengths = [2, 0, 3, 3, 1, 0];
+        let mut writer = BitWriter::default();
+        writer.table(&lengths);
+        for symbol in [4, 0, 2, 3, 4] {
+            writer.code(&lengths, symbol);
+        }
+
+        let mut reader = BitReader::new(&writer.bytes);
+        let table = reader.huffman().unwrap();
+        let symbols: Vec<_> = (0..5).map(|_| reader.symbol(&table).unwrap()).collect();
+
+        assert_eq!(symbols, [4, 0, 2, 3, 4]);
+    }
+
+    #[test]
+    fn huffman_tables_have_to_be_complete() {
+        let mut writer = BitWriter::default();
+        writer.table(&[2, 2, 2]);
+
+        assert!(BitReader::new(&writer.bytes).huffman().is_err());
+    }
+
+    #[test]
+    fn vlc_chains_chunks() {
+        let mut writer = BitWriter::default();
+        // 0x35 as 5 with a continuation bit, then 3
+        writer.bits(0b1_0101, 5).bits(0b0_0011, 5);
+
+        assert_eq!(BitReader::new(&writer.bytes).vlc(4), 0x35);
+    }
+
+    #[test]
+    fn codebooks_accumulate_deltas() {
+        let Codebooks {
+            endpoints,
+            selectors,
+            ..
+        } = codebooks();
+
+        assert_eq!((endpoints[0].color, endpoints[0].table), ([20, 16, 30], 2));
+        assert_eq!((endpoints[1].color, endpoints[1].table), ([3, 31, 14], 1));
+        assert_eq!(selectors, [[0b11_10_01_00; 4], [0b00_01_10_11; 4]]);
+    }
+
+    #[test]
+    fn slices_follow_predictors_and_history() {
+        let blocks = decode_slice(&codebooks(), &slice_data(), 3, 2).unwrap();
+        let blocks: Vec<_> = blocks
+            .iter()
+            .map(|(endpoint, selector)| (endpoint.table, selector[0]))
+            .collect();
+
+        let (first, second) = (1, 2);
+        let (forward, reverse) = (0b11_10_01_00, 0b00_01_10_11);
+        assert_eq!(
+            blocks,
+            [
+                (first, forward),
+                (first, reverse),
+                (second, reverse),
+                (first, reverse),
+                (first, reverse),
+                (second, reverse),
+            ]
+        );
+    }
+
+    #[test]
+    fn etc1_blocks_map_selectors_to_modifiers() {
+        let endpoint = Endpoint {
+            color: [20, 16, 14],
+            table: 2,
+        };
+        let mut texels = [[0u8; 4]; 16];
+        etc::decode_block(
+            wgpu::TextureFormat::Etc2Rgb8Unorm,
+            &etc1_block(endpoint, [0b11_10_01_00; 4]),
+            texels.as_flattened_mut(),
+        );
+
+        // 20, 16 and 14 widen to 165, 132 and 115
+        for (x, modifier) in [-29, -9, 9, 29].into_iter().enumerate() {
+            let expected = [165, 132, 115].map(|c: i32| (c + modifier) as u8);
+            assert_eq!(texels[x][..3], expected);
+            assert_eq!(texels[12 + x][..3], expected);
+        }
+    }
+
+    fn global_data(flags: u32, slice_length: usize) -> Vec<u8> {
+        let (endpoints, selectors, tables) = (endpoint_data(), selector_data(), table_data());
+        let mut data = Vec::new();
+        data.extend(2u16.to_le_bytes());
+        data.extend(2u16.to_le_bytes());
+        for length in [endpoints.len(), selectors.len(), tables.len(), 0] {
+            data.extend((length as u32).to_le_bytes());
+        }
+        // the color slice, then the same slice again for alpha
+        for field in [flags, 0, slice_length as u32, 0, slice_length as u32] {
+            data.extend(field.to_le_bytes());
+        }
+        data.extend(endpoints);
+        data.extend(selectors);
+        data.extend(tables);
+        data
+    }
+
+    #[test]
+    fn alpha_slices_become_eac_blocks() {
+        let slice = slice_data();
+        let data = transcode_etc1s(&global_data(0, slice.len()), &[&slice], (12, 8), 1, true);
+        let data = data.unwrap();
+        assert_eq!(data.len(), 6 * 16);
+
+        let codebooks = codebooks();
+        let color = etc1_block(codebooks.endpoints[1], codebooks.selectors[0]);
+        assert_eq!(data[8..16], color);
+
+        let (mut colors, mut alphas) = ([[0u8; 4]; 16], [[0u8; 4]; 16]);
+        etc::decode_block(
+            wgpu::TextureFormat::Etc2Rgb8Unorm,
+            &color,
+            colors.as_flattened_mut(),
+        );
+        etc::decode_block(
+            wgpu::TextureFormat::Etc2Rgba8Unorm,
+            &data[..16],
+            alphas.as_flattened_mut(),
+        );
+        for (color, alpha) in colors.iter().zip(&alphas) {
+            assert!(color[1].abs_diff(alpha[3]) <= 17);
+        }
+    }
+
+    #[test]
+    fn video_frames_are_rejected() {
+        let slice = slice_data();
+        let data = global_data(IS_P_FRAME, slice.len());
+
+        assert_eq!(
+            transcode_etc1s(&data, &[&slice], (12, 8), 1, false),
+            Err("basis video frames are not supported".to_owned())
+        );
+    }
+
+    #[test]
+    fn truncated_global_data_is_rejected() {
+        let slice = slice_data();
+        let data = global_data(0, slice.len());
+
+        assert_eq!(
+            transcode_etc1s(&data[..data.len() - 1], &[&slice], (12, 8), 1, false),
+            Err("basis global data is truncated".to_owned())
+        );
+    }
+}
@@ -0,0 +1,824 @@
+// cpu decoders for the bc block formats, used when the adapter has no
+// TEXTURE_COMPRESSION_BC support. every block is 4x4 texels.
+
+use half::f16;
+
+fn unpack_565(color: u16) -> [u8; 3] {
+    let r = (color >> 11) & 0x1f;
+    let g = (color >> 5) & 0x3f;
+    let b = color & 0x1f;
+
+    [
+        ((r << 3) | (r >> 2)) as u8,
+        ((g << 2) | (g >> 4)) as u8,
+        ((b << 3) | (b >> 2)) as u8,
+    ]
+}
+
+fn mix(a: [u8; 3], b: [u8; 3], wa: u16, wb: u16) -> [u8; 4] {
+    let channel = |i: usize| ((a[i] as u16 * wa + b[i] as u16 * wb) / (wa + wb)) as u8;
+
+    [channel(0), channel(1), channel(2), 255]
+}
+
+// `bc1_alpha` enables the 3 color + transparent mode, bc2 and bc3 always use
+// the 4 color palette
+pub(super) fn color_palette(c0: u16, c1: u16, bc1_alpha: bool) -> [[u8; 4]; 4] {
+    let (a, b) = (unpack_565(c0), unpack_565(c1));
+    if c0 > c1 || !bc1_alpha {
+        [
+            [a[0], a[1], a[2], 255],
+            [b[0], b[1], b[2], 255],
+            mix(a, b, 2, 1),
+            mix(a, b, 1, 2),
+        ]
+    } else {
+        [
+            [a[0], a[1], a[2], 255],
+            [b[0], b[1], b[2], 255],
+            mix(a, b, 1, 1),
+            [0, 0, 0, 0],
+        ]
+    }
+}
+
+fn decode_color_block(block: &[u8], bc1_alpha: bool) -> [[u8; 4]; 16] {
+    let c0 = u16::from_le_bytes([block[0], block[1]]);
+    let c1 = u16::from_le_bytes([block[2], block[3]]);
+    let indices = u32::from_le_bytes([block[4], block[5], block[6], block[7]]);
+    let palette = color_palette(c0, c1, bc1_alpha);
+
+    std::array::from_fn(|i| palette[((indices >> (i * 2)) & 0b11) as usize])
+}
+
+fn value_indices(block: &[u8]) -> u64 {
+    let mut bits = [0u8; 8];
+    bits[..6].copy_from_slice(&block[2..8]);
+    u64::from_le_bytes(bits)
+}
+
+pub(super) fn value_palette(v0: u8, v1: u8) -> [u8; 8] {
+    let (v0, v1) = (v0 as u16, v1 as u16);
+
+    std::array::from_fn(|i| {
+        let i = i as u16;
+        match i {
+            0 => v0 as u8,
+            1 => v1 as u8,
+            _ if v0 > v1 => ((v0 * (8 - i) + v1 * (i - 1)) / 7) as u8,
+            6 => 0,
+            7 => 255,
+            _ => ((v0 * (6 - i) + v1 * (i - 1)) / 5) as u8,
+        }
+    })
+}
+
+// the 8 byte alpha block of bc3, also used for each channel of bc4 and bc5
+fn decode_value_block(block: &[u8]) -> [u8; 16] {
+    let palette = value_palette(block[0], block[1]);
+    let indices = value_indices(block);
+
+    std::array::from_fn(|i| palette[((indices >> (i * 3)) & 0b111) as usize])
+}
+
+// the snorm variant of a bc4 channel, -128 decodes like -127
+fn decode_signed_value_block(block: &[u8]) -> [i8; 16] {
+    let v0 = (block[0] as i8).max(-127) as i32;
+    let v1 = (block[1] as i8).max(-127) as i32;
+    let indices = value_indices(block);
+
+    let divide = |value: i32, by: i32| (value as f32 / by as f32).round() as i8;
+    let palette: [i8; 8] = std::array::from_fn(|i| {
+        let i = i as i32;
+        match i {
+            0 => v0 as i8,
+            1 => v1 as i8,
+            _ if v0 > v1 => divide(v0 * (8 - i) + v1 * (i - 1), 7),
+            6 => -127,
+            7 => 127,
+            _ => divide(v0 * (6 - i) + v1 * (i - 1), 5),
+        }
+    });
+
+    std::array::from_fn(|i| palette[((indices >> (i * 3)) & 0b111) as usize])
+}
+
+// bc6h and bc7 pack their fields lsb first across the whole 128 bit block
+struct Bits {
+    bits: u128,
+    position: u32,
+}
+
+impl Bits {
+    fn new(block: &[u8]) -> Self {
+        Self {
+            bits: u128::from_le_bytes(block[..16].try_into().unwrap()),
+            position: 0,
+        }
+    }
+
+    fn read(&mut self, count: u32) -> u32 {
+        let value = (self.bits >> self.position) & ((1 << count) - 1);
+        self.position += count;
+        value as u32
+    }
+}
+
+const WEIGHTS_2: [u32; 4] = [0, 21, 43, 64];
+const WEIGHTS_3: [u32; 8] = [0, 9, 18, 27, 37, 46, 55, 64];
+const WEIGHTS_4: [u32; 16] = [0, 4, 9, 13, 17, 21, 26, 30, 34, 38, 43, 47, 51, 55, 60, 64];
+
+fn weight(index: u32, bits: u32) -> u32 {
+    match bits {
+        2 => WEIGHTS_2[index as usize],
+        3 => WEIGHTS_3[index as usize],
+        _ => WEIGHTS_4[index as usize],
+    }
+}
+
+// subset of every texel, shared by bc6h (the first 32) and bc7
+#[rustfmt::skip]
+const PARTITIONS_2: [&[u8; 16]; 64] = [
+    b"0011001100110011", b"0001000100010001", b"0111011101110111", b"0001001100110111",
+    b"0000000100010011", b"0011011101111111", b"0001001101111111", b"0000000100110111",
+    b"0000000000010011", b"0011011111111111", b"0000000101111111", b"0000000000010111",
+    b"0001011111111111", b"0000000011111111", b"0000111111111111", b"0000000000001111",
+    b"0000100011101111", b"0111000100000000", b"0000000010001110", b"0111001100010000",
+    b"0011000100000000", b"0000100011001110", b"0000000010001100", b"0111001100110001",
+    b"0011000100010000", b"0000100010001100", b"0110011001100110", b"0011011001101100",
+    b"0001011111101000", b"0000111111110000", b"0111000110001110", b"0011100110011100",
+    b"0101010101010101", b"0000111100001111", b"0101101001011010", b"0011001111001100",
+    b"0011110000111100", b"0101010110101010", b"0110100101101001", b"0101101010100101",
+    b"0111001111001110", b"0001001111001000", b"0011001001001100", b"0011101111011100",
+    b"0110100110010110", b"0011110011000011", b"0110011010011001", b"0000011001100000",
+    b"0100111001000000", b"0010011100100000", b"0000001001110010", b"0000010011100100",
+    b"0110110010010011", b"0011011011001001", b"0110001110011100", b"0011100111000110",
+    b"0110110011001001", b"0110001100111001", b"0111111010000001", b"0001100011100111",
+    b"0000111100110011", b"0011001111110000", b"0010001011101110", b"0100010001110111",
+];
+
+#[rustfmt::skip]
+const PARTITIONS_3: [&[u8; 16]; 64] = [
+    b"0011001102212222", b"0001001122112221", b"0000200122112211", b"0222002200110111",
+    b"0000000011221122", b"0011001100220022", b"0022002211111111", b"0011001122112211",
+    b"0000000011112222", b"0000111111112222", b"0000111122222222", b"0012001200120012",
+    b"0112011201120112", b"0122012201220122", b"0011011211221222", b"0011200122002220",
+    b"0001001101121122", b"0111001120012200", b"0000112211221122", b"0022002200221111",
+    b"0111011102220222", b"0001000122212221", b"0000001101220122", b"0000110022102210",
+    b"0122012200110000", b"0012001211222222", b"0110122112210110", b"0000011012211221",
+    b"0022110211020022", b"0110011020022222", b"0011012201220011", b"0000200022112221",
+    b"0000000211221222", b"0222002200120011", b"0011001200220222", b"0120012001200120",
+    b"0000111122220000", b"0120120120120120", b"0120201212010120", b"0011220011220011",
+    b"0011112222000011", b"0101010122222222", b"0000000021212121", b"0022112200221122",
+    b"0022001100220011", b"0220122102201221", b"0101222222220101", b"0000212121212121",
+    b"0101010101012222", b"0222011102220111", b"0002111200021112", b"0000211221122112",
+    b"0222011101110222", b"0002111211120002", b"0110011001102222", b"0000000021122112",
+    b"0110011022222222", b"0022001100110022", b"0022112211220022", b"0000000000002112",
+    b"0002000100020001", b"0222122202221222", b"0101222222222222", b"0111201122012220",
+];
+
+// the texel of each subset past the first whose index drops its top bit
+#[rustfmt::skip]
+const ANCHORS_2: [usize; 64] = [
+    15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15,
+    15, 2, 8, 2, 2, 8, 8, 15, 2, 8, 2, 2, 8, 8, 2, 2,
+    15, 15, 6, 8, 2, 8, 15, 15, 2, 8, 2, 2, 2, 15, 15, 6,
+    6, 2, 6, 8, 15, 15, 2, 2, 15, 15, 15, 15, 15, 2, 2, 15,
+];
+
+#[rustfmt::skip]
+const ANCHORS_3: [[usize; 2]; 64] = [
+    [3, 15], [3, 8], [15, 8], [15, 3], [8, 15], [3, 15], [15, 3], [15, 8],
+    [8, 15], [8, 15], [6, 15], [6, 15], [6, 15], [5, 15], [3, 15], [3, 8],
+    [3, 15], [3, 8], [8, 15], [15, 3], [3, 15], [3, 8], [6, 15], [10, 8],
+    [5, 3], [8, 15], [8, 6], [6, 10], [8, 15], [5, 15], [15, 10], [15, 8],
+    [8, 15], [15, 3], [3, 15], [5, 10], [6, 10], [10, 8], [8, 9], [15, 10],
+    [15, 6], [3, 15], [15, 8], [5, 15], [15, 3], [15, 6], [15, 6], [15, 8],
+    [3, 15], [15, 3], [5, 15], [5, 15], [5, 15], [8, 15], [5, 15], [10, 15],
+    [5, 15], [10, 15], [8, 15], [13, 15], [15, 3], [12, 15], [3, 15], [3, 8],
+];
+
+fn subset(subsets: usize, partition: usize, texel: usize) -> usize {
+    match subsets {
+        1 => 0,
+        2 => (PARTITIONS_2[partition][texel] - b'0') as usize,
+        _ => (PARTITIONS_3[partition][texel] - b'0') as usize,
+    }
+}
+
+fn is_anchor(subsets: usize, partition: usize, texel: usize) -> bool {
+    texel == 0
+        || match subsets {
+            1 => false,
+            2 => ANCHORS_2[partition] == texel,
+            _ => ANCHORS_3[partition].contains(&texel),
+        }
+}
+
+struct Bc7Mode {
+    subsets: usize,
+    partition_bits: u32,
+    rotation_bits: u32,
+    selector_bits: u32,
+    color_bits: u32,
+    alpha_bits: u32,
+    endpoint_pbits: bool,
+    shared_pbits: bool,
+    index_bits: u32,
+    index2_bits: u32,
+}
+
+#[rustfmt::skip]
+const BC7_MODES: [Bc7Mode; 8] = [
+    Bc7Mode { subsets: 3, partition_bits: 4, rotation_bits: 0, selector_bits: 0, color_bits: 4,
+        alpha_bits: 0, endpoint_pbits: true, shared_pbits: false, index_bits: 3, index2_bits: 0 },
+    Bc7Mode { subsets: 2, partition_bits: 6, rotation_bits: 0, selector_bits: 0, color_bits: 6,
+        alpha_bits: 0, endpoint_pbits: false, shared_pbits: true, index_bits: 3, index2_bits: 0 },
+    Bc7Mode { subsets: 3, partition_bits: 6, rotation_bits: 0, selector_bits: 0, color_bits: 5,
+        alpha_bits: 0, endpoint_pbits: false, shared_pbits: false, index_bits: 2, index2_bits: 0 },
+    Bc7Mode { subsets: 2, partition_bits: 6, rotation_bits: 0, selector_bits: 0, color_bits: 7,
+        alpha_bits: 0, endpoint_pbits: true, shared_pbits: false, index_bits: 2, index2_bits: 0 },
+    Bc7Mode { subsets: 1, partition_bits: 0, rotation_bits: 2, selector_bits: 1, color_bits: 5,
+        alpha_bits: 6, endpoint_pbits: false, shared_pbits: false, index_bits: 2, index2_bits: 3 },
+    Bc7Mode { subsets: 1, partition_bits: 0, rotation_bits: 2, selector_bits: 0, color_bits: 7,
+        alpha_bits: 8, endpoint_pbits: false, shared_pbits: false, index_bits: 2, index2_bits: 2 },
+    Bc7Mode { subsets: 1, partition_bits: 0, rotation_bits: 0, selector_bits: 0, color_bits: 7,
+        alpha_bits: 7, endpoint_pbits: true, shared_pbits: false, index_bits: 4, index2_bits: 0 },
+    Bc7Mode { subsets: 2, partition_bits: 6, rotation_bits: 0, selector_bits: 0, color_bits: 5,
+        alpha_bits: 5, endpoint_pbits: true, shared_pbits: false, index_bits: 2, index2_bits: 0 },
+];
+
+fn decode_bc7(block: &[u8]) -> [[u8; 4]; 16] {
+    let mut bits = Bits::new(block);
+
+    // the mode is the number of zero bits before the first set one, blocks
+    // without any set bit are reserved and decode to transparent black
+    let Some(mode) = (0..8).find(|_| bits.read(1) == 1) else {
+        return [[0; 4]; 16];
+    };
+    let mode = &BC7_MODES[mode];
+
+    let partition = bits.read(mode.partition_bits) as usize;
+    let rotation = bits.read(mode.rotation_bits);
+    let selector = bits.read(mode.selector_bits);
+
+    let endpoint_count = mode.subsets * 2;
+    let mut endpoints = [[0u32; 4]; 6];
+    for channel in 0..4 {
+        let count = if channel < 3 {
+            mode.color_bits
+        } else {
+            mode.alpha_bits
+        };
+        for endpoint in &mut endpoints[..endpoint_count] {
+            endpoint[channel] = bits.read(count);
+        }
+    }
+
+    let (mut color_bits, mut alpha_bits) = (mode.color_bits, mode.alpha_bits);
+    if mode.endpoint_pbits || mode.shared_pbits {
+        let mut pbit = 0;
+        for (i, endpoint) in endpoints[..endpoint_count].iter_mut().enumerate() {
+            if mode.endpoint_pbits || i % 2 == 0 {
+                pbit = bits.read(1);
+            }
+            for channel in endpoint.iter_mut() {
+                *channel = *channel << 1 | pbit;
+            }
+        }
+        color_bits += 1;
+        if alpha_bits > 0 {
+            alpha_bits += 1;
+        }
+    }
+
+    let expand = |value: u32, bits: u32| {
+        let value = value << (8 - bits);
+        value | value >> bits
+    };
+    let endpoints = endpoints.map(|[r, g, b, a]| {
+        let alpha = if alpha_bits > 0 {
+            expand(a, alpha_bits)
+        } else {
+            255
+        };
+        [
+            expand(r, color_bits),
+            expand(g, color_bits),
+            expand(b, color_bits),
+            alpha,
+        ]
+    });
+
+    let mut indices = [0; 16];
+    for (i, index) in indices.iter_mut().enumerate() {
+        let anchor = is_anchor(mode.subsets, partition, i);
+        *index = bits.read(mode.index_bits - anchor as u32);
+    }
+    let mut indices2 = [0; 16];
+    if mode.index2_bits > 0 {
+        for (i, index) in indices2.iter_mut().enumerate() {
+            *index = bits.read(mode.index2_bits - (i == 0) as u32);
+        }
+    }
+
+    std::array::from_fn(|i| {
+        let s = subset(mode.subsets, partition, i);
+        let (e0, e1) = (endpoints[s * 2], endpoints[s * 2 + 1]);
+
+        // modes 4 and 5 keep separate color and alpha indices, the selector
+        // bit of mode 4 swaps which one gets the wider indices
+        let (color, alpha) = match (mode.index2_bits, selector) {
+            (0, _) => {
+                let w = weight(indices[i], mode.index_bits);
+                (w, w)
+            }
+            (_, 0) => (
+                weight(indices[i], mode.index_bits),
+                weight(indices2[i], mode.index2_bits),
+            ),
+            _ => (
+                weight(indices2[i], mode.index2_bits),
+                weight(indices[i], mode.index_bits),
+            ),
+        };
+
+        let interpolate = |c: usize, w: u32| (((64 - w) * e0[c] + w * e1[c] + 32) >> 6) as u8;
+        let mut texel = [
+            interpolate(0, color),
+            interpolate(1, color),
+            interpolate(2, color),
+            interpolate(3, alpha),
+        ];
+        if rotation > 0 {
+            texel.swap(rotation as usize - 1, 3);
+        }
+        texel
+    })
+}
+
+// bc6h endpoint fields, endpoints are (rw gw bw) (rx gx bx) (ry gy by) (rz gz
+// bz) in the spec's names, then the partition
+const RW: u8 = 0;
+const GW: u8 = 1;
+const BW: u8 = 2;
+const RX: u8 = 3;
+const GX: u8 = 4;
+const BX: u8 = 5;
+const RY: u8 = 6;
+const GY: u8 = 7;
+const BY: u8 = 8;
+const RZ: u8 = 9;
+const GZ: u8 = 10;
+const BZ: u8 = 11;
+const D: u8 = 12;
+
+struct Bc6hMode {
+    bits: u32,
+    subsets: usize,
+    transformed: bool,
+    precision: u32,
+    delta_bits: [u32; 3],
+    // (field, lowest bit, bit count) in the order they follow the mode bits
+    layout: &'static [(u8, u8, u8)],
+}
+
+#[rustfmt::skip]
+const BC6H_MODES: [Bc6hMode; 14] = [
+    Bc6hMode { bits: 0b00, subsets: 2, transformed: true, precision: 10, delta_bits: [5, 5, 5], layout: &[
+        (GY, 4, 1), (BY, 4, 1), (BZ, 4, 1), (RW, 0, 10), (GW, 0, 10), (BW, 0, 10), (RX, 0, 5),
+        (GZ, 4, 1), (GY, 0, 4), (GX, 0, 5), (BZ, 0, 1), (GZ, 0, 4), (BX, 0, 5), (BZ, 1, 1),
+        (BY, 0, 4), (RY, 0, 5), (BZ, 2, 1), (RZ, 0, 5), (BZ, 3, 1), (D, 0, 5),
+    ] },
+    Bc6hMode { bits: 0b01, subsets: 2, transformed: true, precision: 7, delta_bits: [6, 6, 6], layout: &[
+        (GY, 5, 1), (GZ, 4, 1), (GZ, 5, 1), (RW, 0, 7), (BZ, 0, 1), (BZ, 1, 1), (BY, 4, 1),
+        (GW, 0, 7), (BY, 5, 1), (BZ, 2, 1), (GY, 4, 1), (BW, 0, 7), (BZ, 3, 1), (BZ, 5, 1),
+        (BZ, 4, 1), (RX, 0, 6), (GY, 0, 4), (GX, 0, 6), (GZ, 0, 4), (BX, 0, 6), (BY, 0, 4),
+        (RY, 0, 6), (RZ, 0, 6), (D, 0, 5),
+    ] },
+    Bc6hMode { bits: 0b00010, subsets: 2, transformed: true, precision: 11, delta_bits: [5, 4, 4], layout: &[
+        (RW, 0, 10), (GW, 0, 10), (BW, 0, 10), (RX, 0, 5), (RW, 10, 1), (GY, 0, 4), (GX, 0, 4),
+        (GW, 10, 1), (BZ, 0, 1), (GZ, 0, 4), (BX, 0, 4), (BW, 10, 1), (BZ, 1, 1), (BY, 0, 4),
+        (RY, 0, 5), (BZ, 2, 1), (RZ, 0, 5), (BZ, 3, 1), (D, 0, 5),
+    ] },
+    Bc6hMode { bits: 0b00110, subsets: 2, transformed: true, precision: 11, delta_bits: [4, 5, 4], layout: &[
+        (RW, 0, 10), (GW, 0, 10), (BW, 0, 10), (RX, 0, 4), (RW, 10, 1), (GZ, 4, 1), (GY, 0, 4),
+        (GX, 0, 5), (GW, 10, 1), (GZ, 0, 4), (BX, 0, 4), (BW, 10, 1), (BZ, 1, 1), (BY, 0, 4),
+        (RY, 0, 4), (BZ, 0, 1), (BZ, 2, 1), (RZ, 0, 4), (GY, 4, 1), (BZ, 3, 1), (D, 0, 5),
+    ] },
+    Bc6hMode { bits: 0b01010, subsets: 2, transformed: true, precision: 11, delta_bits: [4, 4, 5], layout: &[
+        (RW, 0, 10), (GW, 0, 10), (BW, 0, 10), (RX, 0, 4), (RW, 10, 1), (BY, 4, 1), (GY, 0, 4),
+        (GX, 0, 4), (GW, 10, 1), (BZ, 0, 1), (GZ, 0, 4), (BX, 0, 5), (BW, 10, 1), (BY, 0, 4),
+        (RY, 0, 4), (BZ, 1, 1), (BZ, 2, 1), (RZ, 0, 4), (BZ, 4, 1), (BZ, 3, 1), (D, 0, 5),
+    ] },
+    Bc6hMode { bits: 0b01110, subsets: 2, transformed: true, precision: 9, delta_bits: [5, 5, 5], layout: &[
+        (RW, 0, 9), (BY, 4, 1), (GW, 0, 9), (GY, 4, 1), (BW, 0, 9), (BZ, 4, 1), (RX, 0, 5),
+        (GZ, 4, 1), (GY, 0, 4), (GX, 0, 5), (BZ, 0, 1), (GZ, 0, 4), (BX, 0, 5), (BZ, 1, 1),
+        (BY, 0, 4), (RY, 0, 5), (BZ, 2, 1), (RZ, 0, 5), (BZ, 3, 1), (D, 0, 5),
+    ] },
+    Bc6hMode { bits: 0b10010, subsets: 2, transformed: true, precision: 8, delta_bits: [6, 5, 5], layout: &[
+        (RW, 0, 8), (GZ, 4, 1), (BY, 4, 1), (GW, 0, 8), (BZ, 2, 1), (GY, 4, 1), (BW, 0, 8),
+        (BZ, 3, 1), (BZ, 4, 1), (RX, 0, 6), (GY, 0, 4), (GX, 0, 5), (BZ, 0, 1), (GZ, 0, 4),
+        (BX, 0, 5), (BZ, 1, 1), (BY, 0, 4), (RY, 0, 6), (RZ, 0, 6), (D, 0, 5),
+    ] },
+    Bc6hMode { bits: 0b10110, subsets: 2, transformed: true, precision: 8, delta_bits: [5, 6, 5], layout: &[
+        (RW, 0, 8), (BZ, 0, 1), (BY, 4, 1), (GW, 0, 8), (GY, 5, 1), (GY, 4, 1), (BW, 0, 8),
+        (GZ, 5, 1), (BZ, 4, 1), (RX, 0, 5), (GZ, 4, 1), (GY, 0, 4), (GX, 0, 6), (GZ, 0, 4),
+        (BX, 0, 5), (BZ, 1, 1), (BY, 0, 4), (RY, 0, 5), (BZ, 2, 1), (RZ, 0, 5), (BZ, 3, 1),
+        (D, 0, 5),
+    ] },
+    Bc6hMode { bits: 0b11010, subsets: 2, transformed: true, precision: 8, delta_bits: [5, 5, 6], layout: &[
+        (RW, 0, 8), (BZ, 1, 1), (BY, 4, 1), (GW, 0, 8), (BY, 5, 1), (GY, 4, 1), (BW, 0, 8),
+        (BZ, 5, 1), (BZ, 4, 1), (RX, 0, 5), (GZ, 4, 1), (GY, 0, 4), (GX, 0, 5), (BZ, 0, 1),
+        (GZ, 0, 4), (BX, 0, 6), (BY, 0, 4), (RY, 0, 5), (BZ, 2, 1), (RZ, 0, 5), (BZ, 3, 1),
+        (D, 0, 5),
+    ] },
+    Bc6hMode { bits: 0b11110, subsets: 2, transformed: false, precision: 6, delta_bits: [6, 6, 6], layout: &[
+        (RW, 0, 6), (GZ, 4, 1), (BZ, 0, 1), (BZ, 1, 1), (BY, 4, 1), (GW, 0, 6), (GY, 5, 1),
+        (BY, 5, 1), (BZ, 2, 1), (GY, 4, 1), (BW, 0, 6), (GZ, 5, 1), (BZ, 3, 1), (BZ, 5, 1),
+        (BZ, 4, 1), (RX, 0, 6), (GY, 0, 4), (GX, 0, 6), (GZ, 0, 4), (BX, 0, 6), (BY, 0, 4),
+        (RY, 0, 6), (RZ, 0, 6), (D, 0, 5),
+    ] },
+    Bc6hMode { bits: 0b00011, subsets: 1, transformed: false, precision: 10, delta_bits: [10, 10, 10], layout: &[
+        (RW, 0, 10), (GW, 0, 10), (BW, 0, 10), (RX, 0, 10), (GX, 0, 10), (BX, 0, 10),
+    ] },
+    Bc6hMode { bits: 0b00111, subsets: 1, transformed: true, precision: 11, delta_bits: [9, 9, 9], layout: &[
+        (RW, 0, 10), (GW, 0, 10), (BW, 0, 10), (RX, 0, 9), (RW, 10, 1), (GX, 0, 9), (GW, 10, 1),
+        (BX, 0, 9), (BW, 10, 1),
+    ] },
+    // the top endpoint bits of the last two modes are stored reversed
+    Bc6hMode { bits: 0b01011, subsets: 1, transformed: true, precision: 12, delta_bits: [8, 8, 8], layout: &[
+        (RW, 0, 10), (GW, 0, 10), (BW, 0, 10), (RX, 0, 8), (RW, 11, 1), (RW, 10, 1), (GX, 0, 8),
+        (GW, 11, 1), (GW, 10, 1), (BX, 0, 8), (BW, 11, 1), (BW, 10, 1),
+    ] },
+    Bc6hMode { bits: 0b01111, subsets: 1, transformed: true, precision: 16, delta_bits: [4, 4, 4], layout: &[
+        (RW, 0, 10), (GW, 0, 10), (BW, 0, 10), (RX, 0, 4), (RW, 15, 1), (RW, 14, 1), (RW, 13, 1),
+        (RW, 12, 1), (RW, 11, 1), (RW, 10, 1), (GX, 0, 4), (GW, 15, 1), (GW, 14, 1), (GW, 13, 1),
+        (GW, 12, 1), (GW, 11, 1), (GW, 10, 1), (BX, 0, 4), (BW, 15, 1), (BW, 14, 1), (BW, 13, 1),
+        (BW, 12, 1), (BW, 11, 1), (BW, 10, 1),
+    ] },
+];
+
+fn sign_extend(value: i32, bits: u32) -> i32 {
+    (value << (32 - bits)) >> (32 - bits)
+}
+
+// scales an endpoint to the 16 bit range the weights interpolate in
+fn unquantize(value: i32, precision: u32, signed: bool) -> i32 {
+    if !signed {
+        return match value {
+            _ if precision >= 15 => value,
+            0 => 0,
+            _ if value == (1 << precision) - 1 => 0xffff,
+            _ => ((value << 16) + 0x8000) >> precision,
+        };
+    }
+
+    if precision >= 16 {
+        return value;
+    }
+    let magnitude = value.abs();
+    let unquantized = match magnitude {
+        0 => 0,
+        _ if magnitude >= (1 << (precision - 1)) - 1 => 0x7fff,
+        _ => ((magnitude << 15) + 0x4000) >> (precision - 1),
+    };
+    if value < 0 {
+        -unquantized
+    } else {
+        unquantized
+    }
+}
+
+fn decode_bc6h(block: &[u8], signed: bool) -> [[f16; 4]; 16] {
+    let mut bits = Bits::new(block);
+
+    let mut mode_bits = bits.read(2);
+    if mode_bits > 1 {
+        mode_bits |= bits.read(3) << 2;
+    }
+    // reserved modes decode to black
+    let Some(mode) = BC6H_MODES.iter().find(|mode| mode.bits == mode_bits) else {
+        return [[f16::ZERO, f16::ZERO, f16::ZERO, f16::ONE]; 16];
+    };
+
+    let mut fields = [0i32; 13];
+    for &(field, lowest, count) in mode.layout {
+        fields[field as usize] |= (bits.read(count as u32) as i32) << lowest;
+    }
+    let partition = fields[D as usize] as usize;
+
+    // every endpoint past the first is a delta from it in transformed modes
+    let precision = mode.precision;
+    let endpoints: [[i32; 3]; 4] = std::array::from_fn(|e| {
+        std::array::from_fn(|c| {
+            let mut value = fields[e * 3 + c];
+            let is_delta = e > 0 && mode.transformed;
+            if is_delta {
+                value = sign_extend(value, mode.delta_bits[c]);
+                value = (fields[c] + value) & ((1 << precision) - 1);
+            }
+            if signed {
+                value = sign_extend(value, precision);
+            }
+            unquantize(value, precision, signed)
+        })
+    });
+
+    let index_bits = if mode.subsets == 2 { 3 } else { 4 };
+    let mut indices = [0; 16];
+    for (i, index) in indices.iter_mut().enumerate() {
+        let anchor = is_anchor(mode.subsets, partition, i);
+        *index = bits.read(index_bits - anchor as u32);
+    }
+
+    std::array::from_fn(|i| {
+        let s = subset(mode.subsets, partition, i);
+        let (e0, e1) = (endpoints[s * 2], endpoints[s * 2 + 1]);
+        let w = weight(indices[i], index_bits) as i32;
+
+        let channel = |c: usize| {
+            let value = ((64 - w) * e0[c] + w * e1[c] + 32) >> 6;
+            // rescale to the bit pattern of a half float
+            let bits = match (signed, value < 0) {
+                (false, _) => (value * 31) >> 6,
+                (true, false) => (value * 31) >> 5,
+                (true, true) => 0x8000 | ((-value * 31) >> 5),
+            };
+            f16::from_bits(bits as u16)
+        };
+
+        [channel(0), channel(1), channel(2), f16::ONE]
+    })
+}
+
+pub fn is_supported(format: wgpu::TextureFormat) -> bool {
+    use wgpu::TextureFormat::*;
+
+    matches!(
+        format,
+        Bc1RgbaUnorm
+            | Bc1RgbaUnormSrgb
+            | Bc2RgbaUnorm
+            | Bc2RgbaUnormSrgb
+            | Bc3RgbaUnorm
+            | Bc3RgbaUnormSrgb
+            | Bc4RUnorm
+            | Bc4RSnorm
+            | Bc5RgUnorm
+            | Bc5RgSnorm
+            | Bc6hRgbUfloat
+            | Bc6hRgbFloat
+            | Bc7RgbaUnorm
+            | Bc7RgbaUnormSrgb
+    )
+}
+
+// writes the 16 texels of one block row by row, as rgba8 (snorm for the
+// signed formats) or rgba16 float for bc6h
+pub fn decode_block(format: wgpu::TextureFormat, block: &[u8], texels: &mut [u8]) {
+    use wgpu::TextureFormat::*;
+
+    let rgba8 = match format {
+        Bc1RgbaUnorm | Bc1RgbaUnormSrgb => decode_color_block(block, true),
+        Bc2RgbaUnorm | Bc2RgbaUnormSrgb => {
+            let mut texels = decode_color_block(&block[8..], false);
+            for (i, texel) in texels.iter_mut().enumerate() {
+                let alpha = (block[i / 2] >> ((i % 2) * 4)) & 0xf;
+                texel[3] = alpha << 4 | alpha;
+            }
+            texels
+        }
+        Bc3RgbaUnorm | Bc3RgbaUnormSrgb => {
+            let mut texels = decode_color_block(&block[8..], false);
+            let alpha = decode_value_block(&block[..8]);
+            for (texel, alpha) in texels.iter_mut().zip(alpha) {
+                texel[3] = alpha;
+            }
+            texels
+        }
+        Bc4RUnorm => decode_value_block(block).map(|r| [r, 0, 0, 255]),
+        Bc5RgUnorm => {
+            let r = decode_value_block(&block[..8]);
+            let g = decode_value_block(&block[8..]);
+            std::array::from_fn(|i| [r[i], g[i], 0, 255])
+        }
+        Bc4RSnorm => decode_signed_value_block(block).map(|r| [r as u8, 0, 0, 127]),
+        Bc5RgSnorm => {
+            let r = decode_signed_value_block(&block[..8]);
+            let g = decode_signed_value_block(&block[8..]);
+            std::array::from_fn(|i| [r[i] as u8, g[i] as u8, 0, 127])
+        }
+        Bc7RgbaUnorm | Bc7RgbaUnormSrgb => decode_bc7(block),
+        Bc6hRgbUfloat | Bc6hRgbFloat => {
+            let texels16 = decode_bc6h(block, format == Bc6hRgbFloat);
+            texels.copy_from_slice(bytemuck::cast_slice(&texels16));
+            return;
+        }
+        _ => unreachable!("no bc decoder for {format:?}"),
+    };
+
+    texels.copy_from_slice(rgba8.as_flattened());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // texel indices and the colors expected there
+    type Expected = &'static [(usize, [u8; 4])];
+
+    // packs (value, bit count) fields from the lowest bit up
+    fn pack(fields: &[(u32, u32)]) -> [u8; 16] {
+        let mut bits = 0u128;
+        let mut position = 0;
+        for &(value, count) in fields {
+            bits |= (value as u128) << position;
+            position += count;
+        }
+        bits.to_le_bytes()
+    }
+
+    fn decode(format: wgpu::TextureFormat, block: &[u8]) -> [[u8; 4]; 16] {
+        let mut texels = [[0; 4]; 16];
+        decode_block(format, block, texels.as_flattened_mut());
+        texels
+    }
+
+    fn decode_half(format: wgpu::TextureFormat, block: &[u8]) -> [[u16; 4]; 16] {
+        let mut texels = [[0u16; 4]; 16];
+        decode_block(format, block, bytemuck::cast_slice_mut(&mut texels));
+        texels
+    }
+
+    #[test]
+    fn bc4_snorm_clamps_minus_128() {
+        // texel 0 takes the first endpoint, texel 1 the second
+        let block = [0x80, 0x7f, 0x08, 0, 0, 0, 0, 0];
+        let texels = decode(wgpu::TextureFormat::Bc4RSnorm, &block);
+
+        assert_eq!(texels[0], [-127i8 as u8, 0, 0, 127]);
+        assert_eq!(texels[1], [127, 0, 0, 127]);
+    }
+
+    #[test]
+    fn bc7_mode_6_interpolates_endpoints() {
+        // endpoints 0 and 127 with p bits 0 and 1, so black and white
+        let mut fields = vec![(1 << 6, 7)];
+        fields.extend([(0, 7), (127, 7)].repeat(4));
+        fields.extend([(0, 1), (1, 1)]);
+        fields.extend([(0, 3), (15, 4), (8, 4)]);
+        let texels = decode(wgpu::TextureFormat::Bc7RgbaUnorm, &pack(&fields));
+
+        assert_eq!(texels[0], [0; 4]);
+        assert_eq!(texels[1], [255; 4]);
+        assert_eq!(texels[2], [135; 4]);
+        assert_eq!(texels[3], [0; 4]);
+    }
+
+    #[test]
+    fn bc7_reserved_mode_is_transparent_black() {
+        assert_eq!(
+            decode(wgpu::TextureFormat::Bc7RgbaUnorm, &[0; 16]),
+            [[0; 4]; 16]
+        );
+    }
+
+    // checked against mesa's decoder
+    #[test]
+    fn bc7_matches_reference_blocks() {
+        let cases: [([u8; 16], Expected); 4] = [
+            (
+                [
+                    0xd3, 0x9c, 0x9c, 0x64, 0xb2, 0x3f, 0x4c, 0x75, 0x2a, 0xf1, 0xbc, 0x56, 0x16,
+                    0x6e, 0xd0, 0xfc,
+                ],
+                &[
+                    (0, [163, 99, 173, 255]),
+                    (4, [96, 226, 61, 255]),
+                    (12, [45, 94, 141, 255]),
+                    (15, [55, 54, 145, 255]),
+                ],
+            ),
+            (
+                [
+                    0x0e, 0x1e, 0xd8, 0xd9, 0xd3, 0x96, 0x30, 0xfb, 0xb2, 0xed, 0xc9, 0xd0, 0x68,
+                    0x2f, 0x5f, 0x98,
+                ],
+                &[
+                    (0, [125, 87, 185, 255]),
+                    (1, [127, 97, 127, 255]),
+                    (15, [144, 39, 144, 255]),
+                ],
+            ),
+            (
+                [
+                    0x10, 0xc5, 0x72, 0xcd, 0x6f, 0x85, 0x94, 0x17, 0x9a, 0x64, 0xcf, 0xdf, 0xce,
+                    0x23, 0x47, 0x9a,
+                ],
+                &[
+                    (0, [87, 225, 217, 63]),
+                    (1, [135, 220, 203, 78]),
+                    (2, [41, 231, 231, 32]),
+                ],
+            ),
+            (
+                [
+                    0xe0, 0x02, 0x87, 0x8a, 0x28, 0xba, 0x15, 0x0b, 0xe5, 0xb4, 0xc4, 0xf3, 0x33,
+                    0x3e, 0x75, 0xa9,
+                ],
+                &[
+                    (0, [12, 101, 154, 82]),
+                    (1, [4, 84, 197, 68]),
+                    (2, [28, 137, 66, 110]),
+                ],
+            ),
+        ];
+
+        for (block, expected) in cases {
+            let texels = decode(wgpu::TextureFormat::Bc7RgbaUnorm, &block);
+            for &(i, texel) in expected {
+                assert_eq!(texels[i], texel, "texel {i} of {block:x?}");
+            }
+        }
+    }
+
+    #[test]
+    fn bc6h_mode_11_reaches_the_largest_half() {
+        let unsigned = pack(&[
+            (0b00011, 5),
+            (0, 30),
+            (1023, 10),
+            (1023, 10),
+            (1023, 10),
+            (0, 3),
+            (15, 4),
+        ]);
+        let texels = decode_half(wgpu::TextureFormat::Bc6hRgbUfloat, &unsigned);
+        assert_eq!(texels[0], [0, 0, 0, 0x3c00]);
+        assert_eq!(texels[1], [0x7bff, 0x7bff, 0x7bff, 0x3c00]);
+
+        // -511 and 511 as 10 bit two's complement
+        let signed = pack(&[
+            (0b00011, 5),
+            (0x201, 10),
+            (0x201, 10),
+            (0x201, 10),
+            (0x1ff, 10),
+            (0x1ff, 10),
+            (0x1ff, 10),
+            (0, 3),
+            (15, 4),
+        ]);
+        let texels = decode_half(wgpu::TextureFormat::Bc6hRgbFloat, &signed);
+        assert_eq!(texels[0], [0xfbff, 0xfbff, 0xfbff, 0x3c00]);
+        assert_eq!(texels[1], [0x7bff, 0x7bff, 0x7bff, 0x3c00]);
+    }
+
+    // checked against mesa's decoder
+    #[test]
+    fn bc6h_matches_reference_blocks() {
+        let two_subsets = [
+            0x10, 0xc5, 0x72, 0xcd, 0x6f, 0x85, 0x94, 0x17, 0x9a, 0x64, 0xcf, 0xdf, 0xce, 0x23,
+            0x47, 0x9a,
+        ];
+        let texels = decode_half(wgpu::TextureFormat::Bc6hRgbUfloat, &two_subsets);
+        assert_eq!(
+            texels[..4],
+            [
+                [0x4391, 0x59fe, 0x79ad, 0x3c00],
+                [0x43d0, 0x5a12, 0x79f6, 0x3c00],
+                [0x4446, 0x5937, 0x783f, 0x3c00],
+                [0x43fe, 0x5818, 0x7756, 0x3c00],
+            ]
+        );
+        let texels = decode_half(wgpu::TextureFormat::Bc6hRgbFloat, &two_subsets);
+        assert_eq!(
+            texels[..4],
+            [
+                [0xf11a, 0xc440, 0x84e2, 0x3c00],
+                [0xf09d, 0xc419, 0x8451, 0x3c00],
+                [0xefb1, 0xc5cf, 0x87bf, 0x3c00],
+                [0xf041, 0xc80d, 0x8991, 0x3c00],
+            ]
+        );
+
+        // mode 13 stores the top endpoint bits reversed
+        let reversed = [
+            0x8b, 0xfa, 0xd8, 0xdd, 0x41, 0x3f, 0xb3, 0xc3, 0xbe, 0x89, 0x70, 0x24, 0xea, 0xdc,
+            0xf5, 0xc0,
+        ];
+        let texels = decode_half(wgpu::TextureFormat::Bc6hRgbUfloat, &reversed);
+        assert_eq!(texels[0], [0x7a57, 0x5929, 0x4381, 0x3c00]);
+        let texels = decode_half(wgpu::TextureFormat::Bc6hRgbFloat, &reversed);
+        assert_eq!(texels[0], [0x8360, 0xc5bc, 0xf10d, 0x3c00]);
+    }
+
+    #[test]
+    fn bc6h_reserved_modes_are_black() {
+        let block = pack(&[(0b10011, 5)]);
+        let texels = decode_half(wgpu::TextureFormat::Bc6hRgbUfloat, &block);
+
+        assert_eq!(texels, [[0, 0, 0, 0x3c00]; 16]);
+    }
+}
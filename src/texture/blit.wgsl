@@ -0,0 +1,24 @@
+@group(0) @binding(0)
+var texture: texture_2d<f32>;
+
+@group(0) @binding(1)
+var texture_sampler: sampler;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(texture, texture_sampler, in.uv);
+}
+
+// what an srgb target does on write, for surfaces that have no srgb view
+@fragment
+fn fs_encode_srgb(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(texture, texture_sampler, in.uv);
+    let linear = max(color.rgb, vec3f(0.0));
+    let encoded = select(
+        1.055 * pow(linear, vec3f(1.0 / 2.4)) - 0.055,
+        linear * 12.92,
+        linear <= vec3f(0.0031308),
+    );
+
+    return vec4f(encoded, color.a);
+}
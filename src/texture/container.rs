@@ -0,0 +1,834 @@
+use std::io::Read;
+
+use ddsfile::{D3DFormat, Dds, DxgiFormat};
+use ktx2::{ColorModel, DfdBlockBasic, SupercompressionScheme, TransferFunction};
+use wgpu::util::TextureDataOrder;
+
+use super::{astc, basis, bcn, encode, etc};
+
+const KTX2_MAGIC: &[u8] = b"\xabKTX 20\xbb\r\n\x1a\n";
+const DDS_MAGIC: &[u8] = b"DDS ";
+
+pub fn is_container(bytes: &[u8]) -> bool {
+    bytes.starts_with(KTX2_MAGIC) || bytes.starts_with(DDS_MAGIC)
+}
+
+// an image loaded from a ktx2 or dds file, with every mip level and array
+// layer it ships with, possibly still block compressed
+pub struct ContainerImage {
+    pub width: u32,
+    pub height: u32,
+    pub layers: u32,
+    pub levels: u32,
+    pub is_cubemap: bool,
+    pub format: wgpu::TextureFormat,
+    pub order: TextureDataOrder,
+    pub data: Vec<u8>,
+    // transcoded from basis universal, which may be re-encoded into whichever
+    // block format the device supports
+    pub is_universal: bool,
+}
+
+impl ContainerImage {
+    pub fn decode(bytes: &[u8]) -> Result<Self, String> {
+        if bytes.starts_with(KTX2_MAGIC) {
+            Self::from_ktx2(bytes)
+        } else if bytes.starts_with(DDS_MAGIC) {
+            Self::from_dds(bytes)
+        } else {
+            Err("not a ktx2 or dds file".to_owned())
+        }
+    }
+
+    pub fn from_ktx2(bytes: &[u8]) -> Result<Self, String> {
+        let reader =
+            ktx2::Reader::new(bytes).map_err(|err| format!("failed to parse ktx2: {err:?}"))?;
+        let header = reader.header();
+
+        let supercompression = header.supercompression_scheme;
+        if supercompression == Some(SupercompressionScheme::BasisLZ) {
+            return Self::from_etc1s(&reader);
+        }
+        if header.format.is_none() && color_model(&reader) == Some(ColorModel::UASTC) {
+            return Err("ktx2 uastc payloads are not supported".to_owned());
+        }
+
+        let format = header
+            .format
+            .and_then(ktx2_format)
+            .ok_or(format!("unsupported ktx2 format {:?}", header.format))?;
+
+        if header.pixel_depth > 1 {
+            return Err("3d ktx2 textures are not supported".to_owned());
+        }
+
+        let mut data = Vec::new();
+        for level in reader.levels() {
+            match supercompression {
+                None => data.extend_from_slice(level.data),
+                Some(SupercompressionScheme::Zstandard) => {
+                    let mut decoder = ruzstd::decoding::StreamingDecoder::new(level.data)
+                        .map_err(|err| format!("failed to decode ktx2 zstd level: {err}"))?;
+                    decoder
+                        .read_to_end(&mut data)
+                        .map_err(|err| format!("failed to decode ktx2 zstd level: {err}"))?;
+                }
+                Some(scheme) => {
+                    return Err(format!("unsupported ktx2 supercompression {scheme:?}"));
+                }
+            }
+        }
+
+        Ok(Self {
+            width: header.pixel_width,
+            height: header.pixel_height.max(1),
+            layers: header.layer_count.max(1) * header.face_count,
+            levels: header.level_count.max(1),
+            is_cubemap: header.face_count == 6,
+            format,
+            order: TextureDataOrder::MipMajor,
+            data,
+            is_universal: false,
+        })
+    }
+
+    fn from_etc1s(reader: &ktx2::Reader<&[u8]>) -> Result<Self, String> {
+        let header = reader.header();
+        if header.pixel_depth > 1 {
+            return Err("3d ktx2 textures are not supported".to_owned());
+        }
+
+        // a second sample means every image has an alpha slice
+        let dfd = basic_dfd(reader).ok_or("ktx2 basis file has no data format descriptor")?;
+        let alpha = dfd.sample_information().count() > 1;
+        let srgb = dfd.header.transfer_function == Some(TransferFunction::SRGB);
+
+        let (width, height) = (header.pixel_width, header.pixel_height.max(1));
+        let layers = header.layer_count.max(1) * header.face_count;
+        let levels: Vec<_> = reader.levels().map(|level| level.data).collect();
+        let data = basis::transcode_etc1s(
+            reader.supercompression_global_data(),
+            &levels,
+            (width, height),
+            layers as usize,
+            alpha,
+        )?;
+
+        let format = match alpha {
+            true => wgpu::TextureFormat::Etc2Rgba8Unorm,
+            false => wgpu::TextureFormat::Etc2Rgb8Unorm,
+        };
+
+        Ok(Self {
+            width,
+            height,
+            layers,
+            levels: header.level_count.max(1),
+            is_cubemap: header.face_count == 6,
+            format: if srgb {
+                format.add_srgb_suffix()
+            } else {
+                format
+            },
+            order: TextureDataOrder::MipMajor,
+            data,
+            is_universal: true,
+        })
+    }
+
+    pub fn from_dds(bytes: &[u8]) -> Result<Self, String> {
+        let dds = Dds::read(bytes).map_err(|err| format!("failed to parse dds: {err}"))?;
+
+        let format = dds
+            .get_dxgi_format()
+            .and_then(dxgi_format)
+            .or(dds.get_d3d_format().and_then(d3d_format))
+            .ok_or("unsupported dds format".to_owned())?;
+        // legacy headers carry no color space, ddsfile reports their block
+        // formats as srgb
+        let format = match dds.header10 {
+            Some(_) => format,
+            None => format.remove_srgb_suffix(),
+        };
+
+        if dds.get_depth() > 1 {
+            return Err("3d dds textures are not supported".to_owned());
+        }
+
+        let is_cubemap = match &dds.header10 {
+            Some(header10) => header10.misc_flag.contains(ddsfile::MiscFlag::TEXTURECUBE),
+            None => dds.header.caps2.contains(ddsfile::Caps2::CUBEMAP),
+        };
+
+        // dx10 headers count whole cubes, legacy cubemaps already report 6
+        let mut layers = dds.get_num_array_layers();
+        if is_cubemap && dds.header10.is_some() {
+            layers *= 6;
+        }
+
+        Ok(Self {
+            width: dds.get_width(),
+            height: dds.get_height(),
+            layers,
+            levels: dds.get_num_mipmap_levels(),
+            is_cubemap,
+            format,
+            order: TextureDataOrder::LayerMajor,
+            data: dds.data,
+            is_universal: false,
+        })
+    }
+
+    pub fn is_supported(&self, features: wgpu::Features) -> bool {
+        features.contains(self.format.required_features())
+    }
+
+    // decodes block compressed data on the cpu, keeping every level and layer
+    pub fn decompress(&self) -> Result<Self, String> {
+        let format =
+            decoded_format(self.format).ok_or(format!("no cpu decoder for {:?}", self.format))?;
+
+        let mut data = Vec::new();
+        for (level, source) in self.images()? {
+            let (width, height) = self.level_extent(level);
+            data.extend(decode_image(self.format, format, source, width, height));
+        }
+
+        Ok(Self {
+            format,
+            data,
+            is_universal: false,
+            ..*self
+        })
+    }
+
+    // picks the block format for a basis universal image: etc2 as
+    // transcoded, then bc, then astc, and uncompressed when the device has
+    // none of them
+    pub fn transcode(&self, features: wgpu::Features) -> Result<Self, String> {
+        use wgpu::TextureFormat::*;
+
+        if self.is_supported(features) {
+            return Ok(Self {
+                data: self.data.clone(),
+                ..*self
+            });
+        }
+
+        let has_alpha = matches!(self.format, Etc2Rgba8Unorm | Etc2Rgba8UnormSrgb);
+        let format = if features.contains(wgpu::Features::TEXTURE_COMPRESSION_BC) {
+            if has_alpha {
+                Bc3RgbaUnorm
+            } else {
+                Bc1RgbaUnorm
+            }
+        } else if features.contains(wgpu::Features::TEXTURE_COMPRESSION_ASTC) {
+            Astc {
+                block: wgpu::AstcBlock::B4x4,
+                channel: wgpu::AstcChannel::Unorm,
+            }
+        } else {
+            return self.decompress();
+        };
+
+        let decoded = self.decompress()?;
+        let mut data = Vec::new();
+        for (level, source) in decoded.images()? {
+            let (width, height) = self.level_extent(level);
+            data.extend(encode::encode_image(format, source, width, height));
+        }
+
+        Ok(Self {
+            format: match self.format.is_srgb() {
+                true => format.add_srgb_suffix(),
+                false => format,
+            },
+            data,
+            ..*self
+        })
+    }
+
+    pub fn can_decompress(&self) -> bool {
+        decoded_format(self.format).is_some()
+    }
+
+    fn level_extent(&self, level: u32) -> (u32, u32) {
+        ((self.width >> level).max(1), (self.height >> level).max(1))
+    }
+
+    // the level and data of every image, in the order they are stored
+    fn images(&self) -> Result<Vec<(u32, &[u8])>, String> {
+        let level_size = |level: u32| {
+            let (width, height) = self.level_extent(level);
+            let (block_width, block_height) = self.format.block_dimensions();
+            let blocks = width.div_ceil(block_width) * height.div_ceil(block_height);
+
+            (blocks * self.format.block_copy_size(None).unwrap()) as usize
+        };
+
+        let levels: Vec<u32> = match self.order {
+            TextureDataOrder::LayerMajor => (0..self.layers).flat_map(|_| 0..self.levels).collect(),
+            _ => (0..self.levels)
+                .flat_map(|level| (0..self.layers).map(move |_| level))
+                .collect(),
+        };
+
+        let mut images = Vec::new();
+        let mut offset = 0;
+        for level in levels {
+            let data = self
+                .data
+                .get(offset..offset + level_size(level))
+                .ok_or(format!("{:?} image data is truncated", self.format))?;
+            images.push((level, data));
+            offset += level_size(level);
+        }
+
+        Ok(images)
+    }
+}
+
+// the basic data format descriptor block, which every ktx2 file should have
+fn basic_dfd<'a>(reader: &'a ktx2::Reader<&[u8]>) -> Option<DfdBlockBasic<'a>> {
+    let block = reader.dfd_blocks().next()?;
+    DfdBlockBasic::parse(block.data).ok()
+}
+
+fn color_model(reader: &ktx2::Reader<&[u8]>) -> Option<ColorModel> {
+    basic_dfd(reader)?.header.color_model
+}
+
+// the uncompressed format the cpu decoders write a block compressed format
+// as, none for formats without a decoder
+fn decoded_format(format: wgpu::TextureFormat) -> Option<wgpu::TextureFormat> {
+    use wgpu::TextureFormat::*;
+
+    if !bcn::is_supported(format) && !etc::is_supported(format) && !astc::is_supported(format) {
+        return None;
+    }
+
+    let decoded = match format {
+        Bc4RSnorm | Bc5RgSnorm => Rgba8Snorm,
+        Bc6hRgbUfloat | Bc6hRgbFloat | EacR11Unorm | EacR11Snorm | EacRg11Unorm | EacRg11Snorm => {
+            Rgba16Float
+        }
+        _ if format.is_srgb() => Rgba8UnormSrgb,
+        _ => Rgba8Unorm,
+    };
+
+    Some(decoded)
+}
+
+fn decode_block(format: wgpu::TextureFormat, block: &[u8], texels: &mut [u8]) {
+    if bcn::is_supported(format) {
+        bcn::decode_block(format, block, texels);
+    } else if etc::is_supported(format) {
+        etc::decode_block(format, block, texels);
+    } else {
+        astc::decode_block(format, block, texels);
+    }
+}
+
+// decodes one image of the given size into tightly packed texels
+fn decode_image(
+    format: wgpu::TextureFormat,
+    decoded: wgpu::TextureFormat,
+    data: &[u8],
+    width: u32,
+    height: u32,
+) -> Vec<u8> {
+    let (block_width, block_height) = format.block_dimensions();
+    let block_size = format.block_copy_size(None).unwrap() as usize;
+    let texel_size = decoded.block_copy_size(None).unwrap() as usize;
+    let blocks_x = width.div_ceil(block_width);
+
+    let mut output = vec![0; (width * height) as usize * texel_size];
+    let mut texels = vec![0; (block_width * block_height) as usize * texel_size];
+
+    for (index, block) in data.chunks_exact(block_size).enumerate() {
+        let (bx, by) = (index as u32 % blocks_x, index as u32 / blocks_x);
+        decode_block(format, block, &mut texels);
+
+        let x = bx * block_width;
+        let row = block_width.min(width - x) as usize * texel_size;
+        for (y, texels) in texels
+            .chunks_exact(block_width as usize * texel_size)
+            .enumerate()
+        {
+            let y = by * block_height + y as u32;
+            if y >= height {
+                break;
+            }
+            let offset = (y * width + x) as usize * texel_size;
+            output[offset..offset + row].copy_from_slice(&texels[..row]);
+        }
+    }
+
+    output
+}
+
+fn ktx2_format(format: ktx2::Format) -> Option<wgpu::TextureFormat> {
+    use ktx2::Format as K;
+    use wgpu::{AstcBlock, AstcChannel, TextureFormat as F};
+
+    let astc = |block, channel| F::Astc { block, channel };
+
+    let format = match format {
+        K::R8G8B8A8_UNORM => F::Rgba8Unorm,
+        K::R8G8B8A8_SRGB => F::Rgba8UnormSrgb,
+        K::B8G8R8A8_UNORM => F::Bgra8Unorm,
+        K::B8G8R8A8_SRGB => F::Bgra8UnormSrgb,
+        K::R16G16B16A16_SFLOAT => F::Rgba16Float,
+        K::R32G32B32A32_SFLOAT => F::Rgba32Float,
+        K::BC1_RGBA_UNORM_BLOCK | K::BC1_RGB_UNORM_BLOCK => F::Bc1RgbaUnorm,
+        K::BC1_RGBA_SRGB_BLOCK | K::BC1_RGB_SRGB_BLOCK => F::Bc1RgbaUnormSrgb,
+        K::BC2_UNORM_BLOCK => F::Bc2RgbaUnorm,
+        K::BC2_SRGB_BLOCK => F::Bc2RgbaUnormSrgb,
+        K::BC3_UNORM_BLOCK => F::Bc3RgbaUnorm,
+        K::BC3_SRGB_BLOCK => F::Bc3RgbaUnormSrgb,
+        K::BC4_UNORM_BLOCK => F::Bc4RUnorm,
+        K::BC4_SNORM_BLOCK => F::Bc4RSnorm,
+        K::BC5_UNORM_BLOCK => F::Bc5RgUnorm,
+        K::BC5_SNORM_BLOCK => F::Bc5RgSnorm,
+        K::BC6H_UFLOAT_BLOCK => F::Bc6hRgbUfloat,
+        K::BC6H_SFLOAT_BLOCK => F::Bc6hRgbFloat,
+        K::BC7_UNORM_BLOCK => F::Bc7RgbaUnorm,
+        K::BC7_SRGB_BLOCK => F::Bc7RgbaUnormSrgb,
+        K::ETC2_R8G8B8_UNORM_BLOCK => F::Etc2Rgb8Unorm,
+        K::ETC2_R8G8B8_SRGB_BLOCK => F::Etc2Rgb8UnormSrgb,
+        K::ETC2_R8G8B8A1_UNORM_BLOCK => F::Etc2Rgb8A1Unorm,
+        K::ETC2_R8G8B8A1_SRGB_BLOCK => F::Etc2Rgb8A1UnormSrgb,
+        K::ETC2_R8G8B8A8_UNORM_BLOCK => F::Etc2Rgba8Unorm,
+        K::ETC2_R8G8B8A8_SRGB_BLOCK => F::Etc2Rgba8UnormSrgb,
+        K::EAC_R11_UNORM_BLOCK => F::EacR11Unorm,
+        K::EAC_R11_SNORM_BLOCK => F::EacR11Snorm,
+        K::EAC_R11G11_UNORM_BLOCK => F::EacRg11Unorm,
+        K::EAC_R11G11_SNORM_BLOCK => F::EacRg11Snorm,
+        K::ASTC_4x4_UNORM_BLOCK => astc(AstcBlock::B4x4, AstcChannel::Unorm),
+        K::ASTC_4x4_SRGB_BLOCK => astc(AstcBlock::B4x4, AstcChannel::UnormSrgb),
+        K::ASTC_5x5_UNORM_BLOCK => astc(AstcBlock::B5x5, AstcChannel::Unorm),
+        K::ASTC_5x5_SRGB_BLOCK => astc(AstcBlock::B5x5, AstcChannel::UnormSrgb),
+        K::ASTC_6x6_UNORM_BLOCK => astc(AstcBlock::B6x6, AstcChannel::Unorm),
+        K::ASTC_6x6_SRGB_BLOCK => astc(AstcBlock::B6x6, AstcChannel::UnormSrgb),
+        K::ASTC_8x8_UNORM_BLOCK => astc(AstcBlock::B8x8, AstcChannel::Unorm),
+        K::ASTC_8x8_SRGB_BLOCK => astc(AstcBlock::B8x8, AstcChannel::UnormSrgb),
+        K::ASTC_10x10_UNORM_BLOCK => astc(AstcBlock::B10x10, AstcChannel::Unorm),
+        K::ASTC_10x10_SRGB_BLOCK => astc(AstcBlock::B10x10, AstcChannel::UnormSrgb),
+        K::ASTC_12x12_UNORM_BLOCK => astc(AstcBlock::B12x12, AstcChannel::Unorm),
+        K::ASTC_12x12_SRGB_BLOCK => astc(AstcBlock::B12x12, AstcChannel::UnormSrgb),
+        _ => return None,
+    };
+
+    Some(format)
+}
+
+fn dxgi_format(format: DxgiFormat) -> Option<wgpu::TextureFormat> {
+    use wgpu::TextureFormat as F;
+    use DxgiFormat as D;
+
+    let format = match format {
+        D::R8G8B8A8_UNorm => F::Rgba8Unorm,
+        D::R8G8B8A8_UNorm_sRGB => F::Rgba8UnormSrgb,
+        D::B8G8R8A8_UNorm => F::Bgra8Unorm,
+        D::B8G8R8A8_UNorm_sRGB => F::Bgra8UnormSrgb,
+        D::R16G16B16A16_Float => F::Rgba16Float,
+        D::R32G32B32A32_Float => F::Rgba32Float,
+        D::BC1_UNorm => F::Bc1RgbaUnorm,
+        D::BC1_UNorm_sRGB => F::Bc1RgbaUnormSrgb,
+        D::BC2_UNorm => F::Bc2RgbaUnorm,
+        D::BC2_UNorm_sRGB => F::Bc2RgbaUnormSrgb,
+        D::BC3_UNorm => F::Bc3RgbaUnorm,
+        D::BC3_UNorm_sRGB => F::Bc3RgbaUnormSrgb,
+        D::BC4_UNorm => F::Bc4RUnorm,
+        D::BC4_SNorm => F::Bc4RSnorm,
+        D::BC5_UNorm => F::Bc5RgUnorm,
+        D::BC5_SNorm => F::Bc5RgSnorm,
+        D::BC6H_UF16 => F::Bc6hRgbUfloat,
+        D::BC6H_SF16 => F::Bc6hRgbFloat,
+        D::BC7_UNorm => F::Bc7RgbaUnorm,
+        D::BC7_UNorm_sRGB => F::Bc7RgbaUnormSrgb,
+        _ => return None,
+    };
+
+    Some(format)
+}
+
+fn d3d_format(format: D3DFormat) -> Option<wgpu::TextureFormat> {
+    match format {
+        D3DFormat::A8B8G8R8 => Some(wgpu::TextureFormat::Rgba8Unorm),
+        D3DFormat::A8R8G8B8 => Some(wgpu::TextureFormat::Bgra8Unorm),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ddsfile::{AlphaMode, Caps2, D3D10ResourceDimension, NewD3dParams, NewDxgiParams};
+    use ktx2::{Format, Header, Index, LevelIndex};
+
+    use super::*;
+
+    struct Ktx2 {
+        format: Option<Format>,
+        size: (u32, u32, u32),
+        layers: u32,
+        faces: u32,
+        supercompression: Option<SupercompressionScheme>,
+        dfd: Vec<u8>,
+        global_data: Vec<u8>,
+        levels: Vec<Vec<u8>>,
+    }
+
+    impl Ktx2 {
+        fn new(format: Format, width: u32, height: u32) -> Self {
+            Self {
+                format: Some(format),
+                size: (width, height, 0),
+                layers: 0,
+                faces: 1,
+                supercompression: None,
+                dfd: Vec::new(),
+                global_data: Vec::new(),
+                levels: Vec::new(),
+            }
+        }
+
+        // header, level index, dfd, global data and then the levels, largest
+        // first
+        fn write(&self) -> Vec<u8> {
+            let index_end = Header::LENGTH + self.levels.len() * LevelIndex::LENGTH;
+            let dfd_length = 4 + self.dfd.len();
+            let global_data_start = index_end + dfd_length;
+            let data_start = global_data_start + self.global_data.len();
+
+            let header = Header {
+                format: self.format,
+                type_size: 1,
+                pixel_width: self.size.0,
+                pixel_height: self.size.1,
+                pixel_depth: self.size.2,
+                layer_count: self.layers,
+                face_count: self.faces,
+                level_count: self.levels.len() as u32,
+                supercompression_scheme: self.supercompression,
+                index: Index {
+                    dfd_byte_offset: index_end as u32,
+                    dfd_byte_length: dfd_length as u32,
+                    kvd_byte_offset: 0,
+                    kvd_byte_length: 0,
+                    sgd_byte_offset: global_data_start as u64,
+                    sgd_byte_length: self.global_data.len() as u64,
+                },
+            };
+
+            let mut bytes = header.as_bytes().to_vec();
+            let mut offset = data_start;
+            for level in &self.levels {
+                let index = LevelIndex {
+                    byte_offset: offset as u64,
+                    byte_length: level.len() as u64,
+                    uncompressed_byte_length: 0,
+                };
+                bytes.extend(index.as_bytes());
+                offset += level.len();
+            }
+            bytes.extend((dfd_length as u32).to_le_bytes());
+            bytes.extend(&self.dfd);
+            bytes.extend(&self.global_data);
+            for level in &self.levels {
+                bytes.extend(level);
+            }
+
+            bytes
+        }
+    }
+
+    // a basic descriptor block with one 16 byte sample per channel
+    fn basic_dfd(color_model: ColorModel, samples: usize) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend(0u32.to_le_bytes());
+        bytes.extend(2u16.to_le_bytes());
+        bytes.extend((24 + samples as u16 * 16).to_le_bytes());
+        bytes.extend([color_model.value(), 1, TransferFunction::SRGB.value(), 0]);
+        bytes.extend([3, 3, 0, 0]);
+        bytes.extend([0; 8]);
+        bytes.extend(vec![0; samples * 16]);
+        bytes
+    }
+
+    fn dds(dds: Dds) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        dds.write(&mut bytes).unwrap();
+        bytes
+    }
+
+    fn dxgi(format: DxgiFormat, layers: u32, levels: u32, is_cubemap: bool) -> NewDxgiParams {
+        NewDxgiParams {
+            height: 8,
+            width: 8,
+            depth: None,
+            format,
+            mipmap_levels: Some(levels),
+            array_layers: Some(layers),
+            caps2: None,
+            is_cubemap,
+            resource_dimension: D3D10ResourceDimension::Texture2D,
+            alpha_mode: AlphaMode::Straight,
+        }
+    }
+
+    #[test]
+    fn ktx2_keeps_levels_and_layers() {
+        // 8x8 bc7 in two layers, 4 blocks per layer on level 0 and 1 on level 1
+        let mut file = Ktx2::new(Format::BC7_SRGB_BLOCK, 8, 8);
+        file.layers = 2;
+        file.levels = vec![vec![1; 2 * 4 * 16], vec![2; 2 * 16]];
+
+        let image = ContainerImage::decode(&file.write()).unwrap();
+
+        assert_eq!((image.width, image.height), (8, 8));
+        assert_eq!((image.layers, image.levels), (2, 2));
+        assert!(!image.is_cubemap);
+        assert_eq!(image.format, wgpu::TextureFormat::Bc7RgbaUnormSrgb);
+        assert_eq!(image.order, TextureDataOrder::MipMajor);
+        assert_eq!(image.data, [vec![1; 128], vec![2; 32]].concat());
+    }
+
+    #[test]
+    fn ktx2_cubemaps_count_faces_as_layers() {
+        let mut file = Ktx2::new(Format::R8G8B8A8_UNORM, 1, 1);
+        file.faces = 6;
+        file.levels = vec![vec![0; 6 * 4]];
+
+        let image = ContainerImage::decode(&file.write()).unwrap();
+
+        assert!(image.is_cubemap);
+        assert_eq!(image.layers, 6);
+        assert_eq!(image.format, wgpu::TextureFormat::Rgba8Unorm);
+    }
+
+    #[test]
+    fn ktx2_zstd_levels_are_inflated() {
+        let level: Vec<u8> = (0..64).collect();
+        let mut file = Ktx2::new(Format::R8G8B8A8_SRGB, 4, 4);
+        file.supercompression = Some(SupercompressionScheme::Zstandard);
+        file.levels = vec![ruzstd::encoding::compress_to_vec(
+            &level[..],
+            ruzstd::encoding::CompressionLevel::Fastest,
+        )];
+
+        let image = ContainerImage::decode(&file.write()).unwrap();
+
+        assert_eq!(image.data, level);
+    }
+
+    #[test]
+    fn ktx2_rejects_what_it_cant_upload() {
+        let error = |file: Ktx2| ContainerImage::decode(&file.write()).err().unwrap();
+
+        let mut volume = Ktx2::new(Format::R8G8B8A8_UNORM, 1, 1);
+        volume.size.2 = 2;
+        volume.levels = vec![vec![0; 8]];
+        assert_eq!(error(volume), "3d ktx2 textures are not supported");
+
+        let mut packed = Ktx2::new(Format::R5G6B5_UNORM_PACK16, 1, 1);
+        packed.levels = vec![vec![0; 2]];
+        assert!(error(packed).starts_with("unsupported ktx2 format"));
+
+        let mut truncated = Ktx2::new(Format::R8G8B8A8_UNORM, 1, 1).write();
+        truncated.truncate(40);
+        assert!(ContainerImage::decode(&truncated)
+            .err()
+            .unwrap()
+            .starts_with("failed to parse ktx2"));
+    }
+
+    #[test]
+    fn ktx2_basis_payloads_are_routed_by_their_dfd() {
+        let error = |file: Ktx2| ContainerImage::decode(&file.write()).err().unwrap();
+
+        let mut uastc = Ktx2::new(Format::R8G8B8A8_UNORM, 4, 4);
+        uastc.format = None;
+        uastc.dfd = basic_dfd(ColorModel::UASTC, 1);
+        uastc.levels = vec![vec![0; 16]];
+        assert_eq!(error(uastc), "ktx2 uastc payloads are not supported");
+
+        let mut etc1s = Ktx2::new(Format::R8G8B8A8_UNORM, 4, 4);
+        etc1s.format = None;
+        etc1s.supercompression = Some(SupercompressionScheme::BasisLZ);
+        etc1s.levels = vec![vec![0; 8]];
+        etc1s.global_data = vec![0; 8];
+        assert_eq!(
+            error(etc1s),
+            "ktx2 basis file has no data format descriptor"
+        );
+
+        let mut truncated = Ktx2::new(Format::R8G8B8A8_UNORM, 4, 4);
+        truncated.format = None;
+        truncated.supercompression = Some(SupercompressionScheme::BasisLZ);
+        truncated.dfd = basic_dfd(ColorModel::ETC1S, 1);
+        truncated.levels = vec![vec![0; 8]];
+        truncated.global_data = vec![0; 8];
+        assert_eq!(error(truncated), "basis global data is truncated");
+    }
+
+    #[test]
+    fn dds_dx10_arrays_keep_levels_and_layers() {
+        let file = Dds::new_dxgi(dxgi(DxgiFormat::BC7_UNorm_sRGB, 3, 2, false)).unwrap();
+        let image = ContainerImage::decode(&dds(file)).unwrap();
+
+        assert_eq!((image.width, image.height), (8, 8));
+        assert_eq!((image.layers, image.levels), (3, 2));
+        assert!(!image.is_cubemap);
+        assert_eq!(image.format, wgpu::TextureFormat::Bc7RgbaUnormSrgb);
+        assert_eq!(image.order, TextureDataOrder::LayerMajor);
+        // 4 blocks on level 0 and 1 on level 1, per layer
+        assert_eq!(image.data.len(), 3 * 5 * 16);
+    }
+
+    #[test]
+    fn dds_cubemaps_count_faces_as_layers() {
+        // ddsfile takes the number of faces and writes the number of cubes
+        let file = Dds::new_dxgi(dxgi(DxgiFormat::BC1_UNorm, 6, 1, true)).unwrap();
+        let image = ContainerImage::decode(&dds(file)).unwrap();
+        assert!(image.is_cubemap);
+        assert_eq!(image.layers, 6);
+
+        let file = Dds::new_d3d(NewD3dParams {
+            height: 4,
+            width: 4,
+            depth: None,
+            format: D3DFormat::A8R8G8B8,
+            mipmap_levels: None,
+            caps2: Some(Caps2::CUBEMAP | Caps2::CUBEMAP_ALLFACES),
+        })
+        .unwrap();
+        let image = ContainerImage::decode(&dds(file)).unwrap();
+        assert!(image.is_cubemap);
+        assert_eq!(image.layers, 6);
+        assert_eq!(image.format, wgpu::TextureFormat::Bgra8Unorm);
+    }
+
+    #[test]
+    fn dds_legacy_headers_map_to_block_formats() {
+        let file = Dds::new_d3d(NewD3dParams {
+            height: 8,
+            width: 8,
+            depth: None,
+            format: D3DFormat::DXT5,
+            mipmap_levels: Some(4),
+            caps2: None,
+        })
+        .unwrap();
+        let image = ContainerImage::decode(&dds(file)).unwrap();
+
+        assert_eq!(image.format, wgpu::TextureFormat::Bc3RgbaUnorm);
+        assert_eq!((image.layers, image.levels), (1, 4));
+    }
+
+    #[test]
+    fn dds_rejects_unknown_formats() {
+        let file = Dds::new_dxgi(dxgi(DxgiFormat::R8G8_UNorm, 1, 1, false)).unwrap();
+
+        assert_eq!(
+            ContainerImage::decode(&dds(file)).err().unwrap(),
+            "unsupported dds format"
+        );
+        assert_eq!(
+            ContainerImage::decode(b"PNG?").err().unwrap(),
+            "not a ktx2 or dds file"
+        );
+    }
+
+    #[test]
+    fn decompress_crops_partial_blocks() {
+        // 6x6 and 3x3 levels, 2x2 blocks and then 1, all decoding to 2
+        let image = ContainerImage {
+            width: 6,
+            height: 6,
+            layers: 1,
+            levels: 2,
+            is_cubemap: false,
+            format: wgpu::TextureFormat::Etc2Rgb8UnormSrgb,
+            order: TextureDataOrder::MipMajor,
+            data: vec![0; 5 * 8],
+            is_universal: false,
+        };
+        assert!(image.can_decompress());
+
+        let decoded = image.decompress().unwrap();
+
+        assert_eq!(decoded.format, wgpu::TextureFormat::Rgba8UnormSrgb);
+        assert_eq!(decoded.data, [2, 2, 2, 255].repeat(36 + 9));
+    }
+
+    #[test]
+    fn transcode_picks_a_format_the_device_supports() {
+        use wgpu::Features;
+        use wgpu::TextureFormat::*;
+
+        // an 8x8 and a 4x4 level, 5 blocks in all
+        let image = |format| ContainerImage {
+            width: 8,
+            height: 8,
+            layers: 1,
+            levels: 2,
+            is_cubemap: false,
+            format,
+            order: TextureDataOrder::MipMajor,
+            data: vec![0; 5 * format.block_copy_size(None).unwrap() as usize],
+            is_universal: true,
+        };
+        let transcode = |format, features| {
+            let image = image(format).transcode(features).unwrap();
+            (image.format, image.data.len())
+        };
+        let astc = |channel| Astc {
+            block: wgpu::AstcBlock::B4x4,
+            channel,
+        };
+
+        let etc2 = Features::TEXTURE_COMPRESSION_ETC2;
+        let bc = Features::TEXTURE_COMPRESSION_BC;
+        let astc_ldr = Features::TEXTURE_COMPRESSION_ASTC;
+        assert_eq!(transcode(Etc2Rgb8Unorm, etc2), (Etc2Rgb8Unorm, 5 * 8));
+        assert_eq!(transcode(Etc2Rgb8Unorm, bc), (Bc1RgbaUnorm, 5 * 8));
+        assert_eq!(
+            transcode(Etc2Rgba8UnormSrgb, bc),
+            (Bc3RgbaUnormSrgb, 5 * 16)
+        );
+        assert_eq!(
+            transcode(Etc2Rgba8UnormSrgb, astc_ldr),
+            (astc(wgpu::AstcChannel::UnormSrgb), 5 * 16)
+        );
+        assert_eq!(
+            transcode(Etc2Rgb8Unorm, Features::empty()),
+            (Rgba8Unorm, 4 * (64 + 16))
+        );
+    }
+
+    #[test]
+    fn decompress_needs_a_decoder() {
+        let image = ContainerImage {
+            width: 4,
+            height: 4,
+            layers: 1,
+            levels: 1,
+            is_cubemap: false,
+            format: wgpu::TextureFormat::Astc {
+                block: wgpu::AstcBlock::B4x4,
+                channel: wgpu::AstcChannel::Hdr,
+            },
+            order: TextureDataOrder::MipMajor,
+            data: vec![0; 16],
+            is_universal: false,
+        };
+
+        assert!(!image.can_decompress());
+        assert!(image
+            .decompress()
+            .err()
+            .unwrap()
+            .starts_with("no cpu decoder"));
+    }
+}
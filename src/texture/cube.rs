@@ -0,0 +1,180 @@
+use glam::{vec3, Vec3};
+use half::f16;
+
+use super::decode::{DecodedImage, Pixels};
+
+// face order matches the layer order wgpu expects: +x, -x, +y, -y, +z, -z
+fn face_direction(face: usize, u: f32, v: f32) -> Vec3 {
+    match face {
+        0 => vec3(1.0, -v, -u),
+        1 => vec3(-1.0, -v, u),
+        2 => vec3(u, 1.0, v),
+        3 => vec3(u, -1.0, -v),
+        4 => vec3(u, -v, 1.0),
+        _ => vec3(-u, -v, -1.0),
+    }
+}
+
+fn texel(image: &DecodedImage, x: u32, y: u32) -> [f32; 4] {
+    let offset = ((y * image.width + x) * 4) as usize;
+
+    match &image.pixels {
+        Pixels::Rgba8(data) => std::array::from_fn(|i| data[offset + i] as f32 / 255.0),
+        Pixels::Rgba16Float(data) => std::array::from_fn(|i| data[offset + i].to_f32()),
+    }
+}
+
+// bilinear lookup, wrapping horizontally and clamping at the poles
+fn sample(image: &DecodedImage, u: f32, v: f32) -> [f32; 4] {
+    let x = u * image.width as f32 - 0.5;
+    let y = (v * image.height as f32 - 0.5).clamp(0.0, image.height as f32 - 1.0);
+    let (fx, fy) = (x - x.floor(), y - y.floor());
+
+    let wrap = |x: f32| x.rem_euclid(image.width as f32) as u32;
+    let (x0, x1) = (wrap(x.floor()), wrap(x.floor() + 1.0));
+    let (y0, y1) = (y as u32, (y as u32 + 1).min(image.height - 1));
+
+    let (a, b) = (texel(image, x0, y0), texel(image, x1, y0));
+    let (c, d) = (texel(image, x0, y1), texel(image, x1, y1));
+
+    std::array::from_fn(|i| {
+        let top = a[i] + (b[i] - a[i]) * fx;
+        let bottom = c[i] + (d[i] - c[i]) * fx;
+        top + (bottom - top) * fy
+    })
+}
+
+// projects an equirectangular (latitude/longitude) panorama onto six square
+// faces, keeping the pixel format of the source
+pub fn equirect_to_faces(image: &DecodedImage, face_size: u32) -> Vec<DecodedImage> {
+    use std::f32::consts::PI;
+
+    (0..6)
+        .map(|face| {
+            let texels = (0..face_size * face_size).map(|i| {
+                let (x, y) = (i % face_size, i / face_size);
+                let u = 2.0 * (x as f32 + 0.5) / face_size as f32 - 1.0;
+                let v = 2.0 * (y as f32 + 0.5) / face_size as f32 - 1.0;
+                let dir = face_direction(face, u, v).normalize();
+
+                let longitude = dir.z.atan2(dir.x);
+                let latitude = dir.y.asin();
+
+                sample(image, 0.5 + longitude / (2.0 * PI), 0.5 - latitude / PI)
+            });
+
+            let pixels = match image.pixels {
+                Pixels::Rgba8(_) => Pixels::Rgba8(
+                    texels
+                        .flatten()
+                        .map(|value| (value * 255.0).round() as u8)
+                        .collect(),
+                ),
+                Pixels::Rgba16Float(_) => {
+                    Pixels::Rgba16Float(texels.flatten().map(f16::from_f32).collect())
+                }
+            };
+
+            DecodedImage {
+                width: face_size,
+                height: face_size,
+                pixels,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a panorama whose texels store the direction they face, mapped to 0..1
+    fn direction_panorama(width: u32, height: u32) -> DecodedImage {
+        use std::f32::consts::PI;
+
+        let texels = (0..width * height).flat_map(|i| {
+            let (x, y) = (i % width, i / width);
+            let longitude = ((x as f32 + 0.5) / width as f32 - 0.5) * 2.0 * PI;
+            let latitude = (0.5 - (y as f32 + 0.5) / height as f32) * PI;
+            let dir = vec3(
+                latitude.cos() * longitude.cos(),
+                latitude.sin(),
+                latitude.cos() * longitude.sin(),
+            );
+            [dir.x, dir.y, dir.z, 1.0].map(|c| f16::from_f32(c * 0.5 + 0.5))
+        });
+
+        DecodedImage {
+            width,
+            height,
+            pixels: Pixels::Rgba16Float(texels.collect()),
+        }
+    }
+
+    #[test]
+    fn faces_are_in_wgpu_layer_order() {
+        let centers: Vec<_> = (0..6).map(|face| face_direction(face, 0.0, 0.0)).collect();
+
+        assert_eq!(
+            centers,
+            [
+                Vec3::X,
+                Vec3::NEG_X,
+                Vec3::Y,
+                Vec3::NEG_Y,
+                Vec3::Z,
+                Vec3::NEG_Z
+            ]
+        );
+    }
+
+    #[test]
+    fn face_edges_meet_their_neighbours() {
+        // u runs right and v runs down on every face, as seen from inside
+        assert_eq!(face_direction(0, 1.0, 0.0), face_direction(5, -1.0, 0.0));
+        assert_eq!(face_direction(4, 1.0, 0.0), face_direction(0, -1.0, 0.0));
+        assert_eq!(face_direction(1, 1.0, 0.0), face_direction(4, -1.0, 0.0));
+        assert_eq!(face_direction(4, 0.0, -1.0), face_direction(2, 0.0, 1.0));
+        assert_eq!(face_direction(4, 0.0, 1.0), face_direction(3, 0.0, -1.0));
+    }
+
+    #[test]
+    fn faces_sample_the_matching_direction() {
+        let faces = equirect_to_faces(&direction_panorama(256, 128), 8);
+
+        for (face, image) in faces.iter().enumerate() {
+            let Pixels::Rgba16Float(data) = &image.pixels else {
+                panic!("expected float pixels");
+            };
+
+            for (i, texel) in data.chunks(4).enumerate() {
+                let (x, y) = (i as u32 % 8, i as u32 / 8);
+                let u = 2.0 * (x as f32 + 0.5) / 8.0 - 1.0;
+                let v = 2.0 * (y as f32 + 0.5) / 8.0 - 1.0;
+                let expected = face_direction(face, u, v).normalize();
+                let sampled = vec3(texel[0].to_f32(), texel[1].to_f32(), texel[2].to_f32());
+
+                assert!(
+                    (sampled * 2.0 - 1.0).distance(expected) < 0.05,
+                    "face {face} texel {x},{y} sampled {sampled} for {expected}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn faces_keep_the_source_format() {
+        let image = DecodedImage {
+            width: 4,
+            height: 2,
+            pixels: Pixels::Rgba8(vec![255; 32]),
+        };
+        let faces = equirect_to_faces(&image, 2);
+
+        assert_eq!(faces.len(), 6);
+        for face in faces {
+            assert_eq!((face.width, face.height), (2, 2));
+            assert!(matches!(&face.pixels, Pixels::Rgba8(data) if data == &[255; 16]));
+        }
+    }
+}
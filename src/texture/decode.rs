@@ -0,0 +1,292 @@
+use std::fmt;
+
+use half::f16;
+use image::DynamicImage;
+use zune_jpeg::{
+    zune_core::{colorspace::ColorSpace, options::DecoderOptions},
+    JpegDecoder,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+    Bmp,
+    Tga,
+    Gif,
+    Hdr,
+    Exr,
+}
+
+impl ImageFormat {
+    pub fn detect(bytes: &[u8]) -> Option<Self> {
+        use ImageFormat::*;
+
+        const PNG: &[u8] = b"\x89PNG\r\n\x1a\n";
+        const EXR: &[u8] = b"\x76\x2f\x31\x01";
+
+        if bytes.starts_with(PNG) {
+            Some(Png)
+        } else if bytes.starts_with(b"\xff\xd8\xff") {
+            Some(Jpeg)
+        } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+            Some(Gif)
+        } else if bytes.starts_with(b"#?RADIANCE") || bytes.starts_with(b"#?RGBE") {
+            Some(Hdr)
+        } else if bytes.starts_with(EXR) {
+            Some(Exr)
+        } else if bytes.starts_with(b"BM") {
+            Some(Bmp)
+        } else if is_tga(bytes) {
+            Some(Tga)
+        } else {
+            None
+        }
+    }
+
+    fn to_image_format(self) -> image::ImageFormat {
+        use ImageFormat::*;
+
+        match self {
+            Png => image::ImageFormat::Png,
+            Jpeg => image::ImageFormat::Jpeg,
+            Bmp => image::ImageFormat::Bmp,
+            Tga => image::ImageFormat::Tga,
+            Gif => image::ImageFormat::Gif,
+            Hdr => image::ImageFormat::Hdr,
+            Exr => image::ImageFormat::OpenExr,
+        }
+    }
+
+    fn is_float(self) -> bool {
+        matches!(self, ImageFormat::Hdr | ImageFormat::Exr)
+    }
+}
+
+impl fmt::Display for ImageFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use ImageFormat::*;
+
+        let name = match self {
+            Png => "png",
+            Jpeg => "jpeg",
+            Bmp => "bmp",
+            Tga => "tga",
+            Gif => "gif",
+            Hdr => "hdr",
+            Exr => "exr",
+        };
+
+        f.write_str(name)
+    }
+}
+
+// tga has no magic number, so either look for the v2 footer or check that
+// the header fields hold sane values
+fn is_tga(bytes: &[u8]) -> bool {
+    const FOOTER: &[u8] = b"TRUEVISION-XFILE.\0";
+
+    if bytes.ends_with(FOOTER) {
+        return true;
+    }
+
+    if bytes.len() < 18 {
+        return false;
+    }
+
+    let color_map_type = bytes[1];
+    let image_type = bytes[2];
+    let pixel_depth = bytes[16];
+
+    matches!(color_map_type, 0 | 1)
+        && matches!(image_type, 1 | 2 | 3 | 9 | 10 | 11)
+        && matches!(pixel_depth, 8 | 15 | 16 | 24 | 32)
+}
+
+pub enum Pixels {
+    Rgba8(Vec<u8>),
+    Rgba16Float(Vec<f16>),
+}
+
+pub struct DecodedImage {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Pixels,
+}
+
+impl DecodedImage {
+    pub fn decode(bytes: &[u8]) -> Result<Self, String> {
+        let format = ImageFormat::detect(bytes).ok_or("unknown image format".to_owned())?;
+
+        Self::decode_with_format(bytes, format)
+    }
+
+    pub fn decode_with_format(bytes: &[u8], format: ImageFormat) -> Result<Self, String> {
+        if format == ImageFormat::Jpeg {
+            return decode_jpeg(bytes);
+        }
+
+        let image = image::load_from_memory_with_format(bytes, format.to_image_format())
+            .map_err(|err| format!("failed to decode {format}: {err}"))?;
+
+        Ok(Self::from_dynamic(image, format.is_float()))
+    }
+
+    fn from_dynamic(image: DynamicImage, is_float: bool) -> Self {
+        let (width, height) = (image.width(), image.height());
+
+        // to_rgba* expands grayscale and rescales 16 bit channels
+        let pixels = if is_float {
+            let data = image.to_rgba32f().into_raw();
+            Pixels::Rgba16Float(data.into_iter().map(f16::from_f32).collect())
+        } else {
+            Pixels::Rgba8(image.to_rgba8().into_raw())
+        };
+
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    // linear format of the pixel data, see ColorSpace::apply
+    pub fn format(&self) -> wgpu::TextureFormat {
+        match self.pixels {
+            Pixels::Rgba8(_) => wgpu::TextureFormat::Rgba8Unorm,
+            Pixels::Rgba16Float(_) => wgpu::TextureFormat::Rgba16Float,
+        }
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        match &self.pixels {
+            Pixels::Rgba8(data) => data,
+            Pixels::Rgba16Float(data) => bytemuck::cast_slice(data),
+        }
+    }
+}
+
+fn decode_jpeg(bytes: &[u8]) -> Result<DecodedImage, String> {
+    let options = DecoderOptions::default().jpeg_set_out_colorspace(ColorSpace::RGBA);
+    let mut decoder = JpegDecoder::new_with_options(bytes, options);
+
+    let data = decoder
+        .decode()
+        .map_err(|err| format!("failed to decode jpeg: {err}"))?;
+
+    let info = decoder.info().ok_or("failed to get info".to_owned())?;
+
+    Ok(DecodedImage {
+        width: info.width as _,
+        height: info.height as _,
+        pixels: Pixels::Rgba8(data),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{ImageBuffer, Luma, Rgb, Rgba};
+
+    use super::*;
+
+    fn png<P: image::PixelWithColorType>(image: ImageBuffer<P, Vec<P::Subpixel>>) -> Vec<u8>
+    where
+        [P::Subpixel]: image::EncodableLayout,
+    {
+        let mut bytes = std::io::Cursor::new(Vec::new());
+        image.write_to(&mut bytes, image::ImageFormat::Png).unwrap();
+        bytes.into_inner()
+    }
+
+    #[test]
+    fn detects_formats_by_magic_bytes() {
+        let mut tga = vec![0; 18];
+        tga[2] = 2;
+        tga[16] = 32;
+        let mut tga_footer = vec![0xff; 40];
+        tga_footer.extend(b"TRUEVISION-XFILE.\0");
+
+        let cases: &[(&[u8], Option<ImageFormat>)] = &[
+            (b"\x89PNG\r\n\x1a\n....", Some(ImageFormat::Png)),
+            (b"\xff\xd8\xff\xe0", Some(ImageFormat::Jpeg)),
+            (b"GIF87a..", Some(ImageFormat::Gif)),
+            (b"GIF89a..", Some(ImageFormat::Gif)),
+            (b"#?RADIANCE\n", Some(ImageFormat::Hdr)),
+            (b"#?RGBE\n", Some(ImageFormat::Hdr)),
+            (b"\x76\x2f\x31\x01\x02", Some(ImageFormat::Exr)),
+            (b"BM\x3a\x00", Some(ImageFormat::Bmp)),
+            (&tga, Some(ImageFormat::Tga)),
+            (&tga_footer, Some(ImageFormat::Tga)),
+            // a png signature cut short, and nothing at all
+            (b"\x89PNG", None),
+            (b"", None),
+            (b"not an image at all", None),
+        ];
+
+        for (bytes, expected) in cases {
+            assert_eq!(ImageFormat::detect(bytes), *expected, "{bytes:?}");
+        }
+    }
+
+    #[test]
+    fn png_keeps_alpha() {
+        let image = ImageBuffer::from_pixel(2, 1, Rgba([10u8, 20, 30, 40]));
+        let decoded = DecodedImage::decode(&png(image)).unwrap();
+
+        assert_eq!((decoded.width, decoded.height), (2, 1));
+        assert_eq!(decoded.format(), wgpu::TextureFormat::Rgba8Unorm);
+        assert_eq!(decoded.bytes(), [10, 20, 30, 40, 10, 20, 30, 40]);
+    }
+
+    #[test]
+    fn sixteen_bit_grayscale_is_rescaled_and_expanded() {
+        let image = ImageBuffer::from_fn(3, 1, |x, _| Luma([[0u16, 0x8080, 0xffff][x as usize]]));
+        let decoded = DecodedImage::decode(&png(image)).unwrap();
+
+        assert_eq!(decoded.format(), wgpu::TextureFormat::Rgba8Unorm);
+        assert_eq!(
+            decoded.bytes(),
+            [0, 0, 0, 255, 128, 128, 128, 255, 255, 255, 255, 255]
+        );
+    }
+
+    #[test]
+    fn hdr_decodes_to_half_floats() {
+        let pixels = [Rgb([0.5f32, 2.0, 16.0]), Rgb([0.0, 0.25, 1.0])];
+        let mut bytes = Vec::new();
+        image::codecs::hdr::HdrEncoder::new(&mut bytes)
+            .encode(&pixels, 2, 1)
+            .unwrap();
+
+        let decoded = DecodedImage::decode(&bytes).unwrap();
+        assert_eq!(decoded.format(), wgpu::TextureFormat::Rgba16Float);
+        let Pixels::Rgba16Float(data) = &decoded.pixels else {
+            panic!("expected float pixels");
+        };
+        // values above 1 survive, rgbe keeps these exactly
+        let values: Vec<f32> = data.iter().map(|v| v.to_f32()).collect();
+        assert_eq!(values, [0.5, 2.0, 16.0, 1.0, 0.0, 0.25, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn jpeg_decodes_to_rgba() {
+        let bytes = include_bytes!("../sandbox/texture/funyarinpa.jpg");
+        let decoded = DecodedImage::decode(bytes).unwrap();
+
+        assert_eq!(decoded.format(), wgpu::TextureFormat::Rgba8Unorm);
+        assert_eq!(
+            decoded.bytes().len(),
+            (decoded.width * decoded.height * 4) as usize
+        );
+    }
+
+    #[test]
+    fn errors_name_the_format() {
+        let error = |bytes: &[u8]| DecodedImage::decode(bytes).err().unwrap();
+
+        assert_eq!(error(b"nonsense"), "unknown image format");
+        assert!(error(b"\x89PNG\r\n\x1a\ngarbage").starts_with("failed to decode png"));
+        assert!(error(b"#?RADIANCE\ngarbage").starts_with("failed to decode hdr"));
+        assert!(error(b"\xff\xd8\xff\x00").starts_with("failed to decode jpeg"));
+    }
+}
@@ -0,0 +1,323 @@
+// cpu block encoders, used to re-encode transcoded basis universal images for
+// the block formats a device supports. they favor speed over quality: every
+// block interpolates between the texels at the ends of its principal axis
+
+use super::{bcn, etc};
+
+// the texels at either end of the axis the first `channels` channels vary
+// most along, found with one power iteration from the widest channel
+fn extremes(texels: &[[u8; 4]; 16], channels: usize) -> ([u8; 4], [u8; 4]) {
+    let channel = |c: usize| texels.iter().map(move |texel| texel[c] as i32);
+    let mean: [i32; 4] = std::array::from_fn(|c| channel(c).sum::<i32>() / 16);
+    let range = |c: usize| channel(c).max().unwrap() - channel(c).min().unwrap();
+    let widest = (0..channels).max_by_key(|&c| range(c)).unwrap();
+
+    let axis: [i64; 4] = std::array::from_fn(|c| match c < channels {
+        true => channel(c)
+            .zip(channel(widest))
+            .map(|(value, main)| ((value - mean[c]) * (main - mean[widest])) as i64)
+            .sum(),
+        false => 0,
+    });
+    let project = |texel: &&[u8; 4]| (0..4).map(|c| texel[c] as i64 * axis[c]).sum::<i64>();
+
+    let low = texels.iter().min_by_key(project).unwrap();
+    let high = texels.iter().max_by_key(project).unwrap();
+    (*low, *high)
+}
+
+fn distance(a: [i32; 4], b: [u8; 4], channels: usize) -> i32 {
+    (0..channels).map(|c| (a[c] - b[c] as i32).pow(2)).sum()
+}
+
+fn nearest(palette: &[[i32; 4]], texel: [u8; 4], channels: usize) -> usize {
+    (0..palette.len())
+        .min_by_key(|&i| distance(palette[i], texel, channels))
+        .unwrap()
+}
+
+fn pack_565([r, g, b, _]: [u8; 4]) -> u16 {
+    let quantize = |c: u8, bits: u32| ((c as u32 * ((1 << bits) - 1) + 127) / 255) as u16;
+    quantize(r, 5) << 11 | quantize(g, 6) << 5 | quantize(b, 5)
+}
+
+// a bc1 block in its 4 color mode, also the color half of bc3
+fn color_block(texels: &[[u8; 4]; 16]) -> [u8; 8] {
+    let (low, high) = extremes(texels, 3);
+    let (mut c0, mut c1) = (pack_565(high), pack_565(low));
+    if c0 < c1 {
+        std::mem::swap(&mut c0, &mut c1);
+    }
+
+    // equal endpoints switch bc1 to 3 colors, every texel takes the first
+    let mut indices = 0u32;
+    if c0 != c1 {
+        let palette = bcn::color_palette(c0, c1, false).map(|color| color.map(i32::from));
+        for (i, texel) in texels.iter().enumerate() {
+            indices |= (nearest(&palette, *texel, 3) as u32) << (i * 2);
+        }
+    }
+
+    let mut block = [0; 8];
+    block[..2].copy_from_slice(&c0.to_le_bytes());
+    block[2..4].copy_from_slice(&c1.to_le_bytes());
+    block[4..].copy_from_slice(&indices.to_le_bytes());
+    block
+}
+
+// the alpha half of bc3, in its 8 value mode
+fn value_block(values: [u8; 16]) -> [u8; 8] {
+    let (min, max) = (*values.iter().min().unwrap(), *values.iter().max().unwrap());
+
+    let mut indices = 0u64;
+    if max > min {
+        let palette = bcn::value_palette(max, min).map(|value| [value as i32, 0, 0, 0]);
+        for (i, value) in values.into_iter().enumerate() {
+            indices |= (nearest(&palette, [value, 0, 0, 0], 1) as u64) << (i * 3);
+        }
+    }
+
+    let mut block = [max, min, 0, 0, 0, 0, 0, 0];
+    block[2..].copy_from_slice(&indices.to_le_bytes()[..6]);
+    block
+}
+
+// the alpha half of an etc2 rgba8 block. the multiplier only ranges around
+// the one that stretches each modifier table over the values
+pub fn eac_alpha_block(values: &[u8; 16]) -> [u8; 8] {
+    let (min, max) = (*values.iter().min().unwrap(), *values.iter().max().unwrap());
+    let (min, max) = (min as i32, max as i32);
+
+    let mut best = (i32::MAX, 0u64);
+    for (table, modifiers) in etc::EAC_MODIFIERS.iter().enumerate() {
+        let (low, high) = (modifiers[3], modifiers[7]);
+        let ideal = (max - min) / (high - low);
+
+        for multiplier in (ideal..=ideal + 1).map(|m| m.clamp(1, 15)) {
+            let base = ((max + min - (high + low) * multiplier) / 2).clamp(0, 255);
+            let palette = modifiers.map(|m| [(base + m * multiplier).clamp(0, 255), 0, 0, 0]);
+
+            let mut error = 0;
+            let mut word = (base as u64) << 56 | (multiplier as u64) << 52 | (table as u64) << 48;
+            for (i, value) in values.iter().enumerate() {
+                let index = nearest(&palette, [*value, 0, 0, 0], 1);
+                error += distance(palette[index], [*value, 0, 0, 0], 1);
+                word |= (index as u64) << (45 - 3 * etc::column_major(i));
+            }
+            if error < best.0 {
+                best = (error, word);
+            }
+        }
+    }
+
+    best.1.to_be_bytes()
+}
+
+// a single partition astc 4x4 block on a 4x4 weight grid with 8 bit
+// endpoints: rgb with 3 bit weights for opaque blocks, rgba with 2 bit
+// weights otherwise
+fn astc_block(texels: &[[u8; 4]; 16]) -> [u8; 16] {
+    const WEIGHTS_3: [i32; 8] = [0, 9, 18, 27, 37, 46, 55, 64];
+    const WEIGHTS_2: [i32; 4] = [0, 21, 43, 64];
+
+    let opaque = texels.iter().all(|texel| texel[3] == 255);
+    let (mode, endpoint_mode, channels, weights): (u128, u128, _, &[i32]) = match opaque {
+        true => (0x53, 8, 3, &WEIGHTS_3),
+        false => (0x42, 12, 4, &WEIGHTS_2),
+    };
+
+    // endpoints whose second color is darker decode swapped and blue
+    // contracted, so the darker one goes first
+    let (mut low, mut high) = extremes(texels, channels);
+    let sum = |color: [u8; 4]| color[..3].iter().map(|&c| c as u32).sum::<u32>();
+    if sum(high) < sum(low) {
+        std::mem::swap(&mut low, &mut high);
+    }
+
+    let mut bits = mode | endpoint_mode << 13;
+    for c in 0..channels {
+        bits |= (low[c] as u128) << (17 + 16 * c);
+        bits |= (high[c] as u128) << (25 + 16 * c);
+    }
+
+    let palette: Vec<[i32; 4]> = weights
+        .iter()
+        .map(|&w| {
+            std::array::from_fn(|c| {
+                let (e0, e1) = (low[c] as i32 * 257, high[c] as i32 * 257);
+                ((e0 * (64 - w) + e1 * w + 32) >> 6) >> 8
+            })
+        })
+        .collect();
+
+    // weights fill the block from the top bit down, in reverse bit order
+    let weight_bits = weights.len().trailing_zeros() as usize;
+    for (i, texel) in texels.iter().enumerate() {
+        let weight = nearest(&palette, *texel, channels);
+        for bit in 0..weight_bits {
+            let position = 127 - (i * weight_bits + bit);
+            bits |= ((weight >> bit) as u128 & 1) << position;
+        }
+    }
+
+    bits.to_le_bytes()
+}
+
+pub fn encode_block(format: wgpu::TextureFormat, texels: &[[u8; 4]; 16], block: &mut [u8]) {
+    use wgpu::TextureFormat::*;
+
+    match format {
+        Bc1RgbaUnorm | Bc1RgbaUnormSrgb => block.copy_from_slice(&color_block(texels)),
+        Bc3RgbaUnorm | Bc3RgbaUnormSrgb => {
+            block[..8].copy_from_slice(&value_block(texels.map(|texel| texel[3])));
+            block[8..].copy_from_slice(&color_block(texels));
+        }
+        Astc {
+            block: wgpu::AstcBlock::B4x4,
+            channel: wgpu::AstcChannel::Unorm | wgpu::AstcChannel::UnormSrgb,
+        } => block.copy_from_slice(&astc_block(texels)),
+        _ => unreachable!("no encoder for {format:?}"),
+    }
+}
+
+// encodes tightly packed rgba8 texels into 4x4 blocks, repeating the last
+// row and column to fill partial blocks
+pub fn encode_image(format: wgpu::TextureFormat, data: &[u8], width: u32, height: u32) -> Vec<u8> {
+    let block_size = format.block_copy_size(None).unwrap() as usize;
+    let (blocks_x, blocks_y) = (width.div_ceil(4), height.div_ceil(4));
+    let texels: &[[u8; 4]] = bytemuck::cast_slice(data);
+
+    let mut output = vec![0; (blocks_x * blocks_y) as usize * block_size];
+    for (index, block) in output.chunks_exact_mut(block_size).enumerate() {
+        let (bx, by) = (index as u32 % blocks_x, index as u32 / blocks_x);
+        let block_texels = std::array::from_fn(|i| {
+            let x = (bx * 4 + i as u32 % 4).min(width - 1);
+            let y = (by * 4 + i as u32 / 4).min(height - 1);
+            texels[(y * width + x) as usize]
+        });
+        encode_block(format, &block_texels, block);
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use wgpu::TextureFormat;
+
+    use super::*;
+    use crate::texture::astc;
+
+    const ASTC_4X4: TextureFormat = TextureFormat::Astc {
+        block: wgpu::AstcBlock::B4x4,
+        channel: wgpu::AstcChannel::Unorm,
+    };
+
+    // a horizontal ramp from dark red to light blue over 4 steps, fading out
+    // when `fade` is set
+    fn gradient(fade: bool) -> [[u8; 4]; 16] {
+        std::array::from_fn(|i| {
+            let t = (i % 4 * 85) as u8;
+            let alpha = if fade { 255 - t } else { 255 };
+            [128 - t / 2, t / 4, t, alpha]
+        })
+    }
+
+    fn round_trip(format: TextureFormat, texels: &[[u8; 4]; 16]) -> [[u8; 4]; 16] {
+        let mut block = vec![0; format.block_copy_size(None).unwrap() as usize];
+        encode_block(format, texels, &mut block);
+
+        let mut decoded = [[0; 4]; 16];
+        match format {
+            ASTC_4X4 => astc::decode_block(format, &block, decoded.as_flattened_mut()),
+            _ => bcn::decode_block(format, &block, decoded.as_flattened_mut()),
+        }
+        decoded
+    }
+
+    fn max_error(a: &[[u8; 4]; 16], b: &[[u8; 4]; 16]) -> u8 {
+        a.as_flattened()
+            .iter()
+            .zip(b.as_flattened())
+            .map(|(a, b)| a.abs_diff(*b))
+            .max()
+            .unwrap()
+    }
+
+    #[test]
+    fn bc1_keeps_flat_blocks_opaque() {
+        let texels = [[200, 100, 50, 255]; 16];
+        let decoded = round_trip(TextureFormat::Bc1RgbaUnorm, &texels);
+
+        assert!(max_error(&texels, &decoded) <= 4);
+    }
+
+    // steps between palette entries of up to 36, for the 8 bc3 alpha values
+    // and the 3 bit astc weights, bound the errors of a 4 step ramp
+    #[test]
+    fn bc_blocks_follow_gradients() {
+        let texels = gradient(false);
+        assert!(max_error(&texels, &round_trip(TextureFormat::Bc1RgbaUnorm, &texels)) <= 8);
+
+        let texels = gradient(true);
+        let decoded = round_trip(TextureFormat::Bc3RgbaUnorm, &texels);
+        assert!(max_error(&texels, &decoded) <= 18);
+        assert!(texels
+            .iter()
+            .zip(decoded)
+            .all(|(a, b)| a[..3].iter().zip(b).all(|(a, b)| a.abs_diff(b) <= 8)));
+    }
+
+    #[test]
+    fn astc_blocks_follow_gradients() {
+        for fade in [false, true] {
+            let texels = gradient(fade);
+            let decoded = round_trip(ASTC_4X4, &texels);
+
+            assert!(max_error(&texels, &decoded) <= 18, "fade {fade}");
+        }
+    }
+
+    #[test]
+    fn eac_alpha_round_trips() {
+        let decode = |values: &[u8; 16]| {
+            let mut block = [0; 16];
+            block[..8].copy_from_slice(&eac_alpha_block(values));
+            let mut texels = [[0u8; 4]; 16];
+            etc::decode_block(
+                TextureFormat::Etc2Rgba8Unorm,
+                &block,
+                texels.as_flattened_mut(),
+            );
+            texels.map(|texel| texel[3])
+        };
+
+        assert_eq!(decode(&[255; 16]), [255; 16]);
+
+        // 16 values over the 8 an eac block holds
+        let ramp = std::array::from_fn(|i| (i * 17) as u8);
+        let decoded = decode(&ramp);
+        assert!(ramp.iter().zip(decoded).all(|(a, b)| a.abs_diff(b) <= 17));
+    }
+
+    #[test]
+    fn images_repeat_edges_into_partial_blocks() {
+        // a 5x5 image, black but for a white last row and column
+        let texels: Vec<[u8; 4]> = (0..25)
+            .map(|i| match i % 5 == 4 || i / 5 == 4 {
+                true => [255; 4],
+                false => [0, 0, 0, 255],
+            })
+            .collect();
+        let data = encode_image(TextureFormat::Bc1RgbaUnorm, texels.as_flattened(), 5, 5);
+        assert_eq!(data.len(), 4 * 8);
+
+        let mut decoded = [[0u8; 4]; 16];
+        bcn::decode_block(
+            TextureFormat::Bc1RgbaUnorm,
+            &data[24..],
+            decoded.as_flattened_mut(),
+        );
+        assert_eq!(decoded, [[255; 4]; 16]);
+    }
+}
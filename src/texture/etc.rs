@@ -0,0 +1,407 @@
+// cpu decoders for the etc2 and eac block formats, used when the adapter has
+// no TEXTURE_COMPRESSION_ETC2 support. every block is 4x4 texels stored as
+// big endian 64 bit words, with texel indices running down the columns
+
+use half::f16;
+
+const MODIFIERS: [[i32; 2]; 8] = [
+    [2, 8],
+    [5, 17],
+    [9, 29],
+    [13, 42],
+    [18, 60],
+    [24, 80],
+    [33, 106],
+    [47, 183],
+];
+
+// the distance between paint colors in the t and h modes
+const DISTANCES: [i32; 8] = [3, 6, 11, 16, 23, 32, 41, 64];
+
+#[rustfmt::skip]
+pub(super) const EAC_MODIFIERS: [[i32; 8]; 16] = [
+    [-3, -6, -9, -15, 2, 5, 8, 14],
+    [-3, -7, -10, -13, 2, 6, 9, 12],
+    [-2, -5, -8, -13, 1, 4, 7, 12],
+    [-2, -4, -6, -13, 1, 3, 5, 12],
+    [-3, -6, -8, -12, 2, 5, 7, 11],
+    [-3, -7, -9, -11, 2, 6, 8, 10],
+    [-4, -7, -8, -11, 3, 6, 7, 10],
+    [-3, -5, -8, -11, 2, 4, 7, 10],
+    [-2, -6, -8, -10, 1, 5, 7, 9],
+    [-2, -5, -8, -10, 1, 4, 7, 9],
+    [-2, -4, -8, -10, 1, 3, 7, 9],
+    [-2, -5, -7, -10, 1, 4, 6, 9],
+    [-3, -4, -7, -10, 2, 3, 6, 9],
+    [-1, -2, -3, -10, 0, 1, 2, 9],
+    [-4, -6, -8, -9, 3, 5, 7, 8],
+    [-3, -5, -7, -9, 2, 4, 6, 8],
+];
+
+fn word(block: &[u8]) -> u64 {
+    u64::from_be_bytes(block[..8].try_into().unwrap())
+}
+
+fn field(word: u64, lowest: u32, count: u32) -> i32 {
+    ((word >> lowest) & ((1 << count) - 1)) as i32
+}
+
+fn extend(value: i32, bits: u32) -> i32 {
+    let value = value << (8 - bits);
+    value | value >> bits
+}
+
+// where texel `i` (row by row) keeps its index
+pub(super) fn column_major(i: usize) -> usize {
+    (i % 4) * 4 + i / 4
+}
+
+enum Colors {
+    // base color and modifier table of each half, and whether the halves
+    // are stacked instead of side by side
+    Halves([[i32; 3]; 2], [usize; 2], bool),
+    Paint([[i32; 3]; 4]),
+    // origin, horizontal and vertical corner colors
+    Planar([[i32; 3]; 3]),
+}
+
+// `punchthrough` is the rgb8a1 variant, where the differential bit marks the
+// block as opaque instead and index 2 is transparent when it isn't
+fn decode_color_block(block: &[u8], punchthrough: bool) -> [[u8; 4]; 16] {
+    let word = word(block);
+    let bit = |lowest| field(word, lowest, 1);
+    let (differential, opaque) = match punchthrough {
+        true => (true, bit(33) == 1),
+        false => (bit(33) == 1, true),
+    };
+
+    let delta = |lowest| (field(word, lowest, 3) << 29) >> 29;
+    let (r, g, b) = (field(word, 59, 5), field(word, 51, 5), field(word, 43, 5));
+    let (r2, g2, b2) = (r + delta(56), g + delta(48), b + delta(40));
+    let tables = [field(word, 37, 3) as usize, field(word, 34, 3) as usize];
+
+    // overflowing differential colors select the etc2 modes
+    let colors = if !differential {
+        let color = |lowest: [u32; 3]| lowest.map(|lowest| extend(field(word, lowest, 4), 4));
+        Colors::Halves(
+            [color([60, 52, 44]), color([56, 48, 40])],
+            tables,
+            bit(32) == 1,
+        )
+    } else if !(0..32).contains(&r2) {
+        let c1 = [
+            field(word, 59, 2) << 2 | field(word, 56, 2),
+            field(word, 52, 4),
+            field(word, 48, 4),
+        ];
+        let c2 = [field(word, 44, 4), field(word, 40, 4), field(word, 36, 4)];
+        let (c1, c2) = (c1.map(|c| extend(c, 4)), c2.map(|c| extend(c, 4)));
+        let d = DISTANCES[(field(word, 34, 2) << 1 | bit(32)) as usize];
+
+        let offset = |c: [i32; 3], d: i32| c.map(|c| (c + d).clamp(0, 255));
+        Colors::Paint([c1, offset(c2, d), c2, offset(c2, -d)])
+    } else if !(0..32).contains(&g2) {
+        let c1 = [
+            field(word, 59, 4),
+            field(word, 56, 3) << 1 | bit(52),
+            bit(51) << 3 | field(word, 47, 3),
+        ];
+        let c2 = [field(word, 43, 4), field(word, 39, 4), field(word, 35, 4)];
+        let (c1, c2) = (c1.map(|c| extend(c, 4)), c2.map(|c| extend(c, 4)));
+        let value = |c: [i32; 3]| c[0] << 16 | c[1] << 8 | c[2];
+        let d = DISTANCES[(bit(34) << 2 | bit(32) << 1 | (value(c1) >= value(c2)) as i32) as usize];
+
+        let offset = |c: [i32; 3], d: i32| c.map(|c| (c + d).clamp(0, 255));
+        Colors::Paint([offset(c1, d), offset(c1, -d), offset(c2, d), offset(c2, -d)])
+    } else if !(0..32).contains(&b2) {
+        let origin = [
+            extend(field(word, 57, 6), 6),
+            extend(bit(56) << 6 | field(word, 49, 6), 7),
+            extend(
+                bit(48) << 5 | field(word, 43, 2) << 3 | field(word, 39, 3),
+                6,
+            ),
+        ];
+        let horizontal = [
+            extend(field(word, 34, 5) << 1 | bit(32), 6),
+            extend(field(word, 25, 7), 7),
+            extend(field(word, 19, 6), 6),
+        ];
+        let vertical = [
+            extend(field(word, 13, 6), 6),
+            extend(field(word, 6, 7), 7),
+            extend(field(word, 0, 6), 6),
+        ];
+        Colors::Planar([origin, horizontal, vertical])
+    } else {
+        let color = |[r, g, b]: [i32; 3]| [r, g, b].map(|c| extend(c, 5));
+        Colors::Halves(
+            [color([r, g, b]), color([r2, g2, b2])],
+            tables,
+            bit(32) == 1,
+        )
+    };
+
+    std::array::from_fn(|i| {
+        let (x, y) = ((i % 4) as i32, (i / 4) as i32);
+        let j = column_major(i) as u32;
+        let index = (field(word, 16 + j, 1) << 1 | field(word, j, 1)) as usize;
+
+        if !opaque && index == 2 && !matches!(colors, Colors::Planar(_)) {
+            return [0; 4];
+        }
+
+        let rgb = match &colors {
+            Colors::Halves(bases, tables, flip) => {
+                let half = if *flip { y >= 2 } else { x >= 2 } as usize;
+                let [small, large] = MODIFIERS[tables[half]];
+                let modifier = match index {
+                    0 if !opaque => 0,
+                    0 => small,
+                    1 => large,
+                    2 => -small,
+                    _ => -large,
+                };
+                bases[half].map(|c| c + modifier)
+            }
+            Colors::Paint(paint) => paint[index],
+            Colors::Planar([o, h, v]) => {
+                std::array::from_fn(|c| (x * (h[c] - o[c]) + y * (v[c] - o[c]) + 4 * o[c] + 2) >> 2)
+            }
+        };
+
+        let [r, g, b] = rgb.map(|c| c.clamp(0, 255) as u8);
+        [r, g, b, 255]
+    })
+}
+
+// the base, multiplier, modifier table and per texel indices of an eac block
+fn eac_fields(block: &[u8]) -> (u8, i32, [i32; 16]) {
+    let word = word(block);
+    let multiplier = field(word, 52, 4);
+    let modifiers = EAC_MODIFIERS[field(word, 48, 4) as usize];
+
+    let modifiers = std::array::from_fn(|i| {
+        let j = column_major(i) as u32;
+        modifiers[field(word, 45 - 3 * j, 3) as usize]
+    });
+
+    (block[0], multiplier, modifiers)
+}
+
+fn decode_alpha_block(block: &[u8]) -> [u8; 16] {
+    let (base, multiplier, modifiers) = eac_fields(block);
+
+    modifiers.map(|modifier| (base as i32 + modifier * multiplier).clamp(0, 255) as u8)
+}
+
+// one 11 bit channel of the r11 and rg11 formats
+fn decode_r11_block(block: &[u8], signed: bool) -> [f16; 16] {
+    let (base, multiplier, modifiers) = eac_fields(block);
+
+    modifiers.map(|modifier| {
+        // a zero multiplier steps in eighths of the usual size
+        let step = match multiplier {
+            0 => modifier,
+            _ => modifier * multiplier * 8,
+        };
+        let value = if signed {
+            let base = (base as i8).max(-127) as i32;
+            (base * 8 + step).clamp(-1023, 1023) as f32 / 1023.0
+        } else {
+            (base as i32 * 8 + 4 + step).clamp(0, 2047) as f32 / 2047.0
+        };
+        f16::from_f32(value)
+    })
+}
+
+pub fn is_supported(format: wgpu::TextureFormat) -> bool {
+    use wgpu::TextureFormat::*;
+
+    matches!(
+        format,
+        Etc2Rgb8Unorm
+            | Etc2Rgb8UnormSrgb
+            | Etc2Rgb8A1Unorm
+            | Etc2Rgb8A1UnormSrgb
+            | Etc2Rgba8Unorm
+            | Etc2Rgba8UnormSrgb
+            | EacR11Unorm
+            | EacR11Snorm
+            | EacRg11Unorm
+            | EacRg11Snorm
+    )
+}
+
+// writes the 16 texels of one block row by row, as rgba8 or as rgba16 float
+// for the 11 bit eac formats
+pub fn decode_block(format: wgpu::TextureFormat, block: &[u8], texels: &mut [u8]) {
+    use wgpu::TextureFormat::*;
+
+    let channels = |signed: bool| {
+        let r = decode_r11_block(block, signed);
+        let g = match format {
+            EacRg11Unorm | EacRg11Snorm => decode_r11_block(&block[8..], signed),
+            _ => [f16::ZERO; 16],
+        };
+        let texels: [[f16; 4]; 16] = std::array::from_fn(|i| [r[i], g[i], f16::ZERO, f16::ONE]);
+        texels
+    };
+
+    let rgba8 = match format {
+        Etc2Rgb8Unorm | Etc2Rgb8UnormSrgb => decode_color_block(block, false),
+        Etc2Rgb8A1Unorm | Etc2Rgb8A1UnormSrgb => decode_color_block(block, true),
+        Etc2Rgba8Unorm | Etc2Rgba8UnormSrgb => {
+            let mut texels = decode_color_block(&block[8..], false);
+            let alpha = decode_alpha_block(&block[..8]);
+            for (texel, alpha) in texels.iter_mut().zip(alpha) {
+                texel[3] = alpha;
+            }
+            texels
+        }
+        EacR11Unorm | EacRg11Unorm | EacR11Snorm | EacRg11Snorm => {
+            let signed = matches!(format, EacR11Snorm | EacRg11Snorm);
+            texels.copy_from_slice(bytemuck::cast_slice(&channels(signed)));
+            return;
+        }
+        _ => unreachable!("no etc decoder for {format:?}"),
+    };
+
+    texels.copy_from_slice(rgba8.as_flattened());
+}
+
+#[cfg(test)]
+mod tests {
+    use wgpu::TextureFormat;
+
+    use super::*;
+
+    // texel indices and the colors expected there
+    type Expected = &'static [(usize, [u8; 4])];
+
+    fn decode(format: TextureFormat, block: &[u8]) -> [[u8; 4]; 16] {
+        let mut texels = [[0; 4]; 16];
+        decode_block(format, block, texels.as_flattened_mut());
+        texels
+    }
+
+    fn decode_half(format: TextureFormat, block: &[u8]) -> [[u16; 4]; 16] {
+        let mut texels = [[0u16; 4]; 16];
+        decode_block(format, block, bytemuck::cast_slice_mut(&mut texels));
+        texels
+    }
+
+    #[test]
+    fn differential_blocks_add_the_modifier_to_the_base() {
+        // base 16 in every channel, no delta, table 0 and texel 1 on index 3
+        let word: u64 = 16 << 59 | 16 << 51 | 16 << 43 | 1 << 33 | 1 << 20 | 1 << 4;
+        let texels = decode(TextureFormat::Etc2Rgb8Unorm, &word.to_be_bytes());
+
+        assert_eq!(texels[0], [134, 134, 134, 255]);
+        assert_eq!(texels[1], [124, 124, 124, 255]);
+    }
+
+    // checked against mesa's decoder
+    #[test]
+    fn etc2_modes_match_reference_blocks() {
+        let cases: [([u8; 8], Expected); 3] = [
+            (
+                [0x04, 0xbc, 0x9b, 0xc3, 0x2e, 0x12, 0x7b, 0x75],
+                &[
+                    (0, [159, 193, 210, 255]),
+                    (1, [147, 181, 198, 255]),
+                    (4, [153, 187, 204, 255]),
+                    (12, [0, 187, 204, 255]),
+                ],
+            ),
+            (
+                [0x47, 0x0c, 0x9d, 0xc6, 0xd7, 0x25, 0x93, 0xbe],
+                &[
+                    (0, [83, 219, 168, 255]),
+                    (1, [104, 206, 121, 255]),
+                    (2, [19, 155, 104, 255]),
+                    (7, [168, 255, 185, 255]),
+                ],
+            ),
+            (
+                [0x40, 0x28, 0x0d, 0x43, 0x90, 0xf1, 0x3f, 0xf0],
+                &[
+                    (0, [130, 40, 40, 255]),
+                    (3, [133, 119, 101, 255]),
+                    (12, [60, 201, 156, 255]),
+                    (15, [63, 255, 217, 255]),
+                ],
+            ),
+        ];
+
+        for (block, expected) in cases {
+            let texels = decode(TextureFormat::Etc2Rgb8Unorm, &block);
+            for &(i, texel) in expected {
+                assert_eq!(texels[i], texel, "texel {i} of {block:x?}");
+            }
+        }
+    }
+
+    // checked against mesa's decoder
+    #[test]
+    fn punchthrough_alpha_follows_the_opaque_bit() {
+        let opaque = [0x48, 0x82, 0x98, 0x4f, 0x99, 0x17, 0xe0, 0xf3];
+        let texels = decode(TextureFormat::Etc2Rgb8A1Unorm, &opaque);
+        assert_eq!(texels[0], [45, 103, 127, 255]);
+        assert_eq!(texels[5], [103, 161, 185, 255]);
+        assert_eq!(texels[15], [32, 106, 114, 255]);
+
+        let translucent = [0x10, 0xc5, 0x72, 0xcd, 0x6f, 0x85, 0x94, 0x17];
+        let texels = decode(TextureFormat::Etc2Rgb8A1Unorm, &translucent);
+        assert_eq!(texels[0], [0, 92, 9, 255]);
+        assert_eq!(texels[2], [0; 4]);
+        assert_eq!(texels[5], [16, 198, 115, 255]);
+        assert_eq!(texels[15], [58, 215, 174, 255]);
+    }
+
+    #[test]
+    fn eac_alpha_scales_the_modifier() {
+        // base 128, multiplier 2, table 0 and texel 0 on index 7
+        let mut block = [0; 16];
+        block[..3].copy_from_slice(&[128, 0x20, 0xe0]);
+        let texels = decode(TextureFormat::Etc2Rgba8Unorm, &block);
+
+        assert_eq!(texels[0][3], 128 + 14 * 2);
+        assert_eq!(texels[1][3], 128 - 3 * 2);
+    }
+
+    #[test]
+    fn r11_widens_the_base() {
+        let half = |value: f32| f16::from_f32(value).to_bits();
+
+        let block = [128, 0x20, 0xe0, 0, 0, 0, 0, 0];
+        let texels = decode_half(TextureFormat::EacR11Unorm, &block);
+        assert_eq!(texels[0], [half(1252.0 / 2047.0), 0, 0, 0x3c00]);
+
+        // a zero multiplier keeps the modifier at an eighth
+        let block = [0, 0, 0, 0, 0, 0, 0, 0];
+        let texels = decode_half(TextureFormat::EacR11Unorm, &block);
+        assert_eq!(texels[0][0], half(1.0 / 2047.0));
+
+        let block = [0x80, 0x20, 0xe0, 0, 0, 0, 0, 0];
+        let texels = decode_half(TextureFormat::EacR11Snorm, &block);
+        assert_eq!(texels[0][0], half(-792.0 / 1023.0));
+    }
+
+    // checked against mesa's decoder
+    #[test]
+    fn r11_snorm_matches_a_reference_block() {
+        let block = [0x10, 0xc5, 0x72, 0xcd, 0x6f, 0x85, 0x94, 0x17];
+        let texels = decode_half(TextureFormat::EacR11Snorm, &block);
+
+        assert_eq!(
+            texels[..4],
+            [
+                [0xbb42, 0, 0, 0x3c00],
+                [0x3b02, 0, 0, 0x3c00],
+                [0x3501, 0, 0, 0x3c00],
+                [0xb9c1, 0, 0, 0x3c00],
+            ]
+        );
+    }
+}
@@ -0,0 +1,255 @@
+use super::{cube, decode::DecodedImage, extent, Texture, TextureOptions};
+
+// copies each cell of a `columns` x `rows` grid out in turn, row by row
+fn slice_cells(image: &DecodedImage, columns: u32, rows: u32) -> Result<Vec<u8>, String> {
+    if columns == 0
+        || rows == 0
+        || !image.width.is_multiple_of(columns)
+        || !image.height.is_multiple_of(rows)
+    {
+        return Err(format!(
+            "{}x{} sheet can't be split into {columns}x{rows} cells",
+            image.width, image.height
+        ));
+    }
+
+    let (cell_width, cell_height) = (image.width / columns, image.height / rows);
+    let texel_size = image.format().block_copy_size(None).unwrap() as usize;
+    let row_size = image.width as usize * texel_size;
+    let cell_row_size = cell_width as usize * texel_size;
+    let bytes = image.bytes();
+
+    let mut data = Vec::with_capacity(bytes.len());
+    for cell in 0..columns * rows {
+        let (cx, cy) = ((cell % columns) as usize, (cell / columns) as usize);
+
+        for y in 0..cell_height as usize {
+            let offset = (cy * cell_height as usize + y) * row_size + cx * cell_row_size;
+            data.extend_from_slice(&bytes[offset..offset + cell_row_size]);
+        }
+    }
+
+    Ok(data)
+}
+
+// checks that every image shares one size and format and concatenates them
+fn stack(images: &[DecodedImage]) -> Result<Vec<u8>, String> {
+    let first = images.first().ok_or("no layers given".to_owned())?;
+
+    let mut data = Vec::with_capacity(first.bytes().len() * images.len());
+    for (i, image) in images.iter().enumerate() {
+        if (image.width, image.height) != (first.width, first.height) {
+            return Err(format!(
+                "layer {i} is {}x{}, expected {}x{}",
+                image.width, image.height, first.width, first.height
+            ));
+        }
+
+        if image.format() != first.format() {
+            return Err(format!("layer {i} has a different pixel format"));
+        }
+
+        data.extend_from_slice(image.bytes());
+    }
+
+    Ok(data)
+}
+
+impl Texture {
+    pub fn from_layers(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        layers: &[DecodedImage],
+        options: TextureOptions,
+    ) -> Result<Self, String> {
+        let data = stack(layers)?;
+        let first = &layers[0];
+
+        Ok(Self::create(
+            device,
+            queue,
+            &data,
+            extent(first.width, first.height, layers.len() as _),
+            wgpu::TextureViewDimension::D2Array,
+            options.color_space.apply(first.format()),
+            options,
+        ))
+    }
+
+    // slices a grid of equally sized cells into array layers, row by row
+    pub fn from_sprite_sheet(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        image: &DecodedImage,
+        columns: u32,
+        rows: u32,
+        options: TextureOptions,
+    ) -> Result<Self, String> {
+        let data = slice_cells(image, columns, rows)?;
+        let (cell_width, cell_height) = (image.width / columns, image.height / rows);
+
+        Ok(Self::create(
+            device,
+            queue,
+            &data,
+            extent(cell_width, cell_height, columns * rows),
+            wgpu::TextureViewDimension::D2Array,
+            options.color_space.apply(image.format()),
+            options,
+        ))
+    }
+
+    // faces in +x, -x, +y, -y, +z, -z order
+    pub fn from_cube_faces(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        faces: &[DecodedImage],
+        options: TextureOptions,
+    ) -> Result<Self, String> {
+        if faces.len() != 6 {
+            return Err(format!("a cubemap needs 6 faces, got {}", faces.len()));
+        }
+
+        let data = stack(faces)?;
+        let first = &faces[0];
+
+        if first.width != first.height {
+            return Err("cubemap faces must be square".to_owned());
+        }
+
+        Ok(Self::create(
+            device,
+            queue,
+            &data,
+            extent(first.width, first.height, 6),
+            wgpu::TextureViewDimension::Cube,
+            options.color_space.apply(first.format()),
+            options,
+        ))
+    }
+
+    pub fn from_equirectangular(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        image: &DecodedImage,
+        face_size: u32,
+        options: TextureOptions,
+    ) -> Result<Self, String> {
+        if face_size == 0 {
+            return Err("cubemap faces need at least one texel".to_owned());
+        }
+
+        let faces = cube::equirect_to_faces(image, face_size);
+
+        Self::from_cube_faces(device, queue, &faces, options)
+    }
+
+    // `data` holds `depth` slices of `width` x `height` texels, back to back
+    pub fn from_volume(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        data: &[u8],
+        (width, height, depth): (u32, u32, u32),
+        format: wgpu::TextureFormat,
+        options: TextureOptions,
+    ) -> Self {
+        Self::create(
+            device,
+            queue,
+            data,
+            extent(width, height, depth),
+            wgpu::TextureViewDimension::D3,
+            format,
+            options,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::texture::decode::Pixels;
+
+    // every texel holds its own x and y in red and green
+    fn numbered(width: u32, height: u32) -> DecodedImage {
+        DecodedImage {
+            width,
+            height,
+            pixels: Pixels::Rgba8(
+                (0..width * height)
+                    .flat_map(|i| [(i % width) as u8, (i / width) as u8, 0, 255])
+                    .collect(),
+            ),
+        }
+    }
+
+    fn positions(data: &[u8]) -> Vec<(u8, u8)> {
+        data.chunks(4).map(|texel| (texel[0], texel[1])).collect()
+    }
+
+    #[test]
+    fn sheets_are_sliced_row_by_row() {
+        let data = slice_cells(&numbered(4, 2), 2, 2).unwrap();
+
+        assert_eq!(
+            positions(&data),
+            [
+                (0, 0),
+                (1, 0),
+                (2, 0),
+                (3, 0),
+                (0, 1),
+                (1, 1),
+                (2, 1),
+                (3, 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn cells_keep_their_rows_together() {
+        let data = slice_cells(&numbered(4, 4), 2, 1).unwrap();
+
+        // the left 2x4 cell, then the right one
+        let left: Vec<_> = (0..4).flat_map(|y| [(0, y), (1, y)]).collect();
+        let right: Vec<_> = (0..4).flat_map(|y| [(2, y), (3, y)]).collect();
+        assert_eq!(positions(&data), [left, right].concat());
+    }
+
+    #[test]
+    fn sheets_must_split_evenly() {
+        let error = |columns, rows| slice_cells(&numbered(4, 3), columns, rows).err();
+
+        assert_eq!(
+            error(3, 1).unwrap(),
+            "4x3 sheet can't be split into 3x1 cells"
+        );
+        assert!(error(2, 2).is_some());
+        assert!(error(0, 1).is_some());
+        assert!(error(2, 3).is_none());
+    }
+
+    #[test]
+    fn stacked_layers_must_match() {
+        let error = |layers: &[DecodedImage]| stack(layers).err().unwrap();
+
+        assert_eq!(error(&[]), "no layers given");
+        assert_eq!(
+            error(&[numbered(2, 2), numbered(2, 1)]),
+            "layer 1 is 2x1, expected 2x2"
+        );
+
+        let float = DecodedImage {
+            width: 2,
+            height: 2,
+            pixels: Pixels::Rgba16Float(vec![half::f16::ZERO; 16]),
+        };
+        assert_eq!(
+            error(&[numbered(2, 2), float]),
+            "layer 1 has a different pixel format"
+        );
+
+        let data = stack(&[numbered(2, 1), numbered(2, 1)]).unwrap();
+        assert_eq!(positions(&data), [(0, 0), (1, 0), (0, 0), (1, 0)]);
+    }
+}
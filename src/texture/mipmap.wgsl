@@ -0,0 +1,120 @@
+@group(0) @binding(0)
+var flat_texture: texture_2d<f32>;
+
+// nearest, every tap lands on a texel center
+@group(0) @binding(1)
+var texture_sampler: sampler;
+
+// array and cube sources are bound whole (restricted to the source mip) and
+// the layer being written is selected here, see MipmapGenerator::generate
+@group(0) @binding(2)
+var<uniform> layer: u32;
+
+@group(0) @binding(3)
+var cube_texture: texture_cube<f32>;
+
+@group(0) @binding(4)
+var array_texture: texture_2d_array<f32>;
+
+// the source texels a destination texel covers along one axis, as uvs of
+// their centers and their weights. even sizes average two texels, odd ones
+// spread each destination texel over three so the last row and column aren't
+// dropped
+struct Taps {
+    coords: vec3<f32>,
+    weights: vec3<f32>,
+}
+
+fn taps(pixel: f32, source_size: u32) -> Taps {
+    let i = floor(pixel);
+    let size = f32(source_size);
+
+    var out: Taps;
+    out.coords = (vec3f(2.0 * i, 2.0 * i + 1.0, 2.0 * i + 2.0) + 0.5) / size;
+    if source_size == 1u {
+        out.coords = vec3f(0.5);
+        out.weights = vec3f(1.0, 0.0, 0.0);
+    } else if source_size % 2u == 0u {
+        out.weights = vec3f(0.5, 0.5, 0.0);
+    } else {
+        let n = f32(source_size / 2u);
+        out.weights = vec3f(n - i, n, i + 1.0) / (2.0 * n + 1.0);
+    }
+
+    return out;
+}
+
+@fragment
+fn fs_flat(in: VertexOutput) -> @location(0) vec4<f32> {
+    let size = textureDimensions(flat_texture);
+    let x = taps(in.pos.x, size.x);
+    let y = taps(in.pos.y, size.y);
+
+    var color = vec4f(0.0);
+    for (var j = 0; j < 3; j++) {
+        for (var i = 0; i < 3; i++) {
+            let weight = x.weights[i] * y.weights[j];
+            if weight > 0.0 {
+                let uv = vec2f(x.coords[i], y.coords[j]);
+                color += weight * textureSampleLevel(flat_texture, texture_sampler, uv, 0.0);
+            }
+        }
+    }
+
+    return color;
+}
+
+@fragment
+fn fs_array(in: VertexOutput) -> @location(0) vec4<f32> {
+    let size = textureDimensions(array_texture);
+    let x = taps(in.pos.x, size.x);
+    let y = taps(in.pos.y, size.y);
+
+    var color = vec4f(0.0);
+    for (var j = 0; j < 3; j++) {
+        for (var i = 0; i < 3; i++) {
+            let weight = x.weights[i] * y.weights[j];
+            if weight > 0.0 {
+                let uv = vec2f(x.coords[i], y.coords[j]);
+                color += weight * textureSampleLevel(array_texture, texture_sampler, uv, layer, 0.0);
+            }
+        }
+    }
+
+    return color;
+}
+
+// same face orientation as texture::cube::face_direction
+fn cube_direction(uv: vec2<f32>) -> vec3<f32> {
+    let u = uv.x * 2.0 - 1.0;
+    let v = uv.y * 2.0 - 1.0;
+
+    switch layer {
+        case 0u: { return vec3f(1.0, -v, -u); }
+        case 1u: { return vec3f(-1.0, -v, u); }
+        case 2u: { return vec3f(u, 1.0, v); }
+        case 3u: { return vec3f(u, -1.0, -v); }
+        case 4u: { return vec3f(u, -v, 1.0); }
+        default: { return vec3f(-u, -v, -1.0); }
+    }
+}
+
+@fragment
+fn fs_cube(in: VertexOutput) -> @location(0) vec4<f32> {
+    let size = textureDimensions(cube_texture);
+    let x = taps(in.pos.x, size.x);
+    let y = taps(in.pos.y, size.y);
+
+    var color = vec4f(0.0);
+    for (var j = 0; j < 3; j++) {
+        for (var i = 0; i < 3; i++) {
+            let weight = x.weights[i] * y.weights[j];
+            if weight > 0.0 {
+                let direction = cube_direction(vec2f(x.coords[i], y.coords[j]));
+                color += weight * textureSampleLevel(cube_texture, texture_sampler, direction, 0.0);
+            }
+        }
+    }
+
+    return color;
+}
@@ -0,0 +1,529 @@
+use std::path::Path;
+
+use container::ContainerImage;
+use decode::DecodedImage;
+use mipmap::MipmapGenerator;
+use sampler::SamplerPreset;
+use wgpu::util::DeviceExt;
+
+pub mod astc;
+pub mod atlas;
+pub mod basis;
+pub mod bcn;
+pub mod container;
+pub mod cube;
+pub mod decode;
+pub mod encode;
+pub mod etc;
+pub mod layered;
+pub mod mipmap;
+pub mod sampler;
+pub mod sheet;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mipmaps {
+    None,
+    Generate,
+    // the data already holds this many levels, largest first
+    Precomputed(u32),
+}
+
+// color textures hold srgb encoded values and are decoded when sampled, data
+// textures (normal maps, masks, lookup tables) are sampled as is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorSpace {
+    Srgb,
+    Linear,
+}
+
+impl ColorSpace {
+    pub fn apply(self, format: wgpu::TextureFormat) -> wgpu::TextureFormat {
+        match self {
+            ColorSpace::Srgb => format.add_srgb_suffix(),
+            ColorSpace::Linear => format.remove_srgb_suffix(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TextureOptions {
+    pub mipmaps: Mipmaps,
+    pub sampler: SamplerPreset,
+    pub color_space: ColorSpace,
+}
+
+impl Default for TextureOptions {
+    fn default() -> Self {
+        Self {
+            mipmaps: Mipmaps::Generate,
+            sampler: SamplerPreset::Trilinear,
+            color_space: ColorSpace::Srgb,
+        }
+    }
+}
+
+pub struct Texture {
+    pub texture_view: wgpu::TextureView,
+    pub sampler: wgpu::Sampler,
+    pub view_dimension: wgpu::TextureViewDimension,
+}
+
+impl Texture {
+    pub fn from_path(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        path: impl AsRef<Path>,
+    ) -> Result<Self, String> {
+        let path = path.as_ref();
+        let content = std::fs::read(path)
+            .map_err(|err| format!("failed to read {}: {err}", path.display()))?;
+
+        Self::from_bytes(device, queue, &content)
+    }
+
+    pub fn from_bytes(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        bytes: &[u8],
+    ) -> Result<Self, String> {
+        if container::is_container(bytes) {
+            let image = ContainerImage::decode(bytes)?;
+            return Self::from_container(device, queue, &image, TextureOptions::default());
+        }
+
+        let image = DecodedImage::decode(bytes)?;
+
+        Ok(Self::from_image(
+            device,
+            queue,
+            &image,
+            TextureOptions::default(),
+        ))
+    }
+
+    pub fn from_jpeg(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        path: impl AsRef<Path>,
+    ) -> Result<Self, String> {
+        let path = path.as_ref();
+        let content = std::fs::read(path)
+            .map_err(|err| format!("failed to read {}: {err}", path.display()))?;
+        let image = DecodedImage::decode_with_format(&content, decode::ImageFormat::Jpeg)?;
+
+        Ok(Self::from_image(
+            device,
+            queue,
+            &image,
+            TextureOptions::default(),
+        ))
+    }
+
+    pub fn from_image(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        image: &DecodedImage,
+        options: TextureOptions,
+    ) -> Self {
+        Self::create(
+            device,
+            queue,
+            image.bytes(),
+            extent(image.width, image.height, 1),
+            wgpu::TextureViewDimension::D2,
+            options.color_space.apply(image.format()),
+            options,
+        )
+    }
+
+    // uploads the levels and layers stored in the file as they are. formats
+    // the device can't sample are decompressed on the cpu when possible, and
+    // basis universal images are re-encoded for it instead. the color space
+    // overrides whether the file is tagged srgb, for formats that have both
+    // variants
+    pub fn from_container(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        image: &ContainerImage,
+        options: TextureOptions,
+    ) -> Result<Self, String> {
+        if !image.is_supported(device.features()) {
+            if image.is_universal {
+                let image = image.transcode(device.features())?;
+                log::debug!("transcoding basis universal texture to {:?}", image.format);
+                return Self::from_container(device, queue, &image, options);
+            }
+            if !image.can_decompress() {
+                return Err(format!(
+                    "{:?} is not supported by the device and has no cpu decoder",
+                    image.format
+                ));
+            }
+
+            log::debug!("decompressing {:?} texture on the cpu", image.format);
+            return Self::from_container(device, queue, &image.decompress()?, options);
+        }
+
+        let view_dimension = match (image.is_cubemap, image.layers) {
+            (true, 6) => wgpu::TextureViewDimension::Cube,
+            (true, _) => wgpu::TextureViewDimension::CubeArray,
+            (false, 1) => wgpu::TextureViewDimension::D2,
+            (false, _) => wgpu::TextureViewDimension::D2Array,
+        };
+
+        let texture = device.create_texture_with_data(
+            queue,
+            &wgpu::TextureDescriptor {
+                label: None,
+                size: extent(image.width, image.height, image.layers),
+                mip_level_count: image.levels,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: options.color_space.apply(image.format),
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            },
+            image.order,
+            &image.data,
+        );
+
+        Ok(Self::from_texture(
+            device,
+            &texture,
+            view_dimension,
+            options,
+        ))
+    }
+
+    pub fn from_raw_data(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        data: &[u8],
+        dimensions: (u32, u32),
+        options: TextureOptions,
+    ) -> Self {
+        Self::create(
+            device,
+            queue,
+            data,
+            extent(dimensions.0, dimensions.1, 1),
+            wgpu::TextureViewDimension::D2,
+            options.color_space.apply(wgpu::TextureFormat::Rgba8Unorm),
+            options,
+        )
+    }
+
+    // `data` holds every layer (or depth slice) back to back
+    fn create(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        data: &[u8],
+        size: wgpu::Extent3d,
+        view_dimension: wgpu::TextureViewDimension,
+        format: wgpu::TextureFormat,
+        mut options: TextureOptions,
+    ) -> Self {
+        let dimension = view_dimension.compatible_texture_dimension();
+
+        // the blit based generator renders into 2d views only
+        if dimension == wgpu::TextureDimension::D3 && options.mipmaps == Mipmaps::Generate {
+            options.mipmaps = Mipmaps::None;
+        }
+
+        let mip_level_count = match options.mipmaps {
+            Mipmaps::None => 1,
+            Mipmaps::Generate => mipmap::mip_level_count(size.width, size.height),
+            Mipmaps::Precomputed(count) => count,
+        };
+
+        let mut usage = wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST;
+        if options.mipmaps == Mipmaps::Generate {
+            usage |= wgpu::TextureUsages::RENDER_ATTACHMENT;
+        }
+
+        let descriptor = wgpu::TextureDescriptor {
+            label: None,
+            size,
+            mip_level_count,
+            sample_count: 1,
+            dimension,
+            format,
+            usage,
+            view_formats: &[],
+        };
+
+        let texture = if options.mipmaps == Mipmaps::Generate {
+            let texture = device.create_texture(&descriptor);
+
+            let bytes_per_row = size.width * format.block_copy_size(None).unwrap();
+            let layer_size = (bytes_per_row * size.height) as usize;
+
+            // one write per layer, the gl backend can't write a whole cubemap
+            // in one go
+            for (layer, data) in data.chunks(layer_size).enumerate() {
+                queue.write_texture(
+                    wgpu::ImageCopyTexture {
+                        texture: &texture,
+                        mip_level: 0,
+                        origin: wgpu::Origin3d {
+                            x: 0,
+                            y: 0,
+                            z: layer as _,
+                        },
+                        aspect: wgpu::TextureAspect::All,
+                    },
+                    data,
+                    wgpu::ImageDataLayout {
+                        offset: 0,
+                        bytes_per_row: Some(bytes_per_row),
+                        rows_per_image: None,
+                    },
+                    wgpu::Extent3d {
+                        depth_or_array_layers: 1,
+                        ..size
+                    },
+                );
+            }
+
+            MipmapGenerator::shared(device).generate(device, queue, &texture, view_dimension);
+
+            texture
+        } else {
+            device.create_texture_with_data(
+                queue,
+                &descriptor,
+                wgpu::util::TextureDataOrder::LayerMajor,
+                data,
+            )
+        };
+
+        Self::from_texture(device, &texture, view_dimension, options)
+    }
+
+    // wraps a texture created elsewhere, e.g. a render or storage target
+    pub fn from_texture(
+        device: &wgpu::Device,
+        texture: &wgpu::Texture,
+        view_dimension: wgpu::TextureViewDimension,
+        options: TextureOptions,
+    ) -> Self {
+        let texture_view = texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(view_dimension),
+            ..Default::default()
+        });
+        let sampler = options.sampler.create_sampler(device);
+
+        Self {
+            texture_view,
+            sampler,
+            view_dimension,
+        }
+    }
+
+    pub fn with_sampler(mut self, device: &wgpu::Device, preset: SamplerPreset) -> Self {
+        self.sampler = preset.create_sampler(device);
+        self
+    }
+
+    pub fn bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+        Self::bind_group_layout_for(device, wgpu::TextureViewDimension::D2)
+    }
+
+    pub fn bind_group_layout_for(
+        device: &wgpu::Device,
+        view_dimension: wgpu::TextureViewDimension,
+    ) -> wgpu::BindGroupLayout {
+        use wgpu::BindGroupLayoutEntry as Entry;
+
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("texture bind group layout"),
+            entries: &[
+                Entry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                Entry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        })
+    }
+
+    pub fn bind_group(
+        &self,
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("texture bind group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&self.texture_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&self.sampler),
+                },
+            ],
+        })
+    }
+}
+
+fn extent(width: u32, height: u32, depth_or_array_layers: u32) -> wgpu::Extent3d {
+    wgpu::Extent3d {
+        width,
+        height,
+        depth_or_array_layers,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::postprocess::fullscreen_shader;
+
+    const WIDTH: u32 = 256;
+
+    fn srgb_encode(linear: f32) -> f32 {
+        if linear <= 0.0031308 {
+            linear * 12.92
+        } else {
+            1.055 * linear.powf(1.0 / 2.4) - 0.055
+        }
+    }
+
+    // samples a 256x1 gradient texture into an srgb target of the same size and
+    // reads back the red channel
+    fn render_gradient(color_space: ColorSpace) -> Vec<u8> {
+        let (device, queue) = crate::testing::headless_device();
+
+        let data: Vec<u8> = (0..WIDTH)
+            .flat_map(|i| [i as u8, i as u8, i as u8, 255])
+            .collect();
+        let options = TextureOptions {
+            mipmaps: Mipmaps::None,
+            sampler: SamplerPreset::Nearest,
+            color_space,
+        };
+        let texture = Texture::from_raw_data(&device, &queue, &data, (WIDTH, 1), options);
+
+        let target_format = wgpu::TextureFormat::Rgba8UnormSrgb;
+        let target = device.create_texture(&wgpu::TextureDescriptor {
+            label: None,
+            size: wgpu::Extent3d {
+                width: WIDTH,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: target_format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let target_view = target.create_view(&Default::default());
+
+        let shader = fullscreen_shader(&device, "blit shader", include_str!("blit.wgsl"));
+        let bind_group_layout = Texture::bind_group_layout(&device);
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: None,
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: None,
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(target_format.into())],
+            }),
+            primitive: Default::default(),
+            multisample: Default::default(),
+            depth_stencil: None,
+            multiview: None,
+            cache: None,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&texture.texture_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&texture.sampler),
+                },
+            ],
+        });
+
+        let mut encoder = device.create_command_encoder(&Default::default());
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: None,
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &target_view,
+                resolve_target: None,
+                ops: Default::default(),
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        render_pass.set_pipeline(&pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+        drop(render_pass);
+
+        let pixels = crate::testing::read_texture(&device, &queue, encoder, &target);
+
+        pixels.chunks(4).map(|pixel| pixel[0]).collect()
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn srgb_texture_round_trips_through_srgb_target() {
+        let output = render_gradient(ColorSpace::Srgb);
+
+        for (i, &value) in output.iter().enumerate() {
+            assert!(
+                value.abs_diff(i as u8) <= 1,
+                "texel {i} came out as {value}"
+            );
+        }
+    }
+
+    #[test]
+    #[ignore = "needs a GPU adapter"]
+    fn linear_texture_is_encoded_by_srgb_target() {
+        let output = render_gradient(ColorSpace::Linear);
+
+        for (i, &value) in output.iter().enumerate() {
+            let expected = (srgb_encode(i as f32 / 255.0) * 255.0).round() as u8;
+            assert!(
+                value.abs_diff(expected) <= 1,
+                "texel {i} came out as {value}, expected {expected}"
+            );
+        }
+    }
+}
@@ -0,0 +1,99 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SamplerPreset {
+    Nearest,
+    Bilinear,
+    Trilinear,
+    Anisotropic(u16),
+}
+
+impl SamplerPreset {
+    pub fn descriptor(self) -> wgpu::SamplerDescriptor<'static> {
+        use SamplerPreset::*;
+
+        let (filter, mipmap_filter, anisotropy_clamp) = match self {
+            Nearest => (wgpu::FilterMode::Nearest, wgpu::FilterMode::Nearest, 1),
+            Bilinear => (wgpu::FilterMode::Linear, wgpu::FilterMode::Nearest, 1),
+            Trilinear => (wgpu::FilterMode::Linear, wgpu::FilterMode::Linear, 1),
+            // anisotropic filtering requires every filter to be linear
+            Anisotropic(clamp) => (
+                wgpu::FilterMode::Linear,
+                wgpu::FilterMode::Linear,
+                clamp.clamp(1, 16),
+            ),
+        };
+
+        wgpu::SamplerDescriptor {
+            label: None,
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: filter,
+            min_filter: filter,
+            mipmap_filter,
+            anisotropy_clamp,
+            ..Default::default()
+        }
+    }
+
+    pub fn create_sampler(self, device: &wgpu::Device) -> wgpu::Sampler {
+        device.create_sampler(&self.descriptor())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wgpu::FilterMode::{Linear, Nearest};
+
+    #[test]
+    fn presets_pick_their_filters() {
+        let filters = |preset: SamplerPreset| {
+            let descriptor = preset.descriptor();
+            (
+                descriptor.mag_filter,
+                descriptor.min_filter,
+                descriptor.mipmap_filter,
+                descriptor.anisotropy_clamp,
+            )
+        };
+
+        assert_eq!(
+            filters(SamplerPreset::Nearest),
+            (Nearest, Nearest, Nearest, 1)
+        );
+        assert_eq!(
+            filters(SamplerPreset::Bilinear),
+            (Linear, Linear, Nearest, 1)
+        );
+        assert_eq!(
+            filters(SamplerPreset::Trilinear),
+            (Linear, Linear, Linear, 1)
+        );
+        assert_eq!(
+            filters(SamplerPreset::Anisotropic(8)),
+            (Linear, Linear, Linear, 8)
+        );
+    }
+
+    #[test]
+    fn anisotropy_is_clamped_to_what_wgpu_accepts() {
+        let clamp = |level| {
+            SamplerPreset::Anisotropic(level)
+                .descriptor()
+                .anisotropy_clamp
+        };
+
+        assert_eq!(clamp(0), 1);
+        assert_eq!(clamp(16), 16);
+        assert_eq!(clamp(64), 16);
+    }
+
+    #[test]
+    fn presets_clamp_to_edge() {
+        let descriptor = SamplerPreset::Trilinear.descriptor();
+
+        assert_eq!(descriptor.address_mode_u, wgpu::AddressMode::ClampToEdge);
+        assert_eq!(descriptor.address_mode_v, wgpu::AddressMode::ClampToEdge);
+        assert_eq!(descriptor.address_mode_w, wgpu::AddressMode::ClampToEdge);
+    }
+}
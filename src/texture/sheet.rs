@@ -0,0 +1,235 @@
+use std::path::Path;
+
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct Rect {
+    pub x: u32,
+    pub y: u32,
+    pub w: u32,
+    pub h: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+struct Size {
+    w: u32,
+    h: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SheetFrame {
+    pub name: String,
+    // location in the sheet image, w and h are the size of the unrotated sprite
+    pub rect: Rect,
+    // stored rotated 90 degrees clockwise in the sheet
+    pub rotated: bool,
+    // where the trimmed rect sits in the untrimmed sprite, and that sprite's
+    // size. untrimmed frames have no offset and their own size
+    pub offset: (u32, u32),
+    pub source_size: (u32, u32),
+    // milliseconds, only exported by aseprite
+    pub duration: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TagDirection {
+    #[default]
+    Forward,
+    Reverse,
+    Pingpong,
+    PingpongReverse,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct FrameTag {
+    pub name: String,
+    pub from: usize,
+    pub to: usize,
+    #[serde(default)]
+    pub direction: TagDirection,
+}
+
+#[derive(Deserialize)]
+struct RawFrame {
+    filename: Option<String>,
+    frame: Rect,
+    #[serde(default)]
+    rotated: bool,
+    #[serde(rename = "spriteSourceSize")]
+    sprite_source_size: Option<Rect>,
+    #[serde(rename = "sourceSize")]
+    source_size: Option<Size>,
+    duration: Option<u32>,
+}
+
+// texturepacker and aseprite both export either a "hash" (name -> frame) or
+// an "array" (frames carry a filename) layout
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawFrames {
+    Array(Vec<RawFrame>),
+    Hash(serde_json::Map<String, serde_json::Value>),
+}
+
+#[derive(Deserialize, Default)]
+struct RawMeta {
+    image: Option<String>,
+    #[serde(default, rename = "frameTags")]
+    frame_tags: Vec<FrameTag>,
+}
+
+#[derive(Deserialize)]
+struct RawSheet {
+    frames: RawFrames,
+    #[serde(default)]
+    meta: RawMeta,
+}
+
+// frame metadata of a packed sprite sheet in the texturepacker / aseprite
+// json format
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpriteSheet {
+    // path of the sheet image, relative to the json file
+    pub image: Option<String>,
+    pub frames: Vec<SheetFrame>,
+    pub tags: Vec<FrameTag>,
+}
+
+impl SpriteSheet {
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, String> {
+        let json = std::fs::read_to_string(path.as_ref())
+            .map_err(|e| format!("failed to read {}: {e}", path.as_ref().display()))?;
+
+        Self::from_json(&json)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let sheet: RawSheet =
+            serde_json::from_str(json).map_err(|e| format!("invalid sprite sheet: {e}"))?;
+
+        let frames = match sheet.frames {
+            RawFrames::Array(frames) => frames
+                .into_iter()
+                .enumerate()
+                .map(|(i, frame)| {
+                    let name = frame.filename.clone().unwrap_or_else(|| i.to_string());
+                    SheetFrame::new(name, frame)
+                })
+                .collect(),
+            RawFrames::Hash(frames) => frames
+                .into_iter()
+                .map(|(name, frame)| {
+                    let frame = serde_json::from_value(frame)
+                        .map_err(|e| format!("invalid frame {name:?}: {e}"))?;
+                    Ok(SheetFrame::new(name, frame))
+                })
+                .collect::<Result<_, String>>()?,
+        };
+
+        Ok(Self {
+            image: sheet.meta.image,
+            frames,
+            tags: sheet.meta.frame_tags,
+        })
+    }
+
+    pub fn frame(&self, name: &str) -> Option<&SheetFrame> {
+        self.frames.iter().find(|frame| frame.name == name)
+    }
+}
+
+impl SheetFrame {
+    fn new(name: String, frame: RawFrame) -> Self {
+        let rect = frame.frame;
+        let offset = frame
+            .sprite_source_size
+            .map_or((0, 0), |trimmed| (trimmed.x, trimmed.y));
+        let source_size = frame
+            .source_size
+            .map_or((rect.w, rect.h), |size| (size.w, size.h));
+
+        Self {
+            name,
+            rect,
+            rotated: frame.rotated,
+            offset,
+            source_size,
+            duration: frame.duration,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hash_frames_keep_their_trim() {
+        let sheet = SpriteSheet::from_json(
+            r#"{
+                "frames": {
+                    "coin": {
+                        "frame": { "x": 2, "y": 4, "w": 10, "h": 12 },
+                        "rotated": true,
+                        "trimmed": true,
+                        "spriteSourceSize": { "x": 3, "y": 1, "w": 10, "h": 12 },
+                        "sourceSize": { "w": 16, "h": 16 }
+                    }
+                },
+                "meta": { "image": "coin.png" }
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(sheet.image.as_deref(), Some("coin.png"));
+        assert_eq!(
+            sheet.frame("coin"),
+            Some(&SheetFrame {
+                name: "coin".to_owned(),
+                rect: Rect {
+                    x: 2,
+                    y: 4,
+                    w: 10,
+                    h: 12
+                },
+                rotated: true,
+                offset: (3, 1),
+                source_size: (16, 16),
+                duration: None,
+            })
+        );
+    }
+
+    #[test]
+    fn array_frames_default_to_untrimmed() {
+        let sheet = SpriteSheet::from_json(
+            r#"{
+                "frames": [
+                    { "frame": { "x": 0, "y": 0, "w": 8, "h": 6 }, "duration": 100 },
+                    { "filename": "b", "frame": { "x": 8, "y": 0, "w": 8, "h": 6 } }
+                ],
+                "meta": {
+                    "frameTags": [{ "name": "all", "from": 0, "to": 1, "direction": "pingpong" }]
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let frame = &sheet.frames[0];
+        assert_eq!(frame.name, "0");
+        assert_eq!((frame.offset, frame.source_size), ((0, 0), (8, 6)));
+        assert_eq!(frame.duration, Some(100));
+        assert_eq!(sheet.frames[1].name, "b");
+        assert_eq!(sheet.tags[0].direction, TagDirection::Pingpong);
+    }
+
+    #[test]
+    fn invalid_frames_are_named() {
+        let error = SpriteSheet::from_json(r#"{ "frames": { "bad": { "frame": 1 } } }"#)
+            .err()
+            .unwrap();
+
+        assert!(error.starts_with("invalid frame \"bad\""));
+    }
+}
@@ -0,0 +1,450 @@
+use std::{collections::BTreeMap, io::Read};
+
+use base64::Engine;
+
+use crate::assets::AssetSource;
+
+pub use renderer::TileMapRenderer;
+
+pub mod renderer;
+pub mod tmj;
+pub mod tmx;
+
+// tiled keeps the flips of each placed tile in the top bits of its gid
+pub const FLIPPED_HORIZONTALLY: u32 = 0x8000_0000;
+pub const FLIPPED_VERTICALLY: u32 = 0x4000_0000;
+pub const FLIPPED_DIAGONALLY: u32 = 0x2000_0000;
+// only used by hexagonal maps, cleared along with the others
+const ROTATED_HEXAGONAL_120: u32 = 0x1000_0000;
+const FLAGS: u32 =
+    FLIPPED_HORIZONTALLY | FLIPPED_VERTICALLY | FLIPPED_DIAGONALLY | ROTATED_HEXAGONAL_120;
+
+// a gid as stored in a layer, flip flags included. zero is an empty cell
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Tile(pub u32);
+
+impl Tile {
+    pub const EMPTY: Tile = Tile(0);
+
+    pub fn gid(self) -> u32 {
+        self.0 & !FLAGS
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.gid() == 0
+    }
+
+    // the diagonal flip swaps x and y, tiled applies it before the other two
+    pub fn flipped_horizontally(self) -> bool {
+        self.0 & FLIPPED_HORIZONTALLY != 0
+    }
+
+    pub fn flipped_vertically(self) -> bool {
+        self.0 & FLIPPED_VERTICALLY != 0
+    }
+
+    pub fn flipped_diagonally(self) -> bool {
+        self.0 & FLIPPED_DIAGONALLY != 0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Frame {
+    // local to the tileset, like the animated tile
+    pub tile: u32,
+    // in milliseconds
+    pub duration: u32,
+}
+
+// a grid of equally sized tiles cut from a single image
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tileset {
+    pub name: String,
+    pub first_gid: u32,
+    pub tile_width: u32,
+    pub tile_height: u32,
+    // in pixels, between tiles and around the edge of the image
+    pub spacing: u32,
+    pub margin: u32,
+    pub columns: u32,
+    pub tile_count: u32,
+    // relative to the asset root, like the map's own path
+    pub image: String,
+    // drawing offset of every tile, in pixels
+    pub offset: [i32; 2],
+    // keyed by the local id of the animated tile
+    pub animations: BTreeMap<u32, Vec<Frame>>,
+}
+
+impl Tileset {
+    pub fn contains(&self, gid: u32) -> bool {
+        (self.first_gid..self.first_gid + self.tile_count).contains(&gid)
+    }
+
+    // top left corner of a tile in the image, in pixels
+    pub fn tile_origin(&self, local: u32) -> [u32; 2] {
+        let columns = self.columns.max(1);
+        [
+            self.margin + local % columns * (self.tile_width + self.spacing),
+            self.margin + local / columns * (self.tile_height + self.spacing),
+        ]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TileLayer {
+    pub name: String,
+    // in tiles, the same as the map's
+    pub width: u32,
+    pub height: u32,
+    // row by row from the top left
+    pub tiles: Vec<Tile>,
+    pub visible: bool,
+    pub opacity: f32,
+    // in pixels
+    pub offset: [f32; 2],
+}
+
+impl TileLayer {
+    pub fn tile(&self, x: u32, y: u32) -> Tile {
+        if x >= self.width || y >= self.height {
+            return Tile::EMPTY;
+        }
+
+        self.tiles[y as usize * self.width as usize + x as usize]
+    }
+}
+
+// an orthogonal, finite map made in tiled. group layers are flattened into
+// the tile layers they hold, object and image layers are left out
+#[derive(Debug, Clone, PartialEq)]
+pub struct TileMap {
+    // in tiles
+    pub width: u32,
+    pub height: u32,
+    // the grid size, in pixels. tiles larger than that stick out to the top
+    // and right of their cell
+    pub tile_width: u32,
+    pub tile_height: u32,
+    // by ascending first gid
+    pub tilesets: Vec<Tileset>,
+    // bottom to top
+    pub layers: Vec<TileLayer>,
+}
+
+impl TileMap {
+    // picks the format from the extension, tilesets kept in their own files
+    // are read from the same source
+    pub fn load(source: &AssetSource, path: &str) -> Result<Self, String> {
+        let read = |file: &str| {
+            let bytes = source.read(file)?;
+            String::from_utf8(bytes).map_err(|_| format!("{file} is not valid utf-8"))
+        };
+        let text = read(path)?;
+
+        if path.ends_with(".tmx") {
+            tmx::parse(&text, path, &read)
+        } else if path.ends_with(".tmj") || path.ends_with(".json") {
+            tmj::parse(&text, path, &read)
+        } else {
+            Err(format!("{path} is neither a .tmx nor a .tmj map"))
+        }
+    }
+
+    pub fn tileset(&self, gid: u32) -> Option<(usize, &Tileset)> {
+        self.tilesets
+            .iter()
+            .enumerate()
+            .rev()
+            .find(|(_, tileset)| tileset.first_gid <= gid)
+            .filter(|(_, tileset)| tileset.contains(gid))
+    }
+
+    // one past the largest gid any tileset covers
+    pub fn gid_count(&self) -> u32 {
+        self.tilesets
+            .iter()
+            .map(|tileset| tileset.first_gid + tileset.tile_count)
+            .max()
+            .unwrap_or(1)
+    }
+
+    fn validate(mut self) -> Result<Self, String> {
+        self.tilesets.sort_by_key(|tileset| tileset.first_gid);
+
+        // the renderer stores cells in 16 bits
+        if self.width > u16::MAX as u32 || self.height > u16::MAX as u32 {
+            return Err(format!(
+                "{}x{} tiles is too large a map",
+                self.width, self.height
+            ));
+        }
+
+        let tile_count = (self.width as usize).checked_mul(self.height as usize);
+        for layer in &self.layers {
+            if Some(layer.tiles.len()) != tile_count {
+                return Err(format!(
+                    "layer {:?} has {} tiles instead of {}x{}",
+                    layer.name,
+                    layer.tiles.len(),
+                    self.width,
+                    self.height
+                ));
+            }
+        }
+
+        for tileset in &self.tilesets {
+            if let Some((tile, _)) = tileset
+                .animations
+                .iter()
+                .find(|(_, frames)| frames.iter().any(|f| f.tile >= tileset.tile_count))
+            {
+                return Err(format!(
+                    "tile {tile} of {:?} is animated with tiles it doesn't have",
+                    tileset.name
+                ));
+            }
+        }
+
+        Ok(self)
+    }
+}
+
+// reads the files a map refers to, by their path relative to the asset root
+type ReadFile<'a> = &'a dyn Fn(&str) -> Result<String, String>;
+
+// what a group layer passes on to the layers inside it
+#[derive(Debug, Clone, Copy)]
+struct Group {
+    offset: [f32; 2],
+    opacity: f32,
+    visible: bool,
+}
+
+impl Default for Group {
+    fn default() -> Self {
+        Self {
+            offset: [0.0, 0.0],
+            opacity: 1.0,
+            visible: true,
+        }
+    }
+}
+
+impl Group {
+    fn nest(self, offset: [f32; 2], opacity: f32, visible: bool) -> Self {
+        Self {
+            offset: [self.offset[0] + offset[0], self.offset[1] + offset[1]],
+            opacity: self.opacity * opacity,
+            visible: self.visible && visible,
+        }
+    }
+}
+
+// maps of either format may use tilesets saved in either format
+fn external_tileset(path: &str, first_gid: u32, read: ReadFile) -> Result<Tileset, String> {
+    let text = read(path)?;
+
+    if path.ends_with(".tsx") {
+        tmx::tileset_file(&text, first_gid, path)
+    } else {
+        tmj::tileset_file(&text, first_gid, path)
+    }
+}
+
+// older files leave out the column and tile counts
+fn grid_size(image: [u32; 2], tile: [u32; 2], margin: u32, spacing: u32) -> [u32; 2] {
+    let fit = |image: u32, tile: u32| {
+        (image.saturating_sub(margin * 2) + spacing) / (tile + spacing).max(1)
+    };
+
+    [fit(image[0], tile[0]), fit(image[1], tile[1])]
+}
+
+// resolves `path` against the directory of `file`, both relative to the asset
+// root
+fn relative_to(file: &str, path: &str) -> String {
+    let mut parts: Vec<&str> = file.split('/').collect();
+    parts.pop();
+
+    for part in path.split('/') {
+        match part {
+            "." | "" => (),
+            ".." if parts.last().is_some_and(|last| *last != "..") => {
+                parts.pop();
+            }
+            _ => parts.push(part),
+        }
+    }
+
+    parts.join("/")
+}
+
+// tile layer data, either comma separated gids or little endian ones in
+// base64, optionally compressed
+fn decode_tiles(
+    data: &str,
+    encoding: Option<&str>,
+    compression: Option<&str>,
+) -> Result<Vec<Tile>, String> {
+    match encoding {
+        Some("csv") => data
+            .split(',')
+            .map(str::trim)
+            .filter(|gid| !gid.is_empty())
+            .map(|gid| {
+                gid.parse()
+                    .map(Tile)
+                    .map_err(|_| format!("invalid gid {gid:?}"))
+            })
+            .collect(),
+        Some("base64") => {
+            let bytes = base64::engine::general_purpose::STANDARD
+                .decode(data.trim())
+                .map_err(|e| format!("invalid base64 tile data: {e}"))?;
+            let bytes = decompress(&bytes, compression)?;
+
+            if bytes.len() % 4 != 0 {
+                return Err("tile data isn't a whole number of gids".to_owned());
+            }
+
+            Ok(bytes
+                .chunks_exact(4)
+                .map(|gid| Tile(u32::from_le_bytes(gid.try_into().unwrap())))
+                .collect())
+        }
+        Some(encoding) => Err(format!("unknown tile data encoding {encoding:?}")),
+        None => Err("tile data without an encoding".to_owned()),
+    }
+}
+
+fn decompress(bytes: &[u8], compression: Option<&str>) -> Result<Vec<u8>, String> {
+    let mut data = Vec::new();
+    let result = match compression {
+        None | Some("") => return Ok(bytes.to_vec()),
+        Some("zlib") => flate2::read::ZlibDecoder::new(bytes).read_to_end(&mut data),
+        Some("gzip") => flate2::read::GzDecoder::new(bytes).read_to_end(&mut data),
+        Some("zstd") => ruzstd::decoding::StreamingDecoder::new(bytes)
+            .map_err(|e| std::io::Error::other(e.to_string()))
+            .and_then(|mut decoder| decoder.read_to_end(&mut data)),
+        Some(compression) => return Err(format!("unknown tile data compression {compression:?}")),
+    };
+    result.map_err(|e| format!("failed to decompress tile data: {e}"))?;
+
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_are_kept_apart_from_the_gid() {
+        let tile = Tile(FLIPPED_HORIZONTALLY | FLIPPED_DIAGONALLY | 7);
+
+        assert_eq!(tile.gid(), 7);
+        assert!(tile.flipped_horizontally());
+        assert!(!tile.flipped_vertically());
+        assert!(tile.flipped_diagonally());
+        assert!(Tile(FLIPPED_VERTICALLY).is_empty());
+    }
+
+    #[test]
+    fn paths_resolve_next_to_the_map() {
+        assert_eq!(relative_to("maps/a.tmx", "tiles.tsx"), "maps/tiles.tsx");
+        assert_eq!(
+            relative_to("maps/a.tmx", "../images/./tiles.png"),
+            "images/tiles.png"
+        );
+        assert_eq!(relative_to("a.tmx", "../tiles.png"), "../tiles.png");
+    }
+
+    #[test]
+    fn encodings_decode_to_the_same_tiles() {
+        let expected = [1, 2, FLIPPED_VERTICALLY | 3, 0].map(Tile).to_vec();
+        let bytes: Vec<u8> = expected.iter().flat_map(|t| t.0.to_le_bytes()).collect();
+        let base64 = |bytes: &[u8]| base64::engine::general_purpose::STANDARD.encode(bytes);
+
+        let mut zlib = flate2::write::ZlibEncoder::new(Vec::new(), Default::default());
+        std::io::Write::write_all(&mut zlib, &bytes).unwrap();
+        let zlib = zlib.finish().unwrap();
+
+        let csv = format!("1,2,\n{},0\n", FLIPPED_VERTICALLY | 3);
+        assert_eq!(decode_tiles(&csv, Some("csv"), None), Ok(expected.clone()));
+        assert_eq!(
+            decode_tiles(&base64(&bytes), Some("base64"), None),
+            Ok(expected.clone())
+        );
+        assert_eq!(
+            decode_tiles(&base64(&zlib), Some("base64"), Some("zlib")),
+            Ok(expected)
+        );
+
+        assert!(decode_tiles(&base64(&bytes[1..]), Some("base64"), None).is_err());
+        assert!(decode_tiles("1,x", Some("csv"), None).is_err());
+    }
+
+    #[test]
+    fn gids_find_their_tileset() {
+        let tileset = |first_gid, tile_count| Tileset {
+            name: String::new(),
+            first_gid,
+            tile_width: 16,
+            tile_height: 16,
+            spacing: 0,
+            margin: 0,
+            columns: 4,
+            tile_count,
+            image: String::new(),
+            offset: [0, 0],
+            animations: BTreeMap::new(),
+        };
+        let map = TileMap {
+            width: 0,
+            height: 0,
+            tile_width: 16,
+            tile_height: 16,
+            tilesets: vec![tileset(1, 8), tileset(20, 4)],
+            layers: Vec::new(),
+        };
+
+        assert_eq!(map.tileset(1).map(|(i, _)| i), Some(0));
+        assert_eq!(map.tileset(8).map(|(i, _)| i), Some(0));
+        assert_eq!(map.tileset(9), None);
+        assert_eq!(map.tileset(23).map(|(i, _)| i), Some(1));
+        assert_eq!(map.tileset(24), None);
+        assert_eq!(map.gid_count(), 24);
+    }
+
+    #[test]
+    fn oversized_maps_are_rejected() {
+        let map = |width, height, tiles| TileMap {
+            width,
+            height,
+            tile_width: 16,
+            tile_height: 16,
+            tilesets: Vec::new(),
+            layers: vec![TileLayer {
+                name: "ground".to_owned(),
+                width,
+                height,
+                tiles: vec![Tile::EMPTY; tiles],
+                visible: true,
+                opacity: 1.0,
+                offset: [0.0; 2],
+            }],
+        };
+
+        assert!(map(3, 2, 6).validate().is_ok());
+        assert_eq!(
+            map(3, 2, 5).validate().err().unwrap(),
+            "layer \"ground\" has 5 tiles instead of 3x2"
+        );
+        // 65536 * 65536 wraps to 0 in 32 bits
+        assert_eq!(
+            map(65536, 65536, 0).validate().err().unwrap(),
+            "65536x65536 tiles is too large a map"
+        );
+        assert!(map(u16::MAX as u32 + 1, 1, 65536).validate().is_err());
+    }
+}
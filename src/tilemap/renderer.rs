@@ -0,0 +1,599 @@
+use std::ops::Range;
+
+use wgpu::{include_wgsl, util::DeviceExt};
+
+use super::{TileMap, Tileset};
+use crate::{
+    sandbox::camera2d::camera::Camera2D,
+    texture::{decode::DecodedImage, sampler::SamplerPreset, Mipmaps, Texture, TextureOptions},
+};
+
+// in tiles along each side
+pub const CHUNK_SIZE: u32 = 32;
+// width of the animation lookup texture, the shader wraps gids at it too
+const FRAMES_WIDTH: u32 = 256;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct MapUniform {
+    view_projection: [[f32; 4]; 4],
+    tile_size: [f32; 2],
+    _padding: [f32; 2],
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct TilesetUniform {
+    tile_size: [f32; 2],
+    offset: [f32; 2],
+    first_gid: u32,
+    columns: u32,
+    spacing: u32,
+    margin: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct LayerUniform {
+    offset: [f32; 2],
+    opacity: f32,
+    _padding: f32,
+}
+
+// one per tile that isn't empty
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct Instance {
+    cell: [u16; 2],
+    tile: u32,
+}
+
+impl Instance {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 2] =
+        wgpu::vertex_attr_array![0 => Uint16x2, 1 => Uint32];
+
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: size_of::<Self>() as _,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+// the tiles of one layer from one tileset, in a chunk's instance buffer
+struct Draw {
+    layer: usize,
+    tileset: usize,
+    instances: Range<u32>,
+}
+
+struct Chunk {
+    instance_buffer: wgpu::Buffer,
+    // by layer
+    draws: Vec<Draw>,
+}
+
+struct Animation {
+    gid: u32,
+    // as gids, with the time each one ends at
+    frames: Vec<(u32, u64)>,
+}
+
+impl Animation {
+    fn frame_at(&self, time: u64) -> u32 {
+        let length = self.frames.last().map_or(0, |&(_, end)| end);
+        if length == 0 {
+            return self.gid;
+        }
+
+        let time = time % length;
+        self.frames
+            .iter()
+            .find(|&&(_, end)| time < end)
+            .map_or(self.gid, |&(gid, _)| gid)
+    }
+}
+
+struct LayerState {
+    bind_group: wgpu::BindGroup,
+    visible: bool,
+}
+
+// draws a whole `TileMap`. the tiles are uploaded once, cut into square
+// chunks, and only the chunks overlapping the camera are drawn
+pub struct TileMapRenderer {
+    pipeline: wgpu::RenderPipeline,
+    map_buffer: wgpu::Buffer,
+    map_bind_group: wgpu::BindGroup,
+    tileset_bind_groups: Vec<wgpu::BindGroup>,
+    layers: Vec<LayerState>,
+    // row by row, none where every layer is empty
+    chunks: Vec<Option<Chunk>>,
+    chunk_columns: u32,
+    chunk_rows: u32,
+    // in pixels, the chunk size and how far tiles may reach out of it
+    chunk_extent: [f32; 2],
+    overhang: f32,
+    tile_size: [f32; 2],
+    visible: [Range<u32>; 2],
+    frames_texture: wgpu::Texture,
+    frames: Vec<u32>,
+    animations: Vec<Animation>,
+}
+
+impl TileMapRenderer {
+    // `images` holds the image of every tileset, in the map's order
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        view_format: wgpu::TextureFormat,
+        map: &TileMap,
+        images: &[DecodedImage],
+    ) -> Result<Self, String> {
+        if images.len() != map.tilesets.len() {
+            return Err(format!(
+                "{} images for {} tilesets",
+                images.len(),
+                map.tilesets.len()
+            ));
+        }
+        if map.width > u16::MAX as u32 || map.height > u16::MAX as u32 {
+            return Err(format!(
+                "{}x{} tiles is too large a map",
+                map.width, map.height
+            ));
+        }
+
+        let frame_rows = map.gid_count().div_ceil(FRAMES_WIDTH);
+        if frame_rows > device.limits().max_texture_dimension_2d {
+            return Err(format!("{} gids is too many", map.gid_count()));
+        }
+
+        let shader = device.create_shader_module(include_wgsl!("shader.wgsl"));
+
+        let map_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("tilemap bind group layout"),
+            entries: &[
+                uniform_entry(0, wgpu::ShaderStages::VERTEX),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Uint,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let tileset_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("tileset bind group layout"),
+            entries: &[
+                uniform_entry(0, wgpu::ShaderStages::VERTEX_FRAGMENT),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let layer_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("tile layer bind group layout"),
+            entries: &[uniform_entry(0, wgpu::ShaderStages::VERTEX_FRAGMENT)],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("tilemap pipeline layout"),
+            bind_group_layouts: &[&map_layout, &tileset_layout, &layer_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("tilemap pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[Instance::desc()],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: view_format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleStrip,
+                ..Default::default()
+            },
+            multisample: Default::default(),
+            depth_stencil: None,
+            multiview: None,
+            cache: None,
+        });
+
+        let tile_size = [map.tile_width as f32, map.tile_height as f32];
+        let map_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("tilemap buffer"),
+            contents: bytemuck::bytes_of(&MapUniform {
+                view_projection: Camera2D::new(1, 1, 1.0).camera_matrix().to_cols_array_2d(),
+                tile_size,
+                _padding: [0.0; 2],
+            }),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        // every gid shows itself until an animation says otherwise
+        let frames: Vec<u32> = (0..frame_rows * FRAMES_WIDTH).collect();
+        let frames_texture = device.create_texture_with_data(
+            queue,
+            &wgpu::TextureDescriptor {
+                label: Some("tile frames texture"),
+                size: wgpu::Extent3d {
+                    width: FRAMES_WIDTH,
+                    height: frame_rows,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::R32Uint,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            bytemuck::cast_slice(&frames),
+        );
+
+        let map_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("tilemap bind group"),
+            layout: &map_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: map_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(
+                        &frames_texture.create_view(&Default::default()),
+                    ),
+                },
+            ],
+        });
+
+        // pixel art, so no filtering and no mipmaps
+        let options = TextureOptions {
+            mipmaps: Mipmaps::None,
+            sampler: SamplerPreset::Nearest,
+            ..Default::default()
+        };
+        let tileset_bind_groups = map
+            .tilesets
+            .iter()
+            .zip(images)
+            .map(|(tileset, image)| {
+                let texture = Texture::from_image(device, queue, image, options);
+                let uniform = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("tileset buffer"),
+                    contents: bytemuck::bytes_of(&TilesetUniform {
+                        tile_size: [tileset.tile_width as f32, tileset.tile_height as f32],
+                        offset: tileset.offset.map(|offset| offset as f32),
+                        first_gid: tileset.first_gid,
+                        columns: tileset.columns.max(1),
+                        spacing: tileset.spacing,
+                        margin: tileset.margin,
+                    }),
+                    usage: wgpu::BufferUsages::UNIFORM,
+                });
+
+                device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("tileset bind group"),
+                    layout: &tileset_layout,
+                    entries: &[
+                        wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: uniform.as_entire_binding(),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 1,
+                            resource: wgpu::BindingResource::TextureView(&texture.texture_view),
+                        },
+                    ],
+                })
+            })
+            .collect();
+
+        let layers = map
+            .layers
+            .iter()
+            .map(|layer| {
+                let uniform = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("tile layer buffer"),
+                    contents: bytemuck::bytes_of(&LayerUniform {
+                        offset: layer.offset,
+                        opacity: layer.opacity,
+                        _padding: 0.0,
+                    }),
+                    usage: wgpu::BufferUsages::UNIFORM,
+                });
+
+                LayerState {
+                    bind_group: device.create_bind_group(&wgpu::BindGroupDescriptor {
+                        label: Some("tile layer bind group"),
+                        layout: &layer_layout,
+                        entries: &[wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: uniform.as_entire_binding(),
+                        }],
+                    }),
+                    visible: layer.visible,
+                }
+            })
+            .collect();
+
+        let animations = map
+            .tilesets
+            .iter()
+            .flat_map(|tileset| {
+                tileset.animations.iter().map(|(&tile, frames)| {
+                    let mut end = 0;
+                    Animation {
+                        gid: tileset.first_gid + tile,
+                        frames: frames
+                            .iter()
+                            .map(|frame| {
+                                end += frame.duration as u64;
+                                (tileset.first_gid + frame.tile, end)
+                            })
+                            .collect(),
+                    }
+                })
+            })
+            .collect();
+
+        let chunk_columns = map.width.div_ceil(CHUNK_SIZE);
+        let chunk_rows = map.height.div_ceil(CHUNK_SIZE);
+        let chunks = (0..chunk_rows)
+            .flat_map(|y| (0..chunk_columns).map(move |x| (x, y)))
+            .map(|(x, y)| chunk(device, map, x, y))
+            .collect();
+
+        // tiles larger than the grid or drawn offset reach into the chunks
+        // around theirs
+        let overhang = map
+            .tilesets
+            .iter()
+            .map(|tileset| reach(tileset, map))
+            .chain(
+                map.layers
+                    .iter()
+                    .map(|layer| layer.offset[0].abs().max(layer.offset[1].abs())),
+            )
+            .fold(0.0, f32::max);
+
+        Ok(Self {
+            pipeline,
+            map_buffer,
+            map_bind_group,
+            tileset_bind_groups,
+            layers,
+            chunks,
+            chunk_columns,
+            chunk_rows,
+            chunk_extent: tile_size.map(|size| size * CHUNK_SIZE as f32),
+            overhang,
+            tile_size,
+            visible: [0..0, 0..0],
+            frames_texture,
+            frames,
+            animations,
+        })
+    }
+
+    pub fn set_camera(&mut self, camera: &Camera2D, queue: &wgpu::Queue) {
+        queue.write_buffer(
+            &self.map_buffer,
+            0,
+            bytemuck::bytes_of(&MapUniform {
+                view_projection: camera.camera_matrix().to_cols_array_2d(),
+                tile_size: self.tile_size,
+                _padding: [0.0; 2],
+            }),
+        );
+
+        let min = [camera.x, camera.y];
+        let max = [
+            camera.x + camera.width / camera.scale,
+            camera.y + camera.height / camera.scale,
+        ];
+        let range = |axis: usize, count: u32| {
+            let first = (min[axis] - self.overhang) / self.chunk_extent[axis];
+            let last = (max[axis] + self.overhang) / self.chunk_extent[axis];
+            first.floor().clamp(0.0, count as f32) as u32
+                ..last.ceil().clamp(0.0, count as f32) as u32
+        };
+
+        self.visible = [range(0, self.chunk_columns), range(1, self.chunk_rows)];
+    }
+
+    // tiled plays every animation from the moment the map is shown, `time` is
+    // in milliseconds since then. returns true when a tile changed its frame
+    pub fn set_time(&mut self, time: u64, queue: &wgpu::Queue) -> bool {
+        let mut changed = false;
+        for animation in &self.animations {
+            let frame = animation.frame_at(time);
+            let shown = &mut self.frames[animation.gid as usize];
+            changed |= *shown != frame;
+            *shown = frame;
+        }
+
+        if changed {
+            queue.write_texture(
+                self.frames_texture.as_image_copy(),
+                bytemuck::cast_slice(&self.frames),
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(FRAMES_WIDTH * size_of::<u32>() as u32),
+                    rows_per_image: None,
+                },
+                self.frames_texture.size(),
+            );
+        }
+
+        changed
+    }
+
+    pub fn is_animated(&self) -> bool {
+        !self.animations.is_empty()
+    }
+
+    pub fn set_layer_visible(&mut self, layer: usize, visible: bool) {
+        if let Some(layer) = self.layers.get_mut(layer) {
+            layer.visible = visible;
+        }
+    }
+
+    // of the last `set_camera`
+    pub fn visible_chunks(&self) -> usize {
+        self.visible_chunk_iter().count()
+    }
+
+    fn visible_chunk_iter(&self) -> impl Iterator<Item = &Chunk> {
+        let [columns, rows] = self.visible.clone();
+
+        rows.flat_map(move |y| columns.clone().map(move |x| (x, y)))
+            .filter_map(|(x, y)| self.chunks[(y * self.chunk_columns + x) as usize].as_ref())
+    }
+
+    // layer by layer, so tiles reaching out of their chunk still cover the
+    // layers below them everywhere
+    pub fn render(&self, render_pass: &mut wgpu::RenderPass) {
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.map_bind_group, &[]);
+
+        for (index, layer) in self.layers.iter().enumerate() {
+            if !layer.visible {
+                continue;
+            }
+            render_pass.set_bind_group(2, &layer.bind_group, &[]);
+
+            for chunk in self.visible_chunk_iter() {
+                for draw in chunk.draws.iter().filter(|draw| draw.layer == index) {
+                    render_pass.set_bind_group(1, &self.tileset_bind_groups[draw.tileset], &[]);
+                    render_pass.set_vertex_buffer(0, chunk.instance_buffer.slice(..));
+                    render_pass.draw(0..4, draw.instances.clone());
+                }
+            }
+        }
+    }
+}
+
+fn uniform_entry(binding: u32, visibility: wgpu::ShaderStages) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    }
+}
+
+// how far a tileset's tiles may be drawn outside their cell, in pixels
+fn reach(tileset: &Tileset, map: &TileMap) -> f32 {
+    let larger = (tileset.tile_width.saturating_sub(map.tile_width))
+        .max(tileset.tile_height.saturating_sub(map.tile_height));
+    let offset = tileset.offset[0]
+        .unsigned_abs()
+        .max(tileset.offset[1].unsigned_abs());
+
+    (larger + offset) as f32
+}
+
+// the instances of every layer in a chunk, grouped by layer and then by
+// tileset
+fn chunk(device: &wgpu::Device, map: &TileMap, x: u32, y: u32) -> Option<Chunk> {
+    let columns = x * CHUNK_SIZE..((x + 1) * CHUNK_SIZE).min(map.width);
+    let rows = y * CHUNK_SIZE..((y + 1) * CHUNK_SIZE).min(map.height);
+
+    let mut instances = Vec::new();
+    let mut draws = Vec::new();
+    let mut by_tileset = vec![Vec::new(); map.tilesets.len()];
+
+    for (index, layer) in map.layers.iter().enumerate() {
+        for y in rows.clone() {
+            for x in columns.clone() {
+                let tile = layer.tile(x, y);
+                if let Some((tileset, _)) = map.tileset(tile.gid()) {
+                    by_tileset[tileset].push(Instance {
+                        cell: [x as u16, y as u16],
+                        tile: tile.0,
+                    });
+                }
+            }
+        }
+
+        for (tileset, tiles) in by_tileset.iter_mut().enumerate() {
+            if tiles.is_empty() {
+                continue;
+            }
+
+            let start = instances.len() as u32;
+            instances.append(tiles);
+            draws.push(Draw {
+                layer: index,
+                tileset,
+                instances: start..instances.len() as u32,
+            });
+        }
+    }
+
+    if instances.is_empty() {
+        return None;
+    }
+
+    Some(Chunk {
+        instance_buffer: device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("tilemap chunk buffer"),
+            contents: bytemuck::cast_slice(&instances),
+            usage: wgpu::BufferUsages::VERTEX,
+        }),
+        draws,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn animations_loop_through_their_frames() {
+        let animation = Animation {
+            gid: 5,
+            frames: vec![(5, 100), (6, 400), (7, 500)],
+        };
+
+        assert_eq!(animation.frame_at(0), 5);
+        assert_eq!(animation.frame_at(99), 5);
+        assert_eq!(animation.frame_at(100), 6);
+        assert_eq!(animation.frame_at(499), 7);
+        assert_eq!(animation.frame_at(500), 5);
+        assert_eq!(animation.frame_at(1_000_050), 5);
+        assert_eq!(animation.frame_at(1_000_150), 6);
+    }
+}
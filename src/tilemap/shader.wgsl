@@ -0,0 +1,97 @@
+struct MapUniform {
+    view_projection: mat4x4<f32>,
+    // the grid size, in pixels
+    tile_size: vec2<f32>,
+};
+
+struct TilesetUniform {
+    tile_size: vec2<f32>,
+    offset: vec2<f32>,
+    first_gid: u32,
+    columns: u32,
+    spacing: u32,
+    margin: u32,
+};
+
+struct LayerUniform {
+    offset: vec2<f32>,
+    opacity: f32,
+};
+
+@group(0) @binding(0)
+var<uniform> map: MapUniform;
+// the gid each gid shows this frame, they only differ for animated tiles
+@group(0) @binding(1)
+var frames: texture_2d<u32>;
+
+@group(1) @binding(0)
+var<uniform> tileset: TilesetUniform;
+@group(1) @binding(1)
+var atlas: texture_2d<f32>;
+
+@group(2) @binding(0)
+var<uniform> layer: LayerUniform;
+
+const FLIPPED_HORIZONTALLY: u32 = 0x80000000u;
+const FLIPPED_VERTICALLY: u32 = 0x40000000u;
+const FLIPPED_DIAGONALLY: u32 = 0x20000000u;
+const GID_MASK: u32 = 0x0fffffffu;
+const FRAMES_WIDTH: u32 = 256u;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    // in pixels from the tile's top left corner in the atlas, flips applied
+    @location(0) texel: vec2<f32>,
+    @location(1) @interpolate(flat) origin: vec2<u32>,
+};
+
+@vertex
+fn vs_main(
+    @builtin(vertex_index) vertex_index: u32,
+    @location(0) cell: vec2<u32>,
+    @location(1) tile: u32,
+) -> VertexOutput {
+    // a triangle strip over the tile
+    let corner = vec2<f32>(f32(vertex_index & 1u), f32(vertex_index >> 1u));
+
+    let gid = tile & GID_MASK;
+    let shown = textureLoad(frames, vec2<u32>(gid % FRAMES_WIDTH, gid / FRAMES_WIDTH), 0).r;
+    let local = shown - tileset.first_gid;
+    let step = vec2<u32>(tileset.tile_size) + tileset.spacing;
+    let origin = tileset.margin + vec2<u32>(local % tileset.columns, local / tileset.columns) * step;
+
+    // tiled flips the image diagonally before the other two, so looking a
+    // texel up undoes that last
+    var uv = corner;
+    if (tile & FLIPPED_HORIZONTALLY) != 0u {
+        uv.x = 1.0 - uv.x;
+    }
+    if (tile & FLIPPED_VERTICALLY) != 0u {
+        uv.y = 1.0 - uv.y;
+    }
+    if (tile & FLIPPED_DIAGONALLY) != 0u {
+        uv = uv.yx;
+    }
+
+    // tiles taller than the grid are aligned to the bottom of their cell
+    let position = vec2<f32>(cell) * map.tile_size
+        + vec2<f32>(0.0, map.tile_size.y - tileset.tile_size.y)
+        + tileset.offset
+        + layer.offset
+        + corner * tileset.tile_size;
+
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(position, 0.0, 1.0) * map.view_projection;
+    out.texel = uv * tileset.tile_size;
+    out.origin = origin;
+    return out;
+}
+
+// texels are fetched rather than sampled, so neighbouring tiles never bleed in
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = clamp(vec2<u32>(in.texel), vec2<u32>(0u), vec2<u32>(tileset.tile_size) - 1u);
+    var color = textureLoad(atlas, in.origin + texel, 0);
+    color.a *= layer.opacity;
+    return color;
+}
@@ -0,0 +1,369 @@
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+
+use super::{
+    decode_tiles, external_tileset, grid_size, relative_to, Frame, Group, ReadFile, Tile,
+    TileLayer, TileMap, Tileset,
+};
+
+fn one() -> f32 {
+    1.0
+}
+
+fn yes() -> bool {
+    true
+}
+
+fn orthogonal() -> String {
+    "orthogonal".to_owned()
+}
+
+#[derive(Deserialize)]
+struct RawMap {
+    #[serde(default = "orthogonal")]
+    orientation: String,
+    #[serde(default)]
+    infinite: bool,
+    width: u32,
+    height: u32,
+    tilewidth: u32,
+    tileheight: u32,
+    #[serde(default)]
+    tilesets: Vec<RawTilesetRef>,
+    #[serde(default)]
+    layers: Vec<RawLayer>,
+}
+
+#[derive(Deserialize)]
+struct RawTilesetRef {
+    firstgid: u32,
+    source: Option<String>,
+    #[serde(flatten)]
+    tileset: serde_json::Value,
+}
+
+#[derive(Deserialize, Default)]
+struct RawOffset {
+    x: i32,
+    y: i32,
+}
+
+#[derive(Deserialize)]
+struct RawFrame {
+    tileid: u32,
+    duration: u32,
+}
+
+#[derive(Deserialize)]
+struct RawTile {
+    id: u32,
+    #[serde(default)]
+    animation: Vec<RawFrame>,
+}
+
+#[derive(Deserialize)]
+struct RawTileset {
+    #[serde(default)]
+    name: String,
+    tilewidth: u32,
+    tileheight: u32,
+    #[serde(default)]
+    spacing: u32,
+    #[serde(default)]
+    margin: u32,
+    columns: Option<u32>,
+    tilecount: Option<u32>,
+    image: Option<String>,
+    #[serde(default)]
+    imagewidth: u32,
+    #[serde(default)]
+    imageheight: u32,
+    #[serde(default)]
+    tileoffset: RawOffset,
+    #[serde(default)]
+    tiles: Vec<RawTile>,
+}
+
+// csv maps store their gids as numbers, base64 ones as a string
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawData {
+    Gids(Vec<u32>),
+    Encoded(String),
+}
+
+#[derive(Deserialize)]
+struct RawLayer {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    name: String,
+    data: Option<RawData>,
+    encoding: Option<String>,
+    compression: Option<String>,
+    #[serde(default)]
+    chunks: Vec<serde_json::Value>,
+    #[serde(default = "yes")]
+    visible: bool,
+    #[serde(default = "one")]
+    opacity: f32,
+    #[serde(default)]
+    offsetx: f32,
+    #[serde(default)]
+    offsety: f32,
+    #[serde(default)]
+    layers: Vec<RawLayer>,
+}
+
+// `path` is where the map was read from, files it refers to are read through
+// `read` relative to it
+pub fn parse(json: &str, path: &str, read: ReadFile) -> Result<TileMap, String> {
+    let map: RawMap = serde_json::from_str(json).map_err(|e| format!("invalid tmj {path}: {e}"))?;
+
+    if map.orientation != "orthogonal" {
+        return Err(format!("{} maps are not supported", map.orientation));
+    }
+    if map.infinite {
+        return Err("infinite maps are not supported".to_owned());
+    }
+
+    let tilesets = map
+        .tilesets
+        .into_iter()
+        .map(|tileset| match tileset.source {
+            Some(source) => external_tileset(&relative_to(path, &source), tileset.firstgid, read),
+            None => {
+                let raw = serde_json::from_value(tileset.tileset)
+                    .map_err(|e| format!("invalid tileset in {path}: {e}"))?;
+                self::tileset(raw, tileset.firstgid, path)
+            }
+        })
+        .collect::<Result<_, _>>()?;
+
+    let mut layers = Vec::new();
+    collect_layers(
+        map.layers,
+        Group::default(),
+        map.width,
+        map.height,
+        &mut layers,
+    )?;
+
+    TileMap {
+        width: map.width,
+        height: map.height,
+        tile_width: map.tilewidth,
+        tile_height: map.tileheight,
+        tilesets,
+        layers,
+    }
+    .validate()
+}
+
+pub(super) fn tileset_file(json: &str, first_gid: u32, path: &str) -> Result<Tileset, String> {
+    let raw = serde_json::from_str(json).map_err(|e| format!("invalid tsj {path}: {e}"))?;
+
+    tileset(raw, first_gid, path)
+}
+
+// `path` is the file the tileset is defined in, its image is relative to it
+fn tileset(raw: RawTileset, first_gid: u32, path: &str) -> Result<Tileset, String> {
+    let image = raw.image.ok_or_else(|| {
+        format!(
+            "tileset {:?} isn't a single image, which is not supported",
+            raw.name
+        )
+    })?;
+
+    let [columns, rows] = grid_size(
+        [raw.imagewidth, raw.imageheight],
+        [raw.tilewidth, raw.tileheight],
+        raw.margin,
+        raw.spacing,
+    );
+    let columns = raw.columns.unwrap_or(columns);
+
+    let animations: BTreeMap<_, _> = raw
+        .tiles
+        .into_iter()
+        .filter(|tile| !tile.animation.is_empty())
+        .map(|tile| {
+            let frames = tile
+                .animation
+                .into_iter()
+                .map(|frame| Frame {
+                    tile: frame.tileid,
+                    duration: frame.duration,
+                })
+                .collect();
+            (tile.id, frames)
+        })
+        .collect();
+
+    Ok(Tileset {
+        name: raw.name,
+        first_gid,
+        tile_width: raw.tilewidth,
+        tile_height: raw.tileheight,
+        spacing: raw.spacing,
+        margin: raw.margin,
+        columns,
+        tile_count: raw.tilecount.unwrap_or(columns * rows),
+        image: relative_to(path, &image),
+        offset: [raw.tileoffset.x, raw.tileoffset.y],
+        animations,
+    })
+}
+
+fn collect_layers(
+    raw: Vec<RawLayer>,
+    group: Group,
+    width: u32,
+    height: u32,
+    layers: &mut Vec<TileLayer>,
+) -> Result<(), String> {
+    for layer in raw {
+        let nested = group.nest([layer.offsetx, layer.offsety], layer.opacity, layer.visible);
+
+        match layer.kind.as_str() {
+            "group" => collect_layers(layer.layers, nested, width, height, layers)?,
+            "tilelayer" => {
+                if !layer.chunks.is_empty() {
+                    return Err("infinite maps are not supported".to_owned());
+                }
+
+                let tiles = match layer.data {
+                    Some(RawData::Gids(gids)) => gids.into_iter().map(Tile).collect(),
+                    Some(RawData::Encoded(data)) => decode_tiles(
+                        &data,
+                        layer.encoding.as_deref(),
+                        layer.compression.as_deref(),
+                    )?,
+                    None => return Err(format!("layer {:?} has no data", layer.name)),
+                };
+
+                layers.push(TileLayer {
+                    name: layer.name,
+                    width,
+                    height,
+                    tiles,
+                    visible: nested.visible,
+                    opacity: nested.opacity,
+                    offset: nested.offset,
+                });
+            }
+            kind => log::debug!("skipping {kind} layer"),
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tilemap::FLIPPED_VERTICALLY;
+
+    const TSJ: &str = r#"{
+        "name": "terrain",
+        "tilewidth": 16,
+        "tileheight": 16,
+        "image": "terrain.png",
+        "imagewidth": 64,
+        "imageheight": 32,
+        "tiles": [
+            { "id": 1, "animation": [
+                { "tileid": 1, "duration": 250 },
+                { "tileid": 2, "duration": 250 }
+            ] },
+            { "id": 3, "probability": 0.5 }
+        ]
+    }"#;
+
+    fn read(path: &str) -> Result<String, String> {
+        match path {
+            "tilesets/terrain.tsj" => Ok(TSJ.to_owned()),
+            _ => Err(format!("{path} not found")),
+        }
+    }
+
+    #[test]
+    fn reads_maps() {
+        let flipped = FLIPPED_VERTICALLY | 9;
+        let json = format!(
+            r#"{{
+            "type": "map",
+            "orientation": "orthogonal",
+            "infinite": false,
+            "width": 2,
+            "height": 2,
+            "tilewidth": 16,
+            "tileheight": 16,
+            "tilesets": [
+                {{ "firstgid": 1, "source": "../tilesets/terrain.tsj" }},
+                {{ "firstgid": 9, "name": "props", "tilewidth": 32, "tileheight": 32,
+                   "columns": 2, "tilecount": 4, "spacing": 2,
+                   "image": "props.png", "imagewidth": 66, "imageheight": 66 }}
+            ],
+            "layers": [
+                {{ "type": "tilelayer", "name": "ground", "width": 2, "height": 2,
+                   "data": [1, 2, 3, {flipped}] }},
+                {{ "type": "group", "name": "top", "offsetx": 4, "visible": false, "layers": [
+                    {{ "type": "tilelayer", "name": "base64", "width": 2, "height": 2,
+                       "encoding": "base64", "data": "AQAAAAIAAAADAAAABAAAAA==",
+                       "opacity": 0.5 }},
+                    {{ "type": "objectgroup", "name": "spawns", "objects": [] }}
+                ] }}
+            ]
+        }}"#
+        );
+
+        let map = parse(&json, "maps/world.tmj", &read).unwrap();
+
+        let [terrain, props] = &map.tilesets[..] else {
+            panic!("expected two tilesets");
+        };
+        assert_eq!(terrain.image, "tilesets/terrain.png");
+        assert_eq!((terrain.columns, terrain.tile_count), (4, 8));
+        assert_eq!(terrain.animations.len(), 1);
+        assert_eq!(terrain.animations[&1][1].tile, 2);
+        assert_eq!(props.image, "maps/props.png");
+        assert_eq!(props.tile_origin(3), [34, 34]);
+        assert_eq!(map.tileset(12).map(|(i, _)| i), Some(1));
+
+        let [ground, top] = &map.layers[..] else {
+            panic!("expected two tile layers");
+        };
+        assert_eq!(ground.tile(1, 1), Tile(flipped));
+        assert!(ground.visible);
+        assert_eq!(top.tiles, [1, 2, 3, 4].map(Tile));
+        assert_eq!(
+            (top.offset, top.opacity, top.visible),
+            ([4.0, 0.0], 0.5, false)
+        );
+    }
+
+    #[test]
+    fn rejects_what_it_cant_draw() {
+        let map = |extra: &str| {
+            format!(r#"{{ "width": 1, "height": 1, "tilewidth": 8, "tileheight": 8, {extra} }}"#)
+        };
+
+        assert!(parse(&map(r#""layers": []"#), "a.tmj", &read).is_ok());
+        assert!(parse(&map(r#""infinite": true"#), "a.tmj", &read).is_err());
+        assert!(parse(&map(r#""orientation": "staggered""#), "a.tmj", &read).is_err());
+        assert!(parse(
+            &map(r#""layers": [{ "type": "tilelayer", "data": [1, 2] }]"#),
+            "a.tmj",
+            &read
+        )
+        .is_err());
+        assert!(parse(
+            &map(r#""tilesets": [{ "firstgid": 1, "source": "missing.tsj" }]"#),
+            "a.tmj",
+            &read
+        )
+        .is_err());
+    }
+}
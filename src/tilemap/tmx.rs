@@ -0,0 +1,328 @@
+use std::{collections::BTreeMap, str::FromStr};
+
+use roxmltree::{Document, Node};
+
+use super::{
+    decode_tiles, external_tileset, grid_size, relative_to, Frame, Group, ReadFile, Tile,
+    TileLayer, TileMap, Tileset,
+};
+
+// `path` is where the map was read from, files it refers to are read through
+// `read` relative to it
+pub fn parse(xml: &str, path: &str, read: ReadFile) -> Result<TileMap, String> {
+    let document = Document::parse(xml).map_err(|e| format!("invalid tmx {path}: {e}"))?;
+    let map = document.root_element();
+    if !map.has_tag_name("map") {
+        return Err(format!("{path} is not a tmx map"));
+    }
+
+    let orientation = map.attribute("orientation").unwrap_or("orthogonal");
+    if orientation != "orthogonal" {
+        return Err(format!("{orientation} maps are not supported"));
+    }
+    if attribute(map, "infinite")?.unwrap_or(0) != 0 {
+        return Err("infinite maps are not supported".to_owned());
+    }
+
+    let width = required(map, "width")?;
+    let height = required(map, "height")?;
+
+    let mut tilesets = Vec::new();
+    for node in map.children().filter(|node| node.has_tag_name("tileset")) {
+        let first_gid = required(node, "firstgid")?;
+
+        let tileset = match node.attribute("source") {
+            Some(source) => external_tileset(&relative_to(path, source), first_gid, read)?,
+            None => tileset(node, first_gid, path)?,
+        };
+        tilesets.push(tileset);
+    }
+
+    let mut layers = Vec::new();
+    collect_layers(map, Group::default(), width, height, &mut layers)?;
+
+    TileMap {
+        width,
+        height,
+        tile_width: required(map, "tilewidth")?,
+        tile_height: required(map, "tileheight")?,
+        tilesets,
+        layers,
+    }
+    .validate()
+}
+
+fn attribute<T: FromStr>(node: Node, name: &str) -> Result<Option<T>, String> {
+    node.attribute(name)
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|_| format!("invalid {name} {value:?} on <{}>", node.tag_name().name()))
+        })
+        .transpose()
+}
+
+fn required<T: FromStr>(node: Node, name: &str) -> Result<T, String> {
+    attribute(node, name)?.ok_or_else(|| format!("<{}> without a {name}", node.tag_name().name()))
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|child| child.has_tag_name(name))
+}
+
+pub(super) fn tileset_file(xml: &str, first_gid: u32, path: &str) -> Result<Tileset, String> {
+    let document = Document::parse(xml).map_err(|e| format!("invalid tsx {path}: {e}"))?;
+
+    tileset(document.root_element(), first_gid, path)
+}
+
+// `path` is the file the tileset is defined in, its image is relative to it
+fn tileset(node: Node, first_gid: u32, path: &str) -> Result<Tileset, String> {
+    let name: String = attribute(node, "name")?.unwrap_or_default();
+    let image = child(node, "image")
+        .ok_or_else(|| format!("tileset {name:?} isn't a single image, which is not supported"))?;
+
+    let tile_width = required(node, "tilewidth")?;
+    let tile_height = required(node, "tileheight")?;
+    let spacing = attribute(node, "spacing")?.unwrap_or(0);
+    let margin = attribute(node, "margin")?.unwrap_or(0);
+
+    let [columns, rows] = grid_size(
+        [required(image, "width")?, required(image, "height")?],
+        [tile_width, tile_height],
+        margin,
+        spacing,
+    );
+    let columns = attribute(node, "columns")?.unwrap_or(columns);
+
+    let offset = match child(node, "tileoffset") {
+        Some(offset) => [
+            attribute(offset, "x")?.unwrap_or(0),
+            attribute(offset, "y")?.unwrap_or(0),
+        ],
+        None => [0, 0],
+    };
+
+    let mut animations = BTreeMap::new();
+    for tile in node.children().filter(|node| node.has_tag_name("tile")) {
+        let Some(animation) = child(tile, "animation") else {
+            continue;
+        };
+
+        let frames = animation
+            .children()
+            .filter(|node| node.has_tag_name("frame"))
+            .map(|frame| {
+                Ok(Frame {
+                    tile: required(frame, "tileid")?,
+                    duration: required(frame, "duration")?,
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        if !frames.is_empty() {
+            animations.insert(required(tile, "id")?, frames);
+        }
+    }
+
+    Ok(Tileset {
+        first_gid,
+        tile_width,
+        tile_height,
+        spacing,
+        margin,
+        columns,
+        tile_count: attribute(node, "tilecount")?.unwrap_or(columns * rows),
+        image: relative_to(path, &required::<String>(image, "source")?),
+        offset,
+        animations,
+        name,
+    })
+}
+
+fn collect_layers(
+    node: Node,
+    group: Group,
+    width: u32,
+    height: u32,
+    layers: &mut Vec<TileLayer>,
+) -> Result<(), String> {
+    for node in node.children().filter(Node::is_element) {
+        let nested = group.nest(
+            [
+                attribute(node, "offsetx")?.unwrap_or(0.0),
+                attribute(node, "offsety")?.unwrap_or(0.0),
+            ],
+            attribute(node, "opacity")?.unwrap_or(1.0),
+            attribute(node, "visible")?.unwrap_or(1) != 0,
+        );
+
+        match node.tag_name().name() {
+            "group" => collect_layers(node, nested, width, height, layers)?,
+            "layer" => {
+                let name = attribute(node, "name")?.unwrap_or_default();
+                let data =
+                    child(node, "data").ok_or_else(|| format!("layer {name:?} has no data"))?;
+
+                layers.push(TileLayer {
+                    tiles: tiles(data)?,
+                    name,
+                    width,
+                    height,
+                    visible: nested.visible,
+                    opacity: nested.opacity,
+                    offset: nested.offset,
+                });
+            }
+            "objectgroup" | "imagelayer" => {
+                log::debug!("skipping <{}> layer", node.tag_name().name());
+            }
+            _ => (),
+        }
+    }
+
+    Ok(())
+}
+
+fn tiles(data: Node) -> Result<Vec<Tile>, String> {
+    if child(data, "chunk").is_some() {
+        return Err("infinite maps are not supported".to_owned());
+    }
+
+    // without an encoding every tile is an element of its own
+    match data.attribute("encoding") {
+        None => data
+            .children()
+            .filter(|node| node.has_tag_name("tile"))
+            .map(|tile| Ok(Tile(attribute(tile, "gid")?.unwrap_or(0))))
+            .collect(),
+        encoding => decode_tiles(
+            data.text().unwrap_or_default(),
+            encoding,
+            data.attribute("compression"),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tilemap::{FLIPPED_DIAGONALLY, FLIPPED_HORIZONTALLY};
+
+    const TSX: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.10" name="terrain" tilewidth="16" tileheight="16" tilecount="8" columns="4" spacing="1" margin="2">
+ <image source="../images/terrain.png" width="71" height="37"/>
+ <tile id="4">
+  <animation>
+   <frame tileid="4" duration="100"/>
+   <frame tileid="5" duration="300"/>
+  </animation>
+ </tile>
+</tileset>"#;
+
+    fn map(layers: &str) -> String {
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" renderorder="right-down" width="3" height="2" tilewidth="16" tileheight="16" infinite="0">
+ <tileset firstgid="1" source="terrain.tsx"/>
+ <tileset firstgid="9" name="props" tilewidth="16" tileheight="32">
+  <tileoffset x="0" y="4"/>
+  <image source="props.png" width="64" height="32"/>
+ </tileset>
+ {layers}
+</map>"#
+        )
+    }
+
+    fn read(path: &str) -> Result<String, String> {
+        match path {
+            "maps/terrain.tsx" => Ok(TSX.to_owned()),
+            _ => Err(format!("{path} not found")),
+        }
+    }
+
+    #[test]
+    fn reads_tilesets_inline_and_external() {
+        let map = parse(&map(""), "maps/island.tmx", &read).unwrap();
+        let [terrain, props] = &map.tilesets[..] else {
+            panic!("expected two tilesets");
+        };
+
+        assert_eq!(terrain.image, "images/terrain.png");
+        assert_eq!((terrain.columns, terrain.tile_count), (4, 8));
+        assert_eq!((terrain.margin, terrain.spacing), (2, 1));
+        assert_eq!(terrain.tile_origin(5), [19, 19]);
+        assert_eq!(
+            terrain.animations[&4],
+            [
+                Frame {
+                    tile: 4,
+                    duration: 100
+                },
+                Frame {
+                    tile: 5,
+                    duration: 300
+                }
+            ]
+        );
+
+        // no counts given, worked out from the image
+        assert_eq!(props.image, "maps/props.png");
+        assert_eq!((props.columns, props.tile_count), (4, 4));
+        assert_eq!(props.offset, [0, 4]);
+    }
+
+    #[test]
+    fn reads_layers_in_every_encoding() {
+        let flipped = FLIPPED_HORIZONTALLY | FLIPPED_DIAGONALLY | 2;
+        let layers = format!(
+            r#"
+ <layer id="1" name="csv" width="3" height="2">
+  <data encoding="csv">
+1,{flipped},0,
+4,5,9
+</data>
+ </layer>
+ <group name="group" offsetx="8" opacity="0.5">
+  <layer id="2" name="xml" width="3" height="2" offsetx="2" offsety="-4" opacity="0.5" visible="0">
+   <data><tile gid="3"/><tile/><tile/><tile/><tile/><tile gid="1"/></data>
+  </layer>
+  <objectgroup id="3" name="objects"/>
+ </group>
+ <layer id="4" name="base64" width="3" height="2">
+  <data encoding="base64">AQAAAAIAAAADAAAABAAAAAUAAAAGAAAA</data>
+ </layer>"#
+        );
+
+        let map = parse(&map(&layers), "maps/island.tmx", &read).unwrap();
+        let [csv, xml, base64] = &map.layers[..] else {
+            panic!("expected three tile layers");
+        };
+
+        assert_eq!(csv.tile(1, 0), Tile(flipped));
+        assert_eq!(csv.tile(2, 1).gid(), 9);
+        assert!(csv.visible);
+
+        assert_eq!(xml.tile(0, 0), Tile(3));
+        assert_eq!(xml.tile(2, 1), Tile(1));
+        assert_eq!(xml.offset, [10.0, -4.0]);
+        assert_eq!(xml.opacity, 0.25);
+        assert!(!xml.visible);
+
+        assert_eq!(base64.tiles, [1, 2, 3, 4, 5, 6].map(Tile));
+    }
+
+    #[test]
+    fn rejects_what_it_cant_draw() {
+        let short =
+            r#"<layer name="short" width="3" height="2"><data encoding="csv">1,2</data></layer>"#;
+        assert!(parse(&map(short), "maps/a.tmx", &read).is_err());
+
+        let isometric = map("").replace("orthogonal", "isometric");
+        assert!(parse(&isometric, "maps/a.tmx", &read).is_err());
+
+        let missing = map("").replace("terrain.tsx", "missing.tsx");
+        assert!(parse(&missing, "maps/a.tmx", &read).is_err());
+
+        assert!(parse("<map", "maps/a.tmx", &read).is_err());
+    }
+}
@@ -0,0 +1,141 @@
+use std::sync::Arc;
+
+use winit::{event::WindowEvent, window::Window};
+
+// an egui overlay drawn over everything else, including post processing.
+// window events go through `handle_event` before anything else sees them
+pub struct UiOverlay {
+    window: Arc<Window>,
+    context: egui::Context,
+    state: egui_winit::State,
+    renderer: egui_wgpu::Renderer,
+    pub visible: bool,
+    // the output of the last `run`, drawn by the next `render`
+    primitives: Vec<egui::ClippedPrimitive>,
+    textures: egui::TexturesDelta,
+    pixels_per_point: f32,
+}
+
+impl UiOverlay {
+    pub fn new(
+        device: &wgpu::Device,
+        view_format: wgpu::TextureFormat,
+        window: Arc<Window>,
+    ) -> Self {
+        let context = egui::Context::default();
+        let state = egui_winit::State::new(
+            context.clone(),
+            egui::ViewportId::ROOT,
+            &window,
+            Some(window.scale_factor() as f32),
+            None,
+            Some(device.limits().max_texture_dimension_2d as usize),
+        );
+        let renderer = egui_wgpu::Renderer::new(device, view_format, None, 1, false);
+
+        Self {
+            window,
+            context,
+            state,
+            renderer,
+            visible: true,
+            primitives: Vec::new(),
+            textures: Default::default(),
+            pixels_per_point: 1.0,
+        }
+    }
+
+    // returns true when the ui used the event, e.g. a click on one of its
+    // windows or typing into a text field, so it shouldn't go any further
+    pub fn handle_event(&mut self, event: &WindowEvent) -> bool {
+        if !self.visible {
+            return false;
+        }
+
+        let response = self.state.on_window_event(&self.window, event);
+        if response.repaint {
+            self.window.request_redraw();
+        }
+
+        response.consumed
+    }
+
+    // builds the ui for this frame, before the scene is drawn so changes made
+    // by the ui already show up in it
+    pub fn run(&mut self, build: impl FnMut(&egui::Context)) {
+        if !self.visible {
+            self.primitives.clear();
+            return;
+        }
+
+        let input = self.state.take_egui_input(&self.window);
+        let output = self.context.run(input, build);
+        self.state
+            .handle_platform_output(&self.window, output.platform_output);
+
+        // animations and widgets being dragged want the next frame right away
+        let repaint_now = output
+            .viewport_output
+            .get(&egui::ViewportId::ROOT)
+            .is_some_and(|viewport| viewport.repaint_delay.is_zero());
+        if repaint_now {
+            self.window.request_redraw();
+        }
+
+        self.primitives = self
+            .context
+            .tessellate(output.shapes, output.pixels_per_point);
+        self.textures.append(output.textures_delta);
+        self.pixels_per_point = output.pixels_per_point;
+    }
+
+    pub fn render(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        size: [u32; 2],
+    ) {
+        let textures = std::mem::take(&mut self.textures);
+        for (id, delta) in &textures.set {
+            self.renderer.update_texture(device, queue, *id, delta);
+        }
+
+        let screen = egui_wgpu::ScreenDescriptor {
+            size_in_pixels: size,
+            pixels_per_point: self.pixels_per_point,
+        };
+
+        if !self.primitives.is_empty() {
+            let commands =
+                self.renderer
+                    .update_buffers(device, queue, encoder, &self.primitives, &screen);
+            queue.submit(commands);
+
+            let mut render_pass = encoder
+                .begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("ui render pass"),
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Load,
+                            store: wgpu::StoreOp::Store,
+                        },
+                    })],
+                    depth_stencil_attachment: None,
+                    timestamp_writes: None,
+                    occlusion_query_set: None,
+                })
+                .forget_lifetime();
+
+            self.renderer
+                .render(&mut render_pass, &self.primitives, &screen);
+        }
+
+        for id in &textures.free {
+            self.renderer.free_texture(id);
+        }
+    }
+}